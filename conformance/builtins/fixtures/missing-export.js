@@ -0,0 +1,3 @@
+import { readFile } from "lunos:fs";
+
+export default readFile;
@@ -0,0 +1,65 @@
+import * as fs from "lunos:fs";
+import * as http from "lunos:http";
+import * as process from "lunos:process";
+import * as shell from "lunos:shell";
+import processDefault, { argv } from "lunos:process";
+import { assert, test, done } from "../harness.js";
+
+async function importError(specifier) {
+    try {
+        await import(specifier);
+    } catch (error) {
+        return error;
+    }
+    throw new Error(`expected importing ${specifier} to fail`);
+}
+
+const modules = {
+    "lunos:fs": [fs, ["loadFile"]],
+    "lunos:http": [http, ["serve"]],
+    "lunos:process": [process, ["argv", "exit", "input"]],
+    "lunos:shell": [shell, ["shell"]],
+};
+
+for (const [specifier, [namespace, functions]] of Object.entries(modules)) {
+    await test(`${specifier} exports its functions of the Lunos global`, () => {
+        assert.deepEqual(Object.keys(namespace).sort(), [...functions, "default"].sort());
+        for (const name of functions) {
+            assert.equal(typeof namespace[name], "function");
+            assert.equal(namespace[name], Lunos[name]);
+        }
+    });
+
+    await test(`${specifier} has them all on a frozen default export`, () => {
+        assert.deepEqual(Object.keys(namespace.default).sort(), [...functions].sort());
+        assert.ok(Object.isFrozen(namespace.default));
+        for (const name of functions) {
+            assert.equal(namespace.default[name], namespace[name]);
+        }
+    });
+}
+
+await test("named and default imports are the same functions", () => {
+    assert.equal(argv, process.argv);
+    assert.equal(processDefault, process.default);
+});
+
+await test("import() gives the same namespace", async () => {
+    assert.equal(await import("lunos:process"), process);
+});
+
+await test("an unknown lunos: module is a ResolveError with a suggestion", async () => {
+    const error = await importError("lunos:proces");
+    assert.ok(error instanceof ResolveError);
+    assert.equal(error.code, "ERR_MODULE_NOT_FOUND");
+    assert.equal(error.suggestion, "lunos:process");
+});
+
+await test("a function a module doesn't have is a SyntaxError", async () => {
+    const error = await importError("./fixtures/missing-export.js");
+    assert.ok(error instanceof SyntaxError);
+    assert.equal(error.code, "ERR_MODULE_LINK");
+    assert.equal(error.suggestion, "loadFile");
+});
+
+done("builtins");
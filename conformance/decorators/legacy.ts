@@ -0,0 +1,117 @@
+import { assert, test, done } from "../harness.js";
+
+await test("class decorators", () => {
+    const seen: Function[] = [];
+    function register(cls: Function) {
+        seen.push(cls);
+    }
+    function frozen<T extends new (...args: any[]) => object>(cls: T): T {
+        return class extends cls {
+            frozen = true;
+        };
+    }
+
+    @register
+    @frozen
+    class Widget {}
+
+    assert.equal(seen.length, 1);
+    assert.equal(seen[0], Widget);
+    assert.equal((new Widget() as any).frozen, true);
+});
+
+await test("member decorators get the target, key and descriptor", () => {
+    const calls: string[] = [];
+    function readonly(target: any, key: string, descriptor: PropertyDescriptor) {
+        calls.push(`${typeof target === "function" ? "class" : "prototype"}.${key}`);
+        descriptor.writable = false;
+    }
+    function note(target: any, key: string, descriptor?: PropertyDescriptor) {
+        calls.push(`${typeof target === "function" ? "class" : "prototype"}.${key} ${descriptor}`);
+    }
+
+    class Point {
+        @note x: number = 1;
+
+        @readonly
+        norm() {
+            return Math.abs(this.x);
+        }
+
+        @readonly
+        static origin() {
+            return new Point();
+        }
+    }
+
+    assert.deepEqual(calls, ["prototype.x undefined", "prototype.norm", "class.origin"]);
+    assert.equal(new Point().x, 1);
+    assert.equal(Object.getOwnPropertyDescriptor(Point.prototype, "norm")?.writable, false);
+    assert.equal(Object.getOwnPropertyDescriptor(Point, "origin")?.writable, false);
+});
+
+await test("parameter decorators", () => {
+    const injected: [string | undefined, number][] = [];
+    function inject(target: any, key: string | undefined, index: number) {
+        injected.push([key, index]);
+    }
+
+    class Service {
+        constructor(@inject readonly name: string, @inject private port?: number) {}
+
+        call(path: string, @inject retries: number) {}
+    }
+
+    assert.deepEqual(injected, [["call", 1], [undefined, 1], [undefined, 0]]);
+    assert.equal(new Service("api").name, "api");
+});
+
+await test("metadata", () => {
+    const store = new Map<unknown, Record<string, unknown>>();
+    (Reflect as any).metadata = (key: string, value: unknown) => (target: any, property?: string) => {
+        const owner = property === undefined ? target : target[property] ?? target;
+        store.set(property ?? owner, { ...store.get(property ?? owner), [key]: value });
+    };
+    const noop = () => {};
+
+    interface Shape {}
+    class Engine {}
+    enum Mode {
+        On,
+    }
+
+    @noop
+    class Car {
+        @noop wheels: number = 4;
+        @noop name: string | undefined;
+        @noop engine!: Engine;
+        @noop shape!: Shape;
+        @noop tags: string[] = [];
+
+        constructor(engine: Engine, count: number, mode: Mode) {}
+
+        @noop
+        drive(speed: number, to: { x: number }): boolean {
+            return true;
+        }
+
+        @noop
+        async park() {}
+    }
+
+    delete (Reflect as any).metadata;
+    assert.deepEqual(store.get("wheels"), { "design:type": Number });
+    assert.deepEqual(store.get("name"), { "design:type": String });
+    assert.deepEqual(store.get("engine"), { "design:type": Engine });
+    assert.deepEqual(store.get("shape"), { "design:type": Object });
+    assert.deepEqual(store.get("tags"), { "design:type": Array });
+    assert.deepEqual(store.get("drive"), {
+        "design:type": Function,
+        "design:paramtypes": [Number, Object],
+        "design:returntype": Boolean,
+    });
+    assert.deepEqual(store.get("park")?.["design:returntype"], Promise);
+    assert.deepEqual(store.get(Car)?.["design:paramtypes"], [Engine, Number, Object]);
+});
+
+done("legacy");
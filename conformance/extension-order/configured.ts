@@ -0,0 +1,8 @@
+import { assert, test, done } from "../harness.js";
+import { picked } from "./fixtures/order";
+
+await test("lunos.json sets the order", () => {
+    assert.equal(picked, "order.js");
+});
+
+done("configured");
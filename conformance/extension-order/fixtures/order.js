@@ -0,0 +1 @@
+export const picked = "order.js";
@@ -0,0 +1,8 @@
+import values = require("./values.cjs");
+
+let count: number = values.start;
+export = {
+    next(): number {
+        return ++count;
+    },
+};
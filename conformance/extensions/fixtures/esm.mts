@@ -0,0 +1 @@
+export const kind: string = typeof module;
@@ -0,0 +1 @@
+export const from: string = "helper.ts";
@@ -0,0 +1,2 @@
+// no imports or exports, but still a module because of its extension
+globalThis.plainModuleKind = typeof module;
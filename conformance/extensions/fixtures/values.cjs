@@ -0,0 +1 @@
+exports.start = 10;
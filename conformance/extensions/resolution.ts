@@ -0,0 +1,38 @@
+import { assert, test, done } from "../harness.js";
+import { twice } from "./fixtures/utils";
+import { name } from "./fixtures/dir";
+import { from } from "./fixtures/helper.js";
+import { picked } from "./fixtures/order";
+import { kind } from "./fixtures/esm.mjs";
+import counter from "./fixtures/counter.cjs";
+import "./fixtures/plain.mjs";
+import data from "./fixtures/data";
+
+await test("extensionless TypeScript modules", () => {
+    assert.equal(twice(2), 4);
+    assert.equal(name, "dir/index.ts");
+});
+
+await test("a .js specifier finds the .ts source", () => {
+    assert.equal(from, "helper.ts");
+});
+
+await test("TypeScript comes before JavaScript", () => {
+    assert.equal(picked, "order.ts");
+});
+
+await test(".mts and .mjs are ES modules", () => {
+    assert.equal(kind, "undefined");
+    assert.equal((globalThis as any).plainModuleKind, "undefined");
+});
+
+await test(".cts is CommonJS", () => {
+    assert.equal(counter.next(), 11);
+    assert.equal(counter.next(), 12);
+});
+
+await test("JSON comes last", () => {
+    assert.deepEqual(data, { from: "data.json" });
+});
+
+done("resolution");
@@ -0,0 +1,80 @@
+// A tiny test harness for the conformance suites. It only relies on the
+// language itself and `console`, so the same files run under Node for
+// comparison: `node conformance/node/path.js`.
+
+let failed = 0;
+let passed = 0;
+
+function show(value) {
+    try {
+        return JSON.stringify(value);
+    } catch {
+        return String(value);
+    }
+}
+
+function deepEqual(a, b) {
+    if (Object.is(a, b)) {
+        return true;
+    }
+    if (typeof a !== "object" || typeof b !== "object" || a === null || b === null) {
+        return false;
+    }
+    if (Array.isArray(a) !== Array.isArray(b) || ArrayBuffer.isView(a) !== ArrayBuffer.isView(b)) {
+        return false;
+    }
+    const keys = Object.keys(a);
+    if (keys.length !== Object.keys(b).length) {
+        return false;
+    }
+    return keys.every((key) => Object.prototype.hasOwnProperty.call(b, key) && deepEqual(a[key], b[key]));
+}
+
+export const assert = {
+    ok(value, message) {
+        if (!value) {
+            throw new Error(message || `expected a truthy value, got ${show(value)}`);
+        }
+    },
+    equal(actual, expected) {
+        if (!Object.is(actual, expected)) {
+            throw new Error(`expected ${show(expected)}, got ${show(actual)}`);
+        }
+    },
+    deepEqual(actual, expected) {
+        if (!deepEqual(actual, expected)) {
+            throw new Error(`expected ${show(expected)}, got ${show(actual)}`);
+        }
+    },
+    throws(fn, code) {
+        try {
+            fn();
+        } catch (error) {
+            if (code !== undefined && error.code !== code) {
+                throw new Error(`expected error code ${code}, got ${error.code}`);
+            }
+            return;
+        }
+        throw new Error("expected the function to throw");
+    },
+};
+
+/** Runs one named check, which may return a promise. */
+export async function test(name, fn) {
+    try {
+        await fn();
+        passed += 1;
+        console.log(`  ok   ${name}`);
+    } catch (error) {
+        failed += 1;
+        console.log(`  FAIL ${name}: ${error && error.message}`);
+    }
+}
+
+/** Prints the totals; the exit code tells the runner whether all passed. */
+export function done(suite) {
+    console.log(`${suite}: ${passed} passed, ${failed} failed`);
+    if (failed > 0) {
+        throw new Error(`${suite}: ${failed} failed`);
+    }
+}
@@ -0,0 +1,40 @@
+import greeting from "greeting";
+import { name } from "utils/format.js";
+import replaced from "./lib/old.js";
+import { assert, test, done } from "../harness.js";
+
+await test("imports map a bare specifier", () => {
+    assert.equal(greeting, "top-level");
+});
+
+await test("a key ending in / maps everything under it", () => {
+    assert.equal(name, "format");
+});
+
+await test("a path can be mapped to another", () => {
+    assert.equal(replaced, "new");
+});
+
+await test("a scope wins over imports for the modules in it", async () => {
+    const scoped = await import("./scoped/entry.js");
+    assert.equal(scoped.greeting, "scoped");
+});
+
+await test("imports still apply in a scope that doesn't map the specifier", async () => {
+    const scoped = await import("./scoped/entry.js");
+    assert.equal(scoped.name, "format");
+});
+
+await test("the most specific scope wins", async () => {
+    const deeper = await import("./scoped/deeper/entry.js");
+    assert.equal(deeper.greeting, "deeper");
+});
+
+await test("a scope without a trailing / is only the module it names", async () => {
+    const sibling = await import("./scoped-sibling/entry.js");
+    assert.equal(sibling.greeting, "top-level");
+    const file = await import("./file-scoped.js");
+    assert.equal(file.greeting, "deeper");
+});
+
+done("import-map");
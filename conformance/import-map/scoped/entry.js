@@ -0,0 +1,4 @@
+import greeting from "greeting";
+import { name } from "utils/format.js";
+
+export { greeting, name };
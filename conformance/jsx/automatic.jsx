@@ -0,0 +1,32 @@
+/** @jsxImportSource tiny-jsx */
+import { assert, test, done } from "../harness.js";
+
+const Item = () => null;
+
+await test("elements become jsx calls with children in props", () => {
+    assert.deepEqual(<div id="a">text</div>, {
+        type: "div",
+        props: { id: "a", children: "text" },
+        key: undefined,
+        static: false,
+    });
+});
+
+await test("several children use jsxs", () => {
+    const el = <ul><li /><li /></ul>;
+    assert.equal(el.static, true);
+    assert.equal(el.props.children.length, 2);
+});
+
+await test("key is passed apart from props", () => {
+    const el = <Item key="k1" value={1} />;
+    assert.equal(el.type, Item);
+    assert.equal(el.key, "k1");
+    assert.deepEqual(el.props, { value: 1 });
+});
+
+await test("fragments use the runtime's Fragment", () => {
+    assert.equal(<>a</>.type, "#fragment");
+});
+
+done("automatic");
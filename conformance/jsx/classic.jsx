@@ -0,0 +1,82 @@
+import { assert, test, done } from "../harness.js";
+import { h, Fragment } from "./fixtures/h.js";
+
+const Card = (props) => h("card", props);
+const ui = { Button: "button-component" };
+
+await test("intrinsic elements are named by string", () => {
+    assert.deepEqual(<div />, { type: "div", props: null, children: [] });
+    assert.deepEqual(<custom-element />, { type: "custom-element", props: null, children: [] });
+    assert.deepEqual(<svg:rect />, { type: "svg:rect", props: null, children: [] });
+});
+
+await test("components are named by reference", () => {
+    assert.equal(<Card />.type, Card);
+    assert.equal(<ui.Button />.type, "button-component");
+});
+
+await test("attributes", () => {
+    const rest = { b: 2, c: 3 };
+    const el = <input a="1" data-id={7} disabled {...rest} c="last" xlink:href="#x" />;
+    assert.deepEqual(el.props, { a: "1", "data-id": 7, disabled: true, b: 2, c: "last", "xlink:href": "#x" });
+});
+
+await test("attribute strings take entities but no escapes", () => {
+    assert.equal(<a title="Tom &amp; Jerry" />.props.title, "Tom & Jerry");
+    assert.equal(<a title='back\slash' />.props.title, "back\\slash");
+    assert.equal(<a title="it's" />.props.title, "it's");
+});
+
+await test("children", () => {
+    const name = "world";
+    const el = (
+        <p>
+            Hello, {name}!
+            <b>bold</b>
+            {[1, 2].map((n) => <i key={n}>{n}</i>)}
+        </p>
+    );
+    assert.deepEqual(el.children, [
+        "Hello, ",
+        "world",
+        "!",
+        { type: "b", props: null, children: ["bold"] },
+        [
+            { type: "i", props: { key: 1 }, children: [1] },
+            { type: "i", props: { key: 2 }, children: [2] },
+        ],
+    ]);
+});
+
+await test("empty expressions and comments are not children", () => {
+    const el = (
+        <p>
+            {/* nothing here */}
+            {}
+        </p>
+    );
+    assert.deepEqual(el.children, []);
+});
+
+await test("elements as attribute values and in expressions", () => {
+    const el = <Card icon=<i /> label={<b>{1 < 2 ? "yes" : "no"}</b>} />;
+    assert.deepEqual(el.props.icon, { type: "i", props: null, children: [] });
+    assert.deepEqual(el.props.label.children, ["yes"]);
+});
+
+await test("fragments use jsxFragmentFactory", () => {
+    assert.deepEqual(<>a<br /></>, {
+        type: "#fragment",
+        props: null,
+        children: ["a", { type: "br", props: null, children: [] }],
+    });
+});
+
+await test("comparisons and regular expressions are still code", () => {
+    const a = 1, b = 2;
+    assert.ok(a < b && b > a);
+    assert.ok(/<div>/.test("<div>"));
+    assert.equal(`<${"p"}>`, "<p>");
+});
+
+done("classic");
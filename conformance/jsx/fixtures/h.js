@@ -0,0 +1,7 @@
+// A classic JSX factory that builds plain objects to compare against.
+
+export const Fragment = "#fragment";
+
+export function h(type, props, ...children) {
+    return { type, props, children };
+}
@@ -0,0 +1,17 @@
+/** @jsx make */
+/** @jsxFrag Group */
+import { assert, test, done } from "../harness.js";
+
+const Group = "group";
+const make = (type, props, ...children) => ({ made: type, props, children });
+
+await test("pragma comments override the tsconfig", () => {
+    assert.deepEqual(<a />, { made: "a", props: null, children: [] });
+    assert.deepEqual(<>x</>, { made: "group", props: null, children: ["x"] });
+});
+
+await test("@jsx in strings is not a pragma", () => {
+    assert.equal("@jsx other", "@jsx other");
+});
+
+done("pragmas");
@@ -0,0 +1,55 @@
+import { assert, test, done } from "../harness.js";
+import { h, Fragment } from "./fixtures/h.js";
+
+interface Props {
+    label: string;
+    count?: number;
+}
+
+const Badge = ({ label, count = 0 }: Props) => <span title={label}>{count as number}</span>;
+
+// a trailing comma or a constraint makes these type parameters, not tags
+const identity = <T,>(value: T): T => value;
+const first = <T extends unknown[]>(items: T) => items[0];
+
+function wrap<T>(child: T): { children: T[] } {
+    return <div>{child}</div>;
+}
+
+await test("types are erased around and inside JSX", () => {
+    const el: ReturnType<typeof h> = <Badge label="new" count={3} />;
+    assert.equal(el.type, Badge);
+    assert.deepEqual(el.props, { label: "new", count: 3 });
+    const badge = Badge(el.props);
+    assert.deepEqual(badge.children, [3]);
+});
+
+await test("generic arrow functions", () => {
+    assert.equal(identity<number>(5), 5);
+    assert.equal(first([<br />, 2]).type, "br");
+});
+
+await test("generic calls next to elements", () => {
+    assert.deepEqual(wrap<string>("x").children, ["x"]);
+    assert.equal(<>{identity<string>("y")}</>.children[0], "y");
+});
+
+const Select = <T,>({ options }: { options: T[] }) => <ul>{options.length}</ul>;
+const forms = { Select };
+
+await test("type arguments of an element type", () => {
+    const el = <Select<string> options={["a", "b"]} />;
+    assert.equal(el.type, Select);
+    assert.deepEqual(el.props, { options: ["a", "b"] });
+    const nested = (
+        <forms.Select<Map<string, () => void>> options={[]}>
+            <Select<number> options={[1]} />
+        </forms.Select>
+    );
+    assert.equal(nested.type, Select);
+    assert.deepEqual(nested.props, { options: [] });
+    assert.equal(nested.children[0].type, Select);
+    assert.deepEqual(nested.children[0].props, { options: [1] });
+});
+
+done("typescript");
@@ -0,0 +1,43 @@
+import { assert, test, done } from "../harness.js";
+import { h } from "./fixtures/h.js";
+
+await test("text on one line keeps its spaces", () => {
+    assert.deepEqual(<p>  a  b  </p>.children, ["  a  b  "]);
+});
+
+await test("lines are trimmed and joined with a space", () => {
+    const el = (
+        <p>
+            first line
+              second line
+        </p>
+    );
+    assert.deepEqual(el.children, ["first line second line"]);
+});
+
+await test("whitespace-only lines between children go away", () => {
+    const el = (
+        <p>
+            <b />
+            <i />
+        </p>
+    );
+    assert.equal(el.children.length, 2);
+});
+
+await test("spaces next to expressions on the same line stay", () => {
+    const x = "x";
+    assert.deepEqual(<p>a {x} b</p>.children, ["a ", "x", " b"]);
+});
+
+await test("entities", () => {
+    assert.deepEqual(<p>&lt;tag&gt; &amp; &#65;&#x42; &copy;</p>.children, ["<tag> & AB ©"]);
+    assert.deepEqual(<p>a&nbsp;b</p>.children, ["a b"]);
+    assert.deepEqual(<p>&unknown; & alone</p>.children, ["&unknown; & alone"]);
+});
+
+await test("quotes and apostrophes in text", () => {
+    assert.deepEqual(<p>Don't "quote" me</p>.children, ["Don't \"quote\" me"]);
+});
+
+done("whitespace");
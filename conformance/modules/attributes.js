@@ -0,0 +1,50 @@
+import data from "./fixtures/attributes/data.json" with { type: "json" };
+import inferred from "./fixtures/attributes/data.json";
+import text from "./fixtures/attributes/greeting.txt" with { type: "text" };
+import bytes from "./fixtures/attributes/greeting.txt" with { type: "bytes" };
+import { assert, test, done } from "../harness.js";
+
+async function importError(specifier, options) {
+    try {
+        await import(specifier, options);
+    } catch (error) {
+        return error;
+    }
+    throw new Error(`expected importing ${specifier} to fail`);
+}
+
+await test("json imports the parsed file, with or without the attribute", () => {
+    assert.deepEqual(data, { name: "lunos", list: [1, 2] });
+    assert.equal(inferred, data);
+});
+
+await test("text imports the file as a string", () => {
+    assert.equal(text, "héllo\n");
+});
+
+await test("bytes imports the file as a Uint8Array", () => {
+    assert.ok(bytes instanceof Uint8Array);
+    assert.deepEqual(Array.from(bytes), [0x68, 0xc3, 0xa9, 0x6c, 0x6c, 0x6f, 0x0a]);
+});
+
+await test("import() takes the attributes in its options", async () => {
+    const json = await import("./fixtures/attributes/data.json", { with: { type: "json" } });
+    assert.equal(json.default, data);
+    const module = await import("./fixtures/attributes/greeting.txt", { with: { type: "text" } });
+    assert.deepEqual(Object.keys(module), ["default"]);
+    assert.equal(module.default, text);
+});
+
+await test("an unknown type is a TypeError", async () => {
+    const error = await importError("./fixtures/attributes/data.json", { with: { type: "yaml" } });
+    assert.ok(error instanceof TypeError);
+    assert.equal(error.code, "ERR_IMPORT_ATTRIBUTE_UNSUPPORTED");
+});
+
+await test("an unknown type in a static import fails the importer", async () => {
+    const error = await importError("./fixtures/attributes/bad-type.js");
+    assert.ok(error instanceof TypeError);
+    assert.equal(error.code, "ERR_IMPORT_ATTRIBUTE_UNSUPPORTED");
+});
+
+done("attributes");
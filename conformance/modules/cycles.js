@@ -0,0 +1,26 @@
+import { assert, test, done } from "../harness.js";
+import { a, b, seenByB } from "./fixtures/cycle-a.js";
+import { early } from "./fixtures/cycle-b.js";
+import { Derived } from "./fixtures/cycle-class.js";
+import { Base } from "./fixtures/cycle-base.js";
+
+await test("a cycle evaluates the dependency first", () => {
+    assert.equal(a, "a");
+    assert.equal(b, "b");
+});
+
+await test("reading a binding before it's initialized throws then", () => {
+    assert.equal(early, "ReferenceError");
+});
+
+await test("the binding is there once initialized", () => {
+    assert.equal(seenByB, "a");
+});
+
+await test("classes can refer to each other across a cycle", () => {
+    const created = Base.create();
+    assert.ok(created instanceof Derived);
+    assert.equal(created.kind(), "derived");
+});
+
+done("cycles");
@@ -0,0 +1,18 @@
+import { order } from "./fixtures/order-log.js";
+import { assert, test, done } from "../harness.js";
+
+// The loader reads and transforms modules in parallel, but evaluates them in
+// the order the imports are written: each module after its dependencies, and
+// in a cycle the module that closes it first.
+await test("modules evaluate after their dependencies, in import order", async () => {
+    await import("./fixtures/order-entry.js");
+    assert.deepEqual(order, ["d", "b", "e", "c", "entry"]);
+});
+
+await test("a module evaluates once", async () => {
+    await import("./fixtures/order-c.js");
+    await import("./fixtures/order-entry.js");
+    assert.deepEqual(order, ["d", "b", "e", "c", "entry"]);
+});
+
+done("evaluation-order");
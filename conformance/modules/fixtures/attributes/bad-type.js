@@ -0,0 +1,2 @@
+import data from "./data.json" with { type: "yaml" };
+export default data;
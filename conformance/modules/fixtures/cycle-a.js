@@ -0,0 +1,6 @@
+import { b, readA } from "./cycle-b.js";
+
+export const a = "a";
+// cycle-b.js ran first and couldn't read `a` yet, it can now
+export const seenByB = readA();
+export { b };
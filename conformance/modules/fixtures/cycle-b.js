@@ -0,0 +1,14 @@
+import { a } from "./cycle-a.js";
+
+export let early;
+try {
+    early = a;
+} catch (error) {
+    early = error.name;
+}
+
+export const b = "b";
+
+export function readA() {
+    return a;
+}
@@ -0,0 +1,7 @@
+import { Derived } from "./cycle-class.js";
+
+export class Base {
+    static create() {
+        return new Derived();
+    }
+}
@@ -0,0 +1,7 @@
+import { Base } from "./cycle-base.js";
+
+export class Derived extends Base {
+    kind() {
+        return "derived";
+    }
+}
@@ -0,0 +1,4 @@
+// the import's column moves once the element before it is lowered
+const greeting = <p className="hello">Hi</p>; import { greet } from "./missing.js";
+
+export { greeting, greet };
@@ -0,0 +1 @@
+globalThis.sideEffectRan = true;
@@ -0,0 +1,3 @@
+import { cont } from "./counter.js";
+
+export { cont };
@@ -0,0 +1,3 @@
+import { count } from "./countr.js";
+
+export { count };
@@ -0,0 +1,4 @@
+import { log } from "./order-log.js";
+import "./order-d.js";
+
+log("b");
@@ -0,0 +1,5 @@
+import { log } from "./order-log.js";
+import "./order-d.js";
+import "./order-e.js";
+
+log("c");
@@ -0,0 +1,3 @@
+import { log } from "./order-log.js";
+
+log("d");
@@ -0,0 +1,4 @@
+import { log } from "./order-log.js";
+import "./order-c.js";
+
+log("e");
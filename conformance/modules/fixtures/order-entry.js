@@ -0,0 +1,5 @@
+import { log } from "./order-log.js";
+import "./order-b.js";
+import "./order-c.js";
+
+log("entry");
@@ -0,0 +1,5 @@
+export const order = [];
+
+export function log(name) {
+    order.push(name);
+}
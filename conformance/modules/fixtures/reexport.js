@@ -0,0 +1 @@
+export { count, increment } from "./counter.js";
@@ -0,0 +1 @@
+export { greeting } from "local-package";
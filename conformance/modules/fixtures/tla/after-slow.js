@@ -0,0 +1,4 @@
+import { order } from "./log.js";
+import "./slow.js";
+
+order.push("after slow");
@@ -0,0 +1,3 @@
+import "./rejects.js";
+
+globalThis.importerRan = true;
@@ -0,0 +1,2 @@
+await new Promise((resolve) => setTimeout(resolve, 1));
+throw new Error("rejected at the top level");
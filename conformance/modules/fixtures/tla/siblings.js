@@ -0,0 +1,5 @@
+import { order } from "./log.js";
+import "./after-slow.js";
+import "./sync.js";
+
+order.push("siblings");
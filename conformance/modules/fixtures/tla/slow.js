@@ -0,0 +1,5 @@
+import { order } from "./log.js";
+
+order.push("slow start");
+await new Promise((resolve) => setTimeout(resolve, 10));
+order.push("slow end");
@@ -0,0 +1,3 @@
+import { order } from "./log.js";
+
+order.push("sync");
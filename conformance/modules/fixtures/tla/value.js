@@ -0,0 +1 @@
+export const value = await new Promise((resolve) => setTimeout(() => resolve(42), 1));
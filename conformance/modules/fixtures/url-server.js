@@ -0,0 +1,20 @@
+// Serves the files in `remote/` for url-imports.js, and prints its port.
+import { readFileSync } from "node:fs";
+import { createServer } from "node:http";
+import path from "node:path";
+
+const root = path.join(import.meta.dirname, "remote");
+
+const server = createServer((request, response) => {
+    let body;
+    try {
+        body = readFileSync(path.join(root, path.basename(request.url)));
+    } catch {
+        response.writeHead(404);
+        response.end();
+        return;
+    }
+    response.writeHead(200, { "Content-Type": "text/javascript" });
+    response.end(body);
+});
+server.listen(0, "127.0.0.1", () => console.log(server.address().port));
@@ -0,0 +1,33 @@
+import { meta as imported } from "./fixtures/meta/imported.js";
+import { assert, test, done } from "../harness.js";
+
+const dirname = import.meta.dirname;
+
+await test("url, filename and dirname describe the module", () => {
+    assert.ok(import.meta.filename.endsWith("/conformance/modules/import-meta.js"));
+    assert.equal(dirname, import.meta.filename.slice(0, -"/import-meta.js".length));
+    assert.equal(import.meta.url, `file://${import.meta.filename}`);
+});
+
+await test("an imported module has its own", () => {
+    assert.equal(imported.filename, `${dirname}/fixtures/meta/imported.js`);
+    assert.equal(imported.dirname, `${dirname}/fixtures/meta`);
+    assert.equal(imported.url, `file://${dirname}/fixtures/meta/imported.js`);
+});
+
+await test("main is only true for the entry module", () => {
+    assert.equal(import.meta.main, true);
+    assert.equal(imported.main, false);
+});
+
+await test("resolve gives the URL of a module without loading it", () => {
+    assert.equal(import.meta.resolve("./fixtures/meta/side-effect.js"), `file://${dirname}/fixtures/meta/side-effect.js`);
+    assert.equal(imported.resolve("./side-effect.js"), `file://${dirname}/fixtures/meta/side-effect.js`);
+    assert.equal(globalThis.sideEffectRan, undefined);
+});
+
+await test("resolve keeps built-in specifiers", () => {
+    assert.equal(import.meta.resolve("node:path"), "node:path");
+});
+
+done("import-meta");
@@ -0,0 +1,52 @@
+import { assert, test, done } from "../harness.js";
+import { count, current, increment, reset } from "./fixtures/counter.js";
+import * as counter from "./fixtures/counter.js";
+import { count as again } from "./fixtures/reexport.js";
+
+await test("an imported let sees later assignments", () => {
+    assert.equal(count, 0);
+    increment();
+    increment();
+    assert.equal(count, 2);
+    assert.equal(counter.count, 2);
+});
+
+await test("renamed and re-exported bindings are live too", () => {
+    increment();
+    assert.equal(current, 3);
+    assert.equal(again, 3);
+});
+
+await test("inside functions, shorthands and templates", () => {
+    const read = () => count;
+    reset();
+    assert.equal(read(), 0);
+    increment();
+    assert.deepEqual({ count }, { count: 1 });
+    assert.equal(`${count}`, "1");
+});
+
+await test("a local declaration shadows the import", () => {
+    const shadowed = (count) => count * 10;
+    assert.equal(shadowed(5), 50);
+    {
+        const count = "inner";
+        assert.equal(count, "inner");
+    }
+    assert.equal(count, 1);
+});
+
+await test("imported functions are called without a this", () => {
+    assert.equal(typeof increment, "function");
+    increment();
+    assert.equal(count, 2);
+});
+
+await test("imported bindings can't be assigned", () => {
+    assert.throws(() => {
+        count = 5;
+    });
+    assert.equal(count, 2);
+});
+
+done("live-bindings");
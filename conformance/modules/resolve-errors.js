@@ -0,0 +1,35 @@
+import { assert, test, done } from "../harness.js";
+
+async function importError(specifier) {
+    try {
+        await import(specifier);
+    } catch (error) {
+        return error;
+    }
+    throw new Error(`expected importing ${specifier} to fail`);
+}
+
+await test("a missing module is a ResolveError with a suggestion", async () => {
+    const error = await importError("./fixtures/misspelled-import.js");
+    assert.ok(error instanceof ResolveError);
+    assert.equal(error.code, "ERR_MODULE_NOT_FOUND");
+    assert.equal(error.specifier, "./countr.js");
+    assert.equal(error.suggestion, "./counter.js");
+    assert.ok(error.message.includes("Did you mean './counter.js'?"));
+});
+
+await test("a missing export is a SyntaxError with a suggestion", async () => {
+    const error = await importError("./fixtures/misspelled-export.js");
+    assert.ok(error instanceof SyntaxError);
+    assert.equal(error.code, "ERR_MODULE_LINK");
+    assert.equal(error.suggestion, "count");
+});
+
+await test("the position is the one in the file before it was transformed", async () => {
+    const error = await importError("./fixtures/jsx-import.jsx");
+    assert.ok(error instanceof ResolveError);
+    assert.equal(error.line, 2);
+    assert.equal(error.column, 69);
+});
+
+done("resolve-errors");
@@ -0,0 +1,36 @@
+import { order } from "./fixtures/tla/log.js";
+import { assert, test, done } from "../harness.js";
+
+async function importError(specifier) {
+    try {
+        await import(specifier);
+    } catch (error) {
+        return error;
+    }
+    throw new Error(`expected importing ${specifier} to fail`);
+}
+
+await test("a sibling runs while another module awaits, its importers after", async () => {
+    await import("./fixtures/tla/siblings.js");
+    assert.deepEqual(order, ["slow start", "sync", "slow end", "after slow", "siblings"]);
+});
+
+await test("the value of a top-level await is there for importers", async () => {
+    const { value } = await import("./fixtures/tla/value.js");
+    assert.equal(value, 42);
+});
+
+await test("a rejected top-level await rejects the importers", async () => {
+    const error = await importError("./fixtures/tla/imports-rejects.js");
+    assert.equal(error.message, "rejected at the top level");
+    assert.equal(globalThis.importerRan, undefined);
+});
+
+await test("importing the rejected module again gives the same error", async () => {
+    const first = await importError("./fixtures/tla/rejects.js");
+    const second = await importError("./fixtures/tla/imports-rejects.js");
+    assert.equal(first.message, "rejected at the top level");
+    assert.equal(second, first);
+});
+
+done("top-level-await");
@@ -0,0 +1,109 @@
+import { execSync, spawnSync } from "node:child_process";
+import fs from "node:fs";
+import os from "node:os";
+import path from "node:path";
+import process from "node:process";
+import { assert, test, done } from "../harness.js";
+
+// The modules are served by fixtures/url-server.js, in the background. Each
+// import runs in a process of its own with a fresh project directory, so it
+// starts with nothing loaded, but they all share one download cache.
+const dir = fs.mkdtempSync(path.join(os.tmpdir(), "lunos-url-"));
+const cache = path.join(dir, "cache");
+const portFile = path.join(dir, "port");
+const serverScript = path.join(import.meta.dirname, "fixtures", "url-server.js");
+const server = execSync(`"${process.execPath}" "${serverScript}" > "${portFile}" 2>&1 & echo $!`, {
+    encoding: "utf8",
+}).trim();
+
+async function serverPort() {
+    for (let i = 0; i < 500; i++) {
+        const port = fs.readFileSync(portFile, "utf8").trim();
+        if (port) {
+            return port;
+        }
+        await new Promise((resolve) => setTimeout(resolve, 10));
+    }
+    throw new Error("the server didn't start");
+}
+
+const origin = `http://127.0.0.1:${await serverPort()}`;
+const lockFile = path.join(dir, "lunos.lock");
+
+// Imports `url` in a new process and returns what it printed: the greeting,
+// or the code and message of the error.
+function load(url, flags = []) {
+    const entry = path.join(dir, "main.js");
+    fs.writeFileSync(
+        entry,
+        `try {
+            console.log((await import(${JSON.stringify(url)})).greeting);
+        } catch (error) {
+            console.log(error.code + ": " + error.message);
+        }`,
+    );
+    const result = spawnSync(process.execPath, [...flags, entry], {
+        cwd: dir,
+        encoding: "utf8",
+        env: { ...process.env, LUNOS_CACHE_DIR: cache },
+    });
+    assert.equal(result.status, 0);
+    return result.stdout.trim();
+}
+
+await test("a URL import is downloaded and locked", () => {
+    assert.equal(load(`${origin}/greet.js`), "hello remote");
+    const lock = JSON.parse(fs.readFileSync(lockFile, "utf8"));
+    assert.deepEqual(Object.keys(lock.remote), [`${origin}/greet.js`, `${origin}/name.js`]);
+    assert.ok(Object.values(lock.remote).every((hash) => /^sha256-[0-9a-f]{64}$/.test(hash)));
+});
+
+await test("a bare specifier in a URL module doesn't resolve to local packages", () => {
+    const packageDir = path.join(dir, "node_modules", "local-package");
+    fs.mkdirSync(packageDir, { recursive: true });
+    fs.writeFileSync(path.join(packageDir, "index.js"), 'export const greeting = "hello local";');
+    const output = load(`${origin}/bare.js`);
+    assert.ok(output.startsWith("ERR_MODULE_NOT_FOUND: "), output);
+    assert.ok(output.includes("'local-package'"), output);
+});
+
+await test("nor do the package imports of the working directory", () => {
+    const packageJson = path.join(dir, "package.json");
+    fs.writeFileSync(packageJson, JSON.stringify({ imports: { "#local": "./node_modules/local-package/index.js" } }));
+    const output = load(`${origin}/package-imports.js`);
+    fs.rmSync(packageJson);
+    assert.ok(output.startsWith("ERR_MODULE_NOT_FOUND: "), output);
+    assert.ok(output.includes("'#local'"), output);
+});
+
+await test("the import map can point a URL module at a local one", () => {
+    const config = path.join(dir, "lunos.json");
+    fs.writeFileSync(config, JSON.stringify({ imports: { "local-package": "./node_modules/local-package/index.js" } }));
+    assert.equal(load(`${origin}/bare.js`), "hello local");
+    fs.rmSync(config);
+});
+
+await test("once downloaded, it's loaded from the cache", () => {
+    execSync(`kill ${server}`);
+    assert.equal(load(`${origin}/greet.js`), "hello remote");
+    assert.equal(load(`${origin}/greet.js`, ["--offline"]), "hello remote");
+});
+
+await test("--offline only loads from the cache", () => {
+    const output = load(`${origin}/missing.js`, ["--offline"]);
+    assert.ok(output.startsWith("ERR_MODULE_NOT_FOUND: "), output);
+    assert.ok(output.includes("it isn't in the cache and --offline was given"), output);
+});
+
+await test("a module that doesn't match its lock fails to load", () => {
+    const lock = JSON.parse(fs.readFileSync(lockFile, "utf8"));
+    lock.remote[`${origin}/name.js`] = `sha256-${"0".repeat(64)}`;
+    fs.writeFileSync(lockFile, JSON.stringify(lock));
+    const output = load(`${origin}/greet.js`);
+    assert.ok(output.startsWith("ERR_MODULE_NOT_FOUND: "), output);
+    assert.ok(output.includes("its integrity check failed"), output);
+});
+
+fs.rmSync(dir, { recursive: true });
+
+done("url-imports");
@@ -0,0 +1,26 @@
+import { Buffer } from "node:buffer";
+import { assert, test, done } from "../harness.js";
+
+await test("from and toString", () => {
+    assert.equal(Buffer.from("héllo").length, 6);
+    assert.equal(Buffer.from("héllo").toString(), "héllo");
+    assert.equal(Buffer.from("hi").toString("hex"), "6869");
+    assert.equal(Buffer.from("6869", "hex").toString(), "hi");
+    assert.equal(Buffer.from("hello").toString("base64"), "aGVsbG8=");
+    assert.equal(Buffer.from("aGVsbG8=", "base64").toString(), "hello");
+    assert.equal(Buffer.from([104, 105]).toString("latin1"), "hi");
+});
+await test("alloc, concat and equals", () => {
+    assert.deepEqual([...Buffer.alloc(3, 1)], [1, 1, 1]);
+    const joined = Buffer.concat([Buffer.from("a"), Buffer.from("bc")]);
+    assert.equal(joined.toString(), "abc");
+    assert.ok(joined.equals(Buffer.from("abc")));
+    assert.ok(Buffer.isBuffer(joined));
+    assert.ok(!Buffer.isBuffer(new Uint8Array(1)));
+    assert.equal(Buffer.byteLength("héllo"), 6);
+});
+await test("toJSON", () => {
+    assert.deepEqual(Buffer.from("hi").toJSON(), { type: "Buffer", data: [104, 105] });
+});
+
+done("buffer");
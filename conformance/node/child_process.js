@@ -0,0 +1,44 @@
+import { execSync, execFileSync, spawnSync, exec, spawn } from "node:child_process";
+import { assert, test, done } from "../harness.js";
+
+await test("execSync", () => {
+    assert.equal(execSync("echo hi").toString(), "hi\n");
+    assert.equal(execSync("echo $0 | wc -c", { encoding: "utf8" }).trim().length > 0, true);
+    assert.equal(execSync("cat", { input: "piped" }).toString(), "piped");
+    let error;
+    try {
+        execSync("echo err >&2; exit 3", { stdio: "pipe" });
+    } catch (e) {
+        error = e;
+    }
+    assert.equal(error.status, 3);
+    assert.equal(error.stderr.toString(), "err\n");
+});
+await test("execFileSync and spawnSync", () => {
+    assert.equal(execFileSync("printf", ["%s-%s", "a", "b"], { encoding: "utf8" }), "a-b");
+    const result = spawnSync("sh", ["-c", "echo out; exit 2"], { encoding: "utf8" });
+    assert.equal(result.status, 2);
+    assert.equal(result.stdout, "out\n");
+    assert.equal(result.signal, null);
+    assert.equal(spawnSync("pwd", { cwd: "/", encoding: "utf8" }).stdout, "/\n");
+    assert.equal(spawnSync("sh", ["-c", "echo $X"], { env: { X: "1" }, encoding: "utf8" }).stdout, "1\n");
+});
+await test("missing commands", () => {
+    const result = spawnSync("lunos-no-such-command");
+    assert.equal(result.error.code, "ENOENT");
+});
+await test("exec", async () => {
+    const [error, stdout] = await new Promise((resolve) => exec("echo hi", (...args) => resolve(args)));
+    assert.equal(error, null);
+    assert.equal(stdout, "hi\n");
+});
+await test("spawn", async () => {
+    const child = spawn("sh", ["-c", "echo out"]);
+    let output = "";
+    child.stdout.on("data", (chunk) => (output += chunk));
+    const code = await new Promise((resolve) => child.on("close", resolve));
+    assert.equal(code, 0);
+    assert.equal(output, "out\n");
+});
+
+done("child_process");
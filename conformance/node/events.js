@@ -0,0 +1,77 @@
+import EventEmitter, { once } from "node:events";
+import { EventEmitter as Named } from "events";
+import { assert, test, done } from "../harness.js";
+
+await test("exports", () => {
+    assert.equal(Named, EventEmitter);
+    assert.equal(EventEmitter.EventEmitter, EventEmitter);
+});
+await test("on, emit and off", () => {
+    const emitter = new EventEmitter();
+    const seen = [];
+    const listener = (a, b) => seen.push(a + b);
+    emitter.on("x", listener);
+    assert.equal(emitter.emit("x", 1, 2), true);
+    emitter.off("x", listener);
+    assert.equal(emitter.emit("x", 1, 2), false);
+    assert.deepEqual(seen, [3]);
+});
+await test("once and prependListener", () => {
+    const emitter = new EventEmitter();
+    const seen = [];
+    emitter.on("x", () => seen.push("on"));
+    emitter.once("x", () => seen.push("once"));
+    emitter.prependListener("x", () => seen.push("first"));
+    emitter.emit("x");
+    emitter.emit("x");
+    assert.deepEqual(seen, ["first", "on", "once", "first", "on"]);
+    assert.equal(emitter.listenerCount("x"), 2);
+});
+await test("unhandled error events throw", () => {
+    const emitter = new EventEmitter();
+    let thrown;
+    try {
+        emitter.emit("error", new Error("boom"));
+    } catch (error) {
+        thrown = error;
+    }
+    assert.equal(thrown.message, "boom");
+    emitter.on("error", () => {});
+    assert.equal(emitter.emit("error", new Error("boom")), true);
+});
+await test("eventNames, listeners and removeAllListeners", () => {
+    const emitter = new EventEmitter();
+    const listener = () => {};
+    emitter.on("a", listener);
+    emitter.on("b", listener);
+    assert.deepEqual(emitter.eventNames(), ["a", "b"]);
+    assert.deepEqual(emitter.listeners("a"), [listener]);
+    emitter.removeAllListeners("a");
+    assert.deepEqual(emitter.eventNames(), ["b"]);
+    emitter.removeAllListeners();
+    assert.deepEqual(emitter.eventNames(), []);
+});
+await test("subclassing", () => {
+    class Child extends EventEmitter {}
+    const child = new Child();
+    let value;
+    child.on("v", (v) => (value = v));
+    child.emit("v", 7);
+    assert.equal(value, 7);
+
+    function Legacy() {
+        EventEmitter.call(this);
+    }
+    Object.setPrototypeOf(Legacy.prototype, EventEmitter.prototype);
+    const legacy = new Legacy();
+    legacy.on("v", (v) => (value = v));
+    legacy.emit("v", 8);
+    assert.equal(value, 8);
+});
+await test("events.once", async () => {
+    const emitter = new EventEmitter();
+    setTimeout(() => emitter.emit("ready", 1, 2), 0);
+    assert.deepEqual(await once(emitter, "ready"), [1, 2]);
+});
+
+done("events");
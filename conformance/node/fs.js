@@ -0,0 +1,96 @@
+import fs from "node:fs";
+import { readFileSync, writeFileSync, promises } from "fs";
+import * as fsp from "node:fs/promises";
+import os from "node:os";
+import path from "node:path";
+import { assert, test, done } from "../harness.js";
+
+const dir = fs.mkdtempSync(path.join(os.tmpdir(), "lunos-fs-"));
+const file = path.join(dir, "a.txt");
+
+await test("writeFileSync and readFileSync", () => {
+    writeFileSync(file, "héllo");
+    assert.equal(readFileSync(file, "utf8"), "héllo");
+    assert.equal(readFileSync(file, { encoding: "utf8" }), "héllo");
+    assert.equal(readFileSync(file).length, 6);
+    fs.appendFileSync(file, "!");
+    assert.equal(readFileSync(file, "utf8"), "héllo!");
+});
+await test("existsSync, statSync and lstatSync", () => {
+    assert.ok(fs.existsSync(file));
+    assert.ok(!fs.existsSync(path.join(dir, "missing")));
+    const stats = fs.statSync(file);
+    assert.ok(stats.isFile());
+    assert.ok(!stats.isDirectory());
+    assert.equal(stats.size, 7);
+    assert.ok(stats.mtime instanceof Date);
+    assert.ok(fs.lstatSync(dir).isDirectory());
+    assert.equal(fs.statSync(path.join(dir, "missing"), { throwIfNoEntry: false }), undefined);
+});
+await test("errors carry code, syscall and path", () => {
+    assert.throws(() => readFileSync(path.join(dir, "missing")), "ENOENT");
+    try {
+        readFileSync(path.join(dir, "missing"));
+    } catch (error) {
+        assert.ok(error instanceof Error);
+        assert.equal(error.syscall, "open");
+        assert.equal(error.path, path.join(dir, "missing"));
+        assert.ok(error.errno < 0);
+    }
+    assert.throws(() => fs.mkdirSync(dir), "EEXIST");
+    assert.throws(() => fs.rmSync(dir), "ERR_FS_EISDIR");
+    assert.throws(() => process.chdir(path.join(dir, "missing")), "ENOENT");
+});
+await test("invalid arguments are TypeErrors", () => {
+    assert.throws(() => fs.readFileSync({}), "ERR_INVALID_ARG_TYPE");
+    try {
+        fs.readFileSync({});
+    } catch (error) {
+        assert.ok(error instanceof TypeError);
+    }
+});
+await test("mkdirSync, readdirSync and rmSync", () => {
+    const nested = path.join(dir, "x", "y");
+    assert.equal(fs.mkdirSync(nested, { recursive: true }), path.join(dir, "x"));
+    writeFileSync(path.join(nested, "z.txt"), "");
+    assert.deepEqual(fs.readdirSync(dir), ["a.txt", "x"]);
+    const entries = fs.readdirSync(dir, { withFileTypes: true });
+    assert.ok(entries[1].isDirectory());
+    assert.equal(entries[0].name, "a.txt");
+    assert.deepEqual(fs.readdirSync(path.join(dir, "x"), { recursive: true }), ["y", "y/z.txt"]);
+    fs.rmSync(path.join(dir, "x"), { recursive: true });
+    assert.ok(!fs.existsSync(path.join(dir, "x")));
+});
+await test("copyFileSync, renameSync, unlinkSync and realpathSync", () => {
+    const copy = path.join(dir, "b.txt");
+    fs.copyFileSync(file, copy);
+    assert.throws(() => fs.copyFileSync(file, copy, fs.constants.COPYFILE_EXCL), "EEXIST");
+    fs.renameSync(copy, path.join(dir, "c.txt"));
+    assert.equal(fs.realpathSync(path.join(dir, ".", "c.txt")), fs.realpathSync(path.join(dir, "c.txt")));
+    fs.unlinkSync(path.join(dir, "c.txt"));
+    assert.deepEqual(fs.readdirSync(dir), ["a.txt"]);
+});
+await test("callback API", async () => {
+    const data = await new Promise((resolve, reject) => {
+        fs.readFile(file, "utf8", (error, text) => (error ? reject(error) : resolve(text)));
+    });
+    assert.equal(data, "héllo!");
+    const error = await new Promise((resolve) => fs.stat(path.join(dir, "missing"), resolve));
+    assert.equal(error.code, "ENOENT");
+    assert.equal(error.errno, -2);
+    assert.equal(error.syscall, "stat");
+});
+await test("promise API", async () => {
+    await promises.writeFile(path.join(dir, "p.txt"), "p");
+    assert.equal(await fsp.readFile(path.join(dir, "p.txt"), "utf8"), "p");
+    assert.ok((await fsp.stat(dir)).isDirectory());
+    assert.deepEqual(await fsp.readdir(dir), ["a.txt", "p.txt"]);
+    await fsp.unlink(path.join(dir, "p.txt"));
+    let code;
+    await fsp.access(path.join(dir, "p.txt")).catch((error) => (code = error.code));
+    assert.equal(code, "ENOENT");
+    assert.equal(fsp.default, promises);
+});
+
+fs.rmSync(dir, { recursive: true, force: true });
+done("fs");
@@ -0,0 +1,30 @@
+import http, { createServer, STATUS_CODES } from "node:http";
+import { assert, test, done } from "../harness.js";
+
+await test("exports", () => {
+    assert.equal(http.createServer, createServer);
+    assert.equal(STATUS_CODES[404], "Not Found");
+    assert.ok(http.METHODS.includes("GET"));
+});
+await test("listen, address and close", async () => {
+    const server = createServer(() => {});
+    await new Promise((resolve) => server.listen(0, "127.0.0.1", resolve));
+    const { port, family } = server.address();
+    assert.ok(port > 0);
+    assert.equal(family, "IPv4");
+    await new Promise((resolve) => server.close(resolve));
+    assert.equal(server.listening, false);
+});
+await test("listening on a port in use fails", async () => {
+    const first = createServer();
+    await new Promise((resolve) => first.listen(0, "127.0.0.1", resolve));
+    const second = createServer();
+    const error = await new Promise((resolve) => {
+        second.on("error", resolve);
+        second.listen(first.address().port, "127.0.0.1");
+    });
+    assert.equal(error.code, "EADDRINUSE");
+    await new Promise((resolve) => first.close(resolve));
+});
+
+done("http");
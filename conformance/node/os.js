@@ -0,0 +1,35 @@
+import os from "node:os";
+import { EOL, platform } from "os";
+import process from "node:process";
+import { assert, test, done } from "../harness.js";
+
+await test("constants", () => {
+    assert.equal(EOL, "\n");
+    assert.equal(os.devNull, "/dev/null");
+    assert.ok(["LE", "BE"].includes(os.endianness()));
+});
+await test("system information", () => {
+    assert.equal(typeof platform(), "string");
+    assert.equal(os.platform(), process.platform);
+    assert.equal(os.arch(), process.arch);
+    assert.equal(typeof os.release(), "string");
+    assert.ok(os.hostname().length > 0);
+    assert.ok(os.homedir().startsWith("/"));
+    assert.ok(os.tmpdir().startsWith("/"));
+    assert.ok(["Linux", "Darwin", "FreeBSD"].includes(os.type()));
+});
+await test("resources", () => {
+    assert.ok(os.cpus().length > 0);
+    assert.equal(typeof os.cpus()[0].model, "string");
+    assert.ok(os.availableParallelism() > 0);
+    assert.ok(os.totalmem() >= os.freemem());
+    assert.ok(os.uptime() > 0);
+    assert.equal(os.loadavg().length, 3);
+});
+await test("userInfo", () => {
+    const info = os.userInfo();
+    assert.equal(info.homedir, os.homedir());
+    assert.equal(typeof info.username, "string");
+});
+
+done("os");
@@ -0,0 +1,46 @@
+import path from "node:path";
+import { join, posix } from "path";
+import process from "node:process";
+import { assert, test, done } from "../harness.js";
+
+await test("join", () => {
+    assert.equal(path.join("/a", "b", "../c", "./d"), "/a/c/d");
+    assert.equal(join("a", "", "b/"), "a/b/");
+    assert.equal(path.join(), ".");
+});
+await test("normalize", () => {
+    assert.equal(path.normalize("/a//b/../c/."), "/a/c");
+    assert.equal(path.normalize("../a/./b"), "../a/b");
+    assert.equal(path.normalize(""), ".");
+});
+await test("resolve", () => {
+    assert.equal(path.resolve("/a/b", "../c"), "/a/c");
+    assert.equal(path.resolve("/a", "/b", "c"), "/b/c");
+    assert.equal(path.resolve("x"), path.join(process.cwd(), "x"));
+});
+await test("relative", () => {
+    assert.equal(path.relative("/a/b/c", "/a/d"), "../../d");
+    assert.equal(path.relative("/a", "/a"), "");
+    assert.equal(path.relative("/a", "/a/b/c"), "b/c");
+});
+await test("dirname, basename and extname", () => {
+    assert.equal(path.dirname("/a/b/c.txt"), "/a/b");
+    assert.equal(path.dirname("c.txt"), ".");
+    assert.equal(path.basename("/a/b/c.txt"), "c.txt");
+    assert.equal(path.basename("/a/b/c.txt", ".txt"), "c");
+    assert.equal(path.extname("archive.tar.gz"), ".gz");
+    assert.equal(path.extname(".bashrc"), "");
+});
+await test("parse and format", () => {
+    assert.deepEqual(path.parse("/home/u/file.txt"), { root: "/", dir: "/home/u", base: "file.txt", ext: ".txt", name: "file" });
+    assert.equal(path.format({ dir: "/home/u", name: "file", ext: ".txt" }), "/home/u/file.txt");
+});
+await test("isAbsolute, sep and delimiter", () => {
+    assert.ok(path.isAbsolute("/a"));
+    assert.ok(!path.isAbsolute("a"));
+    assert.equal(path.sep, "/");
+    assert.equal(path.delimiter, ":");
+    assert.equal(posix.join("a", "b"), "a/b");
+});
+
+done("path");
@@ -0,0 +1,47 @@
+import util from "node:util";
+import { format, inspect, promisify } from "util";
+import { assert, test, done } from "../harness.js";
+
+await test("format", () => {
+    assert.equal(format("%s:%d:%i", "a", 4.5, 4.5), "a:4.5:4");
+    assert.equal(format("%j", { a: 1 }), '{"a":1}');
+    assert.equal(format("%% %s", "x", "extra"), "% x extra");
+    assert.equal(format(1, "a"), "1 a");
+});
+await test("inspect", () => {
+    assert.equal(inspect("a"), "'a'");
+    assert.equal(inspect({ a: [1, { b: "c" }] }), "{ a: [ 1, { b: 'c' } ] }");
+    assert.equal(inspect(null), "null");
+    assert.equal(inspect([]), "[]");
+    assert.equal(inspect({}), "{}");
+    const custom = { [inspect.custom]: () => "custom" };
+    assert.equal(inspect(custom), "custom");
+});
+await test("promisify and callbackify", async () => {
+    const add = (a, b, callback) => setTimeout(() => callback(null, a + b), 0);
+    assert.equal(await promisify(add)(1, 2), 3);
+    const fail = (callback) => callback(new Error("no"));
+    let message;
+    await promisify(fail)().catch((error) => (message = error.message));
+    assert.equal(message, "no");
+    const result = await new Promise((resolve) => util.callbackify(async (x) => x * 2)(4, (error, value) => resolve(value)));
+    assert.equal(result, 8);
+});
+await test("inherits", () => {
+    function Base() {}
+    Base.prototype.hello = () => "hello";
+    function Child() {}
+    util.inherits(Child, Base);
+    assert.equal(new Child().hello(), "hello");
+    assert.equal(Child.super_, Base);
+});
+await test("isDeepStrictEqual and types", () => {
+    assert.ok(util.isDeepStrictEqual({ a: [1, 2] }, { a: [1, 2] }));
+    assert.ok(!util.isDeepStrictEqual({ a: 1 }, { a: "1" }));
+    assert.ok(util.types.isPromise(Promise.resolve()));
+    assert.ok(util.types.isDate(new Date()));
+    assert.ok(util.types.isRegExp(/a/));
+    assert.ok(!util.types.isDate({}));
+});
+
+done("util");
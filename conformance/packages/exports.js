@@ -0,0 +1,68 @@
+import sugar from "sugar";
+import { format } from "conditional";
+import { feature } from "conditional/feature";
+import { name as a } from "conditional/lib/a";
+import { name as scoped } from "@scope/pkg";
+import { name as scopedSub } from "@scope/pkg/sub";
+import requiredFormat from "./require-conditional.cjs";
+import { where as internal } from "#internal";
+import { where as utils } from "#utils/format";
+import { where as conditionalImport } from "#conditional";
+import sugarImport from "#sugar";
+import { where as self } from "packages-suite/self";
+import { assert, test, done } from "../harness.js";
+
+async function importError(specifier) {
+    try {
+        await import(specifier);
+    } catch (error) {
+        return error;
+    }
+    throw new Error(`expected importing ${specifier} to fail`);
+}
+
+await test("a string exports is sugar for the main entry", () => {
+    assert.equal(sugar, "sugar");
+});
+
+await test("exports conditions pick import or require", () => {
+    assert.equal(format, "esm");
+    assert.equal(requiredFormat, "cjs");
+});
+
+await test("conditions that don't apply are skipped for default", () => {
+    assert.equal(feature, "default");
+});
+
+await test("subpath patterns", () => {
+    assert.equal(a, "a");
+});
+
+// Node has a code of its own for these
+const notExported = ["ERR_MODULE_NOT_FOUND", "ERR_PACKAGE_PATH_NOT_EXPORTED"];
+
+await test("subpaths that aren't exported can't be imported", async () => {
+    const unexported = await importError("sugar/other.js");
+    assert.ok(notExported.includes(unexported.code), unexported.code);
+    // `null` blocks what a less specific pattern would export
+    const blocked = await importError("conditional/lib/private/secret");
+    assert.ok(notExported.includes(blocked.code), blocked.code);
+});
+
+await test("scoped packages", () => {
+    assert.equal(scoped, "@scope/pkg");
+    assert.equal(scopedSub, "@scope/pkg/sub");
+});
+
+await test("# imports of the package", () => {
+    assert.equal(internal, "internal");
+    assert.equal(utils, "utils/format");
+    assert.equal(conditionalImport, "internal");
+    assert.equal(sugarImport, "sugar");
+});
+
+await test("a package imports itself by name through its exports", () => {
+    assert.equal(self, "self");
+});
+
+done("packages");
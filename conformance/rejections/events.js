@@ -0,0 +1,106 @@
+import process from "node:process";
+import { assert, test, done } from "../harness.js";
+
+// rejections are reported at the end of an event loop turn
+const turn = () => new Promise((resolve) => setTimeout(resolve, 10));
+
+function listen(type, listener) {
+    addEventListener(type, listener);
+    return () => removeEventListener(type, listener);
+}
+
+await test("unhandledrejection", async () => {
+    const seen = [];
+    const stop = listen("unhandledrejection", (event) => {
+        assert.ok(event instanceof PromiseRejectionEvent);
+        assert.equal(event.cancelable, true);
+        seen.push([event.promise, event.reason]);
+        event.preventDefault();
+    });
+    const error = new Error("nobody handles this");
+    const promise = Promise.reject(error);
+    await turn();
+    stop();
+    assert.deepEqual(seen, [[promise, error]]);
+});
+
+await test("handled rejections fire nothing", async () => {
+    const seen = [];
+    const stop = listen("unhandledrejection", (event) => {
+        seen.push(event.reason);
+        event.preventDefault();
+    });
+    Promise.reject(new Error("caught")).catch(() => {});
+    await Promise.reject(new Error("awaited")).catch(() => {});
+    try {
+        await Promise.reject(new Error("try"));
+    } catch {}
+    await turn();
+    stop();
+    assert.deepEqual(seen, []);
+});
+
+await test("rejectionhandled", async () => {
+    const handled = [];
+    const stopUnhandled = listen("unhandledrejection", (event) => event.preventDefault());
+    const stopHandled = listen("rejectionhandled", (event) => handled.push([event.promise, event.reason]));
+    const error = new Error("handled late");
+    const promise = Promise.reject(error);
+    await turn();
+    assert.deepEqual(handled, []);
+    promise.catch(() => {});
+    await turn();
+    stopUnhandled();
+    stopHandled();
+    assert.deepEqual(handled, [[promise, error]]);
+});
+
+await test("rejectionhandled through await", async () => {
+    const handled = [];
+    const stopUnhandled = listen("unhandledrejection", (event) => event.preventDefault());
+    const stopHandled = listen("rejectionhandled", (event) => handled.push([event.promise, event.reason]));
+    const error = new Error("awaited late");
+    const promise = Promise.reject(error);
+    await turn();
+    try {
+        await promise;
+    } catch {}
+    await turn();
+    stopUnhandled();
+    stopHandled();
+    assert.deepEqual(handled, [[promise, error]]);
+});
+
+await test("Promise.resolve and looking at the promise don't handle it", async () => {
+    const handled = [];
+    const stopUnhandled = listen("unhandledrejection", (event) => event.preventDefault());
+    const stopHandled = listen("rejectionhandled", (event) => handled.push(event.promise));
+    const promise = Promise.reject(new Error("still unhandled"));
+    await turn();
+    assert.equal(Promise.resolve(promise), promise);
+    assert.deepEqual(Object.getOwnPropertyNames(promise), []);
+    assert.ok(promise instanceof Promise);
+    await turn();
+    assert.deepEqual(handled, []);
+    promise.catch(() => {});
+    await turn();
+    stopUnhandled();
+    stopHandled();
+    assert.deepEqual(handled, [promise]);
+});
+
+await test("Promise.prototype is left alone", () => {
+    assert.ok(String(Promise.prototype.then).includes("[native code]"));
+});
+
+await test("process unhandledRejection", async () => {
+    const seen = [];
+    const listener = (reason, promise) => seen.push([promise, reason]);
+    process.on("unhandledRejection", listener);
+    const promise = Promise.reject("reason");
+    await turn();
+    process.off("unhandledRejection", listener);
+    assert.deepEqual(seen, [[promise, "reason"]]);
+});
+
+done("rejection events");
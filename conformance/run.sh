@@ -0,0 +1,50 @@
+#!/bin/sh
+# Runs the conformance suites, a directory each with one file per topic.
+#   conformance/run.sh                      every suite
+#   conformance/run.sh typescript           one suite
+#   conformance/run.sh node/fs node/path    only these files
+# LUNOS picks the binary (default: target/release/lunos, else `lunos`);
+# LUNOS=node runs the node suite under Node to check the tests themselves.
+cd "$(dirname "$0")" || exit 1
+
+if [ -z "$LUNOS" ]; then
+    if [ -x ../target/release/lunos ]; then
+        LUNOS=../target/release/lunos
+    else
+        LUNOS=lunos
+    fi
+fi
+
+if [ $# -eq 0 ]; then
+    set -- */
+fi
+
+files=""
+for name in "$@"; do
+    name=${name%/}
+    if [ -d "$name" ]; then
+        pattern="$name/*.js $name/*.jsx $name/*.ts $name/*.tsx"
+    else
+        pattern="$name.js $name.jsx $name.ts $name.tsx"
+    fi
+    for file in $pattern; do
+        if [ -f "$file" ]; then
+            files="$files $file"
+        fi
+    done
+done
+
+failed=""
+for file in $files; do
+    test=${file%.*}
+    echo "$test"
+    if ! "$LUNOS" "$file"; then
+        failed="$failed $test"
+    fi
+done
+
+if [ -n "$failed" ]; then
+    echo "failed:$failed"
+    exit 1
+fi
+echo "all passed"
@@ -0,0 +1,58 @@
+import { assert, test, done } from "../harness.js";
+
+// useDefineForClassFields is off in the config this suite extends, so
+// fields are assignments in the constructor, as tsc emits them
+
+const log: string[] = [];
+
+class Base {
+    set value(v: number) {
+        log.push(`set ${v}`);
+    }
+}
+
+class Derived extends Base {
+    value: number = 1;
+    declared!: string;
+    #secret = 2;
+    static counter = 0;
+
+    constructor(public id: number) {
+        super();
+        log.push(`constructor sees ${this.id}`);
+    }
+
+    secret() {
+        return this.#secret;
+    }
+}
+
+class Plain {
+    items = [1, 2].map((n: number): number => n * 10);
+    "quoted key" = true;
+    untouched;
+    handler = () => {
+        return this.items.length
+    };
+}
+
+await test("initializers assign, so base class setters run", () => {
+    log.length = 0;
+    const d = new Derived(7);
+    assert.deepEqual(log, ["set 1", "constructor sees 7"]);
+    assert.equal(d.secret(), 2);
+    assert.equal(Derived.counter, 0);
+});
+
+await test("parameter properties come before field initializers", () => {
+    assert.deepEqual(Object.keys(new Derived(1)), ["id"]);
+});
+
+await test("fields without an initializer are left out", () => {
+    const plain = new Plain();
+    assert.deepEqual(Object.keys(plain), ["items", "quoted key", "handler"]);
+    assert.deepEqual(plain.items, [10, 20]);
+    assert.equal(plain.handler(), 2);
+});
+
+done("class-fields");
@@ -0,0 +1,25 @@
+import { assert, test, done } from "../harness.js";
+import { greeting } from "@lib/greeting.js";
+import { double } from "@lib/math.js";
+import { greeting as exact } from "exact-alias";
+import { top } from "top.js";
+
+await test("paths patterns map to files under baseUrl", () => {
+    assert.equal(greeting, "hello");
+    assert.equal(double(2), 4);
+});
+
+await test("exact paths entries", () => {
+    assert.equal(exact, "hello");
+});
+
+await test("bare specifiers resolve under baseUrl", () => {
+    assert.equal(top, "from baseUrl");
+});
+
+await test("paths apply to dynamic imports", async () => {
+    const math = await import("@lib/math.js");
+    assert.equal(math.double(5), 10);
+});
+
+done("paths");
@@ -0,0 +1 @@
+export const top = "from baseUrl";
@@ -0,0 +1,98 @@
+import { assert, test, done } from "../harness.js";
+
+await test("variables", () => {
+    let a: number = 1, b: string[] = ["x"], c!: boolean;
+    const d: { x: number; y?: string } = { x: 2 };
+    var e: Array<Array<number>> = [[3]];
+    c = true;
+    assert.deepEqual([a, b, c, d, e], [1, ["x"], true, { x: 2 }, [[3]]]);
+});
+
+await test("parameters and return types", () => {
+    function add(x: number, y: number = 2): number {
+        return x + y;
+    }
+    function pad(text: string, width?: number, ...fill: string[]): string {
+        return text.padEnd(width ?? 0, fill.join("") || " ");
+    }
+    assert.equal(add(1), 3);
+    assert.equal(pad("a", 3, "-"), "a--");
+    assert.equal(pad("a"), "a");
+});
+
+await test("destructured parameters", () => {
+    const area = ({ w, h }: { w: number; h: number }, [scale]: [number] = [1]): number => w * h * scale;
+    assert.equal(area({ w: 2, h: 3 }), 6);
+    assert.equal(area({ w: 2, h: 3 }, [2]), 12);
+});
+
+await test("arrow functions", () => {
+    const square = (x: number): number => x * x;
+    const pair = (a?: string, b?: string): [string?, string?] => [a, b];
+    const point = (): { x: number } => ({ x: 1 });
+    assert.equal(square(3), 9);
+    assert.deepEqual(pair("a"), ["a", undefined]);
+    assert.deepEqual(point(), { x: 1 });
+});
+
+await test("this parameter", () => {
+    function name(this: { name: string }, suffix: string): string {
+        return this.name + suffix;
+    }
+    function bare(this: Window) {
+        return arguments.length;
+    }
+    assert.equal(name.call({ name: "a" }, "!"), "a!");
+    assert.equal(name.length, 1);
+    assert.equal(bare(), 0);
+});
+
+await test("type predicates", () => {
+    function isString(value: unknown): value is string {
+        return typeof value === "string";
+    }
+    function check(value: unknown): asserts value {
+        if (!value) throw new Error("falsy");
+    }
+    assert.equal(isString("a"), true);
+    check(1);
+    assert.throws(() => check(0));
+});
+
+await test("non-null assertions", () => {
+    const map = new Map<string, number[]>([["a", [1, 2]]]);
+    const first = map.get("a")![0]!;
+    const list: number[] | undefined = map.get("a");
+    let last: number | undefined;
+    last! = list!.length;
+    assert.equal(first, 1);
+    assert.equal(last, 2);
+    assert.equal(!first, false);
+});
+
+await test("optional methods and catch clauses", () => {
+    const object = {
+        greet(name: string): string {
+            return `hi ${name}`;
+        },
+        get size(): number {
+            return 1;
+        },
+    };
+    let caught: unknown;
+    try {
+        throw new Error("x");
+    } catch (error: unknown) {
+        caught = (error as Error).message;
+    }
+    assert.equal(object.greet("a"), "hi a");
+    assert.equal(object.size, 1);
+    assert.equal(caught, "x");
+});
+
+await test("types are blanked in place", () => {
+    function f(a: number, b?: string): void {}
+    assert.equal(f.toString(), "function f(a        , b         )       {}");
+});
+
+done("annotations");
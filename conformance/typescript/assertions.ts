@@ -0,0 +1,52 @@
+import { assert, test, done } from "../harness.js";
+
+await test("as", () => {
+    const value: unknown = "text";
+    const length = (value as string).length;
+    const twice = value as unknown as string;
+    const items = [1, 2] as number[];
+    assert.equal(length, 4);
+    assert.equal(twice, "text");
+    assert.deepEqual(items, [1, 2]);
+});
+
+await test("as const", () => {
+    const tuple = [1, "a"] as const;
+    const config = { mode: "fast" } as const;
+    assert.deepEqual(tuple, [1, "a"]);
+    assert.equal(config.mode, "fast");
+});
+
+await test("satisfies", () => {
+    const palette = { red: [255, 0, 0] } satisfies Record<string, number[]>;
+    const value = 1 satisfies number;
+    assert.deepEqual(palette.red, [255, 0, 0]);
+    assert.equal(value, 1);
+});
+
+await test("angle bracket assertions", () => {
+    const value: any = "12";
+    const parsed = parseInt(<string>value, 10);
+    const nested = <number[]>(<unknown>[1]);
+    assert.equal(parsed, 12);
+    assert.deepEqual(nested, [1]);
+});
+
+await test("names that are only keywords in types", () => {
+    const as = 1, satisfies = 2, type = 3, declare = 4, abstract = 5;
+    const object = { as, type: "t", interface: "i", declare, abstract };
+    assert.equal(as + satisfies + type, 6);
+    assert.equal(object.type + object.interface, "ti");
+    assert.equal(object.declare + object.abstract, 9);
+});
+
+await test("in expressions", () => {
+    const values = [1, 2, 3].map((n) => n as number * 2);
+    const total = values.reduce((sum: number, n: number) => sum + n, 0 as number);
+    const tag = (strings: TemplateStringsArray, ...rest: unknown[]) => strings.raw.join("|") + rest.length;
+    assert.deepEqual(values, [2, 4, 6]);
+    assert.equal(total, 12);
+    assert.equal(tag<unknown>`a${1}b`, "a|b1");
+});
+
+done("assertions");
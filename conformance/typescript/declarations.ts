@@ -0,0 +1,122 @@
+import { assert, test, done } from "../harness.js";
+
+interface Point {
+    x: number;
+    y: number;
+}
+
+export interface Point3 extends Point {
+    z: number;
+}
+
+type Maybe<T> =
+    | T
+    | null;
+
+export type Handler = (event: { type: string }) => void;
+
+declare const injected: string;
+declare function external(value: number): string;
+declare class Native {
+    method(): void;
+}
+declare module "virtual" {
+    export const value: number;
+}
+declare global {
+    interface Array<T> {
+        custom?: T;
+    }
+}
+
+await test("type-only statements leave nothing behind", () => {
+    const point: Point3 = { x: 1, y: 2, z: 3 };
+    const maybe: Maybe<number> = null;
+    assert.deepEqual(point, { x: 1, y: 2, z: 3 });
+    assert.equal(maybe, null);
+    assert.equal(typeof Native, "undefined");
+});
+
+await test("function overloads", () => {
+    function double(value: number): number;
+    function double(value: string): string;
+    function double(value: any): any {
+        return value + value;
+    }
+    assert.equal(double(2), 4);
+    assert.equal(double("a"), "aa");
+    assert.equal(double.length, 1);
+});
+
+await test("access modifiers and readonly", () => {
+    class Account {
+        private readonly id: number;
+        protected owner: string = "me";
+        public static count: number = 0;
+        declare kind: string;
+        override toString(): string {
+            return `${this.id}:${this.owner}`;
+        }
+        constructor(id: number) {
+            this.id = id;
+            Account.count++;
+        }
+    }
+    const account = new Account(7);
+    assert.equal(account.toString(), "7:me");
+    assert.equal(Account.count, 1);
+    assert.ok(!("kind" in account));
+});
+
+await test("abstract classes", () => {
+    abstract class Shape {
+        abstract area(): number;
+        abstract readonly name: string;
+        describe(): string {
+            return `${this.name} ${this.area()}`;
+        }
+    }
+    class Square extends Shape {
+        name = "square";
+        constructor(private side: number) {
+            super();
+            this.side = side;
+        }
+        area(): number {
+            return this.side ** 2;
+        }
+    }
+    assert.equal(new Square(3).describe(), "square 9");
+});
+
+await test("implements and index signatures", () => {
+    interface Named {
+        name: string;
+    }
+    class Bag implements Named, Iterable<string> {
+        [key: string]: unknown;
+        name = "bag";
+        items?: string[];
+        count!: number;
+        *[Symbol.iterator](): Iterator<string> {
+            yield this.name;
+        }
+    }
+    const bag = new Bag();
+    assert.deepEqual([...bag], ["bag"]);
+    assert.equal(bag.items, undefined);
+});
+
+await test("class members without semicolons", () => {
+    class Counter {
+        step: number = 2
+        value = 0
+        add = (times: number): number => this.value += this.step * times
+        static create<T>(): Counter { return new Counter() }
+    }
+    const counter = Counter.create<number>();
+    counter.add(2);
+    assert.equal(counter.value, 4);
+});
+
+done("declarations");
@@ -0,0 +1,162 @@
+import { assert, test, done } from "../harness.js";
+
+function logged(log: string[]) {
+    return function <T extends (...args: any[]) => any>(method: T, context: ClassMethodDecoratorContext): T {
+        return function (this: unknown, ...args: any[]) {
+            log.push(`${String(context.name)}(${args.join(", ")})`);
+            return method.apply(this, args);
+        } as T;
+    };
+}
+
+await test("class decorators", () => {
+    const seen: string[] = [];
+    function register(cls: Function, context: ClassDecoratorContext) {
+        seen.push(`${context.kind} ${String(context.name)}`);
+    }
+    function sealed<T extends new (...args: any[]) => object>(cls: T) {
+        return class extends cls {
+            sealed = true;
+        };
+    }
+
+    @register
+    @sealed
+    class Widget {
+        name: string = "widget";
+    }
+
+    assert.deepEqual(seen, ["class Widget"]);
+    assert.equal((new Widget() as any).sealed, true);
+    assert.equal(new Widget().name, "widget");
+});
+
+await test("method decorators", () => {
+    const log: string[] = [];
+    class Calculator {
+        base = 10;
+
+        @logged(log)
+        add(x: number, y: number): number {
+            return this.base + x + y;
+        }
+
+        @logged(log)
+        static twice(x: number) {
+            return x * 2;
+        }
+    }
+
+    assert.equal(new Calculator().add(1, 2), 13);
+    assert.equal(Calculator.twice(4), 8);
+    assert.deepEqual(log, ["add(1, 2)", "twice(4)"]);
+});
+
+await test("field and accessor decorators", () => {
+    function double(_: undefined, context: ClassFieldDecoratorContext) {
+        return (value: number) => value * 2;
+    }
+    function tracked<T>(target: ClassAccessorDecoratorTarget<object, T>, context: ClassAccessorDecoratorContext) {
+        const writes: T[] = [];
+        return {
+            get(this: object) {
+                return target.get.call(this);
+            },
+            set(this: object, value: T) {
+                writes.push(value);
+                target.set.call(this, value);
+            },
+            init(value: T) {
+                return value;
+            },
+        };
+    }
+
+    class Settings {
+        @double width: number = 4;
+        @double height!: number;
+        @tracked accessor theme: string = "light";
+        accessor plain = 1;
+    }
+
+    const settings = new Settings();
+    assert.equal(settings.width, 8);
+    assert.ok(Number.isNaN(settings.height));
+    assert.equal(settings.theme, "light");
+    settings.theme = "dark";
+    assert.equal(settings.theme, "dark");
+    assert.equal(settings.plain, 1);
+    settings.plain = 2;
+    assert.equal(settings.plain, 2);
+    assert.ok(Object.getOwnPropertyDescriptor(Settings.prototype, "plain")?.get);
+});
+
+await test("private members", () => {
+    const log: string[] = [];
+    function bound(method: Function, context: ClassMethodDecoratorContext) {
+        context.addInitializer(function (this: any) {
+            log.push(`init ${String(context.name)}`);
+        });
+    }
+    class Counter {
+        #count = 0;
+
+        @logged(log)
+        #step(by: number) {
+            this.#count += by;
+        }
+
+        @bound
+        tick() {
+            this.#step(1);
+            return this.#count;
+        }
+
+        @double
+        #limit = 5;
+
+        get limit() {
+            return this.#limit;
+        }
+    }
+    function double(_: undefined, context: ClassFieldDecoratorContext) {
+        assert.equal(context.private, true);
+        return (value: number) => value * 2;
+    }
+
+    const counter = new Counter();
+    assert.equal(counter.tick(), 1);
+    assert.equal(counter.limit, 10);
+    assert.deepEqual(log, ["init tick", "#step(1)"]);
+});
+
+await test("decorator metadata and context", () => {
+    const names: (string | symbol)[] = [];
+    function meta(value: unknown, context: DecoratorContext) {
+        names.push(context.name!);
+        context.metadata[context.name!] = context.kind;
+    }
+    @meta
+    class Model {
+        @meta id = 1;
+        @meta save() {}
+        @meta static create() {}
+    }
+    assert.deepEqual(names, ["create", "save", "id", "Model"]);
+    assert.deepEqual({ ...(Model as any)[Symbol.metadata] }, {
+        id: "field",
+        save: "method",
+        create: "method",
+        Model: "class",
+    });
+});
+
+await test("exported and expression classes", async () => {
+    const { Service, named } = await import("./fixtures/decorated.ts");
+    assert.equal(new Service().greet(), "hello!");
+    assert.equal(named.label, "labeled");
+    const Anonymous = [@((cls: any) => { cls.tag = "tagged"; }) class {}][0] as any;
+    assert.equal(Anonymous.tag, "tagged");
+});
+
+done("decorators");
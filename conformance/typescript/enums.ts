@@ -0,0 +1,90 @@
+import { assert, test, done } from "../harness.js";
+
+enum Direction {
+    Up,
+    Down,
+    Left = 10,
+    Right,
+}
+
+enum Level {
+    Debug = "debug",
+    Info = "info",
+    Warn = `warn`,
+}
+
+export enum Flags {
+    None = 0,
+    Read = 1 << 0,
+    Write = 1 << 1,
+    ReadWrite = Read | Write,
+    Hex = 0x10,
+}
+
+const enum Inline {
+    A = 2,
+    B,
+}
+
+enum Quoted {
+    "first-name" = 1,
+    'last name',
+}
+
+enum Computed {
+    Length = "text".length,
+    Next,
+}
+
+await test("numeric members count up", () => {
+    assert.equal(Direction.Up, 0);
+    assert.equal(Direction.Down, 1);
+    assert.equal(Direction.Left, 10);
+    assert.equal(Direction.Right, 11);
+});
+
+await test("numeric members map back to their names", () => {
+    assert.equal(Direction[0], "Up");
+    assert.equal(Direction[Direction.Right], "Right");
+    assert.deepEqual(Object.keys(Direction), ["0", "1", "10", "11", "Up", "Down", "Left", "Right"]);
+});
+
+await test("string members", () => {
+    assert.equal(Level.Debug, "debug");
+    assert.equal(Level.Warn, "warn");
+    assert.deepEqual(Object.keys(Level), ["Debug", "Info", "Warn"]);
+    const level: Level = Level.Info;
+    assert.equal(level, "info");
+});
+
+await test("members referring to other members", () => {
+    assert.equal(Flags.Read, 1);
+    assert.equal(Flags.Write, 2);
+    assert.equal(Flags.ReadWrite, 3);
+    assert.equal(Flags.Hex, 16);
+    assert.equal(Flags[3], "ReadWrite");
+});
+
+await test("const enums", () => {
+    assert.equal(Inline.A, 2);
+    assert.equal(Inline.B, 3);
+});
+
+await test("quoted and computed members", () => {
+    assert.equal(Quoted["first-name"], 1);
+    assert.equal(Quoted["last name"], 2);
+    assert.equal(Computed.Length, 4);
+    assert.equal(Computed.Next, 5);
+});
+
+await test("enums merge", () => {
+    enum Merged {
+        A = 1,
+    }
+    enum Merged {
+        B = 2,
+    }
+    assert.deepEqual([Merged.A, Merged.B], [1, 2]);
+});
+
+done("enums");
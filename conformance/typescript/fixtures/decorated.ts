@@ -0,0 +1,23 @@
+function exclaim(method: () => string, context: ClassMethodDecoratorContext) {
+    return function (this: unknown) {
+        return method.call(this) + "!";
+    };
+}
+
+function label(cls: any, context: ClassDecoratorContext) {
+    cls.label = "labeled";
+}
+
+export class Service {
+    @exclaim
+    greet() {
+        return "hello";
+    }
+}
+
+@label
+export default class Labeled {}
+
+export @label class Named {}
+
+export const named = Named as any;
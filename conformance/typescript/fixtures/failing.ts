@@ -0,0 +1,9 @@
+export interface Failure {
+    reason: string;
+}
+
+export function failWith<T extends string>(reason: T): never {
+    const failure: Failure = { reason };
+    // the throw stays on line 8 once the types are gone
+    throw new Error(failure.reason as string);
+}
@@ -0,0 +1,10 @@
+// Exports types only, so nothing is left of it at runtime: importing any of
+// these names only works if the import is dropped with the types.
+export interface Shape {
+    kind: string;
+    area(): number;
+}
+
+export type Unit = "cm" | "in";
+
+export declare const scale: number;
@@ -0,0 +1,9 @@
+export interface Named {
+    name: string;
+}
+
+export const answer: number = 42;
+
+export default function label(named: Named): string {
+    return `<${named.name}>`;
+}
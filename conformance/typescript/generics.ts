@@ -0,0 +1,96 @@
+import { assert, test, done } from "../harness.js";
+
+await test("generic functions", () => {
+    function first<T>(items: T[]): T | undefined {
+        return items[0];
+    }
+    function keys<T extends object, K extends keyof T = keyof T>(value: T): K[] {
+        return Object.keys(value) as K[];
+    }
+    assert.equal(first<number>([1, 2]), 1);
+    assert.equal(first(["a"]), "a");
+    assert.deepEqual(keys({ a: 1, b: 2 }), ["a", "b"]);
+});
+
+await test("generic arrow functions", () => {
+    const identity = <T,>(value: T): T => value;
+    const wrap = async <T>(value: T): Promise<{ value: T }> => ({ value });
+    assert.equal(identity<string>("a"), "a");
+    return wrap(1).then((result) => assert.deepEqual(result, { value: 1 }));
+});
+
+await test("type arguments on calls and new", () => {
+    const map = new Map<string, Array<Array<number>>>();
+    map.set("a", [[1]]);
+    const set = new Set<number>([1, 1, 2]);
+    const parsed = JSON.parse<{ a: number }>('{"a":1}');
+    assert.deepEqual(map.get("a"), [[1]]);
+    assert.equal(set.size, 2);
+    assert.equal(parsed.a, 1);
+});
+
+await test("generic classes and methods", () => {
+    class Box<T> {
+        constructor(value: T) {
+            this.value = value;
+        }
+        value: T;
+        map<U>(f: (value: T) => U): Box<U> {
+            return new Box<U>(f(this.value));
+        }
+    }
+    class Labelled<T extends string> extends Box<T> {}
+    assert.equal(new Box(2).map<string>((n: number): string => `${n * 2}`).value, "4");
+    assert.equal(new Labelled("x").value, "x");
+});
+
+await test("comparisons and shifts are left alone", () => {
+    const a = 1, b = 2, c = 3;
+    const lt = a < b && c > a;
+    const chained = a < b == b > a;
+    const grouped = (a < b) ? (c > a) : false;
+    const shifted = (c >> 1) + (c >>> 0) + (a << 2);
+    const mixed = a < b ? [a] : [b];
+    assert.equal(lt, true);
+    assert.equal(chained, true);
+    assert.equal(grouped, true);
+    assert.equal(shifted, 8);
+    assert.deepEqual(mixed, [1]);
+    for (let i: number = 0; i < 2; i++) {
+        assert.ok(i < 2);
+    }
+});
+
+await test("strings, templates and regular expressions", () => {
+    const text = "a as b < c > d: e";
+    const template = `x as ${"y"} <T>(z)`;
+    const re = /<T>|as\s+any/;
+    assert.equal(text.length, 17);
+    assert.equal(template, "x as y <T>(z)");
+    assert.ok(re.test("as  any"));
+});
+
+await test("conditional and mapped types", () => {
+    type Unwrap<T> = T extends Promise<infer U> ? U : T;
+    type Flags<T> = { readonly [K in keyof T]?: boolean };
+    type Id = `id-${number}`;
+    const unwrapped: Unwrap<Promise<number>> = 1;
+    const flags: Flags<{ a: 1 }> = { a: true };
+    const id: Id = `id-${1}`;
+    assert.deepEqual([unwrapped, flags, id], [1, { a: true }, "id-1"]);
+});
+
+await test("type arguments of new without parentheses", () => {
+    class Box<T> {
+        value?: T;
+    }
+    const boxes = { Box };
+    const plain = new Box<string>;
+    const member = new boxes.Box<Map<string, number>>;
+    const listed = [new Box<number>, new boxes.Box<string>];
+    const next = new Box<boolean>
+    assert.ok(plain instanceof Box && member instanceof Box && next instanceof Box);
+    assert.equal(listed.length, 2);
+});
+
+done("generics");
@@ -0,0 +1,31 @@
+import type { Shape } from "./fixtures/shapes.ts";
+import { type Unit, scale } from "./fixtures/shapes.ts";
+import label, { answer, type Named } from "./fixtures/values.ts";
+import * as values from "./fixtures/values.ts";
+import { assert, test, done } from "../harness.js";
+
+export type { Shape };
+export { type Unit };
+export type Length = { value: number; unit: Unit };
+
+await test("type-only imports are removed", () => {
+    const shape: Shape = { kind: "dot", area: () => 0 };
+    const unit: Unit = "cm";
+    assert.equal(shape.area(), 0);
+    assert.equal(unit, "cm");
+});
+
+await test("imports only used as types are removed", () => {
+    // `scale` only exists in a declaration, importing it for real would fail
+    const factor: typeof scale = 2;
+    assert.equal(factor, 2);
+});
+
+await test("value imports are kept", () => {
+    const named: Named = { name: "x" };
+    assert.equal(label(named), "<x>");
+    assert.equal(answer, 42);
+    assert.equal(values.answer, 42);
+});
+
+done("imports");
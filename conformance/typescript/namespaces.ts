@@ -0,0 +1,76 @@
+import { assert, test, done } from "../harness.js";
+
+namespace Geometry {
+    export const unit: number = 1;
+    export let origin = { x: 0, y: 0 };
+    const hidden = 42;
+
+    export function area(width: number, height: number = unit): number {
+        return width * height * scale();
+    }
+
+    function scale(): number {
+        return hidden / 42;
+    }
+
+    export class Point {
+        constructor(public x: number, public y: number) {}
+    }
+
+    export enum Axis {
+        X,
+        Y,
+    }
+
+    export interface Sized {
+        size: number;
+    }
+
+    export type Pair = [number, number];
+}
+
+namespace Outer.Inner.Deepest {
+    export const depth = 3;
+}
+
+module Legacy {
+    export const old = true;
+}
+
+export namespace Shared {
+    export namespace Nested {
+        export const value = "nested";
+    }
+}
+
+namespace Geometry {
+    export const merged = true;
+}
+
+await test("exported values", () => {
+    assert.equal(Geometry.unit, 1);
+    assert.deepEqual(Geometry.origin, { x: 0, y: 0 });
+    assert.equal(Geometry.area(2, 3), 6);
+    assert.equal(Geometry.area(5), 5);
+    assert.equal(new Geometry.Point(1, 2).y, 2);
+    assert.equal(Geometry.Axis.Y, 1);
+});
+
+await test("private members stay private", () => {
+    assert.equal("hidden" in Geometry, false);
+    assert.equal("scale" in Geometry, false);
+    assert.deepEqual(Object.keys(Geometry).sort(), ["Axis", "Point", "area", "merged", "origin", "unit"]);
+});
+
+await test("dotted and nested namespaces", () => {
+    assert.equal(Outer.Inner.Deepest.depth, 3);
+    assert.equal(Shared.Nested.value, "nested");
+    assert.equal(Legacy.old, true);
+});
+
+await test("declarations merge", () => {
+    assert.equal(Geometry.merged, true);
+    assert.equal(Geometry.unit, 1);
+});
+
+done("namespaces");
@@ -0,0 +1,52 @@
+import { assert, test, done } from "../harness.js";
+
+class Point {
+    constructor(public x: number, private y: number, readonly z = 0) {}
+    sum(): number {
+        return this.x + this.y + this.z;
+    }
+}
+
+class Base {
+    constructor(protected name: string) {}
+}
+
+class Derived extends Base {
+    order: string[] = [];
+
+    constructor(name: string, public readonly tag?: string, override id: number = 1) {
+        const upper = name.toUpperCase();
+        super(upper)
+        this.order.push(this.name);
+    }
+}
+
+class Mixed {
+    constructor(plain: number, public kept: number, ...rest: number[]) {
+        this.total = plain + kept + rest.length;
+    }
+    total: number;
+}
+
+await test("parameter properties are assigned", () => {
+    const point = new Point(1, 2, 3);
+    assert.equal(point.x, 1);
+    assert.equal(point.sum(), 6);
+    assert.equal(new Point(1, 2).z, 0);
+});
+
+await test("after super() in a derived class", () => {
+    const derived = new Derived("a", "t");
+    assert.equal(derived.tag, "t");
+    assert.equal(derived.id, 1);
+    assert.deepEqual(derived.order, ["A"]);
+});
+
+await test("plain parameters are left alone", () => {
+    const mixed = new Mixed(1, 2, 3, 4);
+    assert.equal(mixed.kept, 2);
+    assert.equal(mixed.total, 5);
+    assert.equal("plain" in mixed, false);
+});
+
+done("parameter-properties");
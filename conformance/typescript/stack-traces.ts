@@ -0,0 +1,31 @@
+import { assert, test, done } from "../harness.js";
+import { failWith } from "./fixtures/failing.ts";
+
+function lineOf(stack: string | undefined, file: string): number | undefined {
+    const at = stack?.indexOf(`${file}:`) ?? -1;
+    return at < 0 ? undefined : Number(stack!.slice(at + file.length + 1).split(":")[0]);
+}
+
+await test("errors name the module they come from", () => {
+    const error: Error = new Error("here");
+    assert.equal(lineOf(error.stack, import.meta.filename), 10);
+});
+
+await test("lines survive type erasure", () => {
+    type Shape = { kind: string };
+    interface Named {
+        name: string;
+    }
+    const shape: Shape & Named = { kind: "box", name: "b" };
+    let stack: string | undefined;
+    try {
+        failWith(shape.kind);
+    } catch (error) {
+        stack = (error as Error).stack;
+    }
+    const fixture = import.meta.dirname + "/fixtures/failing.ts";
+    assert.equal(lineOf(stack, fixture), 8);
+    assert.equal(lineOf(stack, import.meta.filename), 22);
+});
+
+done("stack traces");
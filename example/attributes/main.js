@@ -0,0 +1,12 @@
+// JSON is inferred from the extension, other files need a `type` attribute:
+// "text" imports a string and "bytes" imports a Uint8Array.
+import settings from './settings.json';
+import template from './greeting.txt' with { type: 'text' };
+import raw from './greeting.txt' with { type: 'bytes' };
+
+console.log(template.replace('{{name}}', settings.name).trim());
+console.log(`greeting.txt is ${raw.length} bytes`);
+
+import('./greeting.txt', { with: { type: 'text' } }).then((module) => {
+    console.log(module.default === template);
+});
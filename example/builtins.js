@@ -0,0 +1,11 @@
+/// Built-in modules
+/// The functions of the `Lunos` global can be imported from `lunos:` modules,
+// see types/lunos.d.ts for what each one exports
+
+import { argv } from "lunos:process";
+import { shell } from "lunos:shell";
+
+console.log("Arguments:", argv());
+
+const { result } = shell("sh", "echo Hello from lunos:shell");
+console.log(result);
@@ -0,0 +1,11 @@
+let count = 0;
+
+function increment() {
+    return ++count;
+}
+
+function current() {
+    return count;
+}
+
+module.exports = { increment, current };
@@ -0,0 +1,5 @@
+exports.greet = function (name) {
+    return `Hello, ${name}!`;
+};
+
+exports.shout = (name) => exports.greet(name).toUpperCase();
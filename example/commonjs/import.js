@@ -0,0 +1,7 @@
+// ES modules can import CommonJS: the default import is `module.exports`
+// and statically detected names can be imported directly.
+import counter from './counter.js';
+import { greet, shout } from './greet.js';
+
+counter.increment();
+console.log(greet('ESM'), shout('interop'), counter.current());
@@ -0,0 +1,14 @@
+// A CommonJS entry script: `require`, `module.exports`, `__filename`,
+// `__dirname` and JSON requires all work like they do in Node.
+const { greet } = require('./greet');
+const counter = require('./counter.js');
+const config = require('./config.json');
+
+console.log(greet('CommonJS'));
+console.log('Loaded from', __dirname);
+console.log('Config:', config.name, config.retries);
+
+counter.increment();
+counter.increment();
+console.log('Counter (cached between requires):', require('./counter').current());
+console.log('Is main module:', require.main === module);
@@ -0,0 +1,4 @@
+// "@app/" is mapped to ./src/ by the import map in lunos.json
+import { greet } from "@app/greet.js";
+
+console.log(greet("import maps"));
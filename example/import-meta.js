@@ -0,0 +1,7 @@
+// import.meta describes the current module, so files next to the script can
+// be found regardless of the working directory.
+console.log('url:', import.meta.url);
+console.log('filename:', import.meta.filename);
+console.log('dirname:', import.meta.dirname);
+console.log('main:', import.meta.main);
+console.log('resolved:', import.meta.resolve('./commonjs/greet.js'));
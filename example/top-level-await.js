@@ -0,0 +1,8 @@
+// ES modules can await at the top level. Modules importing this one wait
+// until it finishes, and the process stays alive until the promise settles.
+const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+
+console.log('Loading config...');
+await sleep(100);
+const { default: settings } = await import('./attributes/settings.json');
+console.log(`Loaded config for ${settings.name}`);
@@ -0,0 +1,6 @@
+// With serve.js running: `lunos main.js` downloads the modules into the
+// cache and records their hashes in lunos.lock. After that,
+// `lunos --offline main.js` runs without the server.
+import { greet } from 'http://localhost:9696/greet.js';
+
+console.log(greet('lunos'));
@@ -0,0 +1,5 @@
+import { shout } from './lib/shout.js';
+
+export function greet(name) {
+    return shout(`hello, ${name}`);
+}
@@ -0,0 +1 @@
+export const shout = (text) => `${text.toUpperCase()}!`;
@@ -0,0 +1,7 @@
+// Serves ./modules for main.js, run it from this directory first:
+//   lunos serve.js
+Lunos.serve({
+    port: 9696,
+    type: 'text/javascript',
+    dir: './modules'
+});
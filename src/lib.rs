@@ -0,0 +1,38 @@
+//! Lunos as a library, for running JavaScript and TypeScript from Rust.
+//!
+//! A `Runtime` is a JavaScriptCore context with the Lunos globals and module
+//! system. `RuntimeBuilder` chooses which globals it gets, and adds Rust
+//! functions and modules written with `native_function!`:
+//!
+//! ```ignore
+//! use lunos::{NativeError, Runtime, native_function};
+//!
+//! native_function! {
+//!     fn add(args, a: f64, b: f64) -> Result<f64, NativeError> {
+//!         Ok(a + b)
+//!     }
+//! }
+//!
+//! let runtime = Runtime::builder().module("math", &[("add", add)]).build();
+//! runtime.evaluate_module(Path::new("main.js"))?;
+//! runtime.run_event_loop()?;
+//! ```
+//!
+//! `main.js` can then `import { add } from "math"`.
+//!
+//! The unsafe functions of `convert`, `errors` and `native` all take a
+//! context of a live `Runtime`, and values that were made in it.
+
+#![allow(clippy::missing_safety_doc)]
+
+pub mod lunos;
+pub mod modules;
+mod runtime;
+pub mod utility;
+
+pub use lunos::options::UnhandledRejections;
+pub use modules::convert::{FromJs, JsFunction, JsObject, JsString, JsValue, Mismatch, ToJs};
+pub use modules::errors::{ErrorClass, NativeError};
+pub use modules::native::{Args, Failure, NativeCallback, Throw};
+pub use runtime::{Exception, Runtime, RuntimeBuilder, RuntimeError};
+pub use rusty_jsc;
@@ -0,0 +1,65 @@
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Where Lunos keeps downloaded modules and transformed sources:
+/// `$LUNOS_CACHE_DIR`, else `$XDG_CACHE_HOME/lunos`, else `~/.cache/lunos`.
+pub fn dir() -> PathBuf {
+    if let Some(dir) = env::var_os("LUNOS_CACHE_DIR") {
+        return PathBuf::from(dir);
+    }
+    if let Some(dir) = env::var_os("XDG_CACHE_HOME") {
+        return PathBuf::from(dir).join("lunos");
+    }
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    match home {
+        Some(home) => PathBuf::from(home).join(".cache").join("lunos"),
+        None => env::temp_dir().join("lunos-cache"),
+    }
+}
+
+/// The hex SHA-256 of `bytes`, which cache entries are named by.
+pub fn hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Writes a cache entry through a temporary file, so a concurrent run never
+/// reads half of it. Each write gets a temporary file of its own, named after
+/// the whole entry, since entries like `<key>` and `<key>.map` are written at
+/// the same time from different threads.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    let write = WRITES.fetch_add(1, Ordering::Relaxed);
+    temporary.push(format!(".tmp{}-{write}", std::process::id()));
+    let temporary = PathBuf::from(temporary);
+    if let Err(e) = fs::write(&temporary, contents) {
+        let _ = fs::remove_file(&temporary);
+        return Err(e);
+    }
+    fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+/// `lunos clean`: deletes the whole cache.
+pub fn clean(exit_code: i32) {
+    let dir = dir();
+    match fs::remove_dir_all(&dir) {
+        Ok(()) => println!("Removed {}", dir.display()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            println!("Nothing to clean in {}", dir.display())
+        }
+        Err(e) => {
+            eprintln!("Error removing {}: {e}", dir.display());
+            std::process::exit(1);
+        }
+    }
+    std::process::exit(exit_code);
+}
@@ -21,7 +21,16 @@ Help:
     -h / --help / none    show this screen
     -v / --version        show version info
     repl                  start the repl
+    clean                 delete the module and transform cache
     <js_file>             execute a js file
+  Run flags (before <js_file>):
+    --import-map <file>   resolve imports with an import map
+                          (default: the one in lunos.json, if any)
+    --offline             only use URL imports from the cache
+    --no-cache            transform every module again, without the cache
+    --unhandled-rejections <mode>
+                          strict: exit on a rejection nobody handles (default)
+                          warn: print it and go on, none: ignore it
 "#;
 pub const REPL_HELP: &str = r#"Lunos REPL help:
     Commands:
@@ -1,5 +1,7 @@
+pub mod cache;
 pub mod constants;
 pub mod help;
 pub mod io;
+pub mod options;
 pub mod repl;
 pub mod version;
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+/// What to do about a promise rejection nobody handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnhandledRejections {
+    /// Report it like an uncaught exception and exit.
+    Strict,
+    /// Print a warning and go on.
+    Warn,
+    None,
+}
+
+/// Flags given before the script: `lunos [flags] <js_file> [args]`. They
+/// configure the runtime the script runs in, see `RuntimeBuilder`.
+#[derive(Debug)]
+pub struct Options {
+    pub import_map: Option<PathBuf>,
+    /// Only use URL imports that are already cached, never the network.
+    pub offline: bool,
+    /// Transform every module again instead of using the transform cache.
+    pub no_cache: bool,
+    pub unhandled_rejections: UnhandledRejections,
+    /// Index of the script in the process arguments, the script's own
+    /// arguments follow it.
+    pub script_index: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            import_map: None,
+            offline: false,
+            no_cache: false,
+            unhandled_rejections: UnhandledRejections::Strict,
+            script_index: 1,
+        }
+    }
+}
+
+/// A flag that turns something on by itself, or sets it with `=true` or
+/// `=false`.
+fn switch(flag: &str, inline_value: Option<&str>) -> Result<bool, String> {
+    match inline_value {
+        None | Some("true") => Ok(true),
+        Some("false") => Ok(false),
+        Some(value) => Err(format!(
+            "Invalid value for {flag}: {value} (expected true or false)"
+        )),
+    }
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut i = 1;
+
+        while let Some(arg) = args.get(i) {
+            if !arg.starts_with("--") {
+                break;
+            }
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value.clone().or_else(|| {
+                    i += 1;
+                    args.get(i).cloned()
+                })
+            };
+
+            match flag {
+                "--import-map" => {
+                    let path = value().ok_or("--import-map requires a file path")?;
+                    options.import_map = Some(PathBuf::from(path));
+                }
+                "--offline" => options.offline = switch(flag, inline_value.as_deref())?,
+                "--no-cache" => options.no_cache = switch(flag, inline_value.as_deref())?,
+                "--unhandled-rejections" => {
+                    let mode = value().ok_or("--unhandled-rejections requires a mode")?;
+                    options.unhandled_rejections = match mode.as_str() {
+                        "strict" => UnhandledRejections::Strict,
+                        "warn" => UnhandledRejections::Warn,
+                        "none" => UnhandledRejections::None,
+                        _ => {
+                            return Err(format!(
+                                "Invalid --unhandled-rejections mode: {mode} (expected strict, warn or none)"
+                            ));
+                        }
+                    };
+                }
+                _ => return Err(format!("Unknown flag: {flag}")),
+            }
+            i += 1;
+        }
+
+        if i >= args.len() {
+            return Err("Missing script to run".to_string());
+        }
+        options.script_index = i;
+        Ok(options)
+    }
+}
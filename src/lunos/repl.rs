@@ -1,15 +1,15 @@
+use crate::lunos::constants::{
+    ASCII_BANNER, NAME, REPL_HELP, THE_ULTIMATE_QUESTION_AND_ANSWER, VERSION,
+};
+use crate::lunos::io::colorize;
+use crate::{JsString, Runtime, RuntimeError};
 use crossterm::execute;
 use crossterm::terminal::{Clear, ClearType};
-use rusty_jsc::*;
 use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
 use std::io::{Write, stdout};
-use std::{ffi::CString, process};
+use std::process;
 use std::time::Duration;
-use rustyline::error::ReadlineError;
-use crate::lunos::constants::{
-    ASCII_BANNER, NAME, REPL_HELP, THE_ULTIMATE_QUESTION_AND_ANSWER, VERSION,
-};
-use crate::lunos::io::colorize;
 
 fn print_welcome() {
     println!("Welcome to Lunos v{VERSION}");
@@ -66,101 +66,69 @@ fn handle_command(input: &str, exit_code: i32) -> bool {
     }
 }
 
-pub fn start_repl(exit_code: i32) {
-    unsafe {
-        let context = JSGlobalContextCreate(std::ptr::null_mut());
-
-        let console = crate::modules::console::Console::new();
-        console.bind_to_context(context);
-
-        crate::modules::lunos::Lunos::bind_to_context(context);
-
-        print_welcome();
-
-        let mut rusty_line = DefaultEditor::new().unwrap();
-        let mut last_was_ctrl_c = false;
-
-        loop {
-            let readline = rusty_line.readline("> ");
-            match readline {
-                Ok(input) => {
-                    last_was_ctrl_c = false;
-                    let input = input.trim();
-
-                    if handle_command(input, exit_code) {
-                        continue;
-                    }
+/// Prints what evaluating a line failed with, and goes on.
+fn report(error: RuntimeError) {
+    match error {
+        RuntimeError::Uncaught(exception) if exception.value().0.is_null() => {
+            eprintln!("Error evaluating input!");
+        }
+        RuntimeError::Uncaught(exception) => eprint!("Uncaught {}", exception.report()),
+        error => eprintln!("{error}"),
+    }
+}
 
-                    rusty_line.add_history_entry(input).unwrap();
+pub fn start_repl(exit_code: i32) {
+    let runtime = Runtime::new();
+    print_welcome();
 
-                    let js_code = format!("eval('{input}')");
-                    let js_cstr = CString::new(js_code).unwrap();
-                    let script = JSStringCreateWithUTF8CString(js_cstr.as_ptr());
+    let mut rusty_line = DefaultEditor::new().unwrap();
+    let mut last_was_ctrl_c = false;
 
-                    let result = JSEvaluateScript(
-                        context,
-                        script,
-                        std::ptr::null_mut(),
-                        std::ptr::null_mut(),
-                        0,
-                        std::ptr::null_mut(),
-                    );
+    loop {
+        let readline = rusty_line.readline("> ");
+        match readline {
+            Ok(input) => {
+                last_was_ctrl_c = false;
+                let input = input.trim();
 
-                    JSStringRelease(script);
+                if handle_command(input, exit_code) {
+                    continue;
+                }
 
-                    if result.is_null() {
-                        println!("Error occurred! (Probably a syntax error)");
-                    } else {
-                        let result_str = JSValueToStringCopy(context, result, std::ptr::null_mut());
-                        let mut buffer = [0u8; 1024];
-                        let length = JSStringGetUTF8CString(
-                            result_str,
-                            buffer.as_mut_ptr() as *mut i8,
-                            buffer.len(),
-                        );
+                rusty_line.add_history_entry(input).unwrap();
 
-                        if length > 0 {
-                            let output = String::from_utf8_lossy(&buffer[..length - 1]);
-                            if output != "undefined" {
-                                println!("{output}");
-                            }
+                match runtime.evaluate(input) {
+                    Ok(result) => {
+                        let output = unsafe { JsString::from_value(runtime.context(), result.0) };
+                        let output = output.to_string();
+                        if output != "undefined" {
+                            println!("{output}");
                         }
-
-                        JSStringRelease(result_str);
                     }
-
-                    let flush_code = "console.flush();";
-                    let flush_cstr = CString::new(flush_code).unwrap();
-                    let flush_script = JSStringCreateWithUTF8CString(flush_cstr.as_ptr());
-
-                    JSEvaluateScript(
-                        context,
-                        flush_script,
-                        std::ptr::null_mut(),
-                        std::ptr::null_mut(),
-                        0,
-                        std::ptr::null_mut(),
-                    );
-
-                    JSStringRelease(flush_script);
+                    Err(error) => report(error),
                 }
-                Err(ReadlineError::Interrupted) => {
-                    if last_was_ctrl_c {
-                        colorize("Goodbye!", "green");
-                        process::exit(exit_code);
-                    }
-                    last_was_ctrl_c = true;
-                    println!("To exit, press Ctrl+C again or Ctrl+D or type .exit");
+                // timers the line scheduled run before the next prompt
+                if let Err(error) = runtime.run_event_loop() {
+                    report(error);
                 }
-                Err(ReadlineError::Eof) => {
+                runtime.flush_console();
+            }
+            Err(ReadlineError::Interrupted) => {
+                if last_was_ctrl_c {
                     colorize("Goodbye!", "green");
                     process::exit(exit_code);
                 }
-                Err(err) => {
-                    println!("Error: {err}");
-                    break;
-                }
+                last_was_ctrl_c = true;
+                println!("To exit, press Ctrl+C again or Ctrl+D or type .exit");
+            }
+            Err(ReadlineError::Eof) => {
+                colorize("Goodbye!", "green");
+                process::exit(exit_code);
+            }
+            Err(err) => {
+                println!("Error: {err}");
+                break;
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,368 @@
+//! References to the names an ES module imports.
+//!
+//! An imported name isn't a variable of the importing module, it's a view of
+//! the exporting module's binding: a later assignment there shows through,
+//! and in an import cycle a binding that isn't initialized yet only throws
+//! once it's read. The loader gets that by rewriting each reference into a
+//! read of the namespace the name is imported from, so this finds them: the
+//! identifiers with an imported name that aren't property names, object keys,
+//! class members or labels, and aren't shadowed by a declaration in an inner
+//! scope.
+
+use super::es6::{
+    declaration_name, declared_names, ends_statement, pattern_names, starts_function_body,
+};
+use super::lexer::{Token, TokenKind};
+use std::collections::HashSet;
+
+/// How a reference is used, which decides how it's rewritten.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Usage {
+    Read,
+    /// Called, or the tag of a template. The namespace mustn't become its
+    /// `this`.
+    Call,
+    /// An object literal shorthand, `{ name }`.
+    Shorthand,
+}
+
+pub(crate) struct Reference {
+    /// Index of the identifier's token.
+    pub token: usize,
+    pub usage: Usage,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Frame {
+    Block,
+    Function,
+    Object,
+    Class,
+    Paren,
+    Bracket,
+}
+
+impl Frame {
+    fn is_brace(self) -> bool {
+        !matches!(self, Frame::Paren | Frame::Bracket)
+    }
+}
+
+/// A name declared in an inner scope, and the tokens it's visible in.
+struct Shadow {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+fn is_at(src: &str, tokens: &[Token], i: usize, text: &str) -> bool {
+    tokens.get(i).is_some_and(|t| t.is(src, text))
+}
+
+fn prev_is_dot(src: &str, tokens: &[Token], i: usize) -> bool {
+    i > 0 && (tokens[i - 1].is(src, ".") || tokens[i - 1].is(src, "?."))
+}
+
+/// The matching close of every open bracket, by index. Unbalanced ones close
+/// at the last token.
+fn closes(src: &str, tokens: &[Token]) -> Vec<usize> {
+    let last = tokens.len().saturating_sub(1);
+    let mut closes = vec![last; tokens.len()];
+    let mut open = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        match token.text(src) {
+            "{" | "(" | "[" => open.push(i),
+            "}" | ")" | "]" => {
+                if let Some(start) = open.pop() {
+                    closes[start] = i;
+                }
+            }
+            _ => {}
+        }
+    }
+    closes
+}
+
+/// The last token of the statement or expression starting at `start`: up to
+/// a `;`, a `,` when `at_comma`, a line break that ends a statement, or the
+/// bracket around it.
+fn extent(src: &str, tokens: &[Token], closes: &[usize], start: usize, at_comma: bool) -> usize {
+    let mut k = start;
+    while k < tokens.len() {
+        let token = tokens[k];
+        if k > start && ends_statement(src, tokens, k) {
+            return k - 1;
+        }
+        if token.kind == TokenKind::Punct {
+            match token.text(src) {
+                "{" | "(" | "[" => {
+                    k = closes[k] + 1;
+                    continue;
+                }
+                "}" | ")" | "]" => return k.saturating_sub(1).max(start),
+                ";" => return k,
+                "," if at_comma => return k.saturating_sub(1).max(start),
+                _ => {}
+            }
+        }
+        k += 1;
+    }
+    tokens.len().saturating_sub(1)
+}
+
+/// What the `{` at `i` opens, given the brackets around it.
+fn brace_frame(src: &str, tokens: &[Token], i: usize, last_paren_open: usize) -> Frame {
+    if starts_function_body(src, tokens, i, last_paren_open) {
+        return Frame::Function;
+    }
+    let Some(previous) = i.checked_sub(1).map(|p| tokens[p]) else {
+        return Frame::Block;
+    };
+    match previous.kind {
+        TokenKind::Punct => match previous.text(src) {
+            ";" | "{" | "}" | ")" => Frame::Block,
+            // `case 1: {` is a block, but taking it for an object is harmless
+            _ => Frame::Object,
+        },
+        TokenKind::Ident => match previous.text(src) {
+            "return" | "typeof" | "in" | "of" | "instanceof" | "new" | "delete" | "void"
+            | "throw" | "case" | "yield" | "await" | "default" | "const" | "let" | "var"
+            | "export" => Frame::Object,
+            _ => Frame::Block,
+        },
+        _ => Frame::Block,
+    }
+}
+
+/// Whether the token at `i` starts a statement.
+fn starts_statement(src: &str, tokens: &[Token], i: usize) -> bool {
+    i == 0 || matches!(tokens[i - 1].text(src), ";" | "{" | "}") || ends_statement(src, tokens, i)
+}
+
+/// Finds the references to `names` in a module's top-level code.
+pub(crate) fn references(src: &str, tokens: &[Token], names: &HashSet<&str>) -> Vec<Reference> {
+    if names.is_empty() {
+        return Vec::new();
+    }
+    let closes = closes(src, tokens);
+    let mut stack: Vec<(usize, Frame)> = Vec::new();
+    let mut shadows: Vec<Shadow> = Vec::new();
+    let mut found = Vec::new();
+    let mut last_paren_open = 0;
+    // the depth of the `class` whose body is the next `{` at that depth
+    let mut pending_class = None;
+
+    let shadow = |shadows: &mut Vec<Shadow>, bound: Vec<String>, start, end| {
+        for name in bound {
+            if names.contains(name.as_str()) {
+                shadows.push(Shadow { name, start, end });
+            }
+        }
+    };
+    let innermost_brace = |stack: &[(usize, Frame)]| {
+        stack
+            .iter()
+            .rev()
+            .find(|(_, frame)| frame.is_brace())
+            .map(|&(open, _)| open)
+    };
+
+    for i in 0..tokens.len() {
+        let token = tokens[i];
+
+        if token.kind == TokenKind::Punct {
+            match token.text(src) {
+                "{" => {
+                    let frame = if pending_class == Some(stack.len()) {
+                        pending_class = None;
+                        Frame::Class
+                    } else {
+                        brace_frame(src, tokens, i, last_paren_open)
+                    };
+                    // the parameters of a function, or what `catch` binds
+                    let binds_parameters = match frame {
+                        Frame::Function => true,
+                        Frame::Block => {
+                            last_paren_open > 0 && is_at(src, tokens, last_paren_open - 1, "catch")
+                        }
+                        _ => false,
+                    };
+                    if binds_parameters && i > 0 && tokens[i - 1].is(src, ")") {
+                        let mut bound = Vec::new();
+                        pattern_names(src, tokens, last_paren_open, i - 1, &mut bound);
+                        shadow(&mut shadows, bound, last_paren_open, closes[i]);
+                    }
+                    stack.push((i, frame));
+                }
+                "(" => stack.push((i, Frame::Paren)),
+                "[" => stack.push((i, Frame::Bracket)),
+                "}" | ")" | "]" => {
+                    if let Some((open, _)) = stack.pop() {
+                        last_paren_open = open;
+                    }
+                }
+                "=>" if i > 0 => {
+                    let (start, bound) = if tokens[i - 1].is(src, ")") {
+                        let mut bound = Vec::new();
+                        pattern_names(src, tokens, last_paren_open, i - 1, &mut bound);
+                        (last_paren_open, bound)
+                    } else {
+                        (i - 1, vec![tokens[i - 1].text(src).to_string()])
+                    };
+                    let end = if is_at(src, tokens, i + 1, "{") {
+                        closes[i + 1]
+                    } else {
+                        extent(src, tokens, &closes, i + 1, true)
+                    };
+                    shadow(&mut shadows, bound, start, end);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if token.kind != TokenKind::Ident || prev_is_dot(src, tokens, i) {
+            continue;
+        }
+        let text = token.text(src);
+
+        match text {
+            "let" | "const" | "var"
+                if tokens.get(i + 1).is_some_and(|next| {
+                    next.kind == TokenKind::Ident || next.is(src, "{") || next.is(src, "[")
+                }) =>
+            {
+                let bound = declared_names(src, tokens, i);
+                let for_head = stack.last().filter(|&&(open, frame)| {
+                    frame == Frame::Paren
+                        && open > 0
+                        && (tokens[open - 1].is(src, "for")
+                            || (tokens[open - 1].is(src, "await")
+                                && open > 1
+                                && tokens[open - 2].is(src, "for")))
+                });
+                let region = if text == "var" {
+                    stack
+                        .iter()
+                        .rev()
+                        .find(|(_, frame)| *frame == Frame::Function)
+                        .map(|&(open, _)| (open, closes[open]))
+                } else if let Some(&(open, _)) = for_head {
+                    let body = closes[open] + 1;
+                    let end = if is_at(src, tokens, body, "{") {
+                        closes[body]
+                    } else {
+                        extent(src, tokens, &closes, body, false)
+                    };
+                    Some((open, end))
+                } else {
+                    innermost_brace(&stack).map(|open| (open, closes[open]))
+                };
+                // a top-level declaration can't have the name of an import
+                if let Some((start, end)) = region {
+                    shadow(&mut shadows, bound, start, end);
+                }
+            }
+            "function" | "class" => {
+                let (name, keyword) = declaration_name(src, tokens, i);
+                let end = if text == "class" {
+                    pending_class = Some(stack.len());
+                    (i + 1..tokens.len())
+                        .find(|&k| tokens[k].is(src, "{"))
+                        .map_or(i, |open| closes[open])
+                } else {
+                    let params = keyword + 1 + usize::from(name.is_some());
+                    if is_at(src, tokens, params, "(") {
+                        closes[(closes[params] + 1).min(tokens.len() - 1)]
+                    } else {
+                        i
+                    }
+                };
+                let Some(name) = name else {
+                    continue;
+                };
+                let start = if i > 0 && tokens[i - 1].is(src, "async") {
+                    i - 1
+                } else {
+                    i
+                };
+                let region = if starts_statement(src, tokens, start) {
+                    innermost_brace(&stack).map(|open| (open, closes[open]))
+                } else {
+                    Some((i, end))
+                };
+                if let Some((start, end)) = region {
+                    shadow(&mut shadows, vec![name], start, end);
+                }
+            }
+            _ => {}
+        }
+
+        if !names.contains(text) {
+            continue;
+        }
+        if i > 0 && matches!(tokens[i - 1].text(src), "break" | "continue") {
+            continue;
+        }
+        let previous = i.checked_sub(1).map_or("", |p| tokens[p].text(src));
+        let next = tokens.get(i + 1).map_or("", |t| t.text(src));
+        let frame = stack.last().map(|&(_, frame)| frame);
+
+        match frame {
+            Some(Frame::Object) => {
+                let modifier = matches!(previous, "get" | "set" | "async" | "*")
+                    && i > 1
+                    && matches!(tokens[i - 2].text(src), "{" | "," | "async");
+                if matches!(previous, "{" | ",") || modifier {
+                    let is_method = next == "(" && is_at(src, tokens, closes[i + 1] + 1, "{");
+                    if next == ":" || is_method {
+                        continue;
+                    }
+                    if !modifier && matches!(next, "," | "}" | "=") {
+                        found.push(Reference {
+                            token: i,
+                            usage: Usage::Shorthand,
+                        });
+                        continue;
+                    }
+                }
+            }
+            Some(Frame::Class) => {
+                let member = matches!(
+                    previous,
+                    "{" | "}" | ";" | "static" | "get" | "set" | "async" | "*" | "accessor"
+                ) || ends_statement(src, tokens, i);
+                if member {
+                    continue;
+                }
+            }
+            _ => {
+                // a label
+                if next == ":" && starts_statement(src, tokens, i) {
+                    continue;
+                }
+            }
+        }
+
+        let is_call = next == "("
+            || next == "?."
+            || tokens
+                .get(i + 1)
+                .is_some_and(|t| t.kind == TokenKind::Template);
+        found.push(Reference {
+            token: i,
+            usage: if is_call { Usage::Call } else { Usage::Read },
+        });
+    }
+
+    found.retain(|reference| {
+        let name = tokens[reference.token].text(src);
+        !shadows
+            .iter()
+            .any(|s| s.name == name && s.start <= reference.token && reference.token <= s.end)
+    });
+    found
+}
@@ -0,0 +1,190 @@
+use super::es6::js_string;
+use super::registry::Registry;
+
+/// The prefix of built-in module specifiers, as in `lunos:http`.
+pub(crate) const SCHEME: &str = "lunos:";
+
+/// The prefix of the Node.js compatibility modules, as in `node:fs`. Like in
+/// Node they can also be imported by their bare name, `fs`.
+pub(crate) const NODE_SCHEME: &str = "node:";
+
+#[derive(Clone, Copy)]
+pub(crate) enum BuiltinSource<'a> {
+    /// Functions of the `Lunos` global with the same names, see
+    /// `Lunos::bind_to_context`.
+    Lunos(&'static [&'static str]),
+    /// Native functions a runtime was built with, see
+    /// `RuntimeBuilder::module`.
+    Native(&'a [String]),
+    /// An ES module shipped with the runtime, see the `node` directory.
+    Script(&'static str),
+}
+
+/// A module that ships with the runtime, or that a runtime was built with.
+#[derive(Clone, Copy)]
+pub(crate) struct Builtin<'a> {
+    pub name: &'a str,
+    pub source: BuiltinSource<'a>,
+}
+
+/// A module of native functions, in `__lunos_native_modules[name]`.
+pub(crate) struct NativeModule {
+    pub name: String,
+    pub exports: Vec<String>,
+}
+
+impl NativeModule {
+    fn builtin(&self) -> Builtin<'_> {
+        Builtin {
+            name: &self.name,
+            source: BuiltinSource::Native(&self.exports),
+        }
+    }
+}
+
+static BUILTINS: &[Builtin<'static>] = &[
+    Builtin {
+        name: "lunos:fs",
+        source: BuiltinSource::Lunos(&["loadFile"]),
+    },
+    Builtin {
+        name: "lunos:http",
+        source: BuiltinSource::Lunos(&["serve"]),
+    },
+    Builtin {
+        name: "lunos:process",
+        source: BuiltinSource::Lunos(&["argv", "exit", "input"]),
+    },
+    Builtin {
+        name: "lunos:shell",
+        source: BuiltinSource::Lunos(&["shell"]),
+    },
+    Builtin {
+        name: "node:buffer",
+        source: BuiltinSource::Script(include_str!("node/buffer.js")),
+    },
+    Builtin {
+        name: "node:child_process",
+        source: BuiltinSource::Script(include_str!("node/child_process.js")),
+    },
+    Builtin {
+        name: "node:events",
+        source: BuiltinSource::Script(include_str!("node/events.js")),
+    },
+    Builtin {
+        name: "node:fs",
+        source: BuiltinSource::Script(include_str!("node/fs.js")),
+    },
+    Builtin {
+        name: "node:fs/promises",
+        source: BuiltinSource::Script(include_str!("node/fs_promises.js")),
+    },
+    Builtin {
+        name: "node:http",
+        source: BuiltinSource::Script(include_str!("node/http.js")),
+    },
+    Builtin {
+        name: "node:os",
+        source: BuiltinSource::Script(include_str!("node/os.js")),
+    },
+    Builtin {
+        name: "node:path",
+        source: BuiltinSource::Script(include_str!("node/path.js")),
+    },
+    Builtin {
+        name: "node:process",
+        source: BuiltinSource::Script(include_str!("node/process.js")),
+    },
+    Builtin {
+        name: "node:util",
+        source: BuiltinSource::Script(include_str!("node/util.js")),
+    },
+];
+
+/// Whether `specifier` can only name a built-in module. Bare Node names like
+/// `fs` aren't, a package could be called that.
+pub(crate) fn is_builtin_specifier(specifier: &str) -> bool {
+    specifier.starts_with(SCHEME) || specifier.starts_with(NODE_SCHEME)
+}
+
+/// The built-in modules shipped with the runtime whose group, `lunos:` or
+/// `node:`, it was built with.
+fn shipped(registry: &Registry) -> impl Iterator<Item = Builtin<'static>> + '_ {
+    BUILTINS.iter().copied().filter(|builtin| {
+        if builtin.name.starts_with(SCHEME) {
+            registry.lunos
+        } else {
+            registry.node
+        }
+    })
+}
+
+/// The built-in module `specifier` names, either by its full name or, for
+/// Node modules, by its bare one. The ones shipped with the runtime take
+/// precedence over native ones of the same name.
+pub(crate) fn find<'a>(registry: &'a Registry, specifier: &str) -> Option<Builtin<'a>> {
+    shipped(registry)
+        .find(|builtin| {
+            builtin.name == specifier
+                || builtin
+                    .name
+                    .strip_prefix(NODE_SCHEME)
+                    .is_some_and(|bare| bare == specifier)
+        })
+        .or_else(|| {
+            let native = registry
+                .natives
+                .iter()
+                .find(|native| native.name == specifier);
+            native.map(NativeModule::builtin)
+        })
+}
+
+pub(crate) fn names(registry: &Registry) -> impl Iterator<Item = &str> {
+    let natives = registry.natives.iter().map(|native| native.name.as_str());
+    shipped(registry).map(|builtin| builtin.name).chain(natives)
+}
+
+impl Builtin<'_> {
+    /// The functions of a `lunos:` or native module.
+    fn functions(&self) -> Option<Vec<&str>> {
+        match self.source {
+            BuiltinSource::Lunos(exports) => Some(exports.to_vec()),
+            BuiltinSource::Native(exports) => Some(exports.iter().map(String::as_str).collect()),
+            BuiltinSource::Script(_) => None,
+        }
+    }
+
+    /// The export names of a `lunos:` or native module: its functions plus
+    /// `default`, an object holding all of them. Script modules are scanned
+    /// instead.
+    pub fn export_names(&self) -> Option<Vec<String>> {
+        let names = self
+            .functions()?
+            .into_iter()
+            .chain(["default"])
+            .map(str::to_string)
+            .collect();
+        Some(names)
+    }
+
+    /// The body of a `lunos:` or native module's `define` factory, after
+    /// `function (__lunos_exports) {`.
+    pub fn factory_body(&self) -> String {
+        let Some(functions) = self.functions() else {
+            return String::new();
+        };
+        let names = functions
+            .iter()
+            .map(|name| js_string(name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let module = match self.source {
+            BuiltinSource::Native(_) => format!(", {}", js_string(self.name)),
+            _ => String::new(),
+        };
+        format!(
+            "__lunos_modules.export(__lunos_exports, __lunos_modules.builtin([{names}]{module}));"
+        )
+    }
+}
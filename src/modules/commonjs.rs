@@ -0,0 +1,201 @@
+//! Static analysis of CommonJS modules.
+//!
+//! Much like Node's `cjs-module-lexer`, this only recognises the common ways a
+//! module assigns to `exports`, so ES modules importing it can use named
+//! imports. Anything it can't see is still reachable through `default`.
+
+use super::lexer::{Token, TokenKind, matching_close, string_value};
+use serde_json::{Value, json};
+
+#[derive(Default)]
+pub(crate) struct CommonJsSyntax {
+    /// Specifiers passed as string literals to `require`.
+    pub requires: Vec<String>,
+    /// Names assigned to `exports`/`module.exports`.
+    pub exports: Vec<String>,
+    /// Specifiers whose exports are re-exported wholesale, as in
+    /// `module.exports = require('./other')`.
+    pub reexports: Vec<String>,
+}
+
+impl CommonJsSyntax {
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "requires": self.requires,
+            "exports": self.exports,
+            "reexports": self.reexports,
+        })
+    }
+
+    /// Reads what `to_json` wrote.
+    pub(crate) fn from_json(json: &Value) -> Option<CommonJsSyntax> {
+        let strings = |name: &str| -> Option<Vec<String>> {
+            json.get(name)?
+                .as_array()?
+                .iter()
+                .map(|value| value.as_str().map(str::to_string))
+                .collect()
+        };
+        Some(CommonJsSyntax {
+            requires: strings("requires")?,
+            exports: strings("exports")?,
+            reexports: strings("reexports")?,
+        })
+    }
+}
+
+fn prev_is_dot(src: &str, tokens: &[Token], i: usize) -> bool {
+    i > 0 && (tokens[i - 1].is(src, ".") || tokens[i - 1].is(src, "?."))
+}
+
+fn is_at(src: &str, tokens: &[Token], i: usize, text: &str) -> bool {
+    tokens.get(i).is_some_and(|t| t.is(src, text))
+}
+
+fn string_at(src: &str, tokens: &[Token], i: usize) -> Option<String> {
+    tokens
+        .get(i)
+        .filter(|t| t.kind == TokenKind::Str)
+        .map(|t| string_value(t.text(src)))
+}
+
+/// Index of the token after `exports` or `module.exports` starting at `i`.
+fn exports_target(src: &str, tokens: &[Token], i: usize) -> Option<usize> {
+    if prev_is_dot(src, tokens, i) {
+        return None;
+    }
+    if tokens[i].is(src, "exports") {
+        return Some(i + 1);
+    }
+    if tokens[i].is(src, "module")
+        && is_at(src, tokens, i + 1, ".")
+        && is_at(src, tokens, i + 2, "exports")
+    {
+        return Some(i + 3);
+    }
+    None
+}
+
+/// Returns the specifier of a `require("...")` call starting at `i`.
+pub(crate) fn require_call(src: &str, tokens: &[Token], i: usize) -> Option<String> {
+    if !tokens[i].is(src, "require") || prev_is_dot(src, tokens, i) {
+        return None;
+    }
+    if !is_at(src, tokens, i + 1, "(") || !is_at(src, tokens, i + 3, ")") {
+        return None;
+    }
+    string_at(src, tokens, i + 2)
+}
+
+/// Whether a file without `import`/`export` statements reads like CommonJS.
+pub(crate) fn looks_like_commonjs(src: &str, tokens: &[Token]) -> bool {
+    (0..tokens.len()).any(|i| {
+        tokens[i].kind == TokenKind::Ident
+            && (exports_target(src, tokens, i).is_some()
+                || (tokens[i].is(src, "require")
+                    && !prev_is_dot(src, tokens, i)
+                    && is_at(src, tokens, i + 1, "(")))
+    })
+}
+
+fn push_unique(names: &mut Vec<String>, name: String) {
+    if !names.contains(&name) {
+        names.push(name);
+    }
+}
+
+pub(crate) fn scan(src: &str, tokens: &[Token]) -> CommonJsSyntax {
+    let mut syntax = CommonJsSyntax::default();
+
+    for i in 0..tokens.len() {
+        if tokens[i].kind != TokenKind::Ident {
+            continue;
+        }
+
+        if let Some(specifier) = require_call(src, tokens, i) {
+            push_unique(&mut syntax.requires, specifier);
+            continue;
+        }
+
+        // Object.defineProperty(exports, "name", ...)
+        if tokens[i].is(src, "Object")
+            && is_at(src, tokens, i + 1, ".")
+            && is_at(src, tokens, i + 2, "defineProperty")
+            && is_at(src, tokens, i + 3, "(")
+            && let Some(after) = exports_target(src, tokens, i + 4)
+            && is_at(src, tokens, after, ",")
+            && let Some(name) = string_at(src, tokens, after + 1)
+        {
+            push_unique(&mut syntax.exports, name);
+            continue;
+        }
+
+        let Some(after) = exports_target(src, tokens, i) else {
+            continue;
+        };
+
+        // exports.name = ... / module.exports.name = ...
+        if is_at(src, tokens, after, ".")
+            && let Some(name) = tokens.get(after + 1).filter(|t| t.kind == TokenKind::Ident)
+            && is_at(src, tokens, after + 2, "=")
+        {
+            push_unique(&mut syntax.exports, name.text(src).to_string());
+            continue;
+        }
+
+        // exports["name"] = ...
+        if is_at(src, tokens, after, "[")
+            && let Some(name) = string_at(src, tokens, after + 1)
+            && is_at(src, tokens, after + 2, "]")
+            && is_at(src, tokens, after + 3, "=")
+        {
+            push_unique(&mut syntax.exports, name);
+            continue;
+        }
+
+        if !tokens[i].is(src, "module") || !is_at(src, tokens, after, "=") {
+            continue;
+        }
+
+        // module.exports = require("./other")
+        if let Some(specifier) = require_call(src, tokens, after + 1) {
+            syntax.reexports.push(specifier);
+            continue;
+        }
+
+        // module.exports = { a, b: ..., c() {}, ...require("./other") }
+        if is_at(src, tokens, after + 1, "{")
+            && let Some(close) = matching_close(src, tokens, after + 1)
+        {
+            let mut k = after + 2;
+            while k < close {
+                let token = tokens[k];
+                if token.is(src, "...") {
+                    if let Some(specifier) = require_call(src, tokens, k + 1) {
+                        syntax.reexports.push(specifier);
+                    }
+                } else if matches!(token.kind, TokenKind::Ident | TokenKind::Str)
+                    && (tokens[k - 1].is(src, "{") || tokens[k - 1].is(src, ","))
+                    && tokens
+                        .get(k + 1)
+                        .is_some_and(|t| matches!(t.text(src), "," | "}" | ":" | "("))
+                {
+                    let name = if token.kind == TokenKind::Str {
+                        string_value(token.text(src))
+                    } else {
+                        token.text(src).to_string()
+                    };
+                    push_unique(&mut syntax.exports, name);
+                }
+
+                // skip over nested values so their keys aren't picked up
+                if matches!(token.text(src), "{" | "(" | "[") && token.kind == TokenKind::Punct {
+                    k = matching_close(src, tokens, k).unwrap_or(close);
+                }
+                k += 1;
+            }
+        }
+    }
+
+    syntax
+}
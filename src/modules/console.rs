@@ -1,8 +1,10 @@
+use super::convert::{JsObject, JsString};
+use super::errors::NativeError;
+use super::native::{Args, define_functions, native_function};
+use super::state::State;
 use crate::utility::stdout::write_stdout;
 use once_cell::sync::Lazy;
 use rusty_jsc::*;
-use std::ffi::CString;
-use std::mem::MaybeUninit;
 use std::sync::Mutex;
 
 const BUF_SIZE: usize = 1024 * 1024;
@@ -20,26 +22,6 @@ static COLORS: Lazy<JSColors> = Lazy::new(|| JSColors {
     reset: b"\x1b[0m",
 });
 
-type JSCallback = unsafe extern "C" fn(
-    *const OpaqueJSContext,
-    *mut OpaqueJSValue,
-    *mut OpaqueJSValue,
-    usize,
-    *const *const OpaqueJSValue,
-    *mut *const OpaqueJSValue,
-) -> *const OpaqueJSValue;
-
-static FUNCTION_NAMES: Lazy<[(CString, JSCallback); 4]> = Lazy::new(|| {
-    [
-        (CString::new("log").unwrap(), Console::log_callback),
-        (CString::new("warn").unwrap(), Console::warn_callback),
-        (CString::new("error").unwrap(), Console::error_callback),
-        (CString::new("flush").unwrap(), Console::flush_callback),
-    ]
-});
-
-static CONSOLE_STR: Lazy<CString> = Lazy::new(|| CString::new("console").unwrap());
-
 #[repr(u8)]
 #[derive(Copy, Clone)]
 enum JSType {
@@ -77,36 +59,20 @@ impl Console {
         }
     }
 
-    pub fn bind_to_context(&self, context: *mut OpaqueJSContext) {
+    pub fn bind_to_context(context: *mut OpaqueJSContext) {
         unsafe {
-            let global_object = JSContextGetGlobalObject(context);
-            let console = JSObjectMake(context, std::ptr::null_mut(), std::ptr::null_mut());
-
-            for (name, callback) in FUNCTION_NAMES.iter() {
-                let js_string = JSStringCreateWithUTF8CString(name.as_ptr());
-                let function =
-                    JSObjectMakeFunctionWithCallback(context, js_string, Some(*callback));
-                JSObjectSetProperty(
-                    context,
-                    console,
-                    js_string,
-                    function,
-                    kJSPropertyAttributeNone,
-                    std::ptr::null_mut(),
-                );
-                JSStringRelease(js_string);
-            }
-
-            let console_js_string = JSStringCreateWithUTF8CString(CONSOLE_STR.as_ptr());
-            JSObjectSetProperty(
+            let console = JsObject::new(context);
+            define_functions(
                 context,
-                global_object,
-                console_js_string,
                 console,
-                kJSPropertyAttributeNone,
-                std::ptr::null_mut(),
+                &[
+                    ("log", Self::log_callback),
+                    ("warn", Self::warn_callback),
+                    ("error", Self::error_callback),
+                    ("flush", Self::flush_callback),
+                ],
             );
-            JSStringRelease(console_js_string);
+            JsObject::global(context).set(context, "console", console);
         }
     }
 
@@ -166,121 +132,78 @@ impl Console {
         let value_type = unsafe { Self::get_value_type(context, arg) };
         buffer.extend_from_slice(unsafe { Self::get_value_color(value_type) });
 
-        let mut js_string = std::ptr::null_mut();
+        let mut json = std::ptr::null_mut();
         if matches!(value_type, JSType::Object | JSType::Array) {
-            js_string = unsafe { JSValueCreateJSONString(context, arg, 2, std::ptr::null_mut()) };
+            json = unsafe { JSValueCreateJSONString(context, arg, 2, std::ptr::null_mut()) };
         }
-        if js_string.is_null() {
-            js_string = unsafe { JSValueToStringCopy(context, arg, std::ptr::null_mut()) };
-        }
-        let c_string = unsafe { JSStringGetCharactersPtr(js_string) };
-        let length = unsafe { JSStringGetLength(js_string) };
-
-        if length < 128 {
-            let mut stack_buf = [MaybeUninit::<u8>::uninit(); 256];
-            let mut pos = 0;
-            for i in 0..length {
-                let c = unsafe { *c_string.add(i) };
-                if c < 128 {
-                    stack_buf[pos].write(c as u8);
-                    pos += 1;
-                } else {
-                    let bytes = (c as u16).to_le_bytes();
-                    stack_buf[pos].write(bytes[0]);
-                    stack_buf[pos + 1].write(bytes[1]);
-                    pos += 2;
-                }
-            }
-            buffer.extend_from_slice(unsafe {
-                std::slice::from_raw_parts(stack_buf.as_ptr() as *const u8, pos)
-            });
+        let js_string = if json.is_null() {
+            unsafe { JsString::from_value(context, arg) }
         } else {
-            let rust_string =
-                String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(c_string, length) });
-            buffer.extend_from_slice(rust_string.as_bytes());
-        }
+            unsafe { JsString::from_raw(json) }
+        };
+        js_string.write_utf8(buffer);
 
         buffer.extend_from_slice(COLORS.reset);
-        unsafe { JSStringRelease(js_string) };
     }
 
     #[inline(always)]
-    unsafe fn process_arguments(
-        argument_count: usize,
-        arguments: *const *const OpaqueJSValue,
-        context: *const OpaqueJSContext,
-        buffer: &mut Vec<u8>,
-    ) {
-        for i in 0..argument_count {
-            let arg = unsafe { *arguments.add(i) };
-            unsafe { Self::write_value(context, arg, buffer, i == 0) };
+    fn process_arguments(args: &Args, buffer: &mut Vec<u8>) {
+        for (i, arg) in args.values().enumerate() {
+            unsafe { Self::write_value(args.context(), arg.0, buffer, i == 0) };
         }
         buffer.push(b'\n');
     }
 
-    unsafe extern "C" fn log_callback(
-        context: *const OpaqueJSContext,
-        _: *mut OpaqueJSValue,
-        _: *mut OpaqueJSValue,
-        argument_count: usize,
-        arguments: *const *const OpaqueJSValue,
-        _: *mut *const OpaqueJSValue,
-    ) -> *const OpaqueJSValue {
-        if let Ok(mut buffer) = Console::get_instance().buffer.lock() {
-            unsafe { Self::process_arguments(argument_count, arguments, context, &mut buffer) };
+    native_function! {
+        fn log_callback(args) -> Result<(), NativeError> {
+            let state = State::of(args.context());
+            if let Ok(mut buffer) = state.console.buffer.lock() {
+                Self::process_arguments(&args, &mut buffer);
 
-            if buffer.len() >= CHUNK_SIZE {
-                Self::flush_buffer(&mut buffer);
+                if buffer.len() >= CHUNK_SIZE {
+                    Self::flush_buffer(&mut buffer);
+                }
             }
+            Ok(())
         }
+    }
 
-        unsafe { JSValueMakeUndefined(context) }
+    native_function! {
+        fn warn_callback(args) -> Result<(), NativeError> {
+            let state = State::of(args.context());
+            if let Ok(mut buffer) = state.console.buffer.lock() {
+                buffer.extend_from_slice(COLORS.number);
+                Self::process_arguments(&args, &mut buffer);
+                buffer.extend_from_slice(COLORS.reset);
+            }
+            Ok(())
+        }
     }
 
-    unsafe extern "C" fn warn_callback(
-        context: *const OpaqueJSContext,
-        _: *mut OpaqueJSValue,
-        _: *mut OpaqueJSValue,
-        argument_count: usize,
-        arguments: *const *const OpaqueJSValue,
-        _: *mut *const OpaqueJSValue,
-    ) -> *const OpaqueJSValue {
-        if let Ok(mut buffer) = Console::get_instance().buffer.lock() {
-            buffer.extend_from_slice(COLORS.number);
-            unsafe { Self::process_arguments(argument_count, arguments, context, &mut buffer) };
-            buffer.extend_from_slice(COLORS.reset);
+    native_function! {
+        fn error_callback(args) -> Result<(), NativeError> {
+            let state = State::of(args.context());
+            if let Ok(mut buffer) = state.console.buffer.lock() {
+                buffer.extend_from_slice(b"\x1b[31m");
+                Self::process_arguments(&args, &mut buffer);
+                buffer.extend_from_slice(COLORS.reset);
+            }
+            Ok(())
         }
-        unsafe { JSValueMakeUndefined(context) }
     }
 
-    unsafe extern "C" fn error_callback(
-        context: *const OpaqueJSContext,
-        _: *mut OpaqueJSValue,
-        _: *mut OpaqueJSValue,
-        argument_count: usize,
-        arguments: *const *const OpaqueJSValue,
-        _: *mut *const OpaqueJSValue,
-    ) -> *const OpaqueJSValue {
-        if let Ok(mut buffer) = Console::get_instance().buffer.lock() {
-            buffer.extend_from_slice(b"\x1b[31m");
-            unsafe { Self::process_arguments(argument_count, arguments, context, &mut buffer) };
-            buffer.extend_from_slice(COLORS.reset);
+    native_function! {
+        fn flush_callback(args) -> Result<(), NativeError> {
+            State::of(args.context()).console.flush();
+            Ok(())
         }
-        unsafe { JSValueMakeUndefined(context) }
     }
 
-    unsafe extern "C" fn flush_callback(
-        context: *const OpaqueJSContext,
-        _: *mut OpaqueJSValue,
-        _: *mut OpaqueJSValue,
-        _: usize,
-        _: *const *const OpaqueJSValue,
-        _: *mut *const OpaqueJSValue,
-    ) -> *const OpaqueJSValue {
-        if let Ok(mut buffer) = Console::get_instance().buffer.lock() {
+    /// Writes out what's buffered.
+    pub fn flush(&self) {
+        if let Ok(mut buffer) = self.buffer.lock() {
             Self::flush_buffer(&mut buffer);
         }
-        unsafe { JSValueMakeUndefined(context) }
     }
 
     #[inline(always)]
@@ -292,10 +215,4 @@ impl Console {
             buffer.clear();
         }
     }
-
-    #[inline(always)]
-    fn get_instance() -> &'static Console {
-        static INSTANCE: Lazy<Console> = Lazy::new(Console::new);
-        &INSTANCE
-    }
 }
@@ -0,0 +1,537 @@
+//! Conversions between JavaScript values and Rust types.
+//!
+//! Native functions take their arguments as `FromJs` types and return a
+//! `ToJs` one, see `native_function!`. Strings cross over as UTF-16, so they
+//! may hold any character, NUL included.
+//!
+//! The wrappers hold raw JavaScriptCore pointers, which stay alive while
+//! they're on the stack; store a value and it needs `JSValueProtect`.
+
+use super::errors::{ErrorClass, NativeError};
+use rusty_jsc::*;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+/// A value that isn't looked into, as JavaScript gave it.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy)]
+pub struct JsValue(pub *const OpaqueJSValue);
+
+/// Any object, functions and arrays included.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy)]
+pub struct JsObject(pub *mut OpaqueJSValue);
+
+/// An object that can be called.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy)]
+pub struct JsFunction(pub *mut OpaqueJSValue);
+
+/// An owned JavaScriptCore string, released when dropped.
+pub struct JsString(*mut OpaqueJSString);
+
+/// Why a value couldn't be converted.
+#[derive(Debug)]
+pub enum Mismatch {
+    /// The value isn't of the type asked for.
+    Type,
+    /// The value is a number the type can't hold, which must be as described.
+    Range(String),
+}
+
+pub trait FromJs: Sized {
+    /// The type the value must be of, as the `TypeError` names it.
+    const EXPECTED: &'static str;
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch>;
+}
+
+pub trait ToJs {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue;
+}
+
+impl JsString {
+    pub fn new(value: &str) -> JsString {
+        let units: Vec<u16> = value.encode_utf16().collect();
+        JsString(unsafe { JSStringCreateWithCharacters(units.as_ptr(), units.len()) })
+    }
+
+    /// `value` converted to a string, the way `String(value)` does it.
+    pub unsafe fn from_value(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> JsString {
+        JsString(unsafe { JSValueToStringCopy(context, value, std::ptr::null_mut()) })
+    }
+
+    /// Takes over a string some JavaScriptCore call created.
+    pub unsafe fn from_raw(string: *mut OpaqueJSString) -> JsString {
+        JsString(string)
+    }
+
+    pub fn as_ptr(&self) -> *mut OpaqueJSString {
+        self.0
+    }
+
+    pub fn units(&self) -> &[u16] {
+        unsafe { units(self.0) }
+    }
+
+    /// Appends the string to `out` as UTF-8, with lone surrogates replaced.
+    pub fn write_utf8(&self, out: &mut Vec<u8>) {
+        let units = self.units();
+        if units.iter().all(|&unit| unit < 0x80) {
+            out.extend(units.iter().map(|&unit| unit as u8));
+            return;
+        }
+        let mut bytes = [0; 4];
+        for c in char::decode_utf16(units.iter().copied()) {
+            let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+            out.extend_from_slice(c.encode_utf8(&mut bytes).as_bytes());
+        }
+    }
+}
+
+unsafe fn units<'a>(string: *mut OpaqueJSString) -> &'a [u16] {
+    unsafe {
+        let length = JSStringGetLength(string);
+        if length == 0 {
+            return &[];
+        }
+        std::slice::from_raw_parts(JSStringGetCharactersPtr(string), length)
+    }
+}
+
+impl std::fmt::Display for JsString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&String::from_utf16_lossy(self.units()))
+    }
+}
+
+impl Drop for JsString {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { JSStringRelease(self.0) };
+        }
+    }
+}
+
+impl Mismatch {
+    /// The error thrown for `value`, which is the argument or property
+    /// `name` and should have been a `T`.
+    pub unsafe fn error<T: FromJs>(
+        self,
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+        name: &str,
+        kind: &str,
+    ) -> NativeError {
+        let received = unsafe { received(context, value) };
+        match self {
+            Mismatch::Type => NativeError::invalid_arg(format!(
+                "The \"{name}\" {kind} must be of type {}. Received {received}",
+                T::EXPECTED
+            )),
+            Mismatch::Range(range) => NativeError::new(
+                ErrorClass::RangeError,
+                "ERR_OUT_OF_RANGE",
+                format!(
+                    "The value of \"{name}\" is out of range. It must be {range}. Received {received}"
+                ),
+            ),
+        }
+    }
+}
+
+/// How errors describe the value they got, as Node does.
+unsafe fn received(context: *const OpaqueJSContext, value: *const OpaqueJSValue) -> String {
+    unsafe {
+        if JSValueIsUndefined(context, value) {
+            "undefined".to_string()
+        } else if JSValueIsNull(context, value) {
+            "null".to_string()
+        } else if JSValueIsString(context, value) {
+            format!("type string ('{}')", JsString::from_value(context, value))
+        } else if JSValueIsNumber(context, value) {
+            format!("type number ({})", JsString::from_value(context, value))
+        } else if JSValueIsBoolean(context, value) {
+            format!("type boolean ({})", JSValueToBoolean(context, value))
+        } else if JSValueIsArray(context, value) {
+            "an instance of Array".to_string()
+        } else if JSValueIsObject(context, value)
+            && JSObjectIsFunction(context, value as *mut OpaqueJSValue)
+        {
+            "type function".to_string()
+        } else if JSValueIsObject(context, value) {
+            "an instance of Object".to_string()
+        } else {
+            format!("type {}", JsString::from_value(context, value))
+        }
+    }
+}
+
+impl JsValue {
+    pub unsafe fn get<T: FromJs>(self, context: *const OpaqueJSContext) -> Result<T, Mismatch> {
+        unsafe { T::from_js(context, self.0) }
+    }
+}
+
+impl JsObject {
+    pub unsafe fn new(context: *const OpaqueJSContext) -> JsObject {
+        JsObject(unsafe { JSObjectMake(context, std::ptr::null_mut(), std::ptr::null_mut()) })
+    }
+
+    pub unsafe fn global(context: *const OpaqueJSContext) -> JsObject {
+        JsObject(unsafe { JSContextGetGlobalObject(context) })
+    }
+
+    pub unsafe fn property(self, context: *const OpaqueJSContext, name: &str) -> JsValue {
+        let name = JsString::new(name);
+        JsValue(unsafe {
+            JSObjectGetProperty(context, self.0, name.as_ptr(), std::ptr::null_mut())
+        })
+    }
+
+    /// The property `name` as a `T`, throwing a `TypeError` that names it if
+    /// it isn't one.
+    pub unsafe fn get<T: FromJs>(
+        self,
+        context: *const OpaqueJSContext,
+        name: &str,
+    ) -> Result<T, NativeError> {
+        unsafe {
+            let value = self.property(context, name);
+            T::from_js(context, value.0)
+                .map_err(|mismatch| mismatch.error::<T>(context, value.0, name, "property"))
+        }
+    }
+
+    pub unsafe fn set(self, context: *const OpaqueJSContext, name: &str, value: impl ToJs) {
+        let name = JsString::new(name);
+        unsafe {
+            let value = value.to_js(context);
+            JSObjectSetProperty(
+                context,
+                self.0,
+                name.as_ptr(),
+                value,
+                kJSPropertyAttributeNone,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+
+    /// The names of the object's enumerable properties.
+    pub unsafe fn keys(self, context: *const OpaqueJSContext) -> Vec<String> {
+        unsafe {
+            let names = JSObjectCopyPropertyNames(context, self.0);
+            // the array owns its names
+            let keys = (0..JSPropertyNameArrayGetCount(names))
+                .map(|index| {
+                    let name = JSPropertyNameArrayGetNameAtIndex(names, index);
+                    String::from_utf16_lossy(units(name))
+                })
+                .collect();
+            JSPropertyNameArrayRelease(names);
+            keys
+        }
+    }
+}
+
+impl JsFunction {
+    /// Calls the function, failing with what it threw.
+    pub unsafe fn call(
+        self,
+        context: *const OpaqueJSContext,
+        this: Option<JsObject>,
+        arguments: &[JsValue],
+    ) -> Result<JsValue, JsValue> {
+        let mut exception: *const OpaqueJSValue = std::ptr::null();
+        let result = unsafe {
+            JSObjectCallAsFunction(
+                context,
+                self.0,
+                this.map_or(std::ptr::null_mut(), |this| this.0),
+                arguments.len(),
+                arguments.as_ptr() as *const *const OpaqueJSValue,
+                &mut exception,
+            )
+        };
+        if exception.is_null() {
+            Ok(JsValue(result))
+        } else {
+            Err(JsValue(exception))
+        }
+    }
+}
+
+impl FromJs for JsValue {
+    const EXPECTED: &'static str = "any";
+
+    unsafe fn from_js(
+        _: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        Ok(JsValue(value))
+    }
+}
+
+impl FromJs for JsObject {
+    const EXPECTED: &'static str = "object";
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        if unsafe { !JSValueIsObject(context, value) } {
+            return Err(Mismatch::Type);
+        }
+        Ok(JsObject(value as *mut OpaqueJSValue))
+    }
+}
+
+impl FromJs for JsFunction {
+    const EXPECTED: &'static str = "function";
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        let object = value as *mut OpaqueJSValue;
+        if unsafe { !JSValueIsObject(context, value) || !JSObjectIsFunction(context, object) } {
+            return Err(Mismatch::Type);
+        }
+        Ok(JsFunction(object))
+    }
+}
+
+impl FromJs for String {
+    const EXPECTED: &'static str = "string";
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        if unsafe { !JSValueIsString(context, value) } {
+            return Err(Mismatch::Type);
+        }
+        Ok(unsafe { JsString::from_value(context, value) }.to_string())
+    }
+}
+
+impl FromJs for bool {
+    const EXPECTED: &'static str = "boolean";
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        if unsafe { !JSValueIsBoolean(context, value) } {
+            return Err(Mismatch::Type);
+        }
+        Ok(unsafe { JSValueToBoolean(context, value) })
+    }
+}
+
+impl FromJs for f64 {
+    const EXPECTED: &'static str = "number";
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        if unsafe { !JSValueIsNumber(context, value) } {
+            return Err(Mismatch::Type);
+        }
+        Ok(unsafe { JSValueToNumber(context, value, std::ptr::null_mut()) })
+    }
+}
+
+macro_rules! integer {
+    ($($ty:ty),*) => {$(
+        impl FromJs for $ty {
+            const EXPECTED: &'static str = "number";
+
+            unsafe fn from_js(
+                context: *const OpaqueJSContext,
+                value: *const OpaqueJSValue,
+            ) -> Result<Self, Mismatch> {
+                let number = unsafe { f64::from_js(context, value) }?;
+                if number.fract() != 0.0 || number < <$ty>::MIN as f64 || number > <$ty>::MAX as f64 {
+                    return Err(Mismatch::Range(format!(
+                        "an integer >= {} and <= {}",
+                        <$ty>::MIN,
+                        <$ty>::MAX
+                    )));
+                }
+                Ok(number as $ty)
+            }
+        }
+
+        impl ToJs for $ty {
+            unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+                unsafe { JSValueMakeNumber(context, self as f64) }
+            }
+        }
+    )*};
+}
+
+integer!(i32, u32, u16, u8, usize);
+
+/// `undefined` and `null` are `None`, so an optional argument may be left
+/// out.
+impl<T: FromJs> FromJs for Option<T> {
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        unsafe {
+            if JSValueIsUndefined(context, value) || JSValueIsNull(context, value) {
+                return Ok(None);
+            }
+            T::from_js(context, value).map(Some)
+        }
+    }
+}
+
+impl<T: FromJs> FromJs for Vec<T> {
+    const EXPECTED: &'static str = "Array";
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        unsafe {
+            if !JSValueIsArray(context, value) {
+                return Err(Mismatch::Type);
+            }
+            let array = JsObject(value as *mut OpaqueJSValue);
+            let length: u32 = array.property(context, "length").get(context)?;
+            (0..length)
+                .map(|index| {
+                    let item =
+                        JSObjectGetPropertyAtIndex(context, array.0, index, std::ptr::null_mut());
+                    T::from_js(context, item)
+                })
+                .collect()
+        }
+    }
+}
+
+impl<T: FromJs, S: BuildHasher + Default> FromJs for HashMap<String, T, S> {
+    const EXPECTED: &'static str = "object";
+
+    unsafe fn from_js(
+        context: *const OpaqueJSContext,
+        value: *const OpaqueJSValue,
+    ) -> Result<Self, Mismatch> {
+        unsafe {
+            let object = JsObject::from_js(context, value)?;
+            object
+                .keys(context)
+                .into_iter()
+                .map(|key| {
+                    let value = object.property(context, &key);
+                    Ok((key, T::from_js(context, value.0)?))
+                })
+                .collect()
+        }
+    }
+}
+
+impl ToJs for JsValue {
+    unsafe fn to_js(self, _: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        self.0
+    }
+}
+
+impl ToJs for JsObject {
+    unsafe fn to_js(self, _: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        self.0
+    }
+}
+
+impl ToJs for JsFunction {
+    unsafe fn to_js(self, _: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        self.0
+    }
+}
+
+impl ToJs for () {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe { JSValueMakeUndefined(context) }
+    }
+}
+
+impl ToJs for bool {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe { JSValueMakeBoolean(context, self) }
+    }
+}
+
+impl ToJs for f64 {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe { JSValueMakeNumber(context, self) }
+    }
+}
+
+impl ToJs for &str {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        let string = JsString::new(self);
+        unsafe { JSValueMakeString(context, string.as_ptr()) }
+    }
+}
+
+impl ToJs for String {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe { self.as_str().to_js(context) }
+    }
+}
+
+/// `None` is `null`.
+impl<T: ToJs> ToJs for Option<T> {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe {
+            match self {
+                Some(value) => value.to_js(context),
+                None => JSValueMakeNull(context),
+            }
+        }
+    }
+}
+
+impl<T: ToJs> ToJs for Vec<T> {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe {
+            let array = JSObjectMakeArray(context, 0, std::ptr::null(), std::ptr::null_mut());
+            for (index, item) in self.into_iter().enumerate() {
+                let item = item.to_js(context);
+                JSObjectSetPropertyAtIndex(
+                    context,
+                    array,
+                    index as u32,
+                    item,
+                    std::ptr::null_mut(),
+                );
+            }
+            array
+        }
+    }
+}
+
+impl<T: ToJs, S> ToJs for HashMap<String, T, S> {
+    unsafe fn to_js(self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe {
+            let object = JsObject::new(context);
+            for (name, value) in self {
+                object.set(context, &name, value);
+            }
+            object.0
+        }
+    }
+}
@@ -0,0 +1,247 @@
+// Lunos decorator helpers.
+//
+// The TypeScript transform (typescript/decorators.rs) rewrites a decorated
+// class into calls to these. TC39 decorators wrap the class in `standard`,
+// and its member decorators are applied from a static block at the top of
+// the class body. With `experimentalDecorators` the class goes through `legacy`
+// and `decorate`, the way `tsc` emits them with `__decorate`.
+(function (global) {
+    "use strict";
+
+    if (typeof Symbol.metadata !== "symbol") {
+        Object.defineProperty(Symbol, "metadata", { value: Symbol.for("Symbol.metadata") });
+    }
+
+    function expectFunction(value, what) {
+        if (value !== undefined && typeof value !== "function") {
+            throw new TypeError(`${what} must be a function`);
+        }
+        return value;
+    }
+
+    function run(thisArg, initializers) {
+        for (const initializer of initializers) {
+            initializer.call(thisArg);
+        }
+    }
+
+    function publicAccess(kind, name) {
+        const access = { has: (object) => name in object };
+        if (kind !== "setter") {
+            access.get = (object) => object[name];
+        }
+        if (kind === "setter" || kind === "field" || kind === "accessor") {
+            access.set = (object, value) => {
+                object[name] = value;
+            };
+        }
+        return access;
+    }
+
+    // The state of one decorated class while it's defined.
+    class Decoration {
+        constructor() {
+            this.metadata = null;
+            // the decorators of each member, by slot, evaluated in order
+            this.decorators = [];
+            this.instanceInitializers = [];
+            this.staticInitializers = [];
+            // initializers of field values, and the final descriptors of
+            // private members, by slot
+            this.fields = [];
+            this.privates = [];
+        }
+
+        begin(cls, decorators) {
+            this.decorators = decorators;
+            const parent = Object.getPrototypeOf(cls);
+            this.metadata = Object.create((parent && parent[Symbol.metadata]) || null);
+        }
+
+        member(cls, slot, context) {
+            const decorators = this.decorators[slot];
+            const { kind, name } = context;
+            const isStatic = context.static;
+            const isPrivate = context.private;
+            const target = isStatic ? cls : cls.prototype;
+            const extraInitializers = isStatic ? this.staticInitializers : this.instanceInitializers;
+            const access = context.access || publicAccess(kind, name);
+            const descriptor = isPrivate
+                ? { value: context.value, get: context.get, set: context.set }
+                : Object.getOwnPropertyDescriptor(target, name) || {};
+            const key = kind === "getter" ? "get" : kind === "setter" ? "set" : "value";
+            const initializers = [];
+            let done = false;
+
+            for (let i = decorators.length - 1; i >= 0; i--) {
+                const decorator = decorators[i];
+                const value =
+                    kind === "field" ? undefined
+                    : kind === "accessor" ? { get: descriptor.get, set: descriptor.set }
+                    : descriptor[key];
+                const result = decorator(value, {
+                    kind,
+                    name,
+                    static: isStatic,
+                    private: isPrivate,
+                    access,
+                    metadata: this.metadata,
+                    addInitializer(initializer) {
+                        if (done) {
+                            throw new TypeError("Cannot add initializers after decoration has completed");
+                        }
+                        extraInitializers.push(expectFunction(initializer, "An initializer"));
+                    },
+                });
+                if (result === undefined) {
+                    continue;
+                }
+                if (kind === "field") {
+                    initializers.unshift(expectFunction(result, "A field decorator's result"));
+                } else if (kind === "accessor") {
+                    if (result === null || typeof result !== "object") {
+                        throw new TypeError("An accessor decorator must return an object");
+                    }
+                    descriptor.get = expectFunction(result.get, "get") || descriptor.get;
+                    descriptor.set = expectFunction(result.set, "set") || descriptor.set;
+                    if (expectFunction(result.init, "init")) {
+                        initializers.unshift(result.init);
+                    }
+                } else {
+                    descriptor[key] = expectFunction(result, `A ${kind} decorator's result`);
+                }
+            }
+            done = true;
+
+            if (kind === "field" || kind === "accessor") {
+                this.fields[slot] = initializers;
+            }
+            if (isPrivate) {
+                this.privates[slot] = descriptor;
+            } else if (kind !== "field") {
+                Object.defineProperty(target, name, descriptor);
+            }
+        }
+
+        end(cls) {
+            Object.defineProperty(cls, Symbol.metadata, {
+                value: this.metadata,
+                enumerable: true,
+                configurable: true,
+                writable: true,
+            });
+            run(cls, this.staticInitializers);
+        }
+
+        // The first instance field, so it runs before the others.
+        construct(instance) {
+            run(instance, this.instanceInitializers);
+        }
+
+        init(instance, slot, value) {
+            for (const initializer of this.fields[slot] || []) {
+                value = initializer.call(instance, value);
+            }
+            return value;
+        }
+
+        value(slot) {
+            return this.privates[slot].value;
+        }
+
+        get(slot) {
+            return this.privates[slot].get;
+        }
+
+        set(slot) {
+            return this.privates[slot].set;
+        }
+    }
+
+    function standard(decorators, define) {
+        const decoration = new Decoration();
+        let cls = define(decoration);
+        const name = cls.name;
+        const initializers = [];
+        let done = false;
+        for (let i = decorators.length - 1; i >= 0; i--) {
+            const decorator = decorators[i];
+            const result = decorator(cls, {
+                kind: "class",
+                name,
+                metadata: decoration.metadata,
+                addInitializer(initializer) {
+                    if (done) {
+                        throw new TypeError("Cannot add initializers after decoration has completed");
+                    }
+                    initializers.push(expectFunction(initializer, "An initializer"));
+                },
+            });
+            if (result !== undefined) {
+                cls = expectFunction(result, "A class decorator's result");
+            }
+        }
+        done = true;
+        run(cls, initializers);
+        return cls;
+    }
+
+    function legacy(cls, apply) {
+        return apply(cls);
+    }
+
+    // `__decorate` of tsc: a class decorator gets the class, a member
+    // decorator the prototype or class, the key and the descriptor.
+    function decorate(decorators, target, key, descriptor) {
+        const count = arguments.length;
+        if (typeof Reflect === "object" && typeof Reflect.decorate === "function") {
+            return count < 3
+                ? Reflect.decorate(decorators, target)
+                : Reflect.decorate(decorators, target, key, descriptor);
+        }
+        let result =
+            count < 3 ? target
+            : descriptor === null ? (descriptor = Object.getOwnPropertyDescriptor(target, key))
+            : descriptor;
+        for (let i = decorators.length - 1; i >= 0; i--) {
+            const decorator = decorators[i];
+            if (decorator) {
+                result =
+                    (count < 3 ? decorator(result)
+                    : count > 3 ? decorator(target, key, result)
+                    : decorator(target, key)) || result;
+            }
+        }
+        if (count > 3 && result) {
+            Object.defineProperty(target, key, result);
+        }
+        return result;
+    }
+
+    // `emitDecoratorMetadata` goes through `Reflect.metadata`, so it only has
+    // an effect with a polyfill like `reflect-metadata` loaded.
+    function metadata(key, value) {
+        if (typeof Reflect === "object" && typeof Reflect.metadata === "function") {
+            return Reflect.metadata(key, value);
+        }
+    }
+
+    function param(index, decorator) {
+        return (target, key) => decorator(target, key, index);
+    }
+
+    // The runtime value of a type named in metadata, `Object` when it has
+    // none, like an interface.
+    function type(get) {
+        try {
+            const value = get();
+            return typeof value === "function" ? value : Object;
+        } catch {
+            return Object;
+        }
+    }
+
+    Object.defineProperty(global, "__lunos_decorators", {
+        value: Object.freeze({ standard, legacy, decorate, metadata, param, type }),
+    });
+})(globalThis);
@@ -0,0 +1,149 @@
+//! Errors thrown by native functions.
+//!
+//! Scripts get real `Error`, `TypeError` and `RangeError` instances with
+//! Node's `code` property, plus `errno`, `syscall` and `path` for failed
+//! system calls, so they can `catch (e)` and branch on `e.code`.
+
+use super::convert::{JsObject, ToJs};
+use rusty_jsc::*;
+use std::io::{self, ErrorKind};
+
+/// The constructor a `NativeError` is thrown with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Error,
+    TypeError,
+    RangeError,
+}
+
+impl ErrorClass {
+    fn name(self) -> &'static str {
+        match self {
+            ErrorClass::Error => "Error",
+            ErrorClass::TypeError => "TypeError",
+            ErrorClass::RangeError => "RangeError",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NativeError {
+    pub class: ErrorClass,
+    pub code: &'static str,
+    pub message: String,
+    /// Negated like Node's, for errors that come from the system.
+    pub errno: Option<i32>,
+    pub syscall: Option<&'static str>,
+    pub path: Option<String>,
+}
+
+impl NativeError {
+    pub fn new(class: ErrorClass, code: &'static str, message: impl Into<String>) -> Self {
+        NativeError {
+            class,
+            code,
+            message: message.into(),
+            errno: None,
+            syscall: None,
+            path: None,
+        }
+    }
+
+    /// An argument of the wrong type, `ERR_INVALID_ARG_TYPE`.
+    pub fn invalid_arg(message: impl Into<String>) -> Self {
+        Self::new(ErrorClass::TypeError, "ERR_INVALID_ARG_TYPE", message)
+    }
+
+    /// A call with too few arguments, `ERR_MISSING_ARGS`.
+    pub fn missing_args(message: impl Into<String>) -> Self {
+        Self::new(ErrorClass::TypeError, "ERR_MISSING_ARGS", message)
+    }
+
+    /// A failed system call, with the message Node would give it:
+    /// `ENOENT: no such file or directory, open 'missing.txt'`.
+    pub fn io(error: &io::Error, syscall: &'static str, path: Option<&str>) -> Self {
+        let (code, description) = io_code(error.kind());
+        let message = match path {
+            Some(path) => format!("{code}: {description}, {syscall} '{path}'"),
+            None => format!("{code}: {description}, {syscall}"),
+        };
+        NativeError {
+            class: ErrorClass::Error,
+            code,
+            message,
+            errno: error.raw_os_error().map(|errno| -errno),
+            syscall: Some(syscall),
+            path: path.map(str::to_string),
+        }
+    }
+
+    /// Sets the error as the exception of a native function, which returns
+    /// what this does.
+    pub unsafe fn throw(
+        &self,
+        context: *const OpaqueJSContext,
+        exception: *mut *const OpaqueJSValue,
+    ) -> *const OpaqueJSValue {
+        unsafe {
+            if !exception.is_null() {
+                *exception = self.to_value(context);
+            }
+            std::ptr::null()
+        }
+    }
+
+    /// The error as a JavaScript object, made by the global constructor.
+    pub unsafe fn to_value(&self, context: *const OpaqueJSContext) -> *const OpaqueJSValue {
+        unsafe {
+            let constructor = JsObject::global(context).property(context, self.class.name());
+            let message = self.message.as_str().to_js(context);
+            let error = match constructor.get::<JsObject>(context) {
+                Ok(constructor) => JSObjectCallAsConstructor(
+                    context,
+                    constructor.0,
+                    1,
+                    &message,
+                    std::ptr::null_mut(),
+                ),
+                // the global was replaced by something that isn't a constructor
+                Err(_) => JSObjectMakeError(context, 1, &message, std::ptr::null_mut()),
+            };
+            if error.is_null() {
+                return message;
+            }
+
+            let error = JsObject(error);
+            error.set(context, "code", self.code);
+            if let Some(errno) = self.errno {
+                error.set(context, "errno", errno);
+            }
+            if let Some(syscall) = self.syscall {
+                error.set(context, "syscall", syscall);
+            }
+            if let Some(path) = &self.path {
+                error.set(context, "path", path.as_str());
+            }
+            error.0
+        }
+    }
+}
+
+/// Node's code for an I/O error, and how its messages describe it.
+pub fn io_code(kind: ErrorKind) -> (&'static str, &'static str) {
+    match kind {
+        ErrorKind::NotFound => ("ENOENT", "no such file or directory"),
+        ErrorKind::PermissionDenied => ("EACCES", "permission denied"),
+        ErrorKind::AlreadyExists => ("EEXIST", "file already exists"),
+        ErrorKind::NotADirectory => ("ENOTDIR", "not a directory"),
+        ErrorKind::IsADirectory => ("EISDIR", "illegal operation on a directory"),
+        ErrorKind::DirectoryNotEmpty => ("ENOTEMPTY", "directory not empty"),
+        ErrorKind::AddrInUse => ("EADDRINUSE", "address already in use"),
+        ErrorKind::AddrNotAvailable => ("EADDRNOTAVAIL", "address not available"),
+        ErrorKind::ConnectionRefused => ("ECONNREFUSED", "connection refused"),
+        ErrorKind::ConnectionReset => ("ECONNRESET", "connection reset by peer"),
+        ErrorKind::BrokenPipe => ("EPIPE", "broken pipe"),
+        ErrorKind::TimedOut => ("ETIMEDOUT", "operation timed out"),
+        ErrorKind::InvalidInput => ("EINVAL", "invalid argument"),
+        _ => ("EIO", "i/o error"),
+    }
+}
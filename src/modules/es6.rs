@@ -1,478 +1,1989 @@
-use crate::{LOCAL_RUNTIME, RUNTIME};
-use regex::Regex;
-use rusty_jsc::OpaqueJSContext;
+use super::bindings::{self, Usage};
+use super::builtins::{self, Builtin, BuiltinSource};
+use super::commonjs::{self, CommonJsSyntax};
+use super::convert::{JsString, JsValue};
+use super::jsx::{self, JsxOptions};
+use super::lexer::{Token, TokenKind, matching_close, string_value, tokenize};
+use super::native::{Failure, define_internal, native_function};
+use super::registry::Registry;
+use super::remote;
+use super::resolver::{self, ResolveError, ResolveErrorKind, ResolveMode};
+use super::source_map::{Generated, SourceMap};
+use super::state::State;
+use super::transform_cache;
+use super::typescript::{self, TsOptions};
+use rayon::prelude::*;
+use rusty_jsc::*;
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Read;
 use std::path::{Path, PathBuf};
 
-pub(crate) fn get_context() -> *mut OpaqueJSContext {
-    LOCAL_RUNTIME.with(|runtime| {
-        let mut runtime = runtime.borrow_mut();
-        if runtime.is_none() {
-            *runtime = Some(RUNTIME.clone());
-        }
-        runtime.as_ref().unwrap().context
-    })
+const LOADER_JS: &str = include_str!("loader.js");
+/// The helpers decorated TypeScript classes are rewritten to call.
+const DECORATORS_JS: &str = include_str!("decorators.js");
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ModuleKind {
+    Module,
+    CommonJs,
+    Json,
+    /// A file imported `with { type: "text" }`, exported as a string.
+    Text,
+    /// A file imported `with { type: "bytes" }`, exported as a `Uint8Array`.
+    Bytes,
+    /// A `lunos:` module, which evaluates like an ES module. The `node:` ones
+    /// are plain ES modules.
+    Builtin,
 }
 
-fn find_node_modules(start_dir: &Path) -> Option<PathBuf> {
-    let mut current_dir = start_dir.to_path_buf();
+impl ModuleKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ModuleKind::Module | ModuleKind::Builtin => "module",
+            ModuleKind::CommonJs => "commonjs",
+            ModuleKind::Json => "json",
+            ModuleKind::Text => "text",
+            ModuleKind::Bytes => "bytes",
+        }
+    }
 
-    if !current_dir.exists() {
-        if let Ok(cwd) = std::env::current_dir() {
-            current_dir = cwd;
-        } else {
-            return None;
+    /// The kind requested by a `type` import attribute.
+    fn from_import_type(import_type: &str) -> Option<ModuleKind> {
+        match import_type {
+            "json" => Some(ModuleKind::Json),
+            "text" => Some(ModuleKind::Text),
+            "bytes" => Some(ModuleKind::Bytes),
+            _ => None,
         }
     }
+}
+
+struct ImportBinding {
+    imported: String,
+    local: String,
+    /// Byte offset of the imported name, for error reporting.
+    position: usize,
+}
+
+struct StaticImport {
+    specifier: String,
+    bindings: Vec<ImportBinding>,
+    /// Byte offset of the specifier string.
+    position: usize,
+    import_type: Option<String>,
+}
 
-    let node_modules = current_dir.join("node_modules");
-    if node_modules.exists() && node_modules.is_dir() {
-        return Some(node_modules);
+impl StaticImport {
+    fn key(&self) -> String {
+        request_key(&self.specifier, self.import_type.as_deref())
     }
+}
 
-    while current_dir.pop() {
-        let node_modules = current_dir.join("node_modules");
-        if node_modules.exists() && node_modules.is_dir() {
-            return Some(node_modules);
-        }
+/// Identifies a dependency within its importer: the specifier, plus the
+/// `type` import attribute since `./a.js` and `./a.js` as text are different
+/// modules.
+fn request_key(specifier: &str, import_type: Option<&str>) -> String {
+    match import_type {
+        Some(import_type) => format!("{specifier}\0{import_type}"),
+        None => specifier.to_string(),
     }
+}
 
-    None
+/// The specifier part of a `request_key`.
+fn key_specifier(key: &str) -> &str {
+    key.split('\0').next().unwrap_or(key)
 }
 
-fn resolve_package_main(package_dir: &Path) -> Option<PathBuf> {
-    let package_json_path = package_dir.join("package.json");
+enum ExportEntry {
+    Local {
+        exported: String,
+        local: String,
+    },
+    // `request` is the `request_key` of the module exported from
+    Indirect {
+        exported: String,
+        imported: String,
+        request: String,
+        position: usize,
+    },
+    Namespace {
+        exported: String,
+        request: String,
+    },
+    Star {
+        request: String,
+    },
+}
 
-    if !package_json_path.exists() {
-        let index_js = package_dir.join("index.js");
-        if index_js.exists() {
-            return Some(index_js);
-        }
-        return None;
-    }
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+}
 
-    let mut file = match fs::File::open(&package_json_path) {
-        Ok(file) => file,
-        Err(_) => return None,
-    };
+/// A specifier an ES module links against.
+struct ModuleRequest {
+    key: String,
+    specifier: String,
+    /// Byte offset of its first occurrence.
+    position: usize,
+    /// The `type` import attribute of its first occurrence.
+    import_type: Option<String>,
+}
 
-    let mut contents = String::new();
-    if file.read_to_string(&mut contents).is_err() {
-        return None;
-    }
+/// A specifier to resolve while loading the module graph.
+struct GraphRequest {
+    key: String,
+    specifier: String,
+    mode: ResolveMode,
+    import_type: Option<String>,
+    /// Where a static import names it. `None` for `require` and `import()`,
+    /// which are left to fail at runtime instead.
+    position: Option<usize>,
+}
 
-    for line in contents.lines() {
-        let line = line.trim();
-        if line.starts_with("\"main\"") || line.starts_with("'main'") {
-            let parts: Vec<&str> = line.split(':').collect();
-            if parts.len() >= 2 {
-                let main_value = parts[1].trim();
-                let main_value = main_value.trim_end_matches(',');
-                let main_value = main_value.trim_matches(|c| c == '"' || c == '\'');
+#[derive(Default)]
+struct EsmSyntax {
+    imports: Vec<StaticImport>,
+    exports: Vec<ExportEntry>,
+    /// Every specifier the module links against, in source order.
+    requests: Vec<ModuleRequest>,
+    dynamic_imports: Vec<String>,
+    edits: Vec<Edit>,
+    has_module_syntax: bool,
+    /// Whether the body refers to `import.meta`.
+    uses_meta: bool,
+    /// Whether the body uses `await` outside of any function.
+    has_top_level_await: bool,
+}
 
-                let main_path = package_dir.join(main_value);
-                if main_path.exists() {
-                    return Some(main_path);
+impl EsmSyntax {
+    /// The syntax as JSON, for the transform cache.
+    fn to_json(&self) -> Value {
+        json!({
+            "imports": self.imports.iter().map(|import| json!({
+                "specifier": import.specifier,
+                "bindings": import
+                    .bindings
+                    .iter()
+                    .map(|b| json!([b.imported, b.local, b.position]))
+                    .collect::<Vec<_>>(),
+                "position": import.position,
+                "importType": import.import_type,
+            })).collect::<Vec<_>>(),
+            "exports": self.exports.iter().map(|export| match export {
+                ExportEntry::Local { exported, local } => json!(["local", exported, local]),
+                ExportEntry::Indirect { exported, imported, request, position } => {
+                    json!(["indirect", exported, imported, request, position])
                 }
+                ExportEntry::Namespace { exported, request } => {
+                    json!(["namespace", exported, request])
+                }
+                ExportEntry::Star { request } => json!(["star", request]),
+            }).collect::<Vec<_>>(),
+            "requests": self.requests.iter().map(|request| json!({
+                "key": request.key,
+                "specifier": request.specifier,
+                "position": request.position,
+                "importType": request.import_type,
+            })).collect::<Vec<_>>(),
+            "dynamicImports": self.dynamic_imports,
+            "edits": self
+                .edits
+                .iter()
+                .map(|edit| json!([edit.start, edit.end, edit.text]))
+                .collect::<Vec<_>>(),
+            "hasModuleSyntax": self.has_module_syntax,
+            "usesMeta": self.uses_meta,
+            "hasTopLevelAwait": self.has_top_level_await,
+        })
+    }
 
-                let main_path_with_js = if !main_value.ends_with(".js") {
-                    package_dir.join(format!("{main_value}.js"))
-                } else {
-                    main_path
-                };
+    /// Reads what `to_json` wrote.
+    fn from_json(json: &Value) -> Option<EsmSyntax> {
+        fn string(value: &Value) -> Option<String> {
+            value.as_str().map(str::to_string)
+        }
+        fn offset(value: &Value) -> Option<usize> {
+            value.as_u64().map(|n| n as usize)
+        }
+        fn each<T>(value: &Value, f: impl Fn(&Value) -> Option<T>) -> Option<Vec<T>> {
+            value.as_array()?.iter().map(f).collect()
+        }
+        let import_type = |value: &Value| match value {
+            Value::Null => Some(None),
+            value => string(value).map(Some),
+        };
 
-                if main_path_with_js.exists() {
-                    return Some(main_path_with_js);
-                }
-            }
+        Some(EsmSyntax {
+            imports: each(&json["imports"], |import| {
+                Some(StaticImport {
+                    specifier: string(&import["specifier"])?,
+                    bindings: each(&import["bindings"], |binding| {
+                        Some(ImportBinding {
+                            imported: string(&binding[0])?,
+                            local: string(&binding[1])?,
+                            position: offset(&binding[2])?,
+                        })
+                    })?,
+                    position: offset(&import["position"])?,
+                    import_type: import_type(&import["importType"])?,
+                })
+            })?,
+            exports: each(&json["exports"], |export| {
+                Some(match export[0].as_str()? {
+                    "local" => ExportEntry::Local {
+                        exported: string(&export[1])?,
+                        local: string(&export[2])?,
+                    },
+                    "indirect" => ExportEntry::Indirect {
+                        exported: string(&export[1])?,
+                        imported: string(&export[2])?,
+                        request: string(&export[3])?,
+                        position: offset(&export[4])?,
+                    },
+                    "namespace" => ExportEntry::Namespace {
+                        exported: string(&export[1])?,
+                        request: string(&export[2])?,
+                    },
+                    "star" => ExportEntry::Star {
+                        request: string(&export[1])?,
+                    },
+                    _ => return None,
+                })
+            })?,
+            requests: each(&json["requests"], |request| {
+                Some(ModuleRequest {
+                    key: string(&request["key"])?,
+                    specifier: string(&request["specifier"])?,
+                    position: offset(&request["position"])?,
+                    import_type: import_type(&request["importType"])?,
+                })
+            })?,
+            dynamic_imports: each(&json["dynamicImports"], string)?,
+            edits: each(&json["edits"], |edit| {
+                Some(Edit {
+                    start: offset(&edit[0])?,
+                    end: offset(&edit[1])?,
+                    text: string(&edit[2])?,
+                })
+            })?,
+            has_module_syntax: json["hasModuleSyntax"].as_bool()?,
+            uses_meta: json["usesMeta"].as_bool()?,
+            has_top_level_await: json["hasTopLevelAwait"].as_bool()?,
+        })
+    }
+
+    /// Records a request and returns its key.
+    fn request(&mut self, specifier: &str, position: usize, import_type: Option<String>) -> String {
+        let key = request_key(specifier, import_type.as_deref());
+        if !self.requests.iter().any(|r| r.key == key) {
+            self.requests.push(ModuleRequest {
+                key: key.clone(),
+                specifier: specifier.to_string(),
+                position,
+                import_type,
+            });
         }
+        key
+    }
+
+    /// The variable the namespace of the request `key` is imported into.
+    fn dep_var(&self, key: &str) -> String {
+        let index = self.requests.iter().position(|r| r.key == key).unwrap_or(0);
+        format!("__lunos_dep{index}")
     }
 
-    let index_js = package_dir.join("index.js");
-    if index_js.exists() {
-        return Some(index_js);
+    /// What a reference to the imported name `local` reads: the export it's
+    /// bound to, so it stays live. Namespace imports are plain variables.
+    fn import_read(&self, local: &str) -> Option<String> {
+        self.imports.iter().find_map(|import| {
+            import
+                .bindings
+                .iter()
+                .find(|binding| binding.local == local && binding.imported != "*")
+                .map(|binding| {
+                    format!(
+                        "{}[{}]",
+                        self.dep_var(&import.key()),
+                        js_string(&binding.imported)
+                    )
+                })
+        })
     }
+}
+
+struct LoadedModule {
+    id: String,
+    path: PathBuf,
+    kind: ModuleKind,
+    source: String,
+    esm: EsmSyntax,
+    cjs: CommonJsSyntax,
+    /// Resolved ids of statically known requests, by `request_key`.
+    deps: Vec<(String, String)>,
+    /// The source maps of the transforms that made `source`, in the order
+    /// they ran.
+    maps: Vec<SourceMap>,
+}
+
+/// Code to evaluate, with the URL stack traces name it by.
+pub(crate) struct Script {
+    pub(crate) url: Option<String>,
+    pub(crate) code: String,
+}
 
-    None
+impl LoadedModule {
+    fn dep(&self, key: &str) -> Option<&str> {
+        self.deps
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, id)| id.as_str())
+    }
 }
 
-fn resolve_module_path(base_path: &Path, import_path: &str) -> PathBuf {
-    if import_path.starts_with("./") || import_path.starts_with("../") {
-        let mut path = base_path.to_path_buf();
-        path.pop();
-        return path.join(import_path);
+/// Quotes `value` as a JavaScript string literal.
+pub(crate) fn js_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
     }
+    out.push('"');
+    out
+}
+
+/// Whitespace that keeps the line count of `src[start..end]`.
+fn blank(src: &str, start: usize, end: usize) -> String {
+    "\n".repeat(src[start..end].matches('\n').count())
+}
+
+fn is_at(src: &str, tokens: &[Token], i: usize, text: &str) -> bool {
+    tokens.get(i).is_some_and(|t| t.is(src, text))
+}
+
+fn prev_is_dot(src: &str, tokens: &[Token], i: usize) -> bool {
+    i > 0 && (tokens[i - 1].is(src, ".") || tokens[i - 1].is(src, "?."))
+}
 
-    let base_dir = base_path.parent().unwrap_or(Path::new("."));
+fn name_at(src: &str, tokens: &[Token], i: usize) -> Option<String> {
+    let token = tokens.get(i)?;
+    match token.kind {
+        TokenKind::Ident => Some(token.text(src).to_string()),
+        TokenKind::Str => Some(string_value(token.text(src))),
+        _ => None,
+    }
+}
 
-    if let Some(node_modules_dir) = find_node_modules(base_dir) {
-        let parts: Vec<&str> = import_path.split('/').collect();
-        let package_name = parts[0];
-        let package_dir = node_modules_dir.join(package_name);
+fn string_at(src: &str, tokens: &[Token], i: usize) -> Option<String> {
+    tokens
+        .get(i)
+        .filter(|t| t.kind == TokenKind::Str)
+        .map(|t| string_value(t.text(src)))
+}
 
-        if !package_dir.exists() {
-            eprintln!("Package '{package_name}' not found in node_modules");
-            std::process::exit(1);
+/// The `type` in a `with { type: "..." }` (or legacy `assert`) clause after
+/// the specifier at `i`.
+fn import_type(src: &str, tokens: &[Token], i: usize) -> Option<String> {
+    if !(is_at(src, tokens, i + 1, "with") || is_at(src, tokens, i + 1, "assert"))
+        || !is_at(src, tokens, i + 2, "{")
+    {
+        return None;
+    }
+    let close = matching_close(src, tokens, i + 2)?;
+    (i + 3..close).find_map(|k| {
+        if name_at(src, tokens, k).as_deref() == Some("type") && is_at(src, tokens, k + 1, ":") {
+            string_at(src, tokens, k + 2)
+        } else {
+            None
         }
+    })
+}
 
-        if parts.len() > 1 {
-            let submodule_path = parts[1..].join("/");
-            let full_path = package_dir.join(&submodule_path);
+/// Skips an optional `with { ... }` attributes clause and the trailing
+/// semicolon after the specifier at `i`, returning the last token index.
+fn statement_tail(src: &str, tokens: &[Token], mut i: usize) -> usize {
+    if (is_at(src, tokens, i + 1, "with") || is_at(src, tokens, i + 1, "assert"))
+        && is_at(src, tokens, i + 2, "{")
+        && let Some(close) = matching_close(src, tokens, i + 2)
+    {
+        i = close;
+    }
+    if is_at(src, tokens, i + 1, ";") {
+        i += 1;
+    }
+    i
+}
 
-            if full_path.exists() {
-                return full_path;
-            }
+/// A `name as alias` entry of a braced list, with the offset of `name`.
+type NamedEntry = (String, String, usize);
+
+/// Parses `{ a, b as c, "d" as e }` starting at the opening brace and returns
+/// its entries and the index of the closing brace.
+fn named_list(src: &str, tokens: &[Token], open: usize) -> Option<(Vec<NamedEntry>, usize)> {
+    let close = matching_close(src, tokens, open)?;
+    let mut names = Vec::new();
+    let mut k = open + 1;
+    while k < close {
+        if tokens[k].is(src, ",") {
+            k += 1;
+            continue;
+        }
+        let name = name_at(src, tokens, k)?;
+        let position = tokens[k].start;
+        if is_at(src, tokens, k + 1, "as") {
+            names.push((name, name_at(src, tokens, k + 2)?, position));
+            k += 3;
+        } else {
+            names.push((name.clone(), name, position));
+            k += 1;
+        }
+    }
+    Some((names, close))
+}
 
-            let full_path_js = package_dir.join(format!("{submodule_path}.js"));
-            if full_path_js.exists() {
-                return full_path_js;
+fn parse_import(src: &str, tokens: &[Token], i: usize) -> Option<(StaticImport, usize)> {
+    let mut j = i + 1;
+    let mut bindings = Vec::new();
+
+    if tokens.get(j)?.kind != TokenKind::Str {
+        if tokens[j].kind == TokenKind::Ident {
+            bindings.push(ImportBinding {
+                imported: "default".to_string(),
+                local: tokens[j].text(src).to_string(),
+                position: tokens[j].start,
+            });
+            j += 1;
+            if is_at(src, tokens, j, ",") {
+                j += 1;
             }
+        }
 
-            eprintln!("Submodule '{submodule_path}' not found in package '{package_name}'",);
-            std::process::exit(1);
+        if is_at(src, tokens, j, "*") && is_at(src, tokens, j + 1, "as") {
+            bindings.push(ImportBinding {
+                imported: "*".to_string(),
+                local: name_at(src, tokens, j + 2)?,
+                position: tokens[j].start,
+            });
+            j += 3;
+        } else if is_at(src, tokens, j, "{") {
+            let (names, close) = named_list(src, tokens, j)?;
+            bindings.extend(
+                names
+                    .into_iter()
+                    .map(|(imported, local, position)| ImportBinding {
+                        imported,
+                        local,
+                        position,
+                    }),
+            );
+            j = close + 1;
         }
 
-        if let Some(main_path) = resolve_package_main(&package_dir) {
-            return main_path;
+        if !is_at(src, tokens, j, "from") {
+            return None;
         }
+        j += 1;
+    }
 
-        eprintln!("Could not resolve main entry point for package '{package_name}'");
-        std::process::exit(1);
+    let specifier = string_at(src, tokens, j)?;
+    Some((
+        StaticImport {
+            specifier,
+            bindings,
+            position: tokens[j].start,
+            import_type: import_type(src, tokens, j),
+        },
+        statement_tail(src, tokens, j),
+    ))
+}
+
+/// Collects the names bound by a destructuring pattern between `open` and
+/// its matching `close`.
+pub(crate) fn pattern_names(
+    src: &str,
+    tokens: &[Token],
+    open: usize,
+    close: usize,
+    out: &mut Vec<String>,
+) {
+    let mut k = open + 1;
+    while k < close {
+        let token = tokens[k];
+        let text = token.text(src);
+        if token.kind == TokenKind::Punct && (text == "{" || text == "[") {
+            let inner = matching_close(src, tokens, k).unwrap_or(close);
+            pattern_names(src, tokens, k, inner, out);
+            k = inner + 1;
+        } else if is_at(src, tokens, k + 1, ":") && tokens[open].is(src, "{") {
+            k += 2;
+        } else if text == "=" {
+            // default value, skip to the next element
+            k += 1;
+            while k < close && !tokens[k].is(src, ",") {
+                if matches!(tokens[k].text(src), "{" | "(" | "[")
+                    && tokens[k].kind == TokenKind::Punct
+                {
+                    k = matching_close(src, tokens, k).unwrap_or(close);
+                }
+                k += 1;
+            }
+        } else {
+            if token.kind == TokenKind::Ident {
+                out.push(text.to_string());
+            }
+            k += 1;
+        }
     }
+}
 
-    let mut path = base_path.to_path_buf();
-    path.pop();
-    path.join(format!("./{import_path}"))
+/// Whether the token at `i` starts a new statement because of a line break.
+pub(crate) fn ends_statement(src: &str, tokens: &[Token], i: usize) -> bool {
+    let token = tokens[i];
+    if !token.nl_before || token.kind == TokenKind::Punct {
+        return false;
+    }
+    let previous = tokens[i - 1];
+    previous.kind != TokenKind::Punct || matches!(previous.text(src), ")" | "]" | "}")
 }
 
-fn extract_imports(js_code: &str) -> Vec<(Vec<String>, String)> {
-    let mut imports = Vec::new();
+/// Names declared by the `const`/`let`/`var` statement at `i`.
+pub(crate) fn declared_names(src: &str, tokens: &[Token], i: usize) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut k = i + 1;
+    while k < tokens.len() {
+        let token = tokens[k];
+        if token.kind == TokenKind::Ident {
+            names.push(token.text(src).to_string());
+            k += 1;
+        } else if token.is(src, "{") || token.is(src, "[") {
+            let Some(close) = matching_close(src, tokens, k) else {
+                break;
+            };
+            pattern_names(src, tokens, k, close, &mut names);
+            k = close + 1;
+        } else {
+            break;
+        }
 
-    for line in js_code.lines() {
-        let line = line.trim();
-        if line.starts_with("import") && line.contains("from") {
-            let parts: Vec<&str> = line.split("from").collect();
-            if parts.len() == 2 {
-                let import_part = parts[0].trim().replace("import", "").trim().to_string();
-                let path_part = parts[1].trim().replace(";", "").trim().to_string();
+        // skip the initializer up to the next declarator
+        let mut next_declarator = false;
+        while k < tokens.len() {
+            let text = tokens[k].text(src);
+            if text == ";" || ends_statement(src, tokens, k) {
+                break;
+            }
+            if text == "," && tokens[k].kind == TokenKind::Punct {
+                next_declarator = true;
+                k += 1;
+                break;
+            }
+            if matches!(text, "{" | "(" | "[") && tokens[k].kind == TokenKind::Punct {
+                k = matching_close(src, tokens, k).unwrap_or(tokens.len());
+            }
+            k += 1;
+        }
+        if !next_declarator {
+            break;
+        }
+    }
+    names
+}
 
-                let module_path = if path_part.starts_with("'") && path_part.ends_with("'") {
-                    path_part[1..path_part.len() - 1].to_string()
-                } else {
-                    continue;
-                };
+/// The name of a `function`/`class` declaration whose keyword is at `i`,
+/// and the index of the token the name follows.
+pub(crate) fn declaration_name(src: &str, tokens: &[Token], i: usize) -> (Option<String>, usize) {
+    let mut k = i;
+    if tokens[k].is(src, "async") {
+        k += 1;
+    }
+    if is_at(src, tokens, k + 1, "*") {
+        k += 1;
+    }
+    let name = tokens
+        .get(k + 1)
+        .filter(|t| t.kind == TokenKind::Ident && !t.is(src, "extends"))
+        .map(|t| t.text(src).to_string());
+    (name, k)
+}
 
-                if !import_part.starts_with("{") && !import_part.contains("*") {
-                    let default_name = import_part.trim().to_string();
-                    imports.push((vec![format!("default:{}", default_name)], module_path));
-                } else if import_part.starts_with("{") && import_part.ends_with("}") {
-                    let symbols_str = import_part[1..import_part.len() - 1].trim();
-                    let symbols = symbols_str
-                        .split(',')
-                        .map(|s| s.trim().to_string())
-                        .collect::<Vec<String>>();
-
-                    imports.push((symbols, module_path));
+fn is_declaration_keyword(src: &str, tokens: &[Token], i: usize) -> bool {
+    is_at(src, tokens, i, "function")
+        || is_at(src, tokens, i, "class")
+        || (is_at(src, tokens, i, "async")
+            && is_at(src, tokens, i + 1, "function")
+            && !tokens[i + 1].nl_before)
+}
+
+fn parse_export(src: &str, tokens: &[Token], i: usize, syntax: &mut EsmSyntax) -> Option<usize> {
+    let export = tokens[i];
+    let next = *tokens.get(i + 1)?;
+
+    if next.is(src, "default") {
+        let k = i + 2;
+        tokens.get(k)?;
+        if is_declaration_keyword(src, tokens, k) {
+            let (name, keyword) = declaration_name(src, tokens, k);
+            let local = match name {
+                Some(name) => name,
+                None => {
+                    syntax.edits.push(Edit {
+                        start: tokens[keyword].end,
+                        end: tokens[keyword].end,
+                        text: " __lunos_default".to_string(),
+                    });
+                    "__lunos_default".to_string()
                 }
+            };
+            syntax.edits.push(Edit {
+                start: export.start,
+                end: tokens[k].start,
+                text: String::new(),
+            });
+            syntax.exports.push(ExportEntry::Local {
+                exported: "default".to_string(),
+                local,
+            });
+        } else {
+            syntax.edits.push(Edit {
+                start: export.start,
+                end: tokens[k].start,
+                text: "var __lunos_default = ".to_string(),
+            });
+            syntax.exports.push(ExportEntry::Local {
+                exported: "default".to_string(),
+                local: "__lunos_default".to_string(),
+            });
+        }
+        return Some(k);
+    }
+
+    if next.is(src, "{") {
+        let (names, close) = named_list(src, tokens, i + 1)?;
+        let mut last = close;
+        if is_at(src, tokens, close + 1, "from") {
+            let specifier = string_at(src, tokens, close + 2)?;
+            let request = syntax.request(
+                &specifier,
+                tokens[close + 2].start,
+                import_type(src, tokens, close + 2),
+            );
+            for (imported, exported, position) in names {
+                syntax.exports.push(ExportEntry::Indirect {
+                    exported,
+                    imported,
+                    request: request.clone(),
+                    position,
+                });
+            }
+            last = statement_tail(src, tokens, close + 2);
+        } else {
+            for (local, exported, _) in names {
+                syntax.exports.push(ExportEntry::Local { exported, local });
+            }
+            if is_at(src, tokens, last + 1, ";") {
+                last += 1;
             }
         }
+        syntax.edits.push(Edit {
+            start: export.start,
+            end: tokens[last].end,
+            text: blank(src, export.start, tokens[last].end),
+        });
+        return Some(last);
     }
 
-    imports
+    if next.is(src, "*") {
+        let (exported, from) = if is_at(src, tokens, i + 2, "as") {
+            (Some(name_at(src, tokens, i + 3)?), i + 4)
+        } else {
+            (None, i + 2)
+        };
+        if !is_at(src, tokens, from, "from") {
+            return None;
+        }
+        let specifier = string_at(src, tokens, from + 1)?;
+        let request = syntax.request(
+            &specifier,
+            tokens[from + 1].start,
+            import_type(src, tokens, from + 1),
+        );
+        syntax.exports.push(match exported {
+            Some(exported) => ExportEntry::Namespace { exported, request },
+            None => ExportEntry::Star { request },
+        });
+        let last = statement_tail(src, tokens, from + 1);
+        syntax.edits.push(Edit {
+            start: export.start,
+            end: tokens[last].end,
+            text: blank(src, export.start, tokens[last].end),
+        });
+        return Some(last);
+    }
+
+    let names = if matches!(next.text(src), "const" | "let" | "var") {
+        declared_names(src, tokens, i + 1)
+    } else if is_declaration_keyword(src, tokens, i + 1) {
+        declaration_name(src, tokens, i + 1).0.into_iter().collect()
+    } else {
+        Vec::new()
+    };
+    for name in names {
+        syntax.exports.push(ExportEntry::Local {
+            exported: name.clone(),
+            local: name,
+        });
+    }
+    syntax.edits.push(Edit {
+        start: export.start,
+        end: next.start,
+        text: String::new(),
+    });
+    Some(i)
 }
 
-fn extract_exports(js_code: &str) -> Vec<String> {
-    let mut exports = Vec::new();
+/// Whether the `{` at `i` opens a function body rather than a block or an
+/// object literal. `paren_open` is the `(` matching a `)` right before it.
+pub(crate) fn starts_function_body(
+    src: &str,
+    tokens: &[Token],
+    i: usize,
+    paren_open: usize,
+) -> bool {
+    if i == 0 {
+        return false;
+    }
+    if tokens[i - 1].is(src, "=>") {
+        return true;
+    }
+    if !tokens[i - 1].is(src, ")") || paren_open == 0 {
+        return false;
+    }
+    // `if (...) {` and friends are blocks, `name(...) {` and `function (...) {` aren't
+    let before = tokens[paren_open - 1].text(src);
+    let is_control = matches!(before, "if" | "for" | "while" | "switch" | "catch" | "with")
+        || (before == "await" && paren_open >= 2 && tokens[paren_open - 2].is(src, "for"));
+    !is_control
+}
 
-    for line in js_code.lines() {
-        let line = line.trim();
-        if line.starts_with("export") {
-            if line.contains("{") && line.contains("}") && !line.starts_with("export default") {
-                let start = line.find('{').unwrap_or(0) + 1;
-                let end = line.find('}').unwrap_or(line.len());
-                if start < end {
-                    let names_str = &line[start..end];
-                    for name in names_str.split(',') {
-                        let clean_name = name.trim();
-                        if !clean_name.is_empty() {
-                            exports.push(clean_name.to_string());
-                        }
+/// Finds the import and export statements of an ES module and records the
+/// edits that turn its body into a plain function body.
+fn scan_esm(src: &str, tokens: &[Token], id: &str) -> EsmSyntax {
+    let mut syntax = EsmSyntax::default();
+    // open brackets with their token index and whether they start a function
+    // body, to tell a top-level `await` from one inside an async function
+    let mut scopes: Vec<(usize, bool)> = Vec::new();
+    let mut function_depth = 0usize;
+    let mut last_paren_open = 0;
+    // scope depths at which a concise arrow body (`async x => await x`) began
+    let mut arrow_bodies: Vec<usize> = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = tokens[i];
+
+        if token.kind == TokenKind::Punct {
+            match token.text(src) {
+                "{" => {
+                    let is_function = starts_function_body(src, tokens, i, last_paren_open);
+                    function_depth += usize::from(is_function);
+                    scopes.push((i, is_function));
+                }
+                "(" | "[" => scopes.push((i, false)),
+                "}" | ")" | "]" => {
+                    if let Some((open, is_function)) = scopes.pop() {
+                        function_depth -= usize::from(is_function);
+                        last_paren_open = open;
                     }
+                    arrow_bodies.retain(|&depth| depth <= scopes.len());
                 }
-            } else if line.starts_with("export default") {
-                exports.push("__default_export_value__".to_string());
-            } else {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3
-                    && (parts[1] == "function"
-                        || parts[1] == "const"
-                        || parts[1] == "let"
-                        || parts[1] == "var"
-                        || parts[1] == "class")
-                {
-                    let name = parts[2].split('(').next().unwrap_or(parts[2]);
-                    exports.push(name.to_string());
+                "," | ";" if arrow_bodies.last() == Some(&scopes.len()) => {
+                    arrow_bodies.pop();
                 }
+                "=>" if !is_at(src, tokens, i + 1, "{") => arrow_bodies.push(scopes.len()),
+                _ => {}
             }
+            i += 1;
+            continue;
+        }
+
+        if token.kind != TokenKind::Ident || prev_is_dot(src, tokens, i) {
+            i += 1;
+            continue;
+        }
+
+        if token.is(src, "await") && function_depth == 0 && arrow_bodies.is_empty() {
+            syntax.has_top_level_await = true;
+        }
+
+        if token.is(src, "import") && is_at(src, tokens, i + 1, "(") {
+            // calls with options may ask for another module type, so only
+            // plain `import("name")` is loaded ahead of time
+            if let Some(specifier) = string_at(src, tokens, i + 2)
+                && is_at(src, tokens, i + 3, ")")
+            {
+                syntax.dynamic_imports.push(specifier);
+            }
+            syntax.edits.push(Edit {
+                start: token.start,
+                end: tokens[i + 1].end,
+                text: format!("__lunos_modules.dynamicImport({}, ", js_string(id)),
+            });
+            scopes.push((i + 1, false));
+            i += 2;
+            continue;
+        }
+
+        if token.is(src, "import")
+            && is_at(src, tokens, i + 1, ".")
+            && is_at(src, tokens, i + 2, "meta")
+        {
+            syntax.edits.push(Edit {
+                start: token.start,
+                end: tokens[i + 2].end,
+                text: "__lunos_meta".to_string(),
+            });
+            syntax.uses_meta = true;
+            syntax.has_module_syntax = true;
+            i += 3;
+            continue;
+        }
+
+        if scopes.is_empty()
+            && token.is(src, "import")
+            && !is_at(src, tokens, i + 1, ".")
+            && let Some((import, last)) = parse_import(src, tokens, i)
+        {
+            syntax.request(
+                &import.specifier,
+                import.position,
+                import.import_type.clone(),
+            );
+            syntax.imports.push(import);
+            syntax.edits.push(Edit {
+                start: token.start,
+                end: tokens[last].end,
+                text: blank(src, token.start, tokens[last].end),
+            });
+            syntax.has_module_syntax = true;
+            i = last + 1;
+            continue;
+        }
+
+        if scopes.is_empty()
+            && token.is(src, "export")
+            && let Some(last) = parse_export(src, tokens, i, &mut syntax)
+        {
+            syntax.has_module_syntax = true;
+            i = last + 1;
+            continue;
+        }
+
+        i += 1;
+    }
+
+    let imported: HashSet<&str> = syntax
+        .imports
+        .iter()
+        .flat_map(|import| &import.bindings)
+        .filter(|binding| binding.imported != "*")
+        .map(|binding| binding.local.as_str())
+        .collect();
+    let mut edits = Vec::new();
+    for reference in bindings::references(src, tokens, &imported) {
+        let token = tokens[reference.token];
+        // names in the import and export statements themselves go away
+        if syntax
+            .edits
+            .iter()
+            .any(|edit| edit.start <= token.start && token.end <= edit.end)
+        {
+            continue;
         }
+        let name = token.text(src);
+        let Some(read) = syntax.import_read(name) else {
+            continue;
+        };
+        edits.push(Edit {
+            start: token.start,
+            end: token.end,
+            text: match reference.usage {
+                Usage::Read => read,
+                Usage::Call => format!("(0, {read})"),
+                Usage::Shorthand => format!("{name}: {read}"),
+            },
+        });
     }
+    syntax.edits.extend(edits);
+
+    syntax
+}
+
+fn apply_edits(out: &mut Generated, src: &str, mut edits: Vec<Edit>) {
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+    let mut cursor = 0;
+    for edit in edits {
+        if edit.start < cursor {
+            continue;
+        }
+        out.push_source(src, cursor, edit.start);
+        out.push_code(&edit.text, edit.start);
+        cursor = edit.end;
+    }
+    out.push_source(src, cursor, src.len());
+}
+
+fn is_ts_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|s| s.to_str()),
+        Some(ext) if ["ts", "tsx", "mts", "cts"].iter().any(|ts| ext.eq_ignore_ascii_case(ts)))
+}
+
+fn is_jsx_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|s| s.to_str()),
+        Some(ext) if ext.eq_ignore_ascii_case("jsx") || ext.eq_ignore_ascii_case("tsx"))
+}
+
+fn module_id(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
 
-    exports
+/// The id of `path` loaded as `kind`. A file imported as another type than
+/// its own becomes a separate module, so `a.js` and `a.js` as text coexist.
+fn typed_module_id(path: &Path, kind: Option<ModuleKind>) -> String {
+    let id = module_id(path);
+    match kind {
+        Some(kind @ (ModuleKind::Text | ModuleKind::Bytes)) => format!("{id}?{}", kind.as_str()),
+        Some(ModuleKind::Json) if !id.ends_with(".json") => format!("{id}?json"),
+        _ => id,
+    }
 }
 
-fn remove_exports(js_code: &str) -> String {
-    let mut result = Vec::new();
-    let mut in_default_export_object = false;
-    let mut default_object_content = String::new();
-    let mut brace_count = 0;
+/// Parses a module. `asset` is the kind asked for by a `type` import
+/// attribute, otherwise `.json` files are JSON and anything else is code.
+fn analyze(
+    registry: &Registry,
+    id: String,
+    path: PathBuf,
+    code: String,
+    asset: Option<ModuleKind>,
+) -> LoadedModule {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let asset = asset.or_else(|| (extension == "json").then_some(ModuleKind::Json));
+    if let Some(kind) = asset {
+        let source = match kind {
+            ModuleKind::Json => code.trim_start_matches('\u{feff}').to_string(),
+            _ => code,
+        };
+        return LoadedModule {
+            id,
+            path,
+            kind,
+            source,
+            esm: EsmSyntax::default(),
+            cjs: CommonJsSyntax::default(),
+            deps: Vec::new(),
+            maps: Vec::new(),
+        };
+    }
 
-    for line in js_code.lines() {
-        let trimmed = line.trim();
+    let mut source = code;
+    let mut maps = Vec::new();
+    if is_jsx_file(&path) {
+        let options = JsxOptions::for_file(registry, &path);
+        let typescript = is_ts_file(&path);
+        let map;
+        let name = options.cache_name(typescript);
+        (source, map) = transform_cache::transform(registry, &name, &source, |source| {
+            jsx::transform(source, &options, typescript)
+        });
+        maps.push(map);
+    }
+    if is_ts_file(&path) {
+        let options = TsOptions::for_file(registry, &path);
+        let map;
+        let name = options.cache_name();
+        (source, map) = transform_cache::transform(registry, &name, &source, |source| {
+            typescript::erase_types(source, &options)
+        });
+        maps.push(map);
+    }
+    if source.starts_with("#!") {
+        let end = source.find('\n').unwrap_or(source.len());
+        source.replace_range(..end, "");
+    }
 
-        if in_default_export_object {
-            brace_count += trimmed.chars().filter(|&c| c == '{').count();
-            brace_count -= trimmed.chars().filter(|&c| c == '}').count();
+    // the scan depends on the id too, which dynamic imports are rewritten with
+    let name = format!("scan\0{id}");
+    let (kind, esm, cjs) = transform_cache::analysis(
+        registry,
+        &name,
+        &source,
+        |(kind, esm, cjs): &(ModuleKind, EsmSyntax, CommonJsSyntax)| {
+            json!({
+                "commonjs": *kind == ModuleKind::CommonJs,
+                "esm": esm.to_json(),
+                "cjs": cjs.to_json(),
+            })
+        },
+        |json| {
+            let kind = match json["commonjs"].as_bool()? {
+                true => ModuleKind::CommonJs,
+                false => ModuleKind::Module,
+            };
+            let esm = EsmSyntax::from_json(&json["esm"])?;
+            Some((kind, esm, CommonJsSyntax::from_json(&json["cjs"])?))
+        },
+        |source| scan(source, &extension, &id),
+    );
+
+    LoadedModule {
+        id,
+        path,
+        kind,
+        source,
+        esm,
+        cjs,
+        deps: Vec::new(),
+        maps,
+    }
+}
 
-            default_object_content.push_str(line);
-            default_object_content.push('\n');
+/// Scans the code of a module, and tells an ES module from a CommonJS one.
+fn scan(source: &str, extension: &str, id: &str) -> (ModuleKind, EsmSyntax, CommonJsSyntax) {
+    let tokens = tokenize(source);
+    let esm = scan_esm(source, &tokens, id);
+    let kind = match extension {
+        // the extension decides, whatever the syntax
+        "cjs" | "cts" => ModuleKind::CommonJs,
+        "mjs" | "mts" => ModuleKind::Module,
+        _ if !esm.has_module_syntax && commonjs::looks_like_commonjs(source, &tokens) => {
+            ModuleKind::CommonJs
+        }
+        _ => ModuleKind::Module,
+    };
+    let cjs = if kind == ModuleKind::CommonJs {
+        commonjs::scan(source, &tokens)
+    } else {
+        CommonJsSyntax::default()
+    };
+    (kind, esm, cjs)
+}
 
-            if brace_count == 0 {
-                in_default_export_object = false;
-                let start = default_object_content.find('{').unwrap_or(0);
-                let end = default_object_content
-                    .rfind('}')
-                    .unwrap_or(default_object_content.len());
-                if start < end {
-                    let object_content = &default_object_content[start..=end];
-                    result.push(format!("var __default_export_value__ = {object_content};"));
+impl LoadedModule {
+    /// Specifiers to resolve ahead of time.
+    fn requests(&self) -> Vec<GraphRequest> {
+        let mut requests = Vec::new();
+        match self.kind {
+            ModuleKind::Module => {
+                for request in &self.esm.requests {
+                    requests.push(GraphRequest {
+                        key: request.key.clone(),
+                        specifier: request.specifier.clone(),
+                        mode: ResolveMode::Import,
+                        import_type: request.import_type.clone(),
+                        position: Some(request.position),
+                    });
                 }
-                default_object_content.clear();
             }
-            continue;
+            ModuleKind::CommonJs => {
+                for specifier in self.cjs.requires.iter().chain(&self.cjs.reexports) {
+                    requests.push(GraphRequest {
+                        key: specifier.clone(),
+                        specifier: specifier.clone(),
+                        mode: ResolveMode::Require,
+                        import_type: None,
+                        position: None,
+                    });
+                }
+            }
+            ModuleKind::Json | ModuleKind::Text | ModuleKind::Bytes | ModuleKind::Builtin => {}
+        }
+        for specifier in &self.esm.dynamic_imports {
+            requests.push(GraphRequest {
+                key: specifier.clone(),
+                specifier: specifier.clone(),
+                mode: ResolveMode::Import,
+                import_type: None,
+                position: None,
+            });
         }
+        requests
+    }
 
-        if trimmed.starts_with("export") {
-            if trimmed.contains("{") && trimmed.contains("}") && !trimmed.contains("=") {
-                continue;
-            } else if trimmed.starts_with("export default {") {
-                in_default_export_object = true;
-                brace_count = 1;
-                default_object_content.push_str(trimmed.replacen("export default", "", 1).trim());
-                default_object_content.push('\n');
-            } else if trimmed.starts_with("export default") {
-                let default_value = trimmed.replacen("export default", "", 1).trim().to_string();
-                let default_value = if default_value.ends_with(';') {
-                    default_value[..default_value.len() - 1].to_string()
-                } else {
-                    default_value
-                };
-                result.push(format!("var __default_export_value__ = {default_value};"));
-            } else {
-                result.push(line.replacen("export ", "", 1));
+    fn own_export_names(&self, registry: &Registry) -> Option<Vec<String>> {
+        match self.kind {
+            ModuleKind::Module => {}
+            ModuleKind::Text | ModuleKind::Bytes => return Some(vec!["default".to_string()]),
+            ModuleKind::Builtin => {
+                return builtins::find(registry, &self.id)
+                    .and_then(|builtin| builtin.export_names());
             }
-        } else {
-            result.push(line.to_string());
+            ModuleKind::CommonJs | ModuleKind::Json => return None,
         }
+        let names = self
+            .esm
+            .exports
+            .iter()
+            .filter_map(|entry| match entry {
+                ExportEntry::Local { exported, .. }
+                | ExportEntry::Indirect { exported, .. }
+                | ExportEntry::Namespace { exported, .. } => Some(exported.clone()),
+                ExportEntry::Star { .. } => None,
+            })
+            .collect();
+        Some(names)
     }
 
-    result
-        .into_iter()
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<String>>()
-        .join("\n")
-        + "\n"
+    fn star_sources(&self) -> Vec<&str> {
+        self.esm
+            .exports
+            .iter()
+            .filter_map(|entry| match entry {
+                ExportEntry::Star { request } => self.dep(request),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
-use std::collections::HashMap;
+/// Reads a module's source. Bytes modules are read as one char per byte.
+fn read_module_code(
+    resolved_path: &Path,
+    kind: Option<ModuleKind>,
+    specifier: &str,
+    referrer: &Path,
+) -> Result<String, ResolveError> {
+    let code = if kind == Some(ModuleKind::Bytes) {
+        fs::read(resolved_path).map(|bytes| bytes.iter().map(|&b| b as char).collect())
+    } else {
+        fs::read_to_string(resolved_path)
+    };
+    code.map_err(|e| {
+        ResolveError::new(
+            ResolveErrorKind::Unreadable(e.to_string()),
+            specifier,
+            referrer,
+        )
+    })
+}
 
-fn is_ts_file(path: &Path) -> bool {
-    matches!(path.extension()
-        .and_then(
-            |s| s.to_str()),
-            Some(ext) if ext.eq_ignore_ascii_case("ts") || ext.eq_ignore_ascii_case("tsx")
-    )
+/// Where a resolved request loads its module from.
+enum Source<'a> {
+    Builtin(Builtin<'a>),
+    /// A file, read as the given kind or else by its extension.
+    File(Option<ModuleKind>),
+    /// A module imported by URL. It's downloaded while resolving, since its
+    /// id is the URL it was served from after redirects.
+    Remote(Option<ModuleKind>, Vec<u8>),
+}
+
+/// The module a request names, before it's read.
+struct Resolved<'a> {
+    id: String,
+    path: PathBuf,
+    source: Source<'a>,
+}
+
+/// Resolves a request made from `referrer` to the module it names.
+fn resolve_request<'a>(
+    registry: &'a Registry,
+    referrer: &Path,
+    request: &GraphRequest,
+) -> Result<Resolved<'a>, ResolveError> {
+    let not_found =
+        || ResolveError::not_found(registry, &request.specifier, referrer, request.mode);
+    // like in Node, a built-in's bare name wins over a package of that name
+    if builtins::is_builtin_specifier(&request.specifier)
+        || builtins::find(registry, &request.specifier).is_some()
+    {
+        if let Some(import_type) = &request.import_type {
+            let kind = ResolveErrorKind::UnsupportedType(import_type.clone());
+            return Err(ResolveError::new(kind, &request.specifier, referrer));
+        }
+        let builtin = builtins::find(registry, &request.specifier).ok_or_else(not_found)?;
+        return Ok(Resolved {
+            id: builtin.name.to_string(),
+            path: PathBuf::from(builtin.name),
+            source: Source::Builtin(builtin),
+        });
+    }
+
+    let kind = match request.import_type.as_deref() {
+        Some(import_type) => Some(ModuleKind::from_import_type(import_type).ok_or_else(|| {
+            let kind = ResolveErrorKind::UnsupportedType(import_type.to_string());
+            ResolveError::new(kind, &request.specifier, referrer)
+        })?),
+        None => None,
+    };
+
+    if let Some(url) = remote::resolve(registry, referrer, &request.specifier) {
+        let fetched = remote::fetch(registry, &url).map_err(|reason| {
+            ResolveError::new(
+                ResolveErrorKind::Unreadable(reason),
+                &request.specifier,
+                referrer,
+            )
+        })?;
+        let path = PathBuf::from(&fetched.url);
+        return Ok(Resolved {
+            id: typed_module_id(&path, kind),
+            path,
+            source: Source::Remote(kind, fetched.bytes),
+        });
+    }
+
+    let path = resolver::resolve(registry, referrer, &request.specifier, request.mode)
+        .ok_or_else(not_found)?;
+    let path = PathBuf::from(module_id(&path));
+    Ok(Resolved {
+        id: typed_module_id(&path, kind),
+        path,
+        source: Source::File(kind),
+    })
 }
 
-fn strip_types(code: &str) -> String {
-    let mut out_lines: Vec<String> = Vec::new();
-    let mut in_interface = false;
-    let mut in_type_alias = false;
-    let mut brace_count: i32 = 0;
+/// Reads and parses a resolved module. Built-ins never touch the filesystem.
+fn load_resolved(
+    registry: &Registry,
+    resolved: &Resolved,
+    referrer: &Path,
+    request: &GraphRequest,
+) -> Result<LoadedModule, ResolveError> {
+    let (id, path) = (resolved.id.clone(), resolved.path.clone());
+    let module = match &resolved.source {
+        Source::Builtin(builtin) => match builtin.source {
+            BuiltinSource::Lunos(_) | BuiltinSource::Native(_) => LoadedModule {
+                id,
+                path,
+                kind: ModuleKind::Builtin,
+                source: String::new(),
+                esm: EsmSyntax::default(),
+                cjs: CommonJsSyntax::default(),
+                deps: Vec::new(),
+                maps: Vec::new(),
+            },
+            BuiltinSource::Script(source) => analyze(registry, id, path, source.to_string(), None),
+        },
+        Source::Remote(kind, bytes) => {
+            let code = if *kind == Some(ModuleKind::Bytes) {
+                bytes.iter().map(|&b| b as char).collect()
+            } else {
+                String::from_utf8_lossy(bytes).into_owned()
+            };
+            analyze(registry, id, path, code, *kind)
+        }
+        Source::File(kind) => {
+            let code = read_module_code(&path, *kind, &request.specifier, referrer)?;
+            analyze(registry, id, path, code, *kind)
+        }
+    };
+    Ok(module)
+}
+
+/// Resolves a request made from `referrer`, returning the id it maps to and
+/// the newly loaded module unless it was already loaded or defined.
+fn load_request(
+    registry: &Registry,
+    referrer: &Path,
+    request: &GraphRequest,
+    defined: &HashSet<String>,
+    seen: &mut HashSet<String>,
+) -> Result<(String, Option<LoadedModule>), ResolveError> {
+    let resolved = resolve_request(registry, referrer, request)?;
+    let id = resolved.id.clone();
+    if defined.contains(&id) || !seen.insert(id.clone()) {
+        return Ok((id, None));
+    }
+    Ok((
+        id,
+        Some(load_resolved(registry, &resolved, referrer, request)?),
+    ))
+}
 
-    for line in code.lines() {
-        let trimmed = line.trim();
+/// What became of one request of a module while loading the graph.
+enum Outcome {
+    /// It names a module that's loaded, defined or about to be loaded.
+    Dep(String, String),
+    /// It names a new module, the next of the level's pending loads.
+    Load,
+    Failed(GraphRequest, ResolveError),
+}
 
-        if in_interface {
-            brace_count += trimmed.chars().filter(|&c| c == '{').count() as i32;
-            brace_count -= trimmed.chars().filter(|&c| c == '}').count() as i32;
-            if brace_count <= 0 {
-                in_interface = false;
-                brace_count = 0;
+/// Loads `entry` and every module it depends on that isn't defined in the
+/// context yet.
+///
+/// The graph is walked breadth first, a level at a time: the requests of a
+/// level's modules are resolved in parallel, then the new modules they name
+/// are read and parsed in parallel. The results are merged in request order,
+/// so the modules come out in the same order as a serial walk.
+///
+/// Only the entry's static imports have to succeed. A module that's only
+/// reachable through `require` or `import()` and fails to resolve or link is
+/// left out, so the runtime load reports the error where it can be caught.
+fn load_graph(registry: &Registry, entry: LoadedModule) -> Result<Vec<LoadedModule>, ResolveError> {
+    let defined: HashSet<String> = registry.defined.lock().unwrap().keys().cloned().collect();
+    let mut seen = HashSet::from([entry.id.clone()]);
+    let mut level = vec![entry];
+    let mut modules = Vec::new();
+    let mut failures = Vec::new();
+
+    while !level.is_empty() {
+        let resolved: Vec<Vec<(GraphRequest, Result<Resolved, ResolveError>)>> = level
+            .par_iter()
+            .map(|module| {
+                let mut keys = HashSet::new();
+                module
+                    .requests()
+                    .into_iter()
+                    .filter(|request| keys.insert(request.key.clone()))
+                    .map(|request| {
+                        let resolved = resolve_request(registry, &module.path, &request);
+                        (request, resolved)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut outcomes = Vec::new();
+        let mut pending = Vec::new();
+        for (index, requests) in resolved.into_iter().enumerate() {
+            let mut module_outcomes = Vec::new();
+            for (request, result) in requests {
+                let outcome = match result {
+                    Ok(resolved) => {
+                        let id = resolved.id.clone();
+                        if defined.contains(&id) || !seen.insert(id.clone()) {
+                            Outcome::Dep(request.key, id)
+                        } else {
+                            pending.push((index, request, resolved));
+                            Outcome::Load
+                        }
+                    }
+                    Err(error) => Outcome::Failed(request, error),
+                };
+                module_outcomes.push(outcome);
             }
-            continue;
+            outcomes.push(module_outcomes);
         }
 
-        if in_type_alias {
-            if trimmed.contains(';') {
-                in_type_alias = false;
-                brace_count = 0;
-            } else {
-                brace_count += trimmed.chars().filter(|&c| c == '{').count() as i32;
-                brace_count -= trimmed.chars().filter(|&c| c == '}').count() as i32;
-                if brace_count <= 0 && trimmed.ends_with('}') {
-                    in_type_alias = false;
-                    brace_count = 0;
+        let loaded: Vec<Result<LoadedModule, ResolveError>> = pending
+            .par_iter()
+            .map(|(index, request, resolved)| {
+                load_resolved(registry, resolved, &level[*index].path, request)
+            })
+            .collect();
+
+        // only static imports fail the graph, the rest fail when they run
+        let mut fail = |module: &LoadedModule, request: GraphRequest, error: ResolveError| {
+            if let Some(position) = request.position {
+                let error = error.at(&module.source, position, &module.maps);
+                failures.push((module.id.clone(), error));
+            }
+        };
+        let mut next = Vec::new();
+        let mut loads = pending.into_iter().zip(loaded);
+        for (module, module_outcomes) in level.iter_mut().zip(outcomes) {
+            for outcome in module_outcomes {
+                match outcome {
+                    Outcome::Dep(key, id) => module.deps.push((key, id)),
+                    Outcome::Load => {
+                        let Some(((_, request, resolved), result)) = loads.next() else {
+                            continue;
+                        };
+                        match result {
+                            Ok(loaded) => {
+                                module.deps.push((request.key, resolved.id));
+                                next.push(loaded);
+                            }
+                            Err(error) => fail(module, request, error),
+                        }
+                    }
+                    Outcome::Failed(request, error) => fail(module, request, error),
                 }
             }
-            continue;
         }
+        modules.append(&mut level);
+        level = next;
+    }
 
-        if trimmed.starts_with("import type ") {
-            continue;
+    failures.extend(link(registry, &modules));
+    if failures.is_empty() {
+        return Ok(modules);
+    }
+
+    // a module is broken if it failed itself or statically imports a broken one
+    let mut broken: HashMap<String, ResolveError> = HashMap::new();
+    for (id, error) in failures {
+        broken.entry(id).or_insert(error);
+    }
+    loop {
+        let mut changed = false;
+        for module in &modules {
+            if broken.contains_key(&module.id) {
+                continue;
+            }
+            let inherited = module
+                .esm
+                .requests
+                .iter()
+                .filter_map(|request| broken.get(module.dep(&request.key)?))
+                .next()
+                .cloned();
+            if let Some(error) = inherited {
+                broken.insert(module.id.clone(), error);
+                changed = true;
+            }
         }
-        if trimmed.starts_with("export type ") {
-            continue;
+        if !changed {
+            break;
         }
-        if trimmed.starts_with("type ") || trimmed.starts_with("export type ") {
-            in_type_alias = true;
-            if !trimmed.contains(';') {
-                brace_count = 0;
-            } else {
-                in_type_alias = false;
-                brace_count = 0;
+    }
+
+    if let Some(error) = broken.get(&modules[0].id) {
+        return Err(error.clone());
+    }
+    modules.retain(|module| !broken.contains_key(&module.id));
+    for module in &mut modules {
+        module.deps.retain(|(_, id)| !broken.contains_key(id));
+    }
+    Ok(modules)
+}
+
+/// Checks that every name imported from an ES module is actually exported,
+/// returning the id of each module that imports a missing name.
+fn link(registry: &Registry, modules: &[LoadedModule]) -> Vec<(String, ResolveError)> {
+    let mut names = registry.defined.lock().unwrap().clone();
+    for module in modules {
+        names.insert(module.id.clone(), module.own_export_names(registry));
+    }
+
+    // `export * from` copies names over until nothing changes
+    loop {
+        let mut changed = false;
+        for module in modules {
+            for source in module.star_sources() {
+                let extra = names.get(source).cloned().flatten();
+                let Some(Some(own)) = names.get_mut(&module.id) else {
+                    continue;
+                };
+                match extra {
+                    Some(extra) => {
+                        for name in extra {
+                            if name != "default" && !own.contains(&name) {
+                                own.push(name);
+                                changed = true;
+                            }
+                        }
+                    }
+                    None => {
+                        names.insert(module.id.clone(), None);
+                        changed = true;
+                    }
+                }
             }
-            continue;
         }
+        if !changed {
+            break;
+        }
+    }
 
-        if trimmed.starts_with("interface ") || trimmed.starts_with("export interface ") {
-            in_interface = true;
-            brace_count = 0;
-            brace_count += trimmed.chars().filter(|&c| c == '{').count() as i32;
-            brace_count -= trimmed.chars().filter(|&c| c == '}').count() as i32;
-            if brace_count <= 0 {
-                in_interface = false;
-                brace_count = 0;
+    let mut failures = Vec::new();
+    for module in modules.iter().filter(|m| m.kind == ModuleKind::Module) {
+        let requested = module.esm.imports.iter().flat_map(|import| {
+            import
+                .bindings
+                .iter()
+                .map(move |binding| (import.key(), &binding.imported, binding.position))
+        });
+        let reexported = module.esm.exports.iter().filter_map(|entry| match entry {
+            ExportEntry::Indirect {
+                imported,
+                request,
+                position,
+                ..
+            } => Some((request.clone(), imported, *position)),
+            _ => None,
+        });
+
+        for (request, imported, position) in requested.chain(reexported) {
+            if imported == "*" {
+                continue;
+            }
+            let Some(Some(exports)) = module.dep(&request).and_then(|id| names.get(id)) else {
+                continue;
+            };
+            if !exports.contains(imported) {
+                let specifier = key_specifier(&request);
+                let error =
+                    ResolveError::missing_export(specifier, &module.path, imported, exports).at(
+                        &module.source,
+                        position,
+                        &module.maps,
+                    );
+                failures.push((module.id.clone(), error));
+                break;
             }
-            continue;
         }
-
-        out_lines.push(line.to_string());
     }
 
-    let mut out = out_lines.join("\n");
-
-    let re_impl = Regex::new(r"\s+implements\s+[^{]+\{").unwrap();
-    let re_var_assign =
-        Regex::new(r"\b(const|let|var)\s+([A-Za-z_$][\w$]*)\s*:\s*[^=;]+=").unwrap();
-    let re_var_decl = Regex::new(r"\b(const|let|var)\s+([A-Za-z_$][\w$]*)\s*:\s*[^=;]+;").unwrap();
-    let re_as = Regex::new(r"\s+as\s+[^;,)}\n]+([;,)}\n])").unwrap();
-    let re_angle = Regex::new(r"<\s*[A-Za-z_$][^>]*>\s*([A-Za-z_$({\[])").unwrap();
+    failures
+}
 
-    out = re_impl.replace_all(&out, " {").into_owned();
-    out = re_var_assign.replace_all(&out, "$1 $2 =").into_owned();
-    out = re_var_decl.replace_all(&out, "$1 $2;").into_owned();
-    out = re_as.replace_all(&out, "$1").into_owned();
-    out = re_angle.replace_all(&out, "$1").into_owned();
+fn module_meta(module: &LoadedModule, is_async: bool) -> String {
+    let deps = module
+        .deps
+        .iter()
+        // typed requests are imported by id, the runtime only looks up plain ones
+        .filter(|(key, _)| key_specifier(key) == key)
+        .map(|(specifier, id)| format!("{}: {}", js_string(specifier), js_string(id)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let names = module
+        .cjs
+        .exports
+        .iter()
+        .map(|name| js_string(name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let dirname = module
+        .path
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    format!(
+        "{{ filename: {}, dirname: {}, deps: {{ {deps} }}, names: [{names}], async: {is_async} }}",
+        js_string(&module.path.to_string_lossy()),
+        js_string(&dirname),
+    )
+}
 
-    out
+/// The ids of modules that evaluate asynchronously: those with a top-level
+/// `await`, and those statically importing one.
+fn async_modules(registry: &Registry, modules: &[LoadedModule]) -> HashSet<String> {
+    let mut found = registry.async_ids.lock().unwrap().clone();
+    loop {
+        let mut changed = false;
+        for module in modules.iter().filter(|m| m.kind == ModuleKind::Module) {
+            if found.contains(&module.id) {
+                continue;
+            }
+            let imports_async = module.esm.requests.iter().any(|request| {
+                module
+                    .dep(&request.key)
+                    .is_some_and(|id| found.contains(id))
+            });
+            if module.esm.has_top_level_await || imports_async {
+                found.insert(module.id.clone());
+                changed = true;
+            }
+        }
+        if !changed {
+            return found;
+        }
+    }
 }
 
-pub(crate) fn process_es6_modules(js_file: &str, js_code: &str) -> String {
-    let mut processed_code = String::new();
-    let mut loaded_modules = Vec::new();
-    let mut module_exports = HashMap::new();
-    let mut default_imports = Vec::new();
-    let base_path = Path::new(js_file);
+/// The statements an ES module runs before its own body: export bindings,
+/// then its dependencies in source order, then its namespace imports.
+/// Dependencies in `async_ids` are awaited once all of them have started.
+fn esm_preamble(module: &LoadedModule, async_ids: &HashSet<String>) -> String {
+    let syntax = &module.esm;
+    let dep_var = |key: &str| syntax.dep_var(key);
 
-    let main_code_ts_stripped = if is_ts_file(base_path) {
-        strip_types(js_code)
-    } else {
-        js_code.to_string()
-    };
-    let imports = extract_imports(&main_code_ts_stripped);
+    let mut preamble = String::new();
 
-    for (_, module_path) in &imports {
-        let resolved_path = resolve_module_path(base_path, module_path);
-        let path_str = resolved_path.to_string_lossy().to_string();
+    if syntax.uses_meta {
+        preamble.push_str(&format!(
+            "const __lunos_meta = __lunos_modules.meta({}); ",
+            js_string(&module.id)
+        ));
+    }
 
-        if module_exports.contains_key(&path_str) {
-            continue;
-        }
+    let getters = syntax
+        .exports
+        .iter()
+        .filter_map(|entry| {
+            let (exported, value) = match entry {
+                ExportEntry::Local { exported, local } => (
+                    exported,
+                    syntax.import_read(local).unwrap_or_else(|| local.clone()),
+                ),
+                ExportEntry::Indirect {
+                    exported,
+                    imported,
+                    request,
+                    ..
+                } => (
+                    exported,
+                    format!("{}[{}]", dep_var(request), js_string(imported)),
+                ),
+                ExportEntry::Namespace { exported, request } => (exported, dep_var(request)),
+                ExportEntry::Star { .. } => return None,
+            };
+            Some(format!("{}: () => {value}", js_string(exported)))
+        })
+        .collect::<Vec<_>>();
+    if !getters.is_empty() {
+        preamble.push_str(&format!(
+            "__lunos_modules.export(__lunos_exports, {{ {} }}); ",
+            getters.join(", ")
+        ));
+    }
 
-        let module_code_raw = read_module_code(&resolved_path);
-        let module_code = if is_ts_file(&resolved_path) {
-            strip_types(&module_code_raw)
+    // every dependency starts in order and the asynchronous ones are awaited
+    // together after, so one waiting doesn't hold up the siblings after it
+    let mut awaited = Vec::new();
+    for request in &syntax.requests {
+        let id = module.dep(&request.key).unwrap_or(&request.specifier);
+        let import = if async_ids.contains(id) {
+            awaited.push(dep_var(&request.key));
+            "__lunos_modules.importAsync"
         } else {
-            module_code_raw
+            "__lunos_modules.import"
         };
-        let exports = extract_exports(&module_code);
-
-        let processed_module_code = remove_exports(&module_code);
-        processed_code.push_str(&processed_module_code);
+        preamble.push_str(&format!(
+            "var {} = {import}({}); ",
+            dep_var(&request.key),
+            js_string(id)
+        ));
+    }
+    if !awaited.is_empty() {
+        let awaited = awaited.join(", ");
+        preamble.push_str(&format!(
+            "[{awaited}] = await __lunos_modules.all([{awaited}]); "
+        ));
+    }
 
-        module_exports.insert(path_str.clone(), (exports, module_code));
-        loaded_modules.push(path_str);
+    for entry in &syntax.exports {
+        if let ExportEntry::Star { request } = entry {
+            preamble.push_str(&format!(
+                "__lunos_modules.exportStar(__lunos_exports, {}); ",
+                dep_var(request)
+            ));
+        }
     }
 
-    for (import_symbols, module_path) in imports {
-        let resolved_path = resolve_module_path(base_path, &module_path);
-        let path_str = resolved_path.to_string_lossy().to_string();
+    // other imported names are read from their namespace where they're used
+    for import in &syntax.imports {
+        for binding in import.bindings.iter().filter(|b| b.imported == "*") {
+            preamble.push_str(&format!(
+                "const {} = {}; ",
+                binding.local,
+                dep_var(&import.key())
+            ));
+        }
+    }
 
-        let (exports, _module_code) = module_exports.get(&path_str).unwrap_or_else(|| {
-            std::process::exit(1);
-        });
+    preamble
+}
 
-        let _module_prefix = module_path
-            .replace("/", "_")
-            .replace("-", "_")
-            .replace(".", "_")
-            .replace("'", "")
-            .replace("\"", "");
-
-        for symbol in import_symbols {
-            if symbol.starts_with("default:") {
-                let import_name = symbol.split(':').nth(1).unwrap_or("default");
-                if exports.contains(&"__default_export_value__".to_string()) {
-                    default_imports.push((import_name.to_string(), String::new()));
+/// Wraps every module in a `__lunos_modules.define` call, one script each
+/// named by the module's id. The wrapper header shares the first line with
+/// the module body so line numbers still match, and the source map of each
+/// module is kept for its stack traces.
+fn define_modules(registry: &Registry, modules: &[LoadedModule]) -> Vec<Script> {
+    let async_ids = async_modules(registry, modules);
+    let mut defined = registry.defined.lock().unwrap();
+    let mut scripts = Vec::new();
+
+    for module in modules {
+        let is_async = async_ids.contains(&module.id);
+        let mut code = Generated::new();
+        let header = format!(
+            "__lunos_modules.define({}, \"{}\", {}, ",
+            js_string(&module.id),
+            module.kind.as_str(),
+            module_meta(module, is_async)
+        );
+        code.push_code(&header, 0);
+
+        match module.kind {
+            ModuleKind::Module => {
+                code.push_str(if is_async {
+                    "async function (__lunos_exports) { "
                 } else {
-                    std::process::exit(1);
-                }
-            } else if exports.contains(&symbol) {
-                continue;
-            } else {
-                std::process::exit(1);
+                    "function (__lunos_exports) { "
+                });
+                code.push_str(&esm_preamble(module, &async_ids));
+                apply_edits(&mut code, &module.source, take_edits(module));
+            }
+            ModuleKind::CommonJs => {
+                code.push_str("function (exports, require, module, __filename, __dirname) { ");
+                apply_edits(&mut code, &module.source, take_edits(module));
+            }
+            ModuleKind::Json => {
+                code.push_str("function (exports, require, module) { module.exports = JSON.parse(");
+                code.push_str(&js_string(&module.source));
+                code.push_str(");");
+            }
+            ModuleKind::Text => {
+                code.push_str("function (exports, require, module) { module.exports = ");
+                code.push_str(&js_string(&module.source));
+                code.push(';');
+            }
+            ModuleKind::Bytes => {
+                code.push_str(
+                    "function (exports, require, module) { module.exports = __lunos_modules.bytes(",
+                );
+                code.push_str(&js_string(&module.source));
+                code.push_str(");");
+            }
+            ModuleKind::Builtin => {
+                code.push_str("function (__lunos_exports) { ");
+                code.push_str(
+                    &builtins::find(registry, &module.id)
+                        .map_or_else(String::new, |builtin| builtin.factory_body()),
+                );
             }
         }
+        code.push_code("\n});\n", module.source.len());
+
+        let (code, map) = code.finish(&module.source);
+        let mut maps = vec![map];
+        maps.extend(module.maps.iter().rev().cloned());
+        registry.source_maps.register(&module.id, maps);
+        scripts.push(Script {
+            url: Some(module.id.clone()),
+            code,
+        });
+
+        defined.insert(module.id.clone(), module.own_export_names(registry));
     }
+    *registry.async_ids.lock().unwrap() = async_ids;
 
-    let main_code = remove_exports(&main_code_ts_stripped);
-    let main_code_without_imports = main_code
-        .lines()
-        .filter(|line| !line.trim().starts_with("import"))
-        .collect::<Vec<&str>>()
-        .join("\n");
+    scripts
+}
 
-    let processed_main_code = main_code_without_imports;
+fn take_edits(module: &LoadedModule) -> Vec<Edit> {
+    module
+        .esm
+        .edits
+        .iter()
+        .map(|edit| Edit {
+            start: edit.start,
+            end: edit.end,
+            text: edit.text.clone(),
+        })
+        .collect()
+}
 
-    processed_code
-        .push_str("\nfunction __get_default_export__() { return __default_export_value__; }\n");
+/// Evaluates `code` in `context`, returning the thrown value on failure.
+pub(crate) unsafe fn evaluate(
+    context: *const OpaqueJSContext,
+    code: &str,
+) -> Result<*const OpaqueJSValue, *const OpaqueJSValue> {
+    unsafe {
+        evaluate_script(
+            context,
+            &Script {
+                url: None,
+                code: code.to_string(),
+            },
+        )
+    }
+}
 
-    for (import_name, _) in default_imports {
-        processed_code.push_str(&format!("var {import_name} = __get_default_export__();\n"));
+/// Evaluates `script` in `context` under its URL, returning the thrown value
+/// on failure.
+pub(crate) unsafe fn evaluate_script(
+    context: *const OpaqueJSContext,
+    script: &Script,
+) -> Result<*const OpaqueJSValue, *const OpaqueJSValue> {
+    let mut exception: *const OpaqueJSValue = std::ptr::null();
+    let code = JsString::new(&script.code);
+    let url = script.url.as_deref().map(JsString::new);
+    unsafe {
+        let url = url.as_ref().map_or(std::ptr::null_mut(), JsString::as_ptr);
+        let result = JSEvaluateScript(
+            context,
+            code.as_ptr(),
+            std::ptr::null_mut(),
+            url,
+            1,
+            &mut exception,
+        );
+        if result.is_null() {
+            Err(exception)
+        } else {
+            Ok(result)
+        }
+    }
+}
+
+/// An expression that builds the `ResolveError` or `SyntaxError` a failed
+/// module load throws.
+fn create_error_expression(error: &ResolveError) -> String {
+    let optional = |value: Option<u32>| value.map_or("undefined".to_string(), |v| v.to_string());
+    format!(
+        "__lunos_modules.createError({{ name: {}, code: {}, message: {}, specifier: {}, referrer: {}, line: {}, column: {}, suggestion: {} }})",
+        js_string(error.name()),
+        js_string(error.code()),
+        js_string(&error.message()),
+        js_string(&error.specifier),
+        js_string(&error.referrer.to_string_lossy()),
+        optional(error.position.map(|(line, _)| line)),
+        optional(error.position.map(|(_, column)| column)),
+        error
+            .suggestion
+            .as_deref()
+            .map_or("undefined".to_string(), js_string),
+    )
+}
+
+unsafe fn make_error(
+    context: *const OpaqueJSContext,
+    error: &ResolveError,
+) -> *const OpaqueJSValue {
+    match unsafe { evaluate(context, &create_error_expression(error)) } {
+        Ok(value) | Err(value) => value,
     }
+}
+
+/// Resolves `request` and defines the module it names (and its static
+/// dependencies) in `context` unless it already is, returning its id.
+fn load_module(
+    registry: &Registry,
+    referrer: &Path,
+    request: &GraphRequest,
+) -> Result<(String, Vec<Script>), ResolveError> {
+    let defined: HashSet<String> = registry.defined.lock().unwrap().keys().cloned().collect();
+    let (id, loaded) = load_request(registry, referrer, request, &defined, &mut HashSet::new())?;
+    let scripts = match loaded {
+        Some(entry) => define_modules(registry, &load_graph(registry, entry)?),
+        None => Vec::new(),
+    };
+    Ok((id, scripts))
+}
 
-    processed_code.push_str(&processed_main_code);
+native_function! {
+    /// `__lunos_native_load(specifier, referrer, mode, type)`: resolves a
+    /// specifier the module graph didn't know about ahead of time, such as
+    /// `require(name)` or an `import()` with attributes. With the `"resolve"`
+    /// mode it only returns the resolved path.
+    fn native_load_callback(
+        args,
+        specifier: String,
+        referrer: String,
+        mode: String,
+        import_type: Option<String>,
+    ) -> Result<String, Failure> {
+        let context = args.context();
+        let state = State::of(context);
+        let registry = &state.modules;
+        let referrer_path = if referrer.is_empty() {
+            std::env::current_dir().unwrap_or_default().join("[eval]")
+        } else {
+            PathBuf::from(&referrer)
+        };
+        let thrown =
+            |error: &ResolveError| Failure::Thrown(JsValue(unsafe { make_error(context, error) }));
 
-    processed_code
+        // `import.meta.resolve` only wants the path, without loading anything
+        if mode == "resolve" {
+            if let Some(builtin) = builtins::find(registry, &specifier) {
+                return Ok(builtin.name.to_string());
+            }
+            if let Some(url) = remote::resolve(registry, &referrer_path, &specifier) {
+                return Ok(url);
+            }
+            let mode = ResolveMode::Import;
+            return match resolver::resolve(registry, &referrer_path, &specifier, mode) {
+                Some(path) => Ok(module_id(&path)),
+                None => {
+                    let error = ResolveError::not_found(registry, &specifier, &referrer_path, mode);
+                    Err(thrown(&error))
+                }
+            };
+        }
+        let mode = match mode.as_str() {
+            "require" => ResolveMode::Require,
+            _ => ResolveMode::Import,
+        };
+
+        let request = GraphRequest {
+            key: request_key(&specifier, import_type.as_deref()),
+            specifier,
+            mode,
+            import_type,
+            position: None,
+        };
+        let (id, scripts) =
+            load_module(registry, &referrer_path, &request).map_err(|error| thrown(&error))?;
+        for script in &scripts {
+            unsafe { evaluate_script(context, script) }
+                .map_err(|exception| Failure::Thrown(JsValue(exception)))?;
+        }
+        Ok(id)
+    }
 }
 
-fn read_module_code(resolved_path: &Path) -> String {
-    match fs::read_to_string(resolved_path) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Error reading module {}: {}", resolved_path.display(), e);
-            std::process::exit(1);
+/// Whether the entry module has finished evaluating. Fails with what it threw
+/// if a top-level `await` rejected.
+pub(crate) fn entry_settled(context: *const OpaqueJSContext) -> Result<bool, *const OpaqueJSValue> {
+    let state = unsafe { evaluate(context, "__lunos_modules.entryState()") }?;
+    Ok(unsafe { JSValueToBoolean(context, state) })
+}
+
+/// Installs the module registry (`__lunos_modules`) into `context`, and the
+/// decorator helpers (`__lunos_decorators`) the modules may use.
+pub(crate) fn bind_to_context(context: *mut OpaqueJSContext) {
+    unsafe {
+        define_internal(context, "__lunos_native_load", native_load_callback);
+        if evaluate(context, LOADER_JS).is_err() {
+            eprintln!("Failed to initialize the module loader");
+        }
+        if evaluate(context, DECORATORS_JS).is_err() {
+            eprintln!("Failed to initialize the decorator helpers");
         }
     }
 }
+
+/// Turns the entry file into scripts defining its module graph, and a last
+/// one running it. A graph that fails to resolve becomes a script throwing
+/// the error.
+pub(crate) fn process_es6_modules(
+    registry: &Registry,
+    js_file: &str,
+    js_code: &str,
+) -> Vec<Script> {
+    let path = PathBuf::from(js_file);
+    let entry = analyze(registry, module_id(&path), path, js_code.to_string(), None);
+    let entry_id = entry.id.clone();
+
+    let modules = match load_graph(registry, entry) {
+        Ok(modules) => modules,
+        Err(error) => {
+            return vec![Script {
+                url: None,
+                code: format!("throw {};", create_error_expression(&error)),
+            }];
+        }
+    };
+
+    let mut scripts = define_modules(registry, &modules);
+    scripts.push(Script {
+        url: None,
+        code: format!("__lunos_modules.run({});", js_string(&entry_id)),
+    });
+    scripts
+}
@@ -0,0 +1,285 @@
+//! Reports of uncaught exceptions.
+//!
+//! A report starts with `Name: message`, then the stack trace with every
+//! frame mapped back to the original file, then the lines of code around
+//! where the exception was thrown. JavaScriptCore writes stack frames as
+//! `name@url:line:column`; they're printed the way Node prints them, and the
+//! frames of Lunos' own scripts, which have no URL, are left out.
+
+use super::convert::{JsObject, JsString, JsValue};
+use super::source_map::SourceMaps;
+use super::state::State;
+use rusty_jsc::*;
+use std::fs;
+use std::io::IsTerminal;
+
+/// How many lines the code frame shows on either side of the failing one.
+const CONTEXT_LINES: u32 = 2;
+
+const RED: &str = "\x1b[31m";
+const GRAY: &str = "\x1b[90m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// What was thrown, as it's reported.
+struct Thrown {
+    /// `Name: message` for errors, otherwise the value as a string.
+    headline: String,
+    stack: Option<String>,
+    /// Where a syntax error is, which it has no stack frame for.
+    location: Option<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Location {
+    url: String,
+    line: u32,
+    column: u32,
+}
+
+struct Frame {
+    function: Option<String>,
+    location: Option<Location>,
+    native: bool,
+}
+
+/// The report of `exception`, for standard error.
+pub(crate) unsafe fn describe(
+    context: *const OpaqueJSContext,
+    exception: *const OpaqueJSValue,
+) -> String {
+    let thrown = unsafe { Thrown::read(context, exception) };
+    let state = State::of(context);
+    render(
+        &thrown,
+        &state.modules.source_maps,
+        std::io::stderr().is_terminal(),
+    )
+}
+
+impl Thrown {
+    unsafe fn read(context: *const OpaqueJSContext, exception: *const OpaqueJSValue) -> Thrown {
+        unsafe {
+            let fallback = || Thrown {
+                headline: JsString::from_value(context, exception).to_string(),
+                stack: None,
+                location: None,
+            };
+            if !JSValueIsObject(context, exception) {
+                return fallback();
+            }
+            let object = JsObject(JSValueToObject(context, exception, std::ptr::null_mut()));
+            let stack = string_property(context, object, "stack");
+            let message = string_property(context, object, "message");
+            // only errors have a message or stack to report
+            let Some(message) = message.filter(|_| stack.is_some()) else {
+                return fallback();
+            };
+            let name =
+                string_property(context, object, "name").unwrap_or_else(|| "Error".to_string());
+            let headline = if message.is_empty() {
+                name
+            } else {
+                format!("{name}: {message}")
+            };
+
+            let location = string_property(context, object, "sourceURL").and_then(|url| {
+                Some(Location {
+                    url,
+                    line: number_property(context, object, "line")?,
+                    column: number_property(context, object, "column").unwrap_or(1),
+                })
+            });
+            Thrown {
+                headline,
+                stack: stack.filter(|stack| !stack.is_empty()),
+                location,
+            }
+        }
+    }
+}
+
+unsafe fn string_property(
+    context: *const OpaqueJSContext,
+    object: JsObject,
+    name: &str,
+) -> Option<String> {
+    unsafe { object.property(context, name).get(context) }.ok()
+}
+
+unsafe fn number_property(
+    context: *const OpaqueJSContext,
+    object: JsObject,
+    name: &str,
+) -> Option<u32> {
+    let value: JsValue = unsafe { object.property(context, name) };
+    let number: f64 = unsafe { value.get(context) }.ok()?;
+    (number >= 1.0).then_some(number as u32)
+}
+
+fn render(thrown: &Thrown, maps: &SourceMaps, color: bool) -> String {
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut out = paint(&format!("{BOLD}{RED}"), &thrown.headline);
+    out.push('\n');
+
+    let frames: Vec<Frame> = thrown
+        .stack
+        .as_deref()
+        .map(|stack| {
+            stack
+                .lines()
+                .filter_map(|line| parse_frame(line, maps))
+                .collect()
+        })
+        .unwrap_or_default();
+    for frame in &frames {
+        let location = match (&frame.location, frame.native) {
+            (Some(location), _) => {
+                format!("{}:{}:{}", location.url, location.line, location.column)
+            }
+            (None, true) => "native".to_string(),
+            (None, false) => continue,
+        };
+        let line = match &frame.function {
+            Some(function) => format!("    at {function} ({location})"),
+            None => format!("    at {location}"),
+        };
+        out.push_str(&paint(GRAY, &line));
+        out.push('\n');
+    }
+
+    let location = frames
+        .iter()
+        .filter_map(|frame| frame.location.clone())
+        .chain(
+            thrown
+                .location
+                .clone()
+                .map(|location| remap(location, maps)),
+        )
+        .find(|location| fs::metadata(&location.url).is_ok_and(|meta| meta.is_file()));
+    if let Some(location) = location
+        && let Some(frame) = code_frame(&location, &paint)
+    {
+        out.push('\n');
+        out.push_str(&frame);
+    }
+    out
+}
+
+/// Reads one line of a stack trace, either JavaScriptCore's
+/// `name@url:line:column` or V8's `at name (url:line:column)`.
+fn parse_frame(line: &str, maps: &SourceMaps) -> Option<Frame> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let (function, place) = if let Some(rest) = line.strip_prefix("at ") {
+        match rest
+            .strip_suffix(')')
+            .and_then(|rest| rest.split_once(" ("))
+        {
+            Some((function, place)) => (Some(function), place),
+            None => (None, rest),
+        }
+    } else {
+        // URLs may have an `@` in them, as in `node_modules/@scope`
+        match line.split_once('@') {
+            Some((function, place)) => (Some(function), place),
+            // a frame without a URL, in Lunos' own scripts
+            None => (Some(line), ""),
+        }
+    };
+    // top-level code isn't in a function
+    let function = function
+        .filter(|function| !function.is_empty() && !function.ends_with(" code"))
+        .map(str::to_string);
+    if place == "[native code]" || place == "native" {
+        return Some(Frame {
+            function,
+            location: None,
+            native: true,
+        });
+    }
+    Some(Frame {
+        function,
+        location: parse_location(place).map(|location| remap(location, maps)),
+        native: false,
+    })
+}
+
+/// `url:line:column`.
+fn parse_location(place: &str) -> Option<Location> {
+    let (rest, column) = place.rsplit_once(':')?;
+    let (url, line) = rest.rsplit_once(':')?;
+    if url.is_empty() {
+        return None;
+    }
+    Some(Location {
+        url: url.to_string(),
+        line: line.parse().ok()?,
+        column: column.parse().ok()?,
+    })
+}
+
+/// The location in the original file, through the module's source maps.
+fn remap(location: Location, maps: &SourceMaps) -> Location {
+    match maps.original_position(&location.url, location.line, location.column) {
+        Some((line, column)) => Location {
+            line,
+            column,
+            ..location
+        },
+        None => location,
+    }
+}
+
+/// The lines around `location` with the failing one marked, and a caret
+/// under its column.
+fn code_frame(location: &Location, paint: &dyn Fn(&str, &str) -> String) -> Option<String> {
+    let source = fs::read_to_string(&location.url).ok()?;
+    let lines: Vec<&str> = source.lines().collect();
+    let line = location.line as usize;
+    if line == 0 || line > lines.len() {
+        return None;
+    }
+    let first = line.saturating_sub(CONTEXT_LINES as usize).max(1);
+    let last = (line + CONTEXT_LINES as usize).min(lines.len());
+    let width = last.to_string().len();
+
+    let mut out = String::new();
+    for number in first..=last {
+        let text = lines[number - 1].trim_end_matches('\r');
+        let marker = if number == line { ">" } else { " " };
+        let gutter = format!("{marker} {number:>width$} | ");
+        if number == line {
+            out.push_str(&paint(RED, &gutter));
+            out.push_str(text);
+        } else {
+            out.push_str(&paint(GRAY, &gutter));
+            out.push_str(&paint(GRAY, text));
+        }
+        out.push('\n');
+
+        if number == line {
+            // tabs are kept so the caret lines up under them
+            let indent: String = text
+                .chars()
+                .take(location.column.saturating_sub(1) as usize)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            out.push_str(&paint(GRAY, &format!("  {:width$} | ", "")));
+            out.push_str(&indent);
+            out.push_str(&paint(RED, "^"));
+            out.push('\n');
+        }
+    }
+    Some(out)
+}
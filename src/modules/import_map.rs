@@ -0,0 +1,266 @@
+use super::registry::Registry;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// The project config file, looked up from the entry script's directory
+/// upwards. It either points at an import map (`"importMap": "./map.json"`)
+/// or has the `imports` and `scopes` of one inline. The resolver also reads
+/// its `"extensions"` from there.
+const PROJECT_CONFIG: &str = "lunos.json";
+
+/// What an import map turns a specifier into.
+pub(crate) enum Mapped {
+    /// An absolute file or directory path.
+    Path(PathBuf),
+    /// Another bare specifier, resolved as a package.
+    Specifier(String),
+}
+
+/// A specifier map sorted by key length, longest first, so the first prefix
+/// match is the most specific one. Keys and targets that are paths are kept
+/// absolute.
+struct SpecifierMap(Vec<(String, String)>);
+
+pub(crate) struct ImportMap {
+    imports: SpecifierMap,
+    /// Sorted by prefix length, longest first, like the specifier maps.
+    scopes: Vec<(String, SpecifierMap)>,
+}
+
+fn is_path_like(specifier: &str) -> bool {
+    specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || specifier.starts_with('/')
+        || specifier == "."
+        || specifier == ".."
+}
+
+/// Resolves `.` and `..` in `path` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// An absolute path for a path-like key or target, keeping a trailing slash
+/// since it marks a prefix mapping.
+fn absolute(base_dir: &Path, specifier: &str) -> String {
+    let mut path = normalize(&base_dir.join(specifier))
+        .to_string_lossy()
+        .to_string();
+    if specifier.ends_with('/') && !path.ends_with('/') {
+        path.push('/');
+    }
+    path
+}
+
+/// The `lunos.json` of the project `start` is in, if it has one.
+pub(crate) fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG))
+        .find(|p| p.is_file())
+}
+
+/// The directory of the project `start` is in: where its `lunos.json` is, or
+/// `start` itself without one.
+pub(crate) fn project_dir(start: &Path) -> PathBuf {
+    find_project_config(start)
+        .and_then(|config| config.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| start.to_path_buf())
+}
+
+/// Whether the module at `referrer` is in the scope `prefix`: the module
+/// itself, or one under it for a prefix ending in `/`.
+fn in_scope(referrer: &str, prefix: &str) -> bool {
+    referrer == prefix || (prefix.ends_with('/') && referrer.starts_with(prefix))
+}
+
+impl SpecifierMap {
+    fn parse(map: &Map<String, Value>, base_dir: &Path, origin: &Path) -> SpecifierMap {
+        let mut entries = Vec::new();
+        for (key, target) in map {
+            let Value::String(target) = target else {
+                eprintln!(
+                    "Ignoring import map entry '{key}' in {}: target must be a string",
+                    origin.display()
+                );
+                continue;
+            };
+            if key.ends_with('/') && !target.ends_with('/') {
+                eprintln!(
+                    "Ignoring import map entry '{key}' in {}: the target of a key ending in '/' must end in '/' too",
+                    origin.display()
+                );
+                continue;
+            }
+
+            let key = if is_path_like(key) {
+                absolute(base_dir, key)
+            } else {
+                key.clone()
+            };
+            let target = if is_path_like(target) {
+                absolute(base_dir, target)
+            } else {
+                target.clone()
+            };
+            entries.push((key, target));
+        }
+        entries.sort_by_key(|(key, _)| std::cmp::Reverse(key.len()));
+        SpecifierMap(entries)
+    }
+
+    fn lookup(&self, specifier: &str) -> Option<String> {
+        if let Some((_, target)) = self.0.iter().find(|(key, _)| key == specifier) {
+            return Some(target.clone());
+        }
+        self.0.iter().find_map(|(key, target)| {
+            let rest = specifier
+                .strip_prefix(key.as_str())
+                .filter(|_| key.ends_with('/'))?;
+            Some(format!("{target}{rest}"))
+        })
+    }
+}
+
+impl ImportMap {
+    /// Parses an import map whose relative paths are relative to `base_dir`.
+    /// `origin` is the file it came from, for messages.
+    fn parse(
+        json: &Map<String, Value>,
+        base_dir: &Path,
+        origin: &Path,
+    ) -> Result<ImportMap, String> {
+        let imports = match json.get("imports") {
+            Some(Value::Object(imports)) => SpecifierMap::parse(imports, base_dir, origin),
+            Some(_) => {
+                return Err(format!(
+                    "\"imports\" in {} must be an object",
+                    origin.display()
+                ));
+            }
+            None => SpecifierMap(Vec::new()),
+        };
+
+        let mut scopes = Vec::new();
+        match json.get("scopes") {
+            Some(Value::Object(map)) => {
+                for (scope, scope_map) in map {
+                    let Value::Object(scope_map) = scope_map else {
+                        return Err(format!(
+                            "Scope '{scope}' in {} must be an object",
+                            origin.display()
+                        ));
+                    };
+                    let prefix = if is_path_like(scope) {
+                        absolute(base_dir, scope)
+                    } else {
+                        scope.clone()
+                    };
+                    scopes.push((prefix, SpecifierMap::parse(scope_map, base_dir, origin)));
+                }
+            }
+            Some(_) => {
+                return Err(format!(
+                    "\"scopes\" in {} must be an object",
+                    origin.display()
+                ));
+            }
+            None => {}
+        }
+        scopes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+
+        Ok(ImportMap { imports, scopes })
+    }
+
+    pub fn read(path: &Path) -> Result<ImportMap, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Error reading import map {}: {e}", path.display()))?;
+        let json: Value = serde_json::from_str(&contents)
+            .map_err(|e| format!("Error parsing import map {}: {e}", path.display()))?;
+        let Value::Object(json) = json else {
+            return Err(format!("Import map {} must be an object", path.display()));
+        };
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        ImportMap::parse(&json, base_dir, path)
+    }
+
+    /// The import map of the project `start` is in, from its `lunos.json`.
+    fn find_project_map(start: &Path) -> Result<Option<ImportMap>, String> {
+        let Some(config_path) = find_project_config(start) else {
+            return Ok(None);
+        };
+        let contents = fs::read_to_string(&config_path)
+            .map_err(|e| format!("Error reading {}: {e}", config_path.display()))?;
+        let Value::Object(config) = serde_json::from_str(&contents)
+            .map_err(|e| format!("Error parsing {}: {e}", config_path.display()))?
+        else {
+            return Err(format!("{} must be an object", config_path.display()));
+        };
+
+        let config_dir = config_path.parent().unwrap_or(Path::new("."));
+        match config.get("importMap") {
+            Some(Value::String(map_path)) => ImportMap::read(&config_dir.join(map_path)).map(Some),
+            Some(_) => Err(format!(
+                "\"importMap\" in {} must be a path",
+                config_path.display()
+            )),
+            None if config.contains_key("imports") || config.contains_key("scopes") => {
+                ImportMap::parse(&config, config_dir, &config_path).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the import map given with `--import-map`, or else the one of the
+    /// project `entry` belongs to, and uses it for all further resolution in
+    /// `registry`.
+    pub fn install(registry: &Registry, entry: &Path, flag: Option<&Path>) -> Result<(), String> {
+        let map = match flag {
+            Some(path) => Some(ImportMap::read(path)?),
+            None => ImportMap::find_project_map(entry.parent().unwrap_or(Path::new(".")))?,
+        };
+        if let Some(map) = map {
+            let _ = registry.import_map.set(map);
+        }
+        Ok(())
+    }
+
+    /// Applies the installed import map to `specifier` as imported from the
+    /// file at `referrer`. Scopes containing the referrer are tried from the
+    /// most specific one, then the top-level `imports`.
+    pub fn map(registry: &Registry, specifier: &str, referrer: &Path) -> Option<Mapped> {
+        let map = registry.import_map.get()?;
+        let key = if is_path_like(specifier) {
+            absolute(referrer.parent().unwrap_or(Path::new(".")), specifier)
+        } else {
+            specifier.to_string()
+        };
+        let referrer = referrer.to_string_lossy();
+
+        let target = map
+            .scopes
+            .iter()
+            .filter(|(prefix, _)| in_scope(&referrer, prefix))
+            .find_map(|(_, scope)| scope.lookup(&key))
+            .or_else(|| map.imports.lookup(&key))?;
+
+        Some(
+            if target.starts_with('/') || Path::new(&target).is_absolute() {
+                Mapped::Path(PathBuf::from(target))
+            } else {
+                Mapped::Specifier(target)
+            },
+        )
+    }
+}
@@ -0,0 +1,555 @@
+//! JSX, lowered to the function calls it stands for.
+//!
+//! With the classic runtime `<p id="a">hi</p>` becomes
+//! `React.createElement("p", { id: "a" }, "hi")`. With the automatic runtime
+//! it becomes `_jsx("p", { id: "a", children: "hi" })`, and `_jsx` is imported
+//! from `react/jsx-runtime`, or the `jsx-runtime` of another import source.
+//! Which runtime and factory apply comes from the `jsx` compiler options of
+//! the module's tsconfig, and pragma comments like `/** @jsx h */` in the
+//! module override them. Like type erasure, the output keeps every line where
+//! it was.
+
+use super::lexer::{Token, TokenKind, tokenize_jsx};
+use super::registry::Registry;
+use super::source_map::{Generated, SourceMap};
+use super::tsconfig::TsConfig;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum JsxRuntime {
+    Classic,
+    Automatic,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct JsxOptions {
+    pub runtime: JsxRuntime,
+    /// The function called to create elements with the classic runtime.
+    pub factory: String,
+    /// The component fragments stand for with the classic runtime.
+    pub fragment: String,
+    /// The package whose `jsx-runtime` the automatic runtime imports.
+    pub import_source: String,
+    /// Whether the automatic runtime uses `jsxDEV` from `jsx-dev-runtime`.
+    pub development: bool,
+}
+
+impl Default for JsxOptions {
+    fn default() -> Self {
+        JsxOptions {
+            runtime: JsxRuntime::Classic,
+            factory: "React.createElement".to_string(),
+            fragment: "React.Fragment".to_string(),
+            import_source: "react".to_string(),
+            development: false,
+        }
+    }
+}
+
+impl JsxOptions {
+    /// The options the tsconfig of the module at `path` sets.
+    pub fn for_file(registry: &Registry, path: &Path) -> JsxOptions {
+        let mut options = JsxOptions::default();
+        let Some(config) = TsConfig::for_file(registry, path) else {
+            return options;
+        };
+        match config.option("jsx") {
+            Some("react-jsx") => options.runtime = JsxRuntime::Automatic,
+            Some("react-jsxdev") => {
+                options.runtime = JsxRuntime::Automatic;
+                options.development = true;
+            }
+            _ => {}
+        }
+        if let Some(factory) = config.option("jsxFactory") {
+            options.factory = factory.to_string();
+        }
+        if let Some(fragment) = config.option("jsxFragmentFactory") {
+            options.fragment = fragment.to_string();
+        }
+        if let Some(source) = config.option("jsxImportSource") {
+            options.import_source = source.to_string();
+        }
+        options
+    }
+
+    /// Names the transform with these options for the transform cache. TSX
+    /// is transformed differently, `<T,>() => {}` being a generic arrow.
+    pub fn cache_name(&self, typescript: bool) -> String {
+        format!("jsx\0{typescript}\0{self:?}")
+    }
+
+    /// Applies the `@jsx`, `@jsxFrag`, `@jsxRuntime` and `@jsxImportSource`
+    /// pragmas in the comments of `src`.
+    fn apply_pragmas(&mut self, src: &str, tokens: &[Token]) {
+        for (at, _) in src.match_indices("@jsx") {
+            let next = tokens.partition_point(|token| token.end <= at);
+            if tokens.get(next).is_some_and(|token| token.start <= at) {
+                continue;
+            }
+            let rest = &src[at + 4..];
+            let name_end = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            let value = rest[name_end..]
+                .trim_start_matches([' ', '\t'])
+                .split(|c: char| c.is_whitespace())
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches("*/");
+            if value.is_empty() {
+                continue;
+            }
+            match &rest[..name_end] {
+                "" => {
+                    self.runtime = JsxRuntime::Classic;
+                    self.factory = value.to_string();
+                }
+                "Frag" => self.fragment = value.to_string(),
+                "Runtime" if value == "classic" => self.runtime = JsxRuntime::Classic,
+                "Runtime" if value == "automatic" => self.runtime = JsxRuntime::Automatic,
+                "ImportSource" => {
+                    self.runtime = JsxRuntime::Automatic;
+                    self.import_source = value.to_string();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lowers the JSX in `src`, with the map of where the output came from. In
+/// TypeScript `<T,>(x: T) => x` is an arrow function rather than an element.
+pub(crate) fn transform(src: &str, options: &JsxOptions, typescript: bool) -> (String, SourceMap) {
+    let tokens = tokenize_jsx(src, typescript);
+    let mut options = options.clone();
+    options.apply_pragmas(src, &tokens);
+    let mut lowering = Lowering {
+        src,
+        tokens,
+        options,
+        synced: 0,
+        used: Vec::new(),
+    };
+    let mut out = Generated::new();
+    lowering.js(&mut out, 0, lowering.tokens.len());
+    out.push_source(src, lowering.synced, src.len());
+    lowering.add_import(&mut out);
+    out.finish(src)
+}
+
+struct Lowering<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    options: JsxOptions,
+    /// How far into `src` the output has come, by the line breaks it has.
+    synced: usize,
+    /// The automatic runtime's exports the module uses, in order of use.
+    used: Vec<&'static str>,
+}
+
+impl Lowering<'_> {
+    fn text(&self, i: usize) -> &str {
+        let token = &self.tokens[i];
+        &self.src[token.start..token.end]
+    }
+
+    fn is_punct(&self, i: usize, text: &str) -> bool {
+        i < self.tokens.len() && self.tokens[i].kind == TokenKind::JsxPunct && self.text(i) == text
+    }
+
+    /// Writes the line breaks between the last output and `to` in `src`.
+    fn sync(&mut self, out: &mut String, to: usize) {
+        if to > self.synced {
+            out.extend(self.src[self.synced..to].matches('\n').map(|_| '\n'));
+            self.synced = to;
+        }
+    }
+
+    /// Copies the code of tokens `i..end`, lowering the elements in it.
+    fn js(&mut self, out: &mut Generated, i: usize, end: usize) {
+        let mut i = i;
+        while i < end {
+            if self.is_punct(i, "<") {
+                let start = self.tokens[i].start;
+                out.push_source(self.src, self.synced, start);
+                self.synced = start;
+                let mut element = String::new();
+                i = self.element(&mut element, i);
+                out.push_code(&element, start);
+            } else {
+                i += 1;
+            }
+        }
+        if end > 0 {
+            let to = self.tokens[end - 1].end.max(self.synced);
+            out.push_source(self.src, self.synced, to);
+            self.synced = to;
+        }
+    }
+
+    /// The code of the expression in tokens `i..end`, after the line breaks
+    /// that come before it.
+    fn expression(&mut self, i: usize, end: usize) -> String {
+        let mut code = String::new();
+        if i < end {
+            self.sync(&mut code, self.tokens[i].start);
+            let mut generated = Generated::new();
+            self.js(&mut generated, i, end);
+            code.push_str(generated.code());
+        }
+        code
+    }
+
+    /// The `}` that closes the expression container opened at `i`.
+    fn container_end(&self, i: usize) -> usize {
+        let mut depth = 0;
+        for k in i..self.tokens.len() {
+            if self.is_punct(k, "{") {
+                depth += 1;
+            } else if self.is_punct(k, "}") {
+                depth -= 1;
+                if depth == 0 {
+                    return k;
+                }
+            }
+        }
+        self.tokens.len()
+    }
+
+    /// The element type named at `i`, and where the name ends.
+    fn tag_name(&self, i: usize) -> (String, usize) {
+        let mut name = self.text(i).to_string();
+        let mut end = i + 1;
+        if self.is_punct(end, ":") && end + 1 < self.tokens.len() {
+            name = format!("{name}:{}", self.text(end + 1));
+            return (js_string(&name), end + 2);
+        }
+        let mut member = false;
+        while self.is_punct(end, ".") && end + 1 < self.tokens.len() {
+            name = format!("{name}.{}", self.text(end + 1));
+            member = true;
+            end += 2;
+        }
+        let intrinsic =
+            !member && (name.starts_with(|c: char| c.is_ascii_lowercase()) || name.contains('-'));
+        (if intrinsic { js_string(&name) } else { name }, end)
+    }
+
+    /// The name of an export of the automatic runtime, imported on first use.
+    fn runtime_name(&mut self, name: &'static str) -> String {
+        if !self.used.contains(&name) {
+            self.used.push(name);
+        }
+        format!("_{name}")
+    }
+
+    /// Lowers the element or fragment whose `<` is at `i` into `out`, and
+    /// returns the index of the token after it.
+    fn element(&mut self, out: &mut String, i: usize) -> usize {
+        let len = self.tokens.len();
+        let automatic = self.options.runtime == JsxRuntime::Automatic;
+        let (tag, mut j) = if self.is_punct(i + 1, ">") {
+            let tag = if automatic {
+                self.runtime_name("Fragment")
+            } else {
+                self.options.fragment.clone()
+            };
+            (tag, i + 1)
+        } else if i + 1 < len {
+            self.tag_name(i + 1)
+        } else {
+            return len;
+        };
+
+        let mut props: Vec<String> = Vec::new();
+        let mut key: Option<String> = None;
+        while j < len && !self.is_punct(j, ">") && !self.is_punct(j, "/>") {
+            let synced = self.synced;
+            let mut code = String::new();
+            self.sync(&mut code, self.tokens[j].start);
+            if self.is_punct(j, "{") {
+                let close = self.container_end(j);
+                code.push_str("...");
+                code.push_str(&self.expression(j + 2, close));
+                props.push(code);
+                j = close + 1;
+                continue;
+            }
+            if self.tokens[j].kind != TokenKind::Ident {
+                self.synced = synced;
+                j += 1;
+                continue;
+            }
+            let mut name = self.text(j).to_string();
+            j += 1;
+            if self.is_punct(j, ":") && j + 1 < len {
+                name = format!("{name}:{}", self.text(j + 1));
+                j += 2;
+            }
+            let value = if self.is_punct(j, "=") {
+                j += 1;
+                if self.is_punct(j, "{") {
+                    let close = self.container_end(j);
+                    let value = self.expression(j + 1, close);
+                    j = close + 1;
+                    value
+                } else if self.is_punct(j, "<") {
+                    let mut value = String::new();
+                    j = self.element(&mut value, j);
+                    value
+                } else if j < len && self.tokens[j].kind == TokenKind::Str {
+                    let text = self.text(j);
+                    let value = js_string(&decode_entities(&text[1..text.len() - 1]));
+                    j += 1;
+                    value
+                } else {
+                    "true".to_string()
+                }
+            } else {
+                "true".to_string()
+            };
+            if automatic && name == "key" {
+                key = Some(value);
+                continue;
+            }
+            let name = if is_identifier(&name) {
+                name
+            } else {
+                js_string(&name)
+            };
+            code.push_str(&format!("{name}: {value}"));
+            props.push(code);
+        }
+
+        let mut children: Vec<String> = Vec::new();
+        let mut spread_children = false;
+        if self.is_punct(j, ">") {
+            j += 1;
+            while j < len && !(self.is_punct(j, "<") && self.is_punct(j + 1, "/")) {
+                // a child that turns out empty leaves its line breaks to the next
+                let synced = self.synced;
+                let mut code = String::new();
+                self.sync(&mut code, self.tokens[j].start);
+                if self.tokens[j].kind == TokenKind::JsxText {
+                    match clean_text(self.text(j)) {
+                        Some(text) => {
+                            code.push_str(&js_string(&text));
+                            children.push(code);
+                        }
+                        None => self.synced = synced,
+                    }
+                    j += 1;
+                } else if self.is_punct(j, "{") {
+                    let close = self.container_end(j);
+                    if close > j + 1 {
+                        let spread = self.tokens[j + 1].kind == TokenKind::Punct
+                            && self.text(j + 1) == "...";
+                        if spread {
+                            spread_children = true;
+                            code.push_str("...");
+                        }
+                        code.push_str(&self.expression(j + 1 + spread as usize, close));
+                        children.push(code);
+                    } else {
+                        self.synced = synced;
+                    }
+                    j = close + 1;
+                } else if self.is_punct(j, "<") {
+                    j = self.element(&mut code, j);
+                    children.push(code);
+                } else {
+                    j += 1;
+                }
+            }
+            while j < len && !self.is_punct(j, ">") {
+                j += 1;
+            }
+        }
+        let end = (j + 1).min(len);
+
+        let mut tail = String::new();
+        self.sync(&mut tail, self.tokens[end - 1].start);
+        self.synced = self.synced.max(self.tokens[end - 1].end);
+
+        if automatic {
+            let is_static = children.len() > 1 || spread_children;
+            match children.len() {
+                0 => {}
+                1 if !spread_children => props.push(format!("children: {}", children[0])),
+                _ => props.push(format!("children: [{}]", children.join(", "))),
+            }
+            let props = if props.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", props.join(", "))
+            };
+            if self.options.development {
+                let jsx = self.runtime_name("jsxDEV");
+                let key = key.unwrap_or_else(|| "void 0".to_string());
+                out.push_str(&format!("{jsx}({tag}, {props}, {key}, {is_static}{tail})"));
+            } else {
+                let jsx = self.runtime_name(if is_static { "jsxs" } else { "jsx" });
+                let key = key.map(|key| format!(", {key}")).unwrap_or_default();
+                out.push_str(&format!("{jsx}({tag}, {props}{key}{tail})"));
+            }
+        } else {
+            let props = if props.is_empty() {
+                "null".to_string()
+            } else {
+                format!("{{ {} }}", props.join(", "))
+            };
+            out.push_str(&format!("{}({tag}, {props}", self.options.factory));
+            for child in children {
+                out.push_str(", ");
+                out.push_str(&child);
+            }
+            out.push_str(&tail);
+            out.push(')');
+        }
+        end
+    }
+
+    /// Imports what the module uses of the automatic runtime, on its first
+    /// line so no other line moves.
+    fn add_import(&self, out: &mut Generated) {
+        if self.used.is_empty() {
+            return;
+        }
+        let module = if self.options.development {
+            "jsx-dev-runtime"
+        } else {
+            "jsx-runtime"
+        };
+        let names: Vec<String> = self
+            .used
+            .iter()
+            .map(|name| format!("{name} as _{name}"))
+            .collect();
+        let import = format!(
+            "import {{ {} }} from {};",
+            names.join(", "),
+            js_string(&format!("{}/{module}", self.options.import_source))
+        );
+        let code = out.code();
+        let at = if code.starts_with("#!") {
+            code.find('\n').map_or(code.len(), |i| i + 1)
+        } else {
+            0
+        };
+        out.insert_code(at, &import, at);
+    }
+}
+
+fn js_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// The string a run of JSX text stands for, the way Babel and TypeScript
+/// read it: lines are trimmed, lines with only whitespace dropped and the
+/// rest joined by a space. `None` when nothing is left.
+fn clean_text(raw: &str) -> Option<String> {
+    let lines: Vec<&str> = raw
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .collect();
+    let last_non_empty = lines
+        .iter()
+        .rposition(|line| !line.trim_matches([' ', '\t']).is_empty())?;
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate().take(last_non_empty + 1) {
+        let line = line.replace('\t', " ");
+        let mut line = line.as_str();
+        if i > 0 {
+            line = line.trim_start_matches(' ');
+        }
+        if i < lines.len() - 1 {
+            line = line.trim_end_matches(' ');
+        }
+        if line.is_empty() {
+            continue;
+        }
+        text.push_str(line);
+        if i < last_non_empty {
+            text.push(' ');
+        }
+    }
+    Some(decode_entities(&text))
+}
+
+/// Decodes the HTML character references in JSX text and attribute strings.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                let c = match entity.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16)
+                        .ok()
+                        .and_then(char::from_u32),
+                    Some(decimal) => decimal.parse().ok().and_then(char::from_u32),
+                    None => named_entity(entity),
+                }?;
+                Some((c, end + 2))
+            });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn named_entity(name: &str) -> Option<char> {
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "middot" => '·',
+        "bull" => '•',
+        "times" => '×',
+        "divide" => '÷',
+        "deg" => '°',
+        "euro" => '€',
+        _ => return None,
+    })
+}
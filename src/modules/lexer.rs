@@ -0,0 +1,554 @@
+//! A small JavaScript tokenizer used by the module loader.
+//!
+//! It does not build a syntax tree, it only knows enough about the language
+//! (strings, template literals, comments and regular expression literals) to
+//! hand back tokens with their byte offsets so the loader can rewrite source
+//! text without tripping over an `import` inside a string or a comment.
+//!
+//! `tokenize_jsx` also reads JSX, where text between tags follows its own
+//! rules: `<p>Don't</p>` has no string in it.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Ident,
+    Punct,
+    Str,
+    Template,
+    Regex,
+    Number,
+    /// Text between JSX tags.
+    JsxText,
+    /// The `<`, `>`, `/>`, `/`, `=`, `.` and `:` of a JSX tag, and the braces
+    /// of a JSX expression container.
+    JsxPunct,
+}
+
+/// Where the JSX lexer is, innermost last.
+enum Jsx {
+    /// Inside a tag, between `<` and `>`.
+    Tag { closing: bool },
+    /// Between the tags of an element.
+    Children,
+    /// In an expression container, until the `}` that brings `braces` back
+    /// to this length.
+    Expression(usize),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+    /// Whether a line break separates this token from the previous one.
+    pub nl_before: bool,
+}
+
+impl Token {
+    pub fn text<'a>(&self, src: &'a str) -> &'a str {
+        &src[self.start..self.end]
+    }
+
+    pub fn is(&self, src: &str, text: &str) -> bool {
+        self.text(src) == text
+    }
+}
+
+const PUNCTUATORS: [&str; 51] = [
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>", "==", "!=",
+    "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "**", "<<", ">>", "{", "}", "(", ")", "[", "]", ";", ",", "<", ">", "+", "-", "*", "%", "&",
+    "|", "^", "!",
+];
+
+const SINGLE_PUNCTUATORS: &[u8] = b"~?:=.@#/";
+
+const REGEX_KEYWORDS: [&str; 14] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+pub(crate) fn tokenize(src: &str) -> Vec<Token> {
+    lex(src, None)
+}
+
+/// Tokenizes JavaScript with JSX in it. In TypeScript `<T,>` and
+/// `<T extends U>` start the type parameters of an arrow function instead.
+pub(crate) fn tokenize_jsx(src: &str, typescript: bool) -> Vec<Token> {
+    lex(src, Some(typescript))
+}
+
+fn lex(src: &str, jsx_typescript: Option<bool>) -> Vec<Token> {
+    let bytes = src.as_bytes();
+    let mut tokens: Vec<Token> = Vec::new();
+    // one entry per open `{`, `true` when it opened a template substitution
+    let mut braces: Vec<bool> = Vec::new();
+    let mut jsx: Vec<Jsx> = Vec::new();
+    let mut pos = 0;
+    let mut nl_before = false;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+
+        if let Some(Jsx::Children) = jsx.last() {
+            let start = pos;
+            let kind = match c {
+                b'{' => {
+                    jsx.push(Jsx::Expression(braces.len()));
+                    pos += 1;
+                    TokenKind::JsxPunct
+                }
+                b'<' => {
+                    let closing = src[pos + 1..].trim_start().starts_with('/');
+                    jsx.push(Jsx::Tag { closing });
+                    pos += 1;
+                    TokenKind::JsxPunct
+                }
+                _ => {
+                    pos = src[pos..].find(['{', '<']).map_or(bytes.len(), |i| pos + i);
+                    TokenKind::JsxText
+                }
+            };
+            tokens.push(Token {
+                kind,
+                start,
+                end: pos,
+                nl_before: false,
+            });
+            continue;
+        }
+
+        if c == b'\n' {
+            nl_before = true;
+            pos += 1;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if src[pos..].starts_with("\u{a0}") || src[pos..].starts_with("\u{feff}") {
+            pos += src[pos..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+
+        if c == b'/' && bytes.get(pos + 1) == Some(&b'/') {
+            while pos < bytes.len() && bytes[pos] != b'\n' {
+                pos += 1;
+            }
+            continue;
+        }
+        if c == b'/' && bytes.get(pos + 1) == Some(&b'*') {
+            let end = src[pos + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |i| pos + 2 + i + 2);
+            if src[pos..end].contains('\n') {
+                nl_before = true;
+            }
+            pos = end;
+            continue;
+        }
+        // `#!` is only valid on the very first line
+        if pos == 0 && src.starts_with("#!") {
+            while pos < bytes.len() && bytes[pos] != b'\n' {
+                pos += 1;
+            }
+            continue;
+        }
+
+        let start = pos;
+        if let Some(Jsx::Tag { closing }) = jsx.last() {
+            let closing = *closing;
+            // `<Select<Option> ...>`: type arguments of the element type leave
+            // no tokens, so they're dropped from the output
+            if c == b'<'
+                && !closing
+                && jsx_typescript == Some(true)
+                && follows_tag_name(src, &tokens)
+            {
+                pos = skip_type_arguments(bytes, pos);
+                continue;
+            }
+            let kind = if is_ident_start(c) {
+                pos += 1;
+                while pos < bytes.len() && (is_ident_part(bytes[pos]) || bytes[pos] == b'-') {
+                    pos += 1;
+                }
+                TokenKind::Ident
+            } else if c == b'"' || c == b'\'' {
+                // attribute strings have no escapes and may span lines
+                pos = src[pos + 1..]
+                    .find(c as char)
+                    .map_or(bytes.len(), |i| pos + i + 2);
+                TokenKind::Str
+            } else {
+                match c {
+                    b'{' => jsx.push(Jsx::Expression(braces.len())),
+                    // an element as an attribute value
+                    b'<' => jsx.push(Jsx::Tag { closing: false }),
+                    b'/' if !closing && bytes.get(pos + 1) == Some(&b'>') => {
+                        jsx.pop();
+                        pos += 1;
+                    }
+                    b'>' => {
+                        jsx.pop();
+                        if closing {
+                            jsx.pop();
+                        } else {
+                            jsx.push(Jsx::Children);
+                        }
+                    }
+                    _ => {}
+                }
+                pos += 1;
+                TokenKind::JsxPunct
+            };
+            tokens.push(Token {
+                kind,
+                start,
+                end: pos.min(bytes.len()),
+                nl_before,
+            });
+            nl_before = false;
+            continue;
+        }
+        if c == b'}'
+            && let Some(Jsx::Expression(depth)) = jsx.last()
+            && *depth == braces.len()
+        {
+            jsx.pop();
+            pos += 1;
+            tokens.push(Token {
+                kind: TokenKind::JsxPunct,
+                start,
+                end: pos,
+                nl_before,
+            });
+            nl_before = false;
+            continue;
+        }
+        if c == b'<'
+            && let Some(typescript) = jsx_typescript
+            && regex_allowed(src, tokens.last())
+            && starts_jsx(src, pos, typescript)
+        {
+            jsx.push(Jsx::Tag { closing: false });
+            pos += 1;
+            tokens.push(Token {
+                kind: TokenKind::JsxPunct,
+                start,
+                end: pos,
+                nl_before,
+            });
+            nl_before = false;
+            continue;
+        }
+
+        let kind = if c == b'"' || c == b'\'' {
+            pos = skip_string(bytes, pos);
+            TokenKind::Str
+        } else if c == b'`' {
+            pos = skip_template_chunk(bytes, pos + 1, &mut braces);
+            TokenKind::Template
+        } else if c == b'}' && braces.last() == Some(&true) {
+            braces.pop();
+            pos = skip_template_chunk(bytes, pos + 1, &mut braces);
+            TokenKind::Template
+        } else if c.is_ascii_digit()
+            || (c == b'.' && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit))
+        {
+            pos = skip_number(bytes, pos);
+            TokenKind::Number
+        } else if is_ident_start(c)
+            || (c == b'#' && bytes.get(pos + 1).is_some_and(|b| is_ident_start(*b)))
+        {
+            pos += 1;
+            while pos < bytes.len() && is_ident_part(bytes[pos]) {
+                pos += 1;
+            }
+            TokenKind::Ident
+        } else if c == b'/' && regex_allowed(src, tokens.last()) {
+            pos = skip_regex(bytes, pos);
+            TokenKind::Regex
+        } else {
+            let len = PUNCTUATORS
+                .iter()
+                .find(|p| src[pos..].starts_with(*p))
+                .map(|p| p.len())
+                .unwrap_or_else(|| {
+                    if SINGLE_PUNCTUATORS.contains(&c) || c.is_ascii() {
+                        1
+                    } else {
+                        src[pos..].chars().next().map_or(1, char::len_utf8)
+                    }
+                });
+            // `?.5` is a conditional followed by a number, not optional chaining
+            let len = if &src[pos..pos + len] == "?."
+                && bytes.get(pos + 2).is_some_and(u8::is_ascii_digit)
+            {
+                1
+            } else {
+                len
+            };
+            match &src[pos..pos + len] {
+                "{" => braces.push(false),
+                "}" => {
+                    braces.pop();
+                }
+                _ => {}
+            }
+            pos += len;
+            TokenKind::Punct
+        };
+
+        tokens.push(Token {
+            kind,
+            start,
+            end: pos.min(bytes.len()),
+            nl_before,
+        });
+        nl_before = false;
+    }
+
+    tokens
+}
+
+/// Whether the `<` at `pos`, where an expression starts, opens a JSX element
+/// or fragment.
+fn starts_jsx(src: &str, pos: usize, typescript: bool) -> bool {
+    let rest = src[pos + 1..].trim_start();
+    if rest.starts_with('>') {
+        return true;
+    }
+    if !rest.bytes().next().is_some_and(is_ident_start) {
+        return false;
+    }
+    if !typescript {
+        return true;
+    }
+    let name_end = rest
+        .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '$')
+        .unwrap_or(rest.len());
+    let after = rest[name_end..].trim_start();
+    !(after.starts_with(',') || after.starts_with("extends ") || after.starts_with("extends\n"))
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c == b'$' || c == b'\\' || c >= 0x80
+}
+
+fn is_ident_part(c: u8) -> bool {
+    is_ident_start(c) || c.is_ascii_digit()
+}
+
+/// Whether the tokens end with the name of the element type of a tag being
+/// opened, `<Name` or `<a.Name`.
+fn follows_tag_name(src: &str, tokens: &[Token]) -> bool {
+    let mut i = tokens.len();
+    while i >= 2 && tokens[i - 1].kind == TokenKind::Ident {
+        let before = &tokens[i - 2];
+        if before.kind != TokenKind::JsxPunct {
+            return false;
+        }
+        match before.text(src) {
+            "<" => return true,
+            "." => i -= 2,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// The end of the type argument list whose `<` is at `pos`.
+fn skip_type_arguments(bytes: &[u8], mut pos: usize) -> usize {
+    let mut depth = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'<' => depth += 1,
+            // the arrow of a function type doesn't close anything
+            b'>' if pos > 0 && bytes[pos - 1] == b'=' => {}
+            b'>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return pos + 1;
+                }
+            }
+            b'"' | b'\'' | b'`' => {
+                pos = skip_string(bytes, pos);
+                continue;
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+    pos
+}
+
+fn skip_string(bytes: &[u8], mut pos: usize) -> usize {
+    let quote = bytes[pos];
+    pos += 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'\n' => return pos,
+            c if c == quote => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Skips template text up to and including the closing backtick or the `${`
+/// that opens a substitution.
+fn skip_template_chunk(bytes: &[u8], mut pos: usize, braces: &mut Vec<bool>) -> usize {
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'`' => return pos + 1,
+            b'$' if bytes.get(pos + 1) == Some(&b'{') => {
+                braces.push(true);
+                return pos + 2;
+            }
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn skip_number(bytes: &[u8], mut pos: usize) -> usize {
+    let hex = bytes[pos] == b'0' && matches!(bytes.get(pos + 1), Some(b'x' | b'X'));
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let exponent_sign =
+            (c == b'+' || c == b'-') && !hex && matches!(bytes[pos - 1], b'e' | b'E');
+        if c.is_ascii_alphanumeric()
+            || c == b'_'
+            || (c == b'.' && bytes.get(pos + 1) != Some(&b'.'))
+            || exponent_sign
+        {
+            pos += 1;
+        } else {
+            break;
+        }
+    }
+    pos
+}
+
+fn skip_regex(bytes: &[u8], mut pos: usize) -> usize {
+    let mut in_class = false;
+    pos += 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'\n' => return pos,
+            b'[' => {
+                in_class = true;
+                pos += 1;
+            }
+            b']' => {
+                in_class = false;
+                pos += 1;
+            }
+            b'/' if !in_class => {
+                pos += 1;
+                while pos < bytes.len() && is_ident_part(bytes[pos]) {
+                    pos += 1;
+                }
+                return pos;
+            }
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+pub(crate) fn regex_allowed(src: &str, previous: Option<&Token>) -> bool {
+    let Some(previous) = previous else {
+        return true;
+    };
+    let text = previous.text(src);
+    match previous.kind {
+        TokenKind::Punct => !matches!(text, ")" | "]" | "}"),
+        TokenKind::Ident => REGEX_KEYWORDS.contains(&text),
+        TokenKind::Template => text.ends_with("${"),
+        TokenKind::JsxPunct => text == "{",
+        _ => false,
+    }
+}
+
+/// Returns the index of the token that closes the bracket opened at `open`.
+pub(crate) fn matching_close(src: &str, tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        match token.text(src) {
+            "{" | "(" | "[" => depth += 1,
+            "}" | ")" | "]" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Decodes the value of a string literal token, quotes included.
+pub(crate) fn string_value(literal: &str) -> String {
+    let inner = &literal[1..literal.len().saturating_sub(1).max(1)];
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    out.push(c);
+                }
+            }
+            Some('u') => {
+                let rest = chars.as_str();
+                let (hex, len) = match rest.strip_prefix('{') {
+                    Some(braced) => {
+                        let end = braced.find('}').unwrap_or(braced.len());
+                        (&braced[..end], end + 2)
+                    }
+                    None => {
+                        let hex = rest.get(..4).unwrap_or(rest);
+                        (hex, hex.len())
+                    }
+                };
+                if let Some(c) = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+                    out.push(c);
+                }
+                chars = rest[len.min(rest.len())..].chars();
+            }
+            Some('\n') | None => {}
+            Some(other) => out.push(other),
+        }
+    }
+    out
+}
@@ -0,0 +1,427 @@
+// Lunos module registry.
+//
+// `process_es6_modules` turns every file in the module graph into a script,
+// named by its path, of one `__lunos_modules.define(...)` call. Nothing runs
+// until the entry module is started with `run`, after which modules are
+// evaluated on first import or require, the same way Node does it.
+(function (global) {
+    "use strict";
+
+    const records = new Map();
+    const cache = Object.create(null);
+    const nativeLoad = global.__lunos_native_load;
+    // kept from startup so `lunos:` modules work even if the global is replaced
+    const natives = global.Lunos;
+    let mainModule;
+    let mainId;
+    let entry = null;
+
+    class ResolveError extends Error {}
+    Object.defineProperty(ResolveError.prototype, "name", {
+        value: "ResolveError",
+        writable: true,
+        configurable: true,
+    });
+
+    // Builds the error thrown for a module that can't be resolved or linked.
+    // `details` comes from the native side, see `ResolveError` in resolver.rs.
+    function createError(details) {
+        const Constructor = { SyntaxError, TypeError }[details.name] || ResolveError;
+        const error = new Constructor(details.message);
+        error.code = details.code;
+        error.specifier = details.specifier;
+        error.referrer = details.referrer;
+        if (details.line !== undefined) {
+            error.line = details.line;
+            error.column = details.column;
+        }
+        if (details.suggestion !== undefined) {
+            error.suggestion = details.suggestion;
+        }
+        return error;
+    }
+
+    function define(id, kind, meta, factory) {
+        if (records.has(id)) {
+            return;
+        }
+        records.set(id, {
+            id,
+            kind,
+            meta,
+            factory,
+            status: "linked",
+            namespace: null,
+            interop: null,
+            importMeta: null,
+            promise: null,
+            error: undefined,
+        });
+    }
+
+    function record(id) {
+        const found = records.get(id);
+        if (!found) {
+            throw new Error(`Cannot find module '${id}'`);
+        }
+        return found;
+    }
+
+    // `type` is an import attribute. Static imports were resolved with
+    // theirs already, so only `import()` with options passes one here.
+    function resolve(specifier, referrer, mode, type) {
+        const deps = referrer ? record(referrer).meta.deps : undefined;
+        if (type === undefined && deps && Object.prototype.hasOwnProperty.call(deps, specifier)) {
+            return deps[specifier];
+        }
+        return nativeLoad(specifier, referrer || "", mode, type);
+    }
+
+    // The factory of a `type: "bytes"` module passes the file with one char
+    // per byte.
+    function bytes(data) {
+        const array = new Uint8Array(data.length);
+        for (let i = 0; i < data.length; i++) {
+            array[i] = data.charCodeAt(i);
+        }
+        return array;
+    }
+
+    // The export getters of a `lunos:` module: the named functions of the
+    // `Lunos` global, and all of them together as `default`. The functions
+    // of a module registered from Rust are in `__lunos_native_modules`.
+    function builtin(names, module) {
+        const source = module === undefined ? natives : global.__lunos_native_modules[module];
+        const getters = Object.create(null);
+        const all = Object.create(null);
+        for (const name of names) {
+            getters[name] = () => source[name];
+            all[name] = source[name];
+        }
+        Object.freeze(all);
+        getters.default = () => all;
+        return getters;
+    }
+
+    // Imported names are read from the namespace they come from, so
+    // assigning one lands here. It throws whether or not the code is strict.
+    function readOnly(name) {
+        return () => {
+            throw new TypeError(`Cannot assign to '${name}', imported bindings are read-only`);
+        };
+    }
+
+    function exportBindings(namespace, getters) {
+        for (const name of Object.keys(getters)) {
+            if (Object.prototype.hasOwnProperty.call(namespace, name)) {
+                continue;
+            }
+            Object.defineProperty(namespace, name, {
+                get: getters[name],
+                set: readOnly(name),
+                enumerable: true,
+            });
+        }
+    }
+
+    function exportStar(namespace, source) {
+        for (const name of Object.keys(source)) {
+            if (name === "default" || Object.prototype.hasOwnProperty.call(namespace, name)) {
+                continue;
+            }
+            Object.defineProperty(namespace, name, {
+                get: () => source[name],
+                set: readOnly(name),
+                enumerable: true,
+            });
+        }
+    }
+
+    function createNamespace() {
+        const namespace = Object.create(null);
+        Object.defineProperty(namespace, Symbol.toStringTag, { value: "Module" });
+        return namespace;
+    }
+
+    function evaluateModule(target) {
+        if (target.status === "evaluated" || target.status === "evaluating") {
+            return target.namespace;
+        }
+        if (target.status === "errored") {
+            throw target.error;
+        }
+
+        target.status = "evaluating";
+        target.namespace = createNamespace();
+        let result;
+        try {
+            result = target.factory.call(undefined, target.namespace);
+        } catch (error) {
+            target.status = "errored";
+            target.error = error;
+            throw error;
+        }
+
+        // a module with top-level await stays "evaluating" until its body
+        // finishes, `importAsync` waits for that
+        if (target.meta.async) {
+            target.promise = result.then(
+                () => {
+                    target.status = "evaluated";
+                },
+                (error) => {
+                    target.status = "errored";
+                    target.error = error;
+                    throw error;
+                },
+            );
+        } else {
+            target.status = "evaluated";
+        }
+        return target.namespace;
+    }
+
+    // Imports a module and waits for its evaluation if it's asynchronous.
+    // While a module is still running its synchronous part (an import cycle)
+    // there's nothing to wait for, so its namespace is returned as is.
+    function importAsync(id) {
+        const namespace = importModule(id);
+        const target = record(id);
+        if (!target.promise) {
+            return Promise.resolve(namespace);
+        }
+        return target.promise.then(() => namespace);
+    }
+
+    // Waits for the asynchronous dependencies of a module, which all started
+    // evaluating before, see `esm_preamble` in es6.rs.
+    function all(imports) {
+        return Promise.all(imports);
+    }
+
+    function createModule(target) {
+        return {
+            id: target.id,
+            filename: target.meta.filename,
+            path: target.meta.dirname,
+            exports: {},
+            loaded: false,
+            children: [],
+            parent: null,
+        };
+    }
+
+    function requireRecord(target, parent, isMain) {
+        if (target.kind === "module") {
+            if (target.meta.async) {
+                const error = new Error(
+                    `require() cannot load '${target.meta.filename}' because it uses top-level await, use import() instead`,
+                );
+                error.code = "ERR_REQUIRE_ASYNC_MODULE";
+                throw error;
+            }
+            const namespace = evaluateModule(target);
+            // Node's built-ins are CommonJS there, `require` gets their exports object
+            return target.id.startsWith("node:") ? namespace.default : namespace;
+        }
+
+        const cached = cache[target.id];
+        if (cached) {
+            return cached.exports;
+        }
+
+        const module = createModule(target);
+        if (parent) {
+            module.parent = parent;
+            parent.children.push(module);
+        }
+        if (isMain) {
+            mainModule = module;
+        }
+
+        cache[target.id] = module;
+        try {
+            target.factory.call(
+                module.exports,
+                module.exports,
+                createRequire(target, module),
+                module,
+                target.meta.filename,
+                target.meta.dirname,
+            );
+        } catch (error) {
+            delete cache[target.id];
+            throw error;
+        }
+        module.loaded = true;
+        return module.exports;
+    }
+
+    // What an ES module sees when it imports a CommonJS or JSON module:
+    // `default` is `module.exports`, and every statically detected name (plus
+    // any own key present at import time) is exposed as a live named export.
+    // Text and bytes modules only have `default`.
+    function interopNamespace(target) {
+        const exports = requireRecord(target, null, false);
+        if (target.interop && target.interop.exports === exports) {
+            return target.interop.namespace;
+        }
+
+        const namespace = createNamespace();
+        const current = () => (cache[target.id] ? cache[target.id].exports : exports);
+        Object.defineProperty(namespace, "default", {
+            get: current,
+            set: readOnly("default"),
+            enumerable: true,
+        });
+
+        const names = new Set(target.meta.names || []);
+        const hasNames = target.kind === "commonjs" || target.kind === "json";
+        if (hasNames && exports !== null && (typeof exports === "object" || typeof exports === "function")) {
+            for (const name of Object.keys(exports)) {
+                names.add(name);
+            }
+        }
+        names.delete("default");
+        for (const name of names) {
+            Object.defineProperty(namespace, name, {
+                get: () => current()[name],
+                set: readOnly(name),
+                enumerable: true,
+            });
+        }
+
+        target.interop = { exports, namespace };
+        return namespace;
+    }
+
+    function importModule(id) {
+        const target = record(id);
+        return target.kind === "module" ? evaluateModule(target) : interopNamespace(target);
+    }
+
+    function createRequire(target, module) {
+        function require(specifier) {
+            if (typeof specifier !== "string") {
+                throw new TypeError("The \"id\" argument must be of type string");
+            }
+            return requireRecord(record(resolve(specifier, target.id, "require")), module, false);
+        }
+
+        require.resolve = (specifier) => resolve(specifier, target.id, "require");
+        require.cache = cache;
+        Object.defineProperty(require, "main", {
+            get: () => mainModule,
+            enumerable: true,
+        });
+        return require;
+    }
+
+    function importType(options) {
+        if (options === undefined) {
+            return undefined;
+        }
+        if (options === null || typeof options !== "object") {
+            throw new TypeError("The second argument of import() must be an object");
+        }
+        const attributes = options.with !== undefined ? options.with : options.assert;
+        if (attributes === undefined || attributes.type === undefined) {
+            return undefined;
+        }
+        return String(attributes.type);
+    }
+
+    function dynamicImport(referrer, specifier, options) {
+        return new Promise((resolvePromise) => {
+            const type = importType(options);
+            resolvePromise(importAsync(resolve(String(specifier), referrer, "import", type)));
+        });
+    }
+
+    function pathToFileURL(path) {
+        const normalized = path.replace(/\\/g, "/");
+        const absolute = normalized.startsWith("/") ? normalized : "/" + normalized;
+        return "file://" + encodeURI(absolute).replace(/[?#]/g, encodeURIComponent);
+    }
+
+    // `import.meta` of an ES module, created on first use.
+    function importMeta(id) {
+        const target = record(id);
+        if (target.importMeta) {
+            return target.importMeta;
+        }
+
+        const meta = Object.create(null);
+        // modules imported by URL have no file, like in the browser
+        const remote = /^https?:\/\//.test(target.meta.filename);
+        meta.url = remote ? target.meta.filename : pathToFileURL(target.meta.filename);
+        meta.filename = remote ? undefined : target.meta.filename;
+        meta.dirname = remote ? undefined : target.meta.dirname;
+        meta.main = id === mainId;
+        meta.resolve = (specifier) => {
+            const resolved = nativeLoad(String(specifier), id, "resolve");
+            return /^(lunos:|node:|https?:\/\/)/.test(resolved) ? resolved : pathToFileURL(resolved);
+        };
+        target.importMeta = meta;
+        return meta;
+    }
+
+    function run(id) {
+        mainId = id;
+        const target = record(id);
+        if (target.kind !== "module") {
+            requireRecord(target, null, true);
+            entry = { settled: true, failed: false, error: undefined };
+            return;
+        }
+
+        evaluateModule(target);
+        entry = { settled: !target.promise, failed: false, error: undefined };
+        if (target.promise) {
+            target.promise.then(
+                () => {
+                    entry.settled = true;
+                },
+                (error) => {
+                    entry.settled = true;
+                    entry.failed = true;
+                    entry.error = error;
+                },
+            );
+        }
+    }
+
+    // Polled by the event loop: whether the entry module has finished
+    // evaluating, throwing what it rejected with if it failed.
+    function entryState() {
+        if (entry && entry.failed) {
+            throw entry.error;
+        }
+        return !entry || entry.settled;
+    }
+
+    Object.defineProperty(global, "__lunos_modules", {
+        value: Object.freeze({
+            define,
+            import: importModule,
+            importAsync,
+            all,
+            export: exportBindings,
+            exportStar,
+            dynamicImport,
+            run,
+            entryState,
+            createError,
+            bytes,
+            builtin,
+            meta: importMeta,
+        }),
+        enumerable: false,
+    });
+    Object.defineProperty(global, "ResolveError", {
+        value: ResolveError,
+        writable: true,
+        enumerable: false,
+        configurable: true,
+    });
+})(globalThis);
@@ -1,3 +1,25 @@
-pub mod console;
+pub(crate) mod bindings;
+pub(crate) mod builtins;
+pub(crate) mod commonjs;
+pub(crate) mod console;
+pub mod convert;
+pub mod errors;
 pub(crate) mod es6;
-pub mod lunos;
+pub(crate) mod exception;
+pub(crate) mod import_map;
+pub(crate) mod jsx;
+pub(crate) mod lexer;
+pub(crate) mod lunos;
+pub mod native;
+pub(crate) mod node;
+pub(crate) mod package_json;
+pub(crate) mod registry;
+pub(crate) mod rejections;
+pub(crate) mod remote;
+pub(crate) mod resolver;
+pub(crate) mod source_map;
+pub(crate) mod state;
+pub(crate) mod timers;
+pub(crate) mod transform_cache;
+pub(crate) mod tsconfig;
+pub(crate) mod typescript;
@@ -0,0 +1,219 @@
+//! Native functions, written as Rust functions of typed arguments.
+//!
+//! `native_function!` turns a function of `FromJs` arguments returning a
+//! `ToJs` result into a JavaScriptCore callback. Its arguments are checked
+//! before the body runs, a missing one throws `ERR_MISSING_ARGS` and one of
+//! the wrong type `ERR_INVALID_ARG_TYPE`, both as a `TypeError`:
+//!
+//! ```ignore
+//! native_function! {
+//!     fn load_file_callback(args, path: String) -> Result<String, NativeError> {
+//!         fs::read_to_string(&path).map_err(|e| NativeError::io(&e, "open", Some(&path)))
+//!     }
+//! }
+//! ```
+//!
+//! Arguments are taken in order. Those of an `Option` type may be left out,
+//! and `args` has the rest of them, see `Args`. A function that runs scripts
+//! fails with `Failure`, to pass on what they threw as it is.
+
+use super::convert::{FromJs, JsObject, JsString, JsValue, ToJs};
+use super::errors::NativeError;
+use rusty_jsc::*;
+use std::cell::Cell;
+
+/// A function JavaScriptCore calls, as `native_function!` makes them.
+pub type NativeCallback = unsafe extern "C" fn(
+    *const OpaqueJSContext,
+    *mut OpaqueJSValue,
+    *mut OpaqueJSValue,
+    usize,
+    *const *const OpaqueJSValue,
+    *mut *const OpaqueJSValue,
+) -> *const OpaqueJSValue;
+
+/// The arguments a native function was called with.
+pub struct Args<'a> {
+    context: *const OpaqueJSContext,
+    values: &'a [*const OpaqueJSValue],
+    /// The argument `next` takes.
+    next: Cell<usize>,
+}
+
+impl<'a> Args<'a> {
+    pub unsafe fn new(
+        context: *const OpaqueJSContext,
+        argument_count: usize,
+        arguments: *const *const OpaqueJSValue,
+    ) -> Args<'a> {
+        let values = if argument_count == 0 || arguments.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(arguments, argument_count) }
+        };
+        Args {
+            context,
+            values,
+            next: Cell::new(0),
+        }
+    }
+
+    pub fn context(&self) -> *const OpaqueJSContext {
+        self.context
+    }
+
+    /// All the arguments, the ones taken already included.
+    pub fn values(&self) -> impl Iterator<Item = JsValue> + '_ {
+        self.values.iter().map(|&value| JsValue(value))
+    }
+
+    /// Takes the next argument as a `T`, the argument `name` in errors.
+    pub fn next<T: FromJs>(&self, name: &str) -> Result<T, NativeError> {
+        let index = self.next.get();
+        self.next.set(index + 1);
+        unsafe {
+            let Some(&value) = self.values.get(index) else {
+                // what may be left out converts from `undefined`
+                let missing = format!("The \"{name}\" argument must be specified");
+                return T::from_js(self.context, JSValueMakeUndefined(self.context))
+                    .map_err(|_| NativeError::missing_args(missing));
+            };
+            T::from_js(self.context, value)
+                .map_err(|mismatch| mismatch.error::<T>(self.context, value, name, "argument"))
+        }
+    }
+}
+
+/// What a native function can fail with.
+pub trait Throw {
+    /// Sets the exception of the native function, which returns what this
+    /// does.
+    unsafe fn throw(
+        &self,
+        context: *const OpaqueJSContext,
+        exception: *mut *const OpaqueJSValue,
+    ) -> *const OpaqueJSValue;
+}
+
+/// A failure of a native function that runs scripts: its own error, or a
+/// value one of them threw.
+pub enum Failure {
+    Error(NativeError),
+    Thrown(JsValue),
+}
+
+impl From<NativeError> for Failure {
+    fn from(error: NativeError) -> Self {
+        Failure::Error(error)
+    }
+}
+
+impl Throw for NativeError {
+    unsafe fn throw(
+        &self,
+        context: *const OpaqueJSContext,
+        exception: *mut *const OpaqueJSValue,
+    ) -> *const OpaqueJSValue {
+        unsafe { NativeError::throw(self, context, exception) }
+    }
+}
+
+impl Throw for Failure {
+    unsafe fn throw(
+        &self,
+        context: *const OpaqueJSContext,
+        exception: *mut *const OpaqueJSValue,
+    ) -> *const OpaqueJSValue {
+        match self {
+            Failure::Error(error) => unsafe { error.throw(context, exception) },
+            Failure::Thrown(value) => {
+                if !exception.is_null() {
+                    unsafe { *exception = value.0 };
+                }
+                std::ptr::null()
+            }
+        }
+    }
+}
+
+/// Returns what a native function gave back, or throws its error.
+pub unsafe fn complete<T: ToJs, E: Throw>(
+    context: *const OpaqueJSContext,
+    exception: *mut *const OpaqueJSValue,
+    result: Result<T, E>,
+) -> *const OpaqueJSValue {
+    unsafe {
+        match result {
+            Ok(value) => value.to_js(context),
+            Err(error) => error.throw(context, exception),
+        }
+    }
+}
+
+/// Sets `functions` on `object` under their names.
+pub unsafe fn define_functions(
+    context: *const OpaqueJSContext,
+    object: JsObject,
+    functions: &[(&str, NativeCallback)],
+) {
+    for &(name, callback) in functions {
+        let js_name = JsString::new(name);
+        unsafe {
+            let function =
+                JSObjectMakeFunctionWithCallback(context, js_name.as_ptr(), Some(callback));
+            object.set(context, name, JsObject(function));
+        }
+    }
+}
+
+/// Sets `callback` as the global `name`, which like the other internals of
+/// Lunos isn't enumerable.
+pub(crate) unsafe fn define_internal(
+    context: *const OpaqueJSContext,
+    name: &str,
+    callback: NativeCallback,
+) {
+    let js_name = JsString::new(name);
+    unsafe {
+        let function = JSObjectMakeFunctionWithCallback(context, js_name.as_ptr(), Some(callback));
+        JSObjectSetProperty(
+            context,
+            JSContextGetGlobalObject(context),
+            js_name.as_ptr(),
+            function,
+            kJSPropertyAttributeDontEnum,
+            std::ptr::null_mut(),
+        );
+    }
+}
+
+/// Defines a native function, see the module documentation.
+#[macro_export]
+macro_rules! native_function {
+    (
+        $(#[$meta:meta])*
+        $vis:vis fn $name:ident($args:ident $(, $param:ident: $ty:ty)* $(,)?) -> $ret:ty $body:block
+    ) => {
+        $(#[$meta])*
+        $vis unsafe extern "C" fn $name(
+            context: *const $crate::rusty_jsc::OpaqueJSContext,
+            _: *mut $crate::rusty_jsc::OpaqueJSValue,
+            _: *mut $crate::rusty_jsc::OpaqueJSValue,
+            argument_count: usize,
+            arguments: *const *const $crate::rusty_jsc::OpaqueJSValue,
+            exception: *mut *const $crate::rusty_jsc::OpaqueJSValue,
+        ) -> *const $crate::rusty_jsc::OpaqueJSValue {
+            let $args = unsafe {
+                $crate::modules::native::Args::new(context, argument_count, arguments)
+            };
+            #[allow(clippy::redundant_closure_call)]
+            let result = (|| -> $ret {
+                $(let $param: $ty = $args.next(stringify!($param))?;)*
+                $body
+            })();
+            unsafe { $crate::modules::native::complete(context, exception, result) }
+        }
+    };
+}
+
+pub use crate::native_function;
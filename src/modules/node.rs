@@ -0,0 +1,874 @@
+use super::errors::{ErrorClass, NativeError};
+use super::es6::evaluate;
+use super::native::{define_internal, native_function};
+use super::state::State;
+use rusty_jsc::*;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Wraps `__lunos_node_call` into the `__lunos_node` object the `node:`
+/// modules use, see node/native.js.
+const NATIVE_JS: &str = include_str!("node/native.js");
+
+/// Longest an HTTP request may take to arrive once its connection is accepted.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a kept-alive connection may wait for its next request, Node's
+/// `keepAliveTimeout`.
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a server's thread looks for new connections, and whether it's
+/// been closed.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The servers a runtime listens with. They close when it's dropped.
+pub(crate) struct HttpState {
+    next_id: u32,
+    servers: HashMap<u32, Server>,
+    /// Requests waiting for their response.
+    connections: HashMap<u32, Connection>,
+}
+
+/// A complete request, and where its response goes.
+type Incoming = (Map<String, Value>, Connection);
+
+/// A listening server. A thread accepts its connections and each gets a
+/// thread reading its requests, so a slow client only holds up its own; the
+/// event loop takes the requests from `requests` once they're complete.
+struct Server {
+    requests: Arc<Mutex<Receiver<Incoming>>>,
+    closed: Arc<AtomicBool>,
+}
+
+/// Where the response to a request goes. Its connection's thread reads the
+/// next request once it's told the connection stays open, and closes it
+/// once this is dropped without a response.
+struct Connection {
+    server: u32,
+    stream: TcpStream,
+    keep_alive: bool,
+    responded: Sender<bool>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl HttpState {
+    pub fn new() -> HttpState {
+        HttpState {
+            next_id: 1,
+            servers: HashMap::new(),
+            connections: HashMap::new(),
+        }
+    }
+}
+
+type NodeResult = Result<Value, NativeError>;
+
+fn string_arg<'a>(args: &'a [Value], index: usize, name: &str) -> Result<&'a str, NativeError> {
+    args.get(index).and_then(Value::as_str).ok_or_else(|| {
+        NativeError::invalid_arg(format!("The \"{name}\" argument must be of type string"))
+    })
+}
+
+fn bool_arg(args: &[Value], index: usize) -> bool {
+    args.get(index).and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// Data crosses over as strings with one char per byte, like `bytes` imports.
+fn to_binary(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn from_binary(data: &str) -> Vec<u8> {
+    data.chars().map(|c| c as u32 as u8).collect()
+}
+
+fn millis(time: io::Result<SystemTime>) -> f64 {
+    time.ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0.0, |duration| duration.as_secs_f64() * 1000.0)
+}
+
+fn file_type(file_type: fs::FileType) -> &'static str {
+    if file_type.is_symlink() {
+        "symlink"
+    } else if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_file() {
+        "file"
+    } else {
+        "other"
+    }
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    let kind = if metadata.is_dir() {
+        0o040000
+    } else {
+        0o100000
+    };
+    kind | if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
+
+fn stat(args: &[Value]) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    let follow = bool_arg(args, 1);
+    let metadata = if follow {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
+    };
+    let syscall = if follow { "stat" } else { "lstat" };
+    let metadata = metadata.map_err(|e| NativeError::io(&e, syscall, Some(path)))?;
+    Ok(json!({
+        "type": file_type(metadata.file_type()),
+        "size": metadata.len(),
+        "mode": mode(&metadata),
+        "atimeMs": millis(metadata.accessed()),
+        "mtimeMs": millis(metadata.modified()),
+        "ctimeMs": millis(metadata.modified()),
+        "birthtimeMs": millis(metadata.created()),
+    }))
+}
+
+fn read_file(args: &[Value]) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    let bytes = fs::read(path).map_err(|e| NativeError::io(&e, "open", Some(path)))?;
+    Ok(Value::String(if bool_arg(args, 1) {
+        to_binary(&bytes)
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    }))
+}
+
+fn write_file(args: &[Value]) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    let data = string_arg(args, 1, "data")?;
+    let bytes = if bool_arg(args, 2) {
+        from_binary(data)
+    } else {
+        data.as_bytes().to_vec()
+    };
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(bool_arg(args, 3))
+        .truncate(!bool_arg(args, 3))
+        .open(path)
+        .map_err(|e| NativeError::io(&e, "open", Some(path)))?;
+    file.write_all(&bytes)
+        .map_err(|e| NativeError::io(&e, "write", Some(path)))?;
+    Ok(Value::Null)
+}
+
+fn read_dir(args: &[Value]) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path).map_err(|e| NativeError::io(&e, "scandir", Some(path)))? {
+        let entry = entry.map_err(|e| NativeError::io(&e, "scandir", Some(path)))?;
+        let kind = entry.file_type().map_or("other", file_type);
+        entries.push(json!([entry.file_name().to_string_lossy(), kind]));
+    }
+    entries.sort_by(|a, b| a[0].as_str().cmp(&b[0].as_str()));
+    Ok(Value::Array(entries))
+}
+
+fn make_dir(args: &[Value]) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    if bool_arg(args, 1) {
+        // like Node, a recursive mkdir returns the first directory it created
+        let first = Path::new(path)
+            .ancestors()
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .last()
+            .map(|dir| dir.to_string_lossy().to_string());
+        fs::create_dir_all(path).map_err(|e| NativeError::io(&e, "mkdir", Some(path)))?;
+        return Ok(first.map_or(Value::Null, Value::String));
+    }
+    fs::create_dir(path).map_err(|e| NativeError::io(&e, "mkdir", Some(path)))?;
+    Ok(Value::Null)
+}
+
+fn remove(args: &[Value]) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    let recursive = bool_arg(args, 1);
+    let force = bool_arg(args, 2);
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if force && e.kind() == ErrorKind::NotFound => return Ok(Value::Null),
+        Err(e) => return Err(NativeError::io(&e, "rm", Some(path))),
+    };
+    let result = if !metadata.is_dir() {
+        fs::remove_file(path)
+    } else if recursive {
+        fs::remove_dir_all(path)
+    } else {
+        return Err(NativeError {
+            syscall: Some("rm"),
+            path: Some(path.to_string()),
+            ..NativeError::new(
+                ErrorClass::Error,
+                "ERR_FS_EISDIR",
+                format!("Path is a directory: rm returned EISDIR (is a directory) {path}"),
+            )
+        });
+    };
+    result.map_err(|e| NativeError::io(&e, "rm", Some(path)))?;
+    Ok(Value::Null)
+}
+
+fn path_call(
+    args: &[Value],
+    syscall: &'static str,
+    call: impl FnOnce(&str) -> io::Result<()>,
+) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    call(path).map_err(|e| NativeError::io(&e, syscall, Some(path)))?;
+    Ok(Value::Null)
+}
+
+fn two_path_call(
+    args: &[Value],
+    syscall: &'static str,
+    call: impl FnOnce(&str, &str) -> io::Result<()>,
+) -> NodeResult {
+    let from = string_arg(args, 0, "src")?;
+    let to = string_arg(args, 1, "dest")?;
+    call(from, to).map_err(|e| NativeError::io(&e, syscall, Some(from)))?;
+    Ok(Value::Null)
+}
+
+fn real_path(args: &[Value]) -> NodeResult {
+    let path = string_arg(args, 0, "path")?;
+    let real = fs::canonicalize(path).map_err(|e| NativeError::io(&e, "realpath", Some(path)))?;
+    Ok(Value::String(real.to_string_lossy().to_string()))
+}
+
+/// `process.argv`: the runtime, the script, then the script's arguments.
+fn process_argv(argv: &[String]) -> Value {
+    let exe = std::env::current_exe()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "lunos".to_string());
+    let script = argv.first().map(|script| {
+        fs::canonicalize(script).map_or(script.clone(), |path| path.to_string_lossy().to_string())
+    });
+    let argv = std::iter::once(exe)
+        .chain(script)
+        .chain(argv.iter().skip(1).cloned())
+        .collect::<Vec<_>>();
+    json!(argv)
+}
+
+/// Writes straight to stdout (fd 1) or stderr (fd 2), bypassing the console
+/// buffer which the caller flushes first.
+fn write_fd(args: &[Value]) -> NodeResult {
+    let fd = args.first().and_then(Value::as_u64).unwrap_or(1);
+    let data = string_arg(args, 1, "data")?;
+    let written = if fd == 2 {
+        io::stderr().write_all(data.as_bytes())
+    } else {
+        let mut stdout = io::stdout();
+        stdout
+            .write_all(data.as_bytes())
+            .and_then(|_| stdout.flush())
+    };
+    written.map_err(|e| NativeError::io(&e, "write", None))?;
+    Ok(Value::Null)
+}
+
+fn read_first_line(path: &str) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let line = contents.lines().next()?.trim().to_string();
+    (!line.is_empty()).then_some(line)
+}
+
+fn os_info() -> Value {
+    let platform = match std::env::consts::OS {
+        "macos" => "darwin",
+        "windows" => "win32",
+        other => other,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "x86" => "ia32",
+        "aarch64" => "arm64",
+        other => other,
+    };
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_default();
+    let hostname = read_first_line("/proc/sys/kernel/hostname")
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_else(|| "localhost".to_string());
+    let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    let memory = |field: &str| -> u64 {
+        meminfo
+            .lines()
+            .find(|line| line.starts_with(field))
+            .and_then(|line| line.split_whitespace().nth(1)?.parse::<u64>().ok())
+            .map_or(0, |kb| kb * 1024)
+    };
+    let uptime = read_first_line("/proc/uptime")
+        .and_then(|line| line.split_whitespace().next()?.parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    json!({
+        "platform": platform,
+        "arch": arch,
+        "release": read_first_line("/proc/sys/kernel/osrelease").unwrap_or_default(),
+        "hostname": hostname,
+        "homedir": home,
+        "tmpdir": std::env::temp_dir().to_string_lossy(),
+        "cpus": num_cpus::get(),
+        "totalmem": memory("MemTotal:"),
+        "freemem": memory("MemAvailable:"),
+        "uptime": uptime,
+        "pid": std::process::id(),
+        "version": crate::lunos::constants::VERSION,
+    })
+}
+
+/// Runs a process to completion: `(file, args, { cwd, env, input, shell })`.
+/// With `shell`, `file` is a command line run by `sh -c` (`cmd /C` on
+/// Windows).
+fn spawn(args: &[Value]) -> NodeResult {
+    let file = string_arg(args, 0, "file")?;
+    let arguments: Vec<String> = args
+        .get(1)
+        .and_then(Value::as_array)
+        .map(|list| {
+            list.iter()
+                .map(|arg| arg.as_str().map_or_else(|| arg.to_string(), str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    let options = args
+        .get(2)
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut command = if options
+        .get("shell")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        let line = std::iter::once(file.to_string())
+            .chain(arguments)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut command = if cfg!(windows) {
+            Command::new("cmd")
+        } else {
+            Command::new("sh")
+        };
+        command
+            .arg(if cfg!(windows) { "/C" } else { "-c" })
+            .arg(line);
+        command
+    } else {
+        let mut command = Command::new(file);
+        command.args(arguments);
+        command
+    };
+    if let Some(cwd) = options.get("cwd").and_then(Value::as_str) {
+        command.current_dir(cwd);
+    }
+    if let Some(env) = options.get("env").and_then(Value::as_object) {
+        command.env_clear();
+        for (key, value) in env {
+            if let Some(value) = value.as_str() {
+                command.env(key, value);
+            }
+        }
+    }
+    let input = options
+        .get("input")
+        .and_then(Value::as_str)
+        .map(from_binary);
+    command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::inherit()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| NativeError::io(&e, "spawnSync", Some(file)))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        let _ = stdin.write_all(&input);
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| NativeError::io(&e, "spawnSync", Some(file)))?;
+
+    #[cfg(unix)]
+    let signal = {
+        use std::os::unix::process::ExitStatusExt;
+        output.status.signal()
+    };
+    #[cfg(not(unix))]
+    let signal: Option<i32> = None;
+
+    Ok(json!({
+        "status": output.status.code(),
+        "signal": signal,
+        "stdout": to_binary(&output.stdout),
+        "stderr": to_binary(&output.stderr),
+    }))
+}
+
+fn http_listen(http: &Mutex<HttpState>, args: &[Value]) -> NodeResult {
+    let port = args.first().and_then(Value::as_u64).unwrap_or(0);
+    let host = args.get(1).and_then(Value::as_str).unwrap_or("0.0.0.0");
+    let address = format!("{host}:{port}");
+    let listener =
+        TcpListener::bind(&address).map_err(|e| NativeError::io(&e, "listen", Some(&address)))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| NativeError::io(&e, "listen", Some(&address)))?;
+    let port = listener
+        .local_addr()
+        .map_or(port as u16, |addr| addr.port());
+
+    let mut http = http.lock().unwrap();
+    let id = http.next_id;
+    http.next_id += 1;
+
+    let (sender, requests) = mpsc::channel();
+    let closed = Arc::new(AtomicBool::new(false));
+    let accepting = closed.clone();
+    std::thread::spawn(move || accept_connections(id, listener, sender, accepting));
+    let requests = Arc::new(Mutex::new(requests));
+    http.servers.insert(id, Server { requests, closed });
+    Ok(json!({ "id": id, "port": port }))
+}
+
+/// Accepts connections until the server is closed, and gives each a thread
+/// of its own.
+fn accept_connections(
+    server: u32,
+    listener: TcpListener,
+    requests: Sender<Incoming>,
+    closed: Arc<AtomicBool>,
+) {
+    while !closed.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let requests = requests.clone();
+                std::thread::spawn(move || serve_connection(server, stream, requests));
+            }
+            Err(_) => std::thread::sleep(ACCEPT_INTERVAL),
+        }
+    }
+}
+
+/// Reads the requests of a connection one after the other, waiting for the
+/// response to each before the next, until either side closes it.
+fn serve_connection(server: u32, stream: TcpStream, requests: Sender<Incoming>) {
+    let Ok(mut reader) = stream.try_clone() else {
+        return;
+    };
+    if reader.set_nonblocking(false).is_err() {
+        return;
+    }
+    let mut buffered = Vec::new();
+    let mut timeout = REQUEST_TIMEOUT;
+    loop {
+        let (request, keep_alive) = match read_request(&mut reader, &mut buffered, timeout) {
+            Ok(Some(request)) => request,
+            Ok(None) => return,
+            Err(e) => {
+                let status = match e.kind() {
+                    ErrorKind::Unsupported => "501 Not Implemented",
+                    ErrorKind::InvalidData => "400 Bad Request",
+                    _ => return,
+                };
+                let response =
+                    format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                let _ = reader.write_all(response.as_bytes());
+                let _ = reader.shutdown(Shutdown::Both);
+                return;
+            }
+        };
+        let Ok(stream) = stream.try_clone() else {
+            return;
+        };
+        let (responded, response) = mpsc::channel();
+        let connection = Connection {
+            server,
+            stream,
+            keep_alive,
+            responded,
+        };
+        // the server closed, the request was dropped without a response, or
+        // the response said the connection is done
+        if requests.send((request, connection)).is_err() || !response.recv().unwrap_or(false) {
+            return;
+        }
+        timeout = KEEP_ALIVE_TIMEOUT;
+    }
+}
+
+/// Reads from `stream` onto `data` until it has `needle` at or after `from`,
+/// returning where it starts. The connection ending first is an error.
+fn read_until(
+    stream: &mut TcpStream,
+    data: &mut Vec<u8>,
+    from: usize,
+    needle: &[u8],
+) -> io::Result<usize> {
+    let mut chunk = [0u8; 8192];
+    loop {
+        let searched = from.min(data.len());
+        if let Some(at) = data[searched..]
+            .windows(needle.len())
+            .position(|w| w == needle)
+        {
+            return Ok(searched + at);
+        }
+        match stream.read(&mut chunk)? {
+            0 => return Err(ErrorKind::UnexpectedEof.into()),
+            read => data.extend_from_slice(&chunk[..read]),
+        }
+    }
+}
+
+/// Reads from `stream` onto `data` until it has `len` bytes.
+fn read_to_len(stream: &mut TcpStream, data: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    let mut chunk = [0u8; 8192];
+    while data.len() < len {
+        match stream.read(&mut chunk)? {
+            0 => return Err(ErrorKind::UnexpectedEof.into()),
+            read => data.extend_from_slice(&chunk[..read]),
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a `Transfer-Encoding: chunked` body that starts at `start` in
+/// `data`, returning it and the index right after it.
+fn read_chunked(
+    stream: &mut TcpStream,
+    data: &mut Vec<u8>,
+    start: usize,
+) -> io::Result<(Vec<u8>, usize)> {
+    let invalid = || io::Error::from(ErrorKind::InvalidData);
+    let mut body = Vec::new();
+    let mut at = start;
+    loop {
+        let line_end = read_until(stream, data, at, b"\r\n")?;
+        let line = std::str::from_utf8(&data[at..line_end]).map_err(|_| invalid())?;
+        // chunk extensions after `;` aren't used
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| invalid())?;
+        at = line_end + 2;
+        if size == 0 {
+            break;
+        }
+        read_to_len(stream, data, at + size + 2)?;
+        if &data[at + size..at + size + 2] != b"\r\n" {
+            return Err(invalid());
+        }
+        body.extend_from_slice(&data[at..at + size]);
+        at += size + 2;
+    }
+    // trailer fields, up to an empty line
+    loop {
+        let line_end = read_until(stream, data, at, b"\r\n")?;
+        let empty = line_end == at;
+        at = line_end + 2;
+        if empty {
+            return Ok((body, at));
+        }
+    }
+}
+
+/// Reads one request off a connection: the head, then its body, as long as
+/// `Content-Length` says or chunked. `buffered` holds what was read past the
+/// previous request. Also returns whether the client wants the connection
+/// kept open. A request that can't be read is `InvalidData`, one in a
+/// transfer coding other than chunked `Unsupported`.
+fn read_request(
+    stream: &mut TcpStream,
+    buffered: &mut Vec<u8>,
+    timeout: Duration,
+) -> io::Result<Option<(Map<String, Value>, bool)>> {
+    stream.set_read_timeout(Some(timeout))?;
+
+    let mut data = std::mem::take(buffered);
+    if data.is_empty() {
+        let mut chunk = [0u8; 8192];
+        match stream.read(&mut chunk)? {
+            0 => return Ok(None),
+            read => data.extend_from_slice(&chunk[..read]),
+        }
+    }
+    // the rest of the request may take as long as it did before
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let head_end = read_until(stream, &mut data, 0, b"\r\n\r\n")?;
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or("GET").to_string();
+    let url = request_line.next().unwrap_or("/").to_string();
+    let version = request_line
+        .next()
+        .and_then(|v| v.strip_prefix("HTTP/"))
+        .unwrap_or("1.1")
+        .to_string();
+
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    let mut transfer_encoding: Option<String> = None;
+    // HTTP/1.1 connections stay open unless they say otherwise, older ones
+    // only if they ask to
+    let mut keep_alive = version != "1.0" && version != "0.9";
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            let (name, value) = (name.trim(), value.trim());
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .parse()
+                    .map_err(|_| io::Error::from(ErrorKind::InvalidData))?;
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                transfer_encoding = Some(value.to_ascii_lowercase());
+            } else if name.eq_ignore_ascii_case("connection") {
+                keep_alive = connection_kept_alive(value, keep_alive);
+            }
+            headers.push(json!([name, value]));
+        }
+    }
+
+    let body_start = head_end + 4;
+    let (body, body_end) = match transfer_encoding {
+        // the last coding decides how the body ends, and the others aren't
+        // decoded
+        Some(codings) => {
+            if codings.split(',').map(str::trim).ne(["chunked"]) {
+                return Err(ErrorKind::Unsupported.into());
+            }
+            read_chunked(stream, &mut data, body_start)?
+        }
+        None => {
+            let body_end = body_start + content_length;
+            read_to_len(stream, &mut data, body_end)?;
+            (data[body_start..body_end].to_vec(), body_end)
+        }
+    };
+    *buffered = data.split_off(body_end);
+
+    let mut request = Map::new();
+    request.insert("method".into(), Value::String(method));
+    request.insert("url".into(), Value::String(url));
+    request.insert("httpVersion".into(), Value::String(version));
+    request.insert("headers".into(), Value::Array(headers));
+    request.insert("body".into(), Value::String(to_binary(&body)));
+    Ok(Some((request, keep_alive)))
+}
+
+/// Whether a `Connection` header keeps the connection open, when it would be
+/// by `default`.
+fn connection_kept_alive(value: &str, default: bool) -> bool {
+    let mut options = value.split(',').map(str::trim);
+    if options
+        .clone()
+        .any(|option| option.eq_ignore_ascii_case("close"))
+    {
+        false
+    } else {
+        default || options.any(|option| option.eq_ignore_ascii_case("keep-alive"))
+    }
+}
+
+/// Waits up to `timeout` milliseconds for a request to a listening server,
+/// returning `null` if none came.
+fn http_accept(http: &Mutex<HttpState>, args: &[Value]) -> NodeResult {
+    let server = args.first().and_then(Value::as_u64).unwrap_or(0) as u32;
+    let timeout = Duration::from_millis(args.get(1).and_then(Value::as_u64).unwrap_or(0));
+
+    // the state stays unlocked while waiting
+    let Some(requests) = http
+        .lock()
+        .unwrap()
+        .servers
+        .get(&server)
+        .map(|s| s.requests.clone())
+    else {
+        return Ok(Value::Null);
+    };
+    let Ok((mut request, connection)) = requests.lock().unwrap().recv_timeout(timeout) else {
+        return Ok(Value::Null);
+    };
+    let mut http = http.lock().unwrap();
+    if !http.servers.contains_key(&server) {
+        return Ok(Value::Null);
+    }
+    let id = http.next_id;
+    http.next_id += 1;
+    http.connections.insert(id, connection);
+    request.insert("id".into(), json!(id));
+    Ok(Value::Object(request))
+}
+
+/// Sends the response to a request: `(id, status, statusText, headers,
+/// body)`. Its connection is closed after it if the client or the response
+/// asked for that.
+fn http_respond(http: &Mutex<HttpState>, args: &[Value]) -> NodeResult {
+    let id = args.first().and_then(Value::as_u64).unwrap_or(0) as u32;
+    let Some(mut connection) = http.lock().unwrap().connections.remove(&id) else {
+        return Ok(Value::Null);
+    };
+    let status = args.get(1).and_then(Value::as_u64).unwrap_or(200);
+    let status_text = args.get(2).and_then(Value::as_str).unwrap_or("OK");
+    let body = from_binary(args.get(4).and_then(Value::as_str).unwrap_or_default());
+
+    let mut head = format!("HTTP/1.1 {status} {status_text}\r\n");
+    let mut has_length = false;
+    let mut keep_alive = connection.keep_alive;
+    let mut has_connection = false;
+    for header in args.get(3).and_then(Value::as_array).into_iter().flatten() {
+        let (Some(name), Some(value)) = (header[0].as_str(), header[1].as_str()) else {
+            continue;
+        };
+        has_length |= name.eq_ignore_ascii_case("content-length");
+        if name.eq_ignore_ascii_case("connection") {
+            has_connection = true;
+            keep_alive = keep_alive && connection_kept_alive(value, true);
+        }
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if !has_length {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    if !has_connection {
+        let value = if keep_alive { "keep-alive" } else { "close" };
+        head.push_str(&format!("Connection: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+
+    let stream = &mut connection.stream;
+    let written = stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(&body))
+        .and_then(|_| stream.flush());
+    if !keep_alive || written.is_err() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+    let _ = connection.responded.send(keep_alive && written.is_ok());
+    written.map_err(|e| NativeError::io(&e, "write", None))?;
+    Ok(Value::Null)
+}
+
+/// Closes the connection of a request that will never be responded to.
+fn http_drop(http: &Mutex<HttpState>, args: &[Value]) -> NodeResult {
+    let id = args.first().and_then(Value::as_u64).unwrap_or(0) as u32;
+    http.lock().unwrap().connections.remove(&id);
+    Ok(Value::Null)
+}
+
+/// Stops listening, and closes the connections of requests still waiting
+/// for their response.
+fn http_close(http: &Mutex<HttpState>, args: &[Value]) -> NodeResult {
+    let id = args.first().and_then(Value::as_u64).unwrap_or(0) as u32;
+    let mut http = http.lock().unwrap();
+    http.servers.remove(&id);
+    http.connections
+        .retain(|_, connection| connection.server != id);
+    Ok(Value::Null)
+}
+
+fn dispatch(state: &State, name: &str, args: &[Value]) -> NodeResult {
+    let http = &state.http;
+    match name {
+        "readFile" => read_file(args),
+        "writeFile" => write_file(args),
+        "stat" => stat(args),
+        "readdir" => read_dir(args),
+        "mkdir" => make_dir(args),
+        "rm" => remove(args),
+        "rmdir" => path_call(args, "rmdir", |path| fs::remove_dir(path)),
+        "unlink" => path_call(args, "unlink", |path| fs::remove_file(path)),
+        "chdir" => path_call(args, "chdir", |path| std::env::set_current_dir(path)),
+        "rename" => two_path_call(args, "rename", |from, to| fs::rename(from, to)),
+        "copyFile" => two_path_call(args, "copyfile", |from, to| fs::copy(from, to).map(|_| ())),
+        "realpath" => real_path(args),
+        "cwd" => Ok(Value::String(
+            std::env::current_dir()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        )),
+        "env" => Ok(Value::Object(
+            std::env::vars()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect(),
+        )),
+        "os" => Ok(os_info()),
+        "argv" => Ok(process_argv(&state.argv)),
+        "write" => write_fd(args),
+        "exit" => std::process::exit(args.first().and_then(Value::as_i64).unwrap_or(0) as i32),
+        "spawn" => spawn(args),
+        "httpListen" => http_listen(http, args),
+        "httpAccept" => http_accept(http, args),
+        "httpRespond" => http_respond(http, args),
+        "httpDrop" => http_drop(http, args),
+        "httpClose" => http_close(http, args),
+        _ => Err(NativeError::invalid_arg(format!(
+            "Unknown native function '{name}'"
+        ))),
+    }
+}
+
+pub struct Node;
+
+impl Node {
+    pub fn bind_to_context(context: *mut OpaqueJSContext) {
+        unsafe {
+            define_internal(context, "__lunos_node_call", Self::call_callback);
+            if evaluate(context, NATIVE_JS).is_err() {
+                eprintln!("Failed to initialize the Node.js compatibility layer");
+            }
+        }
+    }
+
+    native_function! {
+        /// `__lunos_node_call(name, args)`: runs a native function with its
+        /// arguments as a JSON array, returning what it gives back as JSON. A
+        /// failure is thrown as the `NativeError` it is.
+        fn call_callback(args, name: String, arguments: String) -> Result<String, NativeError> {
+            let arguments: Vec<Value> = serde_json::from_str(&arguments).unwrap_or_default();
+            let state = State::of(args.context());
+            dispatch(&state, &name, &arguments).map(|value| value.to_string())
+        }
+    }
+}
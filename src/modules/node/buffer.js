@@ -0,0 +1,201 @@
+// node:buffer, a `Buffer` on top of `Uint8Array` covering the encodings the
+// rest of the compatibility layer deals in: utf8, latin1, ascii, hex and
+// base64.
+
+const BASE64 = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+function normalizeEncoding(encoding) {
+    const name = String(encoding === undefined ? "utf8" : encoding).toLowerCase();
+    switch (name) {
+        case "utf8":
+        case "utf-8":
+            return "utf8";
+        case "latin1":
+        case "binary":
+            return "latin1";
+        case "ascii":
+        case "hex":
+        case "base64":
+            return name;
+        default: {
+            const error = new TypeError(`Unknown encoding: ${encoding}`);
+            error.code = "ERR_UNKNOWN_ENCODING";
+            throw error;
+        }
+    }
+}
+
+// A string with one char per byte.
+function toBinary(bytes) {
+    let out = "";
+    for (let i = 0; i < bytes.length; i += 0x8000) {
+        out += String.fromCharCode.apply(null, bytes.subarray(i, i + 0x8000));
+    }
+    return out;
+}
+
+function binaryBytes(binary) {
+    const bytes = new Uint8Array(binary.length);
+    for (let i = 0; i < binary.length; i++) {
+        bytes[i] = binary.charCodeAt(i) & 0xff;
+    }
+    return bytes;
+}
+
+function decodeUtf8(bytes) {
+    const binary = toBinary(bytes);
+    try {
+        return decodeURIComponent(escape(binary));
+    } catch {
+        // invalid sequences: decode what's valid byte by byte
+        let out = "";
+        for (let i = 0; i < bytes.length; i++) {
+            out += bytes[i] < 0x80 ? String.fromCharCode(bytes[i]) : "�";
+        }
+        return out;
+    }
+}
+
+function encodeBase64(bytes) {
+    let out = "";
+    for (let i = 0; i < bytes.length; i += 3) {
+        const n = (bytes[i] << 16) | ((bytes[i + 1] || 0) << 8) | (bytes[i + 2] || 0);
+        out += BASE64[(n >> 18) & 63] + BASE64[(n >> 12) & 63];
+        out += i + 1 < bytes.length ? BASE64[(n >> 6) & 63] : "=";
+        out += i + 2 < bytes.length ? BASE64[n & 63] : "=";
+    }
+    return out;
+}
+
+function decodeBase64(string) {
+    const clean = string.replace(/[^A-Za-z0-9+/\-_]/g, "").replace(/-/g, "+").replace(/_/g, "/");
+    const bytes = [];
+    for (let i = 0; i < clean.length; i += 4) {
+        const chunk = clean.slice(i, i + 4);
+        let n = 0;
+        for (let j = 0; j < 4; j++) {
+            n = (n << 6) | (j < chunk.length ? BASE64.indexOf(chunk[j]) : 0);
+        }
+        bytes.push((n >> 16) & 0xff);
+        if (chunk.length > 2) bytes.push((n >> 8) & 0xff);
+        if (chunk.length > 3) bytes.push(n & 0xff);
+    }
+    return bytes;
+}
+
+function encode(string, encoding) {
+    switch (normalizeEncoding(encoding)) {
+        case "utf8":
+            return binaryBytes(unescape(encodeURIComponent(string)));
+        case "hex": {
+            const bytes = [];
+            for (let i = 0; i + 1 < string.length; i += 2) {
+                const byte = parseInt(string.slice(i, i + 2), 16);
+                if (Number.isNaN(byte)) break;
+                bytes.push(byte);
+            }
+            return bytes;
+        }
+        case "base64":
+            return decodeBase64(string);
+        default:
+            return binaryBytes(string);
+    }
+}
+
+class Buffer extends Uint8Array {
+    static from(value, encodingOrOffset, length) {
+        if (typeof value === "string") {
+            return new Buffer(encode(value, encodingOrOffset));
+        }
+        if (value instanceof ArrayBuffer) {
+            return new Buffer(value, encodingOrOffset || 0, length);
+        }
+        return new Buffer(value);
+    }
+
+    static alloc(size, fill) {
+        const buffer = new Buffer(size);
+        if (fill !== undefined) {
+            buffer.fill(typeof fill === "string" ? fill.charCodeAt(0) : fill);
+        }
+        return buffer;
+    }
+
+    static allocUnsafe(size) {
+        return new Buffer(size);
+    }
+
+    static isBuffer(value) {
+        return value instanceof Buffer;
+    }
+
+    static isEncoding(encoding) {
+        try {
+            normalizeEncoding(encoding);
+            return true;
+        } catch {
+            return false;
+        }
+    }
+
+    static byteLength(value, encoding) {
+        return typeof value === "string" ? encode(value, encoding).length : value.byteLength;
+    }
+
+    static concat(list, totalLength) {
+        const length = totalLength === undefined
+            ? list.reduce((sum, item) => sum + item.length, 0)
+            : totalLength;
+        const result = new Buffer(length);
+        let offset = 0;
+        for (const item of list) {
+            if (offset >= length) break;
+            result.set(item.subarray(0, length - offset), offset);
+            offset += item.length;
+        }
+        return result;
+    }
+
+    toString(encoding, start = 0, end = this.length) {
+        const bytes = this.subarray(start, end);
+        switch (normalizeEncoding(encoding)) {
+            case "utf8":
+                return decodeUtf8(bytes);
+            case "ascii":
+                return toBinary(bytes.map((byte) => byte & 0x7f));
+            case "hex":
+                return Array.from(bytes, (byte) => byte.toString(16).padStart(2, "0")).join("");
+            case "base64":
+                return encodeBase64(bytes);
+            default:
+                return toBinary(bytes);
+        }
+    }
+
+    toJSON() {
+        return { type: "Buffer", data: Array.from(this) };
+    }
+
+    equals(other) {
+        return this.length === other.length && this.every((byte, i) => byte === other[i]);
+    }
+
+    write(string, offset = 0, encoding) {
+        const bytes = encode(string, encoding).slice(0, this.length - offset);
+        this.set(bytes, offset);
+        return bytes.length;
+    }
+}
+
+// Used by the other `node:` modules to move data across the native boundary,
+// which passes bytes as strings with one char per byte.
+Object.defineProperties(Buffer, {
+    fromBinary: { value: (binary) => new Buffer(binaryBytes(binary)) },
+    toBinary: { value: toBinary },
+});
+
+const constants = { MAX_LENGTH: 2 ** 32 - 1, MAX_STRING_LENGTH: 2 ** 29 - 24 };
+
+export { Buffer, constants };
+export default { Buffer, constants };
@@ -0,0 +1,178 @@
+// node:child_process. Processes always run to completion before the call
+// returns; the asynchronous functions report the result on a later tick.
+
+import { Buffer } from "node:buffer";
+import EventEmitter from "node:events";
+
+const native = __lunos_node;
+const SIGNALS = { 1: "SIGHUP", 2: "SIGINT", 3: "SIGQUIT", 6: "SIGABRT", 9: "SIGKILL", 11: "SIGSEGV", 13: "SIGPIPE", 15: "SIGTERM" };
+
+function nativeOptions(options, shell) {
+    const opts = options || {};
+    const input = opts.input === undefined
+        ? undefined
+        : typeof opts.input === "string"
+            ? Buffer.toBinary(Buffer.from(opts.input))
+            : Buffer.toBinary(opts.input);
+    return {
+        cwd: opts.cwd === undefined ? undefined : String(opts.cwd),
+        env: opts.env,
+        input,
+        shell: Boolean(shell || opts.shell),
+    };
+}
+
+function decode(binary, encoding) {
+    const buffer = Buffer.fromBinary(binary);
+    return encoding && encoding !== "buffer" ? buffer.toString(encoding) : buffer;
+}
+
+function run(file, args, options, shell) {
+    const opts = options || {};
+    try {
+        const result = native.call("spawn", String(file), (args || []).map(String), nativeOptions(opts, shell));
+        return {
+            pid: 0,
+            status: result.status,
+            signal: result.signal === null ? null : SIGNALS[result.signal] || `SIG${result.signal}`,
+            stdout: decode(result.stdout, opts.encoding),
+            stderr: decode(result.stderr, opts.encoding),
+            error: undefined,
+        };
+    } catch (error) {
+        error.spawnargs = (args || []).map(String);
+        return { pid: 0, status: null, signal: null, stdout: decode("", opts.encoding), stderr: decode("", opts.encoding), error };
+    }
+}
+
+function spawnSync(command, args, options) {
+    if (!Array.isArray(args)) {
+        options = args;
+        args = [];
+    }
+    const result = run(command, args, options, false);
+    result.output = [null, result.stdout, result.stderr];
+    return result;
+}
+
+// The error `execSync` and `execFileSync` throw for a failed command.
+function commandError(command, result) {
+    if (result.error) {
+        return result.error;
+    }
+    const stderr = typeof result.stderr === "string" ? result.stderr : result.stderr.toString();
+    const error = new Error(`Command failed: ${command}${stderr ? "\n" + stderr : ""}`);
+    error.status = result.status;
+    error.signal = result.signal;
+    error.stdout = result.stdout;
+    error.stderr = result.stderr;
+    error.output = [null, result.stdout, result.stderr];
+    return error;
+}
+
+function execSync(command, options) {
+    const result = run(command, [], options, true);
+    if (result.error || result.status !== 0) {
+        throw commandError(command, result);
+    }
+    return result.stdout;
+}
+
+function execFileSync(file, args, options) {
+    if (!Array.isArray(args)) {
+        options = args;
+        args = [];
+    }
+    const result = run(file, args, options, false);
+    if (result.error || result.status !== 0) {
+        throw commandError([file, ...args].join(" "), result);
+    }
+    return result.stdout;
+}
+
+// A finished child process: emits `exit` and `close`, with its output on
+// `stdout` and `stderr` as a single `data` event each.
+function finishedChild(file, args, result) {
+    const child = new EventEmitter();
+    child.pid = 0;
+    child.spawnfile = file;
+    child.spawnargs = [file, ...args];
+    child.stdout = new EventEmitter();
+    child.stderr = new EventEmitter();
+    child.stdin = { write() {}, end() {} };
+    child.stdout.setEncoding = child.stderr.setEncoding = function (encoding) {
+        this.encoding = encoding;
+        return this;
+    };
+    child.exitCode = null;
+    child.kill = () => false;
+
+    setTimeout(() => {
+        if (result.error) {
+            child.emit("error", result.error);
+            return;
+        }
+        for (const name of ["stdout", "stderr"]) {
+            const output = Buffer.from(result[name]);
+            if (output.length > 0) {
+                const stream = child[name];
+                stream.emit("data", stream.encoding ? output.toString(stream.encoding) : output);
+            }
+            child[name].emit("end");
+        }
+        child.exitCode = result.status;
+        child.emit("exit", result.status, result.signal);
+        child.emit("close", result.status, result.signal);
+    }, 0);
+    return child;
+}
+
+function spawn(command, args, options) {
+    if (!Array.isArray(args)) {
+        options = args;
+        args = [];
+    }
+    const result = run(command, args, { ...options, encoding: "buffer" }, false);
+    return finishedChild(command, args, result);
+}
+
+function splitOptions(options, callback) {
+    return typeof options === "function" ? [{}, options] : [options || {}, callback];
+}
+
+function exec(command, options, callback) {
+    const [opts, done] = splitOptions(options, callback);
+    const encoding = opts.encoding || "utf8";
+    const result = run(command, [], { ...opts, encoding }, true);
+    const child = finishedChild(command, [], { ...result, stdout: Buffer.from(result.stdout), stderr: Buffer.from(result.stderr) });
+    if (done) {
+        setTimeout(() => {
+            const failed = result.error || result.status !== 0;
+            done(failed ? commandError(command, result) : null, result.stdout, result.stderr);
+        }, 0);
+    }
+    return child;
+}
+
+function execFile(file, args, options, callback) {
+    if (typeof args === "function") {
+        return execFile(file, [], {}, args);
+    }
+    if (!Array.isArray(args)) {
+        return execFile(file, [], args, options);
+    }
+    const [opts, done] = splitOptions(options, callback);
+    const encoding = opts.encoding || "utf8";
+    const result = run(file, args, { ...opts, encoding }, false);
+    const child = finishedChild(file, args, { ...result, stdout: Buffer.from(result.stdout), stderr: Buffer.from(result.stderr) });
+    if (done) {
+        setTimeout(() => {
+            const failed = result.error || result.status !== 0;
+            done(failed ? commandError([file, ...args].join(" "), result) : null, result.stdout, result.stderr);
+        }, 0);
+    }
+    return child;
+}
+
+export { spawn, spawnSync, exec, execSync, execFile, execFileSync };
+export default { spawn, spawnSync, exec, execSync, execFile, execFileSync };
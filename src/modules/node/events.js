@@ -0,0 +1,207 @@
+// node:events
+
+let defaultMaxListeners = 10;
+
+function checkListener(listener) {
+    if (typeof listener !== "function") {
+        const error = new TypeError(`The "listener" argument must be of type function. Received ${typeof listener}`);
+        error.code = "ERR_INVALID_ARG_TYPE";
+        throw error;
+    }
+}
+
+// A plain function rather than a class, so that old-style subclasses can
+// call `EventEmitter.call(this)`.
+function EventEmitter() {
+    EventEmitter.init.call(this);
+}
+
+EventEmitter.init = function () {
+    if (!this._events || this._events === Object.getPrototypeOf(this)._events) {
+        this._events = Object.create(null);
+    }
+    this._maxListeners = this._maxListeners || undefined;
+};
+
+Object.defineProperty(EventEmitter, "defaultMaxListeners", {
+    get: () => defaultMaxListeners,
+    set: (value) => {
+        defaultMaxListeners = value;
+    },
+    enumerable: true,
+});
+
+EventEmitter.prototype._listeners = function (name) {
+    if (!this._events) {
+        this._events = Object.create(null);
+    }
+    return this._events[name] || (this._events[name] = []);
+};
+
+EventEmitter.prototype._add = function (name, listener, prepend) {
+    checkListener(listener);
+    if (this._events && this._events.newListener) {
+        this.emit("newListener", name, listener.listener || listener);
+    }
+    const listeners = this._listeners(name);
+    if (prepend) {
+        listeners.unshift(listener);
+    } else {
+        listeners.push(listener);
+    }
+    const max = this.getMaxListeners();
+    if (max > 0 && listeners.length > max && !listeners.warned) {
+        listeners.warned = true;
+        console.warn(
+            `MaxListenersExceededWarning: Possible EventEmitter memory leak detected. ${listeners.length} ${String(name)} listeners added. Use emitter.setMaxListeners() to increase limit`,
+        );
+    }
+    return this;
+};
+
+EventEmitter.prototype._wrapOnce = function (name, listener) {
+    checkListener(listener);
+    const emitter = this;
+    function wrapper(...args) {
+        emitter.removeListener(name, wrapper);
+        return listener.apply(this, args);
+    }
+    wrapper.listener = listener;
+    return wrapper;
+};
+
+EventEmitter.prototype.on = function (name, listener) {
+    return this._add(name, listener, false);
+};
+
+EventEmitter.prototype.addListener = function (name, listener) {
+    return this._add(name, listener, false);
+};
+
+EventEmitter.prototype.prependListener = function (name, listener) {
+    return this._add(name, listener, true);
+};
+
+EventEmitter.prototype.once = function (name, listener) {
+    return this._add(name, this._wrapOnce(name, listener), false);
+};
+
+EventEmitter.prototype.prependOnceListener = function (name, listener) {
+    return this._add(name, this._wrapOnce(name, listener), true);
+};
+
+EventEmitter.prototype.removeListener = function (name, listener) {
+    checkListener(listener);
+    const listeners = this._events && this._events[name];
+    if (!listeners) {
+        return this;
+    }
+    for (let i = listeners.length - 1; i >= 0; i--) {
+        if (listeners[i] === listener || listeners[i].listener === listener) {
+            listeners.splice(i, 1);
+            if (listeners.length === 0) {
+                delete this._events[name];
+            }
+            if (this._events.removeListener) {
+                this.emit("removeListener", name, listener);
+            }
+            break;
+        }
+    }
+    return this;
+};
+
+EventEmitter.prototype.off = function (name, listener) {
+    return this.removeListener(name, listener);
+};
+
+EventEmitter.prototype.removeAllListeners = function (name) {
+    if (!this._events) {
+        return this;
+    }
+    if (name === undefined) {
+        this._events = Object.create(null);
+    } else {
+        delete this._events[name];
+    }
+    return this;
+};
+
+EventEmitter.prototype.emit = function (name, ...args) {
+    const listeners = this._events && this._events[name];
+    if (!listeners || listeners.length === 0) {
+        if (name === "error") {
+            const error = args[0];
+            if (error instanceof Error) {
+                throw error;
+            }
+            const wrapped = new Error(`Unhandled error. (${String(error)})`);
+            wrapped.code = "ERR_UNHANDLED_ERROR";
+            wrapped.context = error;
+            throw wrapped;
+        }
+        return false;
+    }
+    for (const listener of listeners.slice()) {
+        listener.apply(this, args);
+    }
+    return true;
+};
+
+EventEmitter.prototype.listeners = function (name) {
+    const listeners = (this._events && this._events[name]) || [];
+    return listeners.map((listener) => listener.listener || listener);
+};
+
+EventEmitter.prototype.rawListeners = function (name) {
+    return ((this._events && this._events[name]) || []).slice();
+};
+
+EventEmitter.prototype.listenerCount = function (name) {
+    const listeners = this._events && this._events[name];
+    return listeners ? listeners.length : 0;
+};
+
+EventEmitter.prototype.eventNames = function () {
+    return this._events ? Reflect.ownKeys(this._events) : [];
+};
+
+EventEmitter.prototype.setMaxListeners = function (count) {
+    this._maxListeners = count;
+    return this;
+};
+
+EventEmitter.prototype.getMaxListeners = function () {
+    return this._maxListeners === undefined ? defaultMaxListeners : this._maxListeners;
+};
+
+// Resolves with the arguments of the next `name` event, or rejects on `error`.
+function once(emitter, name) {
+    return new Promise((resolve, reject) => {
+        const onError = (error) => {
+            emitter.removeListener(name, onEvent);
+            reject(error);
+        };
+        const onEvent = (...args) => {
+            if (name !== "error") {
+                emitter.removeListener("error", onError);
+            }
+            resolve(args);
+        };
+        emitter.once(name, onEvent);
+        if (name !== "error") {
+            emitter.once("error", onError);
+        }
+    });
+}
+
+function listenerCount(emitter, name) {
+    return emitter.listenerCount(name);
+}
+
+EventEmitter.EventEmitter = EventEmitter;
+EventEmitter.once = once;
+EventEmitter.listenerCount = listenerCount;
+
+export { EventEmitter, once, listenerCount };
+export default EventEmitter;
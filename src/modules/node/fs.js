@@ -0,0 +1,409 @@
+// node:fs, the synchronous API plus callback and promise versions of it.
+// The callback and promise versions do the work synchronously and only
+// report the result asynchronously.
+
+import { Buffer } from "node:buffer";
+
+const native = __lunos_node;
+
+const constants = Object.freeze({
+    F_OK: 0,
+    R_OK: 4,
+    W_OK: 2,
+    X_OK: 1,
+    COPYFILE_EXCL: 1,
+    COPYFILE_FICLONE: 2,
+    COPYFILE_FICLONE_FORCE: 4,
+    S_IFMT: 0o170000,
+    S_IFREG: 0o100000,
+    S_IFDIR: 0o040000,
+    S_IFLNK: 0o120000,
+});
+
+function invalidArg(name, value) {
+    const error = new TypeError(`The "${name}" argument must be of type string, Buffer or URL. Received ${value === null ? "null" : typeof value}`);
+    error.code = "ERR_INVALID_ARG_TYPE";
+    return error;
+}
+
+function toPath(path, name = "path") {
+    if (typeof path === "string") {
+        return path;
+    }
+    if (path instanceof Uint8Array) {
+        return Buffer.from(path).toString();
+    }
+    if (path !== null && typeof path === "object" && path.protocol === "file:") {
+        return decodeURIComponent(path.pathname);
+    }
+    throw invalidArg(name, path);
+}
+
+function encodingOf(options) {
+    if (typeof options === "string") {
+        return options;
+    }
+    return options && options.encoding ? options.encoding : undefined;
+}
+
+function isUtf8(encoding) {
+    return encoding === "utf8" || encoding === "utf-8";
+}
+
+class Stats {
+    constructor(raw) {
+        this.type = raw.type;
+        this.size = raw.size;
+        this.mode = raw.mode;
+        this.atimeMs = raw.atimeMs;
+        this.mtimeMs = raw.mtimeMs;
+        this.ctimeMs = raw.ctimeMs;
+        this.birthtimeMs = raw.birthtimeMs;
+        this.atime = new Date(raw.atimeMs);
+        this.mtime = new Date(raw.mtimeMs);
+        this.ctime = new Date(raw.ctimeMs);
+        this.birthtime = new Date(raw.birthtimeMs);
+        Object.defineProperty(this, "type", { enumerable: false });
+    }
+
+    isFile() {
+        return this.type === "file";
+    }
+
+    isDirectory() {
+        return this.type === "directory";
+    }
+
+    isSymbolicLink() {
+        return this.type === "symlink";
+    }
+
+    isFIFO() {
+        return false;
+    }
+
+    isSocket() {
+        return false;
+    }
+
+    isBlockDevice() {
+        return false;
+    }
+
+    isCharacterDevice() {
+        return false;
+    }
+}
+
+class Dirent {
+    constructor(name, type, parentPath) {
+        this.name = name;
+        this.parentPath = parentPath;
+        this.path = parentPath;
+        Object.defineProperty(this, "type", { value: type, enumerable: false });
+    }
+}
+for (const method of ["isFile", "isDirectory", "isSymbolicLink", "isFIFO", "isSocket", "isBlockDevice", "isCharacterDevice"]) {
+    Dirent.prototype[method] = Stats.prototype[method];
+}
+
+function existsSync(path) {
+    try {
+        native.call("stat", toPath(path), true);
+        return true;
+    } catch {
+        return false;
+    }
+}
+
+function statSync(path, options) {
+    try {
+        return new Stats(native.call("stat", toPath(path), true));
+    } catch (error) {
+        if (options && options.throwIfNoEntry === false && error.code === "ENOENT") {
+            return undefined;
+        }
+        throw error;
+    }
+}
+
+function lstatSync(path, options) {
+    try {
+        return new Stats(native.call("stat", toPath(path), false));
+    } catch (error) {
+        if (options && options.throwIfNoEntry === false && error.code === "ENOENT") {
+            return undefined;
+        }
+        throw error;
+    }
+}
+
+function accessSync(path) {
+    native.call("stat", toPath(path), true);
+}
+
+function readFileSync(path, options) {
+    const encoding = encodingOf(options);
+    if (isUtf8(encoding)) {
+        return native.call("readFile", toPath(path), false);
+    }
+    const buffer = Buffer.fromBinary(native.call("readFile", toPath(path), true));
+    return encoding ? buffer.toString(encoding) : buffer;
+}
+
+// Whether `data` goes over as UTF-8 text, or as one char per byte.
+function serialize(data, options) {
+    if (typeof data === "string") {
+        const encoding = encodingOf(options) || "utf8";
+        return isUtf8(encoding) ? [data, false] : [Buffer.toBinary(Buffer.from(data, encoding)), true];
+    }
+    if (ArrayBuffer.isView(data)) {
+        return [Buffer.toBinary(new Uint8Array(data.buffer, data.byteOffset, data.byteLength)), true];
+    }
+    return [String(data), false];
+}
+
+function writeFileSync(path, data, options) {
+    const [payload, binary] = serialize(data, options);
+    const append = options && typeof options === "object" && typeof options.flag === "string" && options.flag.startsWith("a");
+    native.call("writeFile", toPath(path), payload, binary, append);
+}
+
+function appendFileSync(path, data, options) {
+    const [payload, binary] = serialize(data, options);
+    native.call("writeFile", toPath(path), payload, binary, true);
+}
+
+function readdirSync(path, options) {
+    const dir = toPath(path);
+    const withFileTypes = options && typeof options === "object" && options.withFileTypes;
+    const recursive = options && typeof options === "object" && options.recursive;
+    const encoding = encodingOf(options);
+
+    const out = [];
+    const visit = (current, prefix) => {
+        for (const [name, type] of native.call("readdir", current)) {
+            const relative = prefix ? `${prefix}/${name}` : name;
+            if (withFileTypes) {
+                out.push(new Dirent(name, type, current));
+            } else {
+                out.push(encoding === "buffer" ? Buffer.from(relative) : relative);
+            }
+            if (recursive && type === "directory") {
+                visit(`${current}/${name}`, relative);
+            }
+        }
+    };
+    visit(dir, "");
+    return out;
+}
+
+function mkdirSync(path, options) {
+    const recursive = options && typeof options === "object" && options.recursive;
+    const first = native.call("mkdir", toPath(path), Boolean(recursive));
+    return recursive ? (first === null ? undefined : first) : undefined;
+}
+
+function mkdtempSync(prefix) {
+    const chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    for (;;) {
+        let suffix = "";
+        for (let i = 0; i < 6; i++) {
+            suffix += chars[Math.floor(Math.random() * chars.length)];
+        }
+        const path = toPath(prefix, "prefix") + suffix;
+        try {
+            native.call("mkdir", path, false);
+            return path;
+        } catch (error) {
+            if (error.code !== "EEXIST") {
+                throw error;
+            }
+        }
+    }
+}
+
+function rmSync(path, options) {
+    const recursive = Boolean(options && options.recursive);
+    const force = Boolean(options && options.force);
+    native.call("rm", toPath(path), recursive, force);
+}
+
+function rmdirSync(path, options) {
+    if (options && options.recursive) {
+        native.call("rm", toPath(path), true, false);
+    } else {
+        native.call("rmdir", toPath(path));
+    }
+}
+
+function unlinkSync(path) {
+    native.call("unlink", toPath(path));
+}
+
+function renameSync(oldPath, newPath) {
+    native.call("rename", toPath(oldPath, "oldPath"), toPath(newPath, "newPath"));
+}
+
+function copyFileSync(src, dest, mode) {
+    const to = toPath(dest, "dest");
+    if (mode & constants.COPYFILE_EXCL && existsSync(to)) {
+        const error = new Error(`EEXIST: file already exists, copyfile '${toPath(src, "src")}' -> '${to}'`);
+        error.code = "EEXIST";
+        error.syscall = "copyfile";
+        error.path = toPath(src, "src");
+        error.dest = to;
+        throw error;
+    }
+    native.call("copyFile", toPath(src, "src"), to);
+}
+
+function realpathSync(path) {
+    return native.call("realpath", toPath(path));
+}
+realpathSync.native = realpathSync;
+
+const syncFunctions = {
+    access: accessSync,
+    appendFile: appendFileSync,
+    copyFile: copyFileSync,
+    lstat: lstatSync,
+    mkdir: mkdirSync,
+    mkdtemp: mkdtempSync,
+    readFile: readFileSync,
+    readdir: readdirSync,
+    realpath: realpathSync,
+    rename: renameSync,
+    rm: rmSync,
+    rmdir: rmdirSync,
+    stat: statSync,
+    unlink: unlinkSync,
+    writeFile: writeFileSync,
+};
+
+function toCallback(sync) {
+    return function (...args) {
+        const callback = args.pop();
+        if (typeof callback !== "function") {
+            const error = new TypeError('The "cb" argument must be of type function');
+            error.code = "ERR_INVALID_ARG_TYPE";
+            throw error;
+        }
+        let result;
+        let failure = null;
+        try {
+            result = sync(...args);
+        } catch (error) {
+            failure = error;
+        }
+        setTimeout(() => (failure ? callback(failure) : callback(null, result)), 0);
+    };
+}
+
+function toPromise(sync) {
+    return (...args) => new Promise((resolve) => resolve(sync(...args)));
+}
+
+const access = toCallback(accessSync);
+const appendFile = toCallback(appendFileSync);
+const copyFile = toCallback(copyFileSync);
+const lstat = toCallback(lstatSync);
+const mkdir = toCallback(mkdirSync);
+const mkdtemp = toCallback(mkdtempSync);
+const readFile = toCallback(readFileSync);
+const readdir = toCallback(readdirSync);
+const realpath = toCallback(realpathSync);
+const rename = toCallback(renameSync);
+const rm = toCallback(rmSync);
+const rmdir = toCallback(rmdirSync);
+const stat = toCallback(statSync);
+const unlink = toCallback(unlinkSync);
+const writeFile = toCallback(writeFileSync);
+
+// the one callback API without an error argument
+function exists(path, callback) {
+    const found = existsSync(path);
+    setTimeout(() => callback(found), 0);
+}
+
+const promises = { constants };
+for (const name of Object.keys(syncFunctions)) {
+    promises[name] = toPromise(syncFunctions[name]);
+}
+
+export {
+    constants,
+    Stats,
+    Dirent,
+    existsSync,
+    statSync,
+    lstatSync,
+    accessSync,
+    readFileSync,
+    writeFileSync,
+    appendFileSync,
+    readdirSync,
+    mkdirSync,
+    mkdtempSync,
+    rmSync,
+    rmdirSync,
+    unlinkSync,
+    renameSync,
+    copyFileSync,
+    realpathSync,
+    access,
+    appendFile,
+    copyFile,
+    exists,
+    lstat,
+    mkdir,
+    mkdtemp,
+    readFile,
+    readdir,
+    realpath,
+    rename,
+    rm,
+    rmdir,
+    stat,
+    unlink,
+    writeFile,
+    promises,
+};
+export default {
+    constants,
+    Stats,
+    Dirent,
+    existsSync,
+    statSync,
+    lstatSync,
+    accessSync,
+    readFileSync,
+    writeFileSync,
+    appendFileSync,
+    readdirSync,
+    mkdirSync,
+    mkdtempSync,
+    rmSync,
+    rmdirSync,
+    unlinkSync,
+    renameSync,
+    copyFileSync,
+    realpathSync,
+    access,
+    appendFile,
+    copyFile,
+    exists,
+    lstat,
+    mkdir,
+    mkdtemp,
+    readFile,
+    readdir,
+    realpath,
+    rename,
+    rm,
+    rmdir,
+    stat,
+    unlink,
+    writeFile,
+    promises,
+};
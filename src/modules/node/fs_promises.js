@@ -0,0 +1,23 @@
+// node:fs/promises
+
+import { promises } from "node:fs";
+
+export const {
+    access,
+    appendFile,
+    constants,
+    copyFile,
+    lstat,
+    mkdir,
+    mkdtemp,
+    readFile,
+    readdir,
+    realpath,
+    rename,
+    rm,
+    rmdir,
+    stat,
+    unlink,
+    writeFile,
+} = promises;
+export default promises;
@@ -0,0 +1,295 @@
+// node:http, the server side. Connections are read off the main thread and
+// the server polls for finished requests from a timer, so other timers keep
+// running while it waits. Connections stay open between requests unless the
+// client or the response asks to close them. A request whose response is
+// collected without being ended has its connection closed.
+
+import { Buffer } from "node:buffer";
+import EventEmitter from "node:events";
+
+const native = __lunos_node;
+
+// How long a single poll waits for a request before yielding to timers.
+const POLL_INTERVAL = 10;
+
+const STATUS_CODES = {
+    100: "Continue", 101: "Switching Protocols", 200: "OK", 201: "Created", 202: "Accepted",
+    204: "No Content", 206: "Partial Content", 301: "Moved Permanently", 302: "Found",
+    303: "See Other", 304: "Not Modified", 307: "Temporary Redirect", 308: "Permanent Redirect",
+    400: "Bad Request", 401: "Unauthorized", 403: "Forbidden", 404: "Not Found",
+    405: "Method Not Allowed", 406: "Not Acceptable", 408: "Request Timeout", 409: "Conflict",
+    410: "Gone", 411: "Length Required", 413: "Payload Too Large", 415: "Unsupported Media Type",
+    418: "I'm a Teapot", 422: "Unprocessable Entity", 429: "Too Many Requests",
+    500: "Internal Server Error", 501: "Not Implemented", 502: "Bad Gateway",
+    503: "Service Unavailable", 504: "Gateway Timeout",
+};
+
+const METHODS = ["DELETE", "GET", "HEAD", "OPTIONS", "PATCH", "POST", "PUT"];
+
+const unanswered = new FinalizationRegistry((id) => native.call("httpDrop", id));
+
+function IncomingMessage(raw) {
+    EventEmitter.call(this);
+    this.method = raw.method;
+    this.url = raw.url;
+    this.httpVersion = raw.httpVersion;
+    this.headers = Object.create(null);
+    this.rawHeaders = [];
+    for (const [name, value] of raw.headers) {
+        const key = name.toLowerCase();
+        this.headers[key] = key in this.headers ? `${this.headers[key]}, ${value}` : value;
+        this.rawHeaders.push(name, value);
+    }
+    this.socket = { remoteAddress: "127.0.0.1" };
+    this.complete = false;
+    this._body = Buffer.fromBinary(raw.body);
+    this._encoding = undefined;
+    this._flowing = false;
+}
+Object.setPrototypeOf(IncomingMessage.prototype, EventEmitter.prototype);
+
+IncomingMessage.prototype.setEncoding = function (encoding) {
+    this._encoding = encoding;
+    return this;
+};
+
+// The body arrived with the request, but like a stream it's held until read:
+// it goes out as one `data` event once a `data` listener is attached or
+// `resume` is called, however late that is.
+IncomingMessage.prototype._add = function (name, listener, prepend) {
+    EventEmitter.prototype._add.call(this, name, listener, prepend);
+    if (name === "data") {
+        this.resume();
+    }
+    return this;
+};
+
+IncomingMessage.prototype.resume = function () {
+    if (this._flowing) {
+        return this;
+    }
+    this._flowing = true;
+    setTimeout(() => {
+        if (this._body.length > 0) {
+            this.emit("data", this._encoding ? this._body.toString(this._encoding) : this._body);
+        }
+        this.complete = true;
+        this.emit("end");
+        this.emit("close");
+    }, 0);
+    return this;
+};
+
+IncomingMessage.prototype[Symbol.asyncIterator] = async function* () {
+    if (this._flowing) {
+        return;
+    }
+    this._flowing = true;
+    if (this._body.length > 0) {
+        yield this._encoding ? this._body.toString(this._encoding) : this._body;
+    }
+    this.complete = true;
+};
+
+function ServerResponse(id, request) {
+    EventEmitter.call(this);
+    this._id = id;
+    this._request = request;
+    this._headers = Object.create(null);
+    this._chunks = [];
+    this.statusCode = 200;
+    this.statusMessage = undefined;
+    this.headersSent = false;
+    this.writableEnded = false;
+    this.finished = false;
+    unanswered.register(this, id, this);
+}
+Object.setPrototypeOf(ServerResponse.prototype, EventEmitter.prototype);
+
+ServerResponse.prototype.setHeader = function (name, value) {
+    this._headers[name.toLowerCase()] = [name, value];
+    return this;
+};
+
+ServerResponse.prototype.getHeader = function (name) {
+    const header = this._headers[name.toLowerCase()];
+    return header ? header[1] : undefined;
+};
+
+ServerResponse.prototype.getHeaders = function () {
+    const headers = Object.create(null);
+    for (const key of Object.keys(this._headers)) {
+        headers[key] = this._headers[key][1];
+    }
+    return headers;
+};
+
+ServerResponse.prototype.hasHeader = function (name) {
+    return name.toLowerCase() in this._headers;
+};
+
+ServerResponse.prototype.removeHeader = function (name) {
+    delete this._headers[name.toLowerCase()];
+};
+
+ServerResponse.prototype.writeHead = function (statusCode, message, headers) {
+    if (typeof message !== "string") {
+        headers = message;
+        message = undefined;
+    }
+    this.statusCode = statusCode;
+    if (message !== undefined) {
+        this.statusMessage = message;
+    }
+    if (Array.isArray(headers)) {
+        for (let i = 0; i + 1 < headers.length; i += 2) {
+            this.setHeader(headers[i], headers[i + 1]);
+        }
+    } else if (headers) {
+        for (const name of Object.keys(headers)) {
+            this.setHeader(name, headers[name]);
+        }
+    }
+    return this;
+};
+
+ServerResponse.prototype.write = function (chunk, encoding, callback) {
+    if (chunk !== undefined && chunk !== null) {
+        this._chunks.push(typeof chunk === "string" ? Buffer.from(chunk, typeof encoding === "string" ? encoding : "utf8") : Buffer.from(chunk));
+    }
+    const done = typeof encoding === "function" ? encoding : callback;
+    if (typeof done === "function") {
+        setTimeout(done, 0);
+    }
+    return true;
+};
+
+ServerResponse.prototype.end = function (chunk, encoding, callback) {
+    if (this.writableEnded) {
+        return this;
+    }
+    if (typeof chunk === "function") {
+        callback = chunk;
+        chunk = undefined;
+    }
+    this.write(chunk, typeof encoding === "string" ? encoding : undefined);
+    this.writableEnded = this.finished = this.headersSent = true;
+
+    const body = this._request.method === "HEAD" ? Buffer.alloc(0) : Buffer.concat(this._chunks);
+    const headers = Object.keys(this._headers).map((key) => {
+        const [name, value] = this._headers[key];
+        return [name, Array.isArray(value) ? value.join(", ") : String(value)];
+    });
+    const message = this.statusMessage || STATUS_CODES[this.statusCode] || "Unknown";
+    native.call("httpRespond", this._id, this.statusCode, message, headers, Buffer.toBinary(body));
+    unanswered.unregister(this);
+    // an unread body is discarded, still ending the request
+    this._request.resume();
+
+    setTimeout(() => {
+        this.emit("finish");
+        this.emit("close");
+        const done = typeof encoding === "function" ? encoding : callback;
+        if (typeof done === "function") {
+            done();
+        }
+    }, 0);
+    return this;
+};
+
+function Server(options, requestListener) {
+    EventEmitter.call(this);
+    if (typeof options === "function") {
+        requestListener = options;
+    }
+    if (requestListener) {
+        this.on("request", requestListener);
+    }
+    this._id = null;
+    this._port = null;
+    this._host = null;
+    this.listening = false;
+}
+Object.setPrototypeOf(Server.prototype, EventEmitter.prototype);
+
+Server.prototype.listen = function (...args) {
+    const callback = typeof args[args.length - 1] === "function" ? args.pop() : undefined;
+    let port = 0;
+    let host = "0.0.0.0";
+    if (args[0] !== null && typeof args[0] === "object") {
+        port = args[0].port || 0;
+        host = args[0].host || host;
+    } else {
+        port = args[0] === undefined ? 0 : Number(args[0]);
+        if (typeof args[1] === "string") {
+            host = args[1];
+        }
+    }
+    if (callback) {
+        this.once("listening", callback);
+    }
+
+    let server;
+    try {
+        server = native.call("httpListen", port, host);
+    } catch (error) {
+        setTimeout(() => this.emit("error", error), 0);
+        return this;
+    }
+    this._id = server.id;
+    this._port = server.port;
+    this._host = host;
+    this.listening = true;
+    setTimeout(() => this.emit("listening"), 0);
+    setTimeout(() => this._poll(), 0);
+    return this;
+};
+
+Server.prototype._poll = function () {
+    if (!this.listening) {
+        return;
+    }
+    const raw = native.call("httpAccept", this._id, POLL_INTERVAL);
+    if (raw) {
+        const request = new IncomingMessage(raw);
+        const response = new ServerResponse(raw.id, request);
+        try {
+            this.emit("request", request, response);
+        } catch (error) {
+            if (!response.writableEnded) {
+                response.statusCode = 500;
+                response.end();
+            }
+            throw error;
+        }
+    }
+    setTimeout(() => this._poll(), 0);
+};
+
+Server.prototype.address = function () {
+    if (!this.listening) {
+        return null;
+    }
+    return { address: this._host, family: this._host.includes(":") ? "IPv6" : "IPv4", port: this._port };
+};
+
+Server.prototype.close = function (callback) {
+    if (this.listening) {
+        native.call("httpClose", this._id);
+        this.listening = false;
+    }
+    setTimeout(() => {
+        this.emit("close");
+        if (typeof callback === "function") {
+            callback();
+        }
+    }, 0);
+    return this;
+};
+
+function createServer(options, requestListener) {
+    return new Server(options, requestListener);
+}
+
+export { createServer, Server, IncomingMessage, ServerResponse, STATUS_CODES, METHODS };
+export default { createServer, Server, IncomingMessage, ServerResponse, STATUS_CODES, METHODS };
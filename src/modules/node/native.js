@@ -0,0 +1,19 @@
+// Native side of the Node.js compatibility layer.
+//
+// `__lunos_node.call(name, ...args)` runs a native function from node.rs.
+// Failures are thrown from the native side as Node-style errors carrying
+// `code`, `errno`, `syscall` and `path`.
+(function (global) {
+    "use strict";
+
+    const nativeCall = global.__lunos_node_call;
+
+    function call(name, ...args) {
+        return JSON.parse(nativeCall(name, JSON.stringify(args)));
+    }
+
+    Object.defineProperty(global, "__lunos_node", {
+        value: Object.freeze({ call }),
+        enumerable: false,
+    });
+})(globalThis);
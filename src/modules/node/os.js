@@ -0,0 +1,131 @@
+// node:os
+
+const native = __lunos_node;
+
+let info;
+// fixed for the life of the process, except for memory and uptime
+function system() {
+    if (!info) {
+        info = native.call("os");
+    }
+    return info;
+}
+
+const EOL = system().platform === "win32" ? "\r\n" : "\n";
+const devNull = system().platform === "win32" ? "\\\\.\\nul" : "/dev/null";
+
+function platform() {
+    return system().platform;
+}
+
+function arch() {
+    return system().arch;
+}
+
+function type() {
+    return { linux: "Linux", darwin: "Darwin", win32: "Windows_NT" }[platform()] || platform();
+}
+
+function release() {
+    return system().release;
+}
+
+function hostname() {
+    return system().hostname;
+}
+
+function homedir() {
+    return system().homedir;
+}
+
+function tmpdir() {
+    return system().tmpdir.replace(/(.)\/+$/, "$1");
+}
+
+function endianness() {
+    return new Uint8Array(new Uint16Array([1]).buffer)[0] === 1 ? "LE" : "BE";
+}
+
+function cpus() {
+    return Array.from({ length: system().cpus }, () => ({
+        model: "unknown",
+        speed: 0,
+        times: { user: 0, nice: 0, sys: 0, idle: 0, irq: 0 },
+    }));
+}
+
+function availableParallelism() {
+    return system().cpus;
+}
+
+function totalmem() {
+    return native.call("os").totalmem;
+}
+
+function freemem() {
+    return native.call("os").freemem;
+}
+
+function uptime() {
+    return native.call("os").uptime;
+}
+
+function loadavg() {
+    return [0, 0, 0];
+}
+
+function userInfo() {
+    const env = native.call("env");
+    return {
+        username: env.USER || env.USERNAME || "",
+        uid: -1,
+        gid: -1,
+        shell: env.SHELL || null,
+        homedir: homedir(),
+    };
+}
+
+function networkInterfaces() {
+    return {};
+}
+
+export {
+    EOL,
+    devNull,
+    platform,
+    arch,
+    type,
+    release,
+    hostname,
+    homedir,
+    tmpdir,
+    endianness,
+    cpus,
+    availableParallelism,
+    totalmem,
+    freemem,
+    uptime,
+    loadavg,
+    userInfo,
+    networkInterfaces,
+};
+export default {
+    EOL,
+    devNull,
+    platform,
+    arch,
+    type,
+    release,
+    hostname,
+    homedir,
+    tmpdir,
+    endianness,
+    cpus,
+    availableParallelism,
+    totalmem,
+    freemem,
+    uptime,
+    loadavg,
+    userInfo,
+    networkInterfaces,
+};
@@ -0,0 +1,201 @@
+// node:path, the POSIX flavour. `path.win32` isn't provided.
+
+function assertPath(path, name = "path") {
+    if (typeof path !== "string") {
+        const error = new TypeError(`The "${name}" argument must be of type string. Received ${typeof path}`);
+        error.code = "ERR_INVALID_ARG_TYPE";
+        throw error;
+    }
+}
+
+// Resolves `.` and `..` segments, dropping the ones that would climb above
+// the root of an absolute path.
+function normalizeSegments(path, allowAboveRoot) {
+    const out = [];
+    for (const segment of path.split("/")) {
+        if (segment === "" || segment === ".") {
+            continue;
+        }
+        if (segment === "..") {
+            if (out.length > 0 && out[out.length - 1] !== "..") {
+                out.pop();
+            } else if (allowAboveRoot) {
+                out.push("..");
+            }
+            continue;
+        }
+        out.push(segment);
+    }
+    return out.join("/");
+}
+
+const sep = "/";
+const delimiter = ":";
+
+function isAbsolute(path) {
+    assertPath(path);
+    return path.startsWith("/");
+}
+
+function normalize(path) {
+    assertPath(path);
+    if (path === "") {
+        return ".";
+    }
+    const absolute = path.startsWith("/");
+    const trailing = path.endsWith("/");
+    let normalized = normalizeSegments(path, !absolute);
+    if (normalized === "" && !absolute) {
+        normalized = ".";
+    }
+    if (normalized !== "" && trailing) {
+        normalized += "/";
+    }
+    return absolute ? "/" + normalized : normalized;
+}
+
+function join(...paths) {
+    const parts = paths.filter((part) => {
+        assertPath(part);
+        return part !== "";
+    });
+    return parts.length === 0 ? "." : normalize(parts.join("/"));
+}
+
+function resolve(...paths) {
+    let resolved = "";
+    for (let i = paths.length - 1; i >= 0 && !resolved.startsWith("/"); i--) {
+        assertPath(paths[i], `paths[${i}]`);
+        if (paths[i] !== "") {
+            resolved = resolved === "" ? paths[i] : `${paths[i]}/${resolved}`;
+        }
+    }
+    if (!resolved.startsWith("/")) {
+        const cwd = __lunos_node.call("cwd");
+        resolved = resolved === "" ? cwd : `${cwd}/${resolved}`;
+    }
+    return "/" + normalizeSegments(resolved, false);
+}
+
+function relative(from, to) {
+    assertPath(from, "from");
+    assertPath(to, "to");
+    if (from === to) {
+        return "";
+    }
+    const fromParts = resolve(from).split("/").filter(Boolean);
+    const toParts = resolve(to).split("/").filter(Boolean);
+    let common = 0;
+    while (common < fromParts.length && common < toParts.length && fromParts[common] === toParts[common]) {
+        common++;
+    }
+    const up = fromParts.slice(common).map(() => "..");
+    return up.concat(toParts.slice(common)).join("/");
+}
+
+function dirname(path) {
+    assertPath(path);
+    if (path === "") {
+        return ".";
+    }
+    const trimmed = path.replace(/\/+$/, "");
+    if (trimmed === "") {
+        return "/";
+    }
+    const index = trimmed.lastIndexOf("/");
+    if (index === -1) {
+        return ".";
+    }
+    const dir = trimmed.slice(0, index).replace(/\/+$/, "");
+    return dir === "" ? "/" : dir;
+}
+
+function basename(path, suffix) {
+    assertPath(path);
+    const trimmed = path.replace(/\/+$/, "");
+    let base = trimmed.slice(trimmed.lastIndexOf("/") + 1);
+    if (suffix !== undefined && base !== suffix && base.endsWith(suffix)) {
+        base = base.slice(0, base.length - suffix.length);
+    }
+    return base;
+}
+
+function extname(path) {
+    assertPath(path);
+    const base = basename(path);
+    const index = base.lastIndexOf(".");
+    if (index <= 0 || (base === ".." && index === 1)) {
+        return "";
+    }
+    return base.slice(index);
+}
+
+function parse(path) {
+    assertPath(path);
+    const root = path.startsWith("/") ? "/" : "";
+    if (path === "" || path === root) {
+        return { root, dir: root, base: "", ext: "", name: "" };
+    }
+    const base = basename(path);
+    const ext = extname(path);
+    const trimmed = path.replace(/\/+$/, "");
+    const index = trimmed.lastIndexOf("/");
+    let dir = index === -1 ? "" : trimmed.slice(0, index);
+    if (dir === "" && root) {
+        dir = "/";
+    }
+    return { root, dir, base, ext, name: base.slice(0, base.length - ext.length) };
+}
+
+function format(object) {
+    if (object === null || typeof object !== "object") {
+        const error = new TypeError(`The "pathObject" argument must be of type object. Received ${object === null ? "null" : typeof object}`);
+        error.code = "ERR_INVALID_ARG_TYPE";
+        throw error;
+    }
+    const dir = object.dir || object.root;
+    const base = object.base || `${object.name || ""}${object.ext ? (object.ext.startsWith(".") ? "" : ".") + object.ext : ""}`;
+    if (!dir) {
+        return base;
+    }
+    return dir === object.root ? `${dir}${base}` : `${dir}/${base}`;
+}
+
+function toNamespacedPath(path) {
+    return path;
+}
+
+const posix = {
+    sep,
+    delimiter,
+    isAbsolute,
+    normalize,
+    join,
+    resolve,
+    relative,
+    dirname,
+    basename,
+    extname,
+    parse,
+    format,
+    toNamespacedPath,
+};
+posix.posix = posix;
+
+export {
+    sep,
+    delimiter,
+    isAbsolute,
+    normalize,
+    join,
+    resolve,
+    relative,
+    dirname,
+    basename,
+    extname,
+    parse,
+    format,
+    toNamespacedPath,
+    posix,
+};
+export default posix;
@@ -0,0 +1,124 @@
+// node:process
+
+import { Buffer } from "node:buffer";
+import EventEmitter from "node:events";
+
+const native = __lunos_node;
+const started = Date.now();
+
+function stream(fd) {
+    return {
+        fd,
+        isTTY: false,
+        write(chunk, encoding, callback) {
+            // keep the order with console output, which is buffered
+            console.flush();
+            const text = typeof chunk === "string" ? chunk : Buffer.from(chunk).toString();
+            native.call("write", fd, text);
+            const done = typeof encoding === "function" ? encoding : callback;
+            if (typeof done === "function") {
+                setTimeout(done, 0);
+            }
+            return true;
+        },
+        on() {
+            return this;
+        },
+        once() {
+            return this;
+        },
+        end() {},
+    };
+}
+
+function hrtime(previous) {
+    const now = Date.now();
+    let seconds = Math.floor(now / 1000);
+    let nanoseconds = Math.floor((now % 1000) * 1e6);
+    if (previous) {
+        seconds -= previous[0];
+        nanoseconds -= previous[1];
+        if (nanoseconds < 0) {
+            seconds -= 1;
+            nanoseconds += 1e9;
+        }
+    }
+    return [seconds, nanoseconds];
+}
+hrtime.bigint = () => BigInt(Date.now()) * 1000000n;
+
+const process = Object.create(EventEmitter.prototype);
+EventEmitter.init.call(process);
+
+const system = native.call("os");
+const argv = native.call("argv");
+
+Object.assign(process, {
+    title: "lunos",
+    argv,
+    argv0: "lunos",
+    execArgv: [],
+    execPath: argv[0],
+    env: native.call("env"),
+    pid: system.pid,
+    ppid: 0,
+    platform: system.platform,
+    arch: system.arch,
+    version: "v20.0.0",
+    versions: { node: "20.0.0", lunos: system.version },
+    release: { name: "node" },
+    exitCode: undefined,
+    stdout: stream(1),
+    stderr: stream(2),
+    cwd: () => native.call("cwd"),
+    chdir: (directory) => {
+        native.call("chdir", String(directory));
+    },
+    exit(code) {
+        const status = code === undefined ? process.exitCode || 0 : code;
+        process.emit("exit", status);
+        console.flush();
+        native.call("exit", Number(status) | 0);
+    },
+    nextTick(callback, ...args) {
+        Promise.resolve().then(() => callback(...args));
+    },
+    hrtime,
+    uptime: () => (Date.now() - started) / 1000,
+    memoryUsage: () => ({ rss: 0, heapTotal: 0, heapUsed: 0, external: 0, arrayBuffers: 0 }),
+    cpuUsage: () => ({ user: 0, system: 0 }),
+    emitWarning: (warning) => console.warn(`Warning: ${warning instanceof Error ? warning.message : warning}`),
+});
+
+// Node's `unhandledRejection` and `rejectionHandled`, from the global
+// events. A listener for `unhandledRejection` handles the rejection.
+globalThis.addEventListener("unhandledrejection", (event) => {
+    if (process.listenerCount("unhandledRejection") > 0) {
+        event.preventDefault();
+        process.emit("unhandledRejection", event.reason, event.promise);
+    }
+});
+globalThis.addEventListener("rejectionhandled", (event) => {
+    process.emit("rejectionHandled", event.promise);
+});
+
+export const {
+    title,
+    env,
+    pid,
+    platform,
+    arch,
+    version,
+    versions,
+    stdout,
+    stderr,
+    cwd,
+    chdir,
+    exit,
+    nextTick,
+    uptime,
+    memoryUsage,
+    emitWarning,
+} = process;
+export { argv, hrtime };
+export default process;
@@ -0,0 +1,265 @@
+// node:util
+
+const customInspect = Symbol.for("nodejs.util.inspect.custom");
+const customPromisify = Symbol.for("nodejs.util.promisify.custom");
+
+function isPlainIdentifier(key) {
+    return /^[A-Za-z_$][\w$]*$/.test(key);
+}
+
+function inspectValue(value, options, depth, seen) {
+    switch (typeof value) {
+        case "string":
+            return `'${value.replace(/\\/g, "\\\\").replace(/'/g, "\\'").replace(/\n/g, "\\n")}'`;
+        case "number":
+            return Object.is(value, -0) ? "-0" : String(value);
+        case "bigint":
+            return `${value}n`;
+        case "symbol":
+            return value.toString();
+        case "undefined":
+            return "undefined";
+        case "boolean":
+            return String(value);
+        case "function": {
+            const name = value.name ? `: ${value.name}` : " (anonymous)";
+            const kind = /^class\s/.test(Function.prototype.toString.call(value)) ? "class" : "Function";
+            return kind === "class" ? `[class ${value.name || "(anonymous)"}]` : `[${kind}${name}]`;
+        }
+    }
+    if (value === null) {
+        return "null";
+    }
+    if (typeof value[customInspect] === "function" && value[customInspect] !== inspect) {
+        return String(value[customInspect](options.depth - depth, options, inspect));
+    }
+    if (seen.includes(value)) {
+        return "[Circular *1]";
+    }
+    if (value instanceof Date) {
+        return Number.isNaN(value.getTime()) ? "Invalid Date" : value.toISOString();
+    }
+    if (value instanceof RegExp) {
+        return String(value);
+    }
+    if (value instanceof Error) {
+        return value.stack || `${value.name}: ${value.message}`;
+    }
+    if (value instanceof Promise) {
+        return "Promise { <unknown> }";
+    }
+    if (depth > options.depth) {
+        return Array.isArray(value) ? "[Array]" : "[Object]";
+    }
+
+    const next = seen.concat([value]);
+    const inner = (item) => inspectValue(item, options, depth + 1, next);
+    let entries;
+    let open;
+    let close;
+
+    if (Array.isArray(value) || ArrayBuffer.isView(value)) {
+        entries = Array.from(value, inner);
+        if (value.length === 0) {
+            return ArrayBuffer.isView(value) ? `${value.constructor.name}(0) []` : "[]";
+        }
+        open = ArrayBuffer.isView(value) ? `${value.constructor.name}(${value.length}) [` : "[";
+        close = "]";
+    } else if (value instanceof Map) {
+        entries = Array.from(value, ([key, item]) => `${inner(key)} => ${inner(item)}`);
+        open = `Map(${value.size}) {`;
+        close = "}";
+    } else if (value instanceof Set) {
+        entries = Array.from(value, inner);
+        open = `Set(${value.size}) {`;
+        close = "}";
+    } else {
+        entries = Object.keys(value).map((key) => `${isPlainIdentifier(key) ? key : `'${key}'`}: ${inner(value[key])}`);
+        const prototype = Object.getPrototypeOf(value);
+        const name = prototype === null
+            ? "[Object: null prototype] "
+            : prototype.constructor && prototype.constructor.name !== "Object"
+                ? `${prototype.constructor.name} `
+                : "";
+        if (entries.length === 0) {
+            return `${name}{}`;
+        }
+        open = `${name}{`;
+        close = "}";
+    }
+
+    const single = `${open} ${entries.join(", ")} ${close}`;
+    if (single.length <= options.breakLength) {
+        return single;
+    }
+    const indent = "  ".repeat(depth + 1);
+    return `${open}\n${entries.map((entry) => indent + entry).join(",\n")}\n${"  ".repeat(depth)}${close}`;
+}
+
+function inspect(value, options) {
+    const settings = { depth: 2, breakLength: 80, ...(typeof options === "object" ? options : {}) };
+    if (settings.depth === null) {
+        settings.depth = Infinity;
+    }
+    return inspectValue(value, settings, 0, []);
+}
+inspect.custom = customInspect;
+
+function format(first, ...args) {
+    if (typeof first !== "string") {
+        return [first, ...args].map((value) => (typeof value === "string" ? value : inspect(value))).join(" ");
+    }
+    let index = 0;
+    let out = first.replace(/%([sdifjoOc%])/g, (match, type) => {
+        if (type === "%") {
+            return "%";
+        }
+        if (index >= args.length) {
+            return match;
+        }
+        const value = args[index++];
+        switch (type) {
+            case "s":
+                return typeof value === "string" ? value : typeof value === "object" && value !== null ? inspect(value, { depth: 0 }) : String(value);
+            case "d":
+            case "i": {
+                const number = type === "i" ? parseInt(value) : Number(value);
+                return typeof value === "bigint" ? `${value}n` : String(number);
+            }
+            case "f":
+                return String(parseFloat(value));
+            case "j":
+                try {
+                    return JSON.stringify(value);
+                } catch {
+                    return "[Circular]";
+                }
+            case "o":
+            case "O":
+                return inspect(value);
+            default:
+                return "";
+        }
+    });
+    for (const value of args.slice(index)) {
+        out += " " + (typeof value === "string" ? value : inspect(value));
+    }
+    return out;
+}
+
+function promisify(original) {
+    if (typeof original !== "function") {
+        const error = new TypeError('The "original" argument must be of type function');
+        error.code = "ERR_INVALID_ARG_TYPE";
+        throw error;
+    }
+    if (original[customPromisify]) {
+        return original[customPromisify];
+    }
+    function promisified(...args) {
+        return new Promise((resolve, reject) => {
+            original.call(this, ...args, (error, value) => (error ? reject(error) : resolve(value)));
+        });
+    }
+    Object.setPrototypeOf(promisified, Object.getPrototypeOf(original));
+    return Object.defineProperties(promisified, Object.getOwnPropertyDescriptors(original));
+}
+promisify.custom = customPromisify;
+
+function callbackify(original) {
+    return function (...args) {
+        const callback = args.pop();
+        original.apply(this, args).then(
+            (value) => setTimeout(callback, 0, null, value),
+            (error) => setTimeout(callback, 0, error),
+        );
+    };
+}
+
+function inherits(constructor, superConstructor) {
+    Object.defineProperty(constructor, "super_", {
+        value: superConstructor,
+        writable: true,
+        configurable: true,
+    });
+    Object.setPrototypeOf(constructor.prototype, superConstructor.prototype);
+}
+
+function deprecate(fn, message, code) {
+    let warned = false;
+    return function (...args) {
+        if (!warned) {
+            warned = true;
+            console.warn(`[${code || "DEP0000"}] DeprecationWarning: ${message}`);
+        }
+        return new.target ? Reflect.construct(fn, args, new.target) : fn.apply(this, args);
+    };
+}
+
+function debuglog(section) {
+    const enabled = new RegExp(`(^|,)${section}($|,)`, "i").test(__lunos_node.call("env").NODE_DEBUG || "");
+    return enabled
+        ? (...args) => console.error(`${section.toUpperCase()}: ${format(...args)}`)
+        : () => {};
+}
+
+function isDeepStrictEqual(a, b) {
+    if (Object.is(a, b)) {
+        return true;
+    }
+    if (typeof a !== "object" || typeof b !== "object" || a === null || b === null) {
+        return false;
+    }
+    if (Object.getPrototypeOf(a) !== Object.getPrototypeOf(b)) {
+        return false;
+    }
+    if (a instanceof Date) {
+        return a.getTime() === b.getTime();
+    }
+    if (a instanceof Map || a instanceof Set) {
+        return a.size === b.size && isDeepStrictEqual(Array.from(a), Array.from(b));
+    }
+    const keysA = Object.keys(a);
+    const keysB = Object.keys(b);
+    return keysA.length === keysB.length
+        && keysA.every((key) => Object.prototype.hasOwnProperty.call(b, key) && isDeepStrictEqual(a[key], b[key]));
+}
+
+const types = {
+    isPromise: (value) => value instanceof Promise,
+    isRegExp: (value) => value instanceof RegExp,
+    isDate: (value) => value instanceof Date,
+    isMap: (value) => value instanceof Map,
+    isSet: (value) => value instanceof Set,
+    isNativeError: (value) => value instanceof Error,
+    isTypedArray: (value) => ArrayBuffer.isView(value) && !(value instanceof DataView),
+    isUint8Array: (value) => value instanceof Uint8Array,
+    isAsyncFunction: (value) => typeof value === "function" && value.constructor && value.constructor.name === "AsyncFunction",
+};
+
+const isArray = Array.isArray;
+
+export {
+    format,
+    inspect,
+    promisify,
+    callbackify,
+    inherits,
+    deprecate,
+    debuglog,
+    isDeepStrictEqual,
+    types,
+    isArray,
+};
+export default {
+    format,
+    inspect,
+    promisify,
+    callbackify,
+    inherits,
+    deprecate,
+    debuglog,
+    isDeepStrictEqual,
+    types,
+    isArray,
+};
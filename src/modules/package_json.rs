@@ -0,0 +1,66 @@
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The fields of a `package.json` that take part in module resolution.
+pub(crate) struct PackageJson {
+    pub dir: PathBuf,
+    pub name: Option<String>,
+    pub main: Option<String>,
+    pub module: Option<String>,
+    pub exports: Option<Value>,
+    pub imports: Option<Value>,
+}
+
+impl PackageJson {
+    pub fn read(dir: &Path) -> Option<PackageJson> {
+        let contents = fs::read_to_string(dir.join("package.json")).ok()?;
+        let Value::Object(mut json) = serde_json::from_str(&contents).ok()? else {
+            return None;
+        };
+
+        let mut string_field = |name: &str| match json.remove(name) {
+            Some(Value::String(value)) if !value.is_empty() => Some(value),
+            _ => None,
+        };
+        let name = string_field("name");
+        let main = string_field("main");
+        let module = string_field("module");
+
+        Some(PackageJson {
+            dir: dir.to_path_buf(),
+            name,
+            main,
+            module,
+            exports: json.remove("exports").filter(|v| !v.is_null()),
+            imports: json.remove("imports").filter(|v| !v.is_null()),
+        })
+    }
+
+    /// The closest `package.json` at or above `start`, without crossing into
+    /// a parent `node_modules` directory.
+    pub fn find_scope(start: &Path) -> Option<PackageJson> {
+        let mut dir = start.to_path_buf();
+        loop {
+            if dir.join("package.json").is_file() {
+                return PackageJson::read(&dir);
+            }
+            if dir.file_name().is_some_and(|name| name == "node_modules") || !dir.pop() {
+                return None;
+            }
+        }
+    }
+
+    /// The `exports` field normalized to a subpath map: a bare string, array or
+    /// conditions object is shorthand for `{ ".": ... }`.
+    pub fn exports_map(&self) -> Option<Map<String, Value>> {
+        match self.exports.as_ref()? {
+            Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => Some(map.clone()),
+            other => {
+                let mut map = Map::new();
+                map.insert(".".to_string(), other.clone());
+                Some(map)
+            }
+        }
+    }
+}
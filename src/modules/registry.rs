@@ -0,0 +1,59 @@
+use super::builtins::NativeModule;
+use super::import_map::ImportMap;
+use super::remote::LockFile;
+use super::source_map::SourceMaps;
+use super::tsconfig::TsConfigs;
+use once_cell::sync::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The modules a runtime has defined, and what it resolves new ones with.
+/// Modules are resolved in parallel, so it's shared across threads while a
+/// graph loads.
+pub(crate) struct Registry {
+    /// Every module defined in the context so far, keyed by id, with its
+    /// export names when they are known statically (ES modules only).
+    pub defined: Mutex<HashMap<String, Option<Vec<String>>>>,
+    /// Ids of the defined modules that evaluate asynchronously.
+    pub async_ids: Mutex<HashSet<String>>,
+    /// The modules of native functions the runtime was built with.
+    pub natives: Vec<NativeModule>,
+    /// The directory of the project the entry modules belong to. What it
+    /// configures is installed before the first one loads, see
+    /// `Runtime::evaluate_module`.
+    pub project: OnceCell<PathBuf>,
+    pub import_map: OnceCell<ImportMap>,
+    pub extensions: OnceCell<Vec<String>>,
+    pub lock: OnceCell<Mutex<LockFile>>,
+    pub tsconfigs: TsConfigs,
+    pub source_maps: SourceMaps,
+    /// Whether the `lunos:` and `node:` modules resolve, as the runtime was
+    /// built with them.
+    pub lunos: bool,
+    pub node: bool,
+    /// Only use URL imports that are already cached, never the network.
+    pub offline: bool,
+    /// Transform every module again instead of using the transform cache.
+    pub no_cache: bool,
+}
+
+impl Registry {
+    pub fn new(natives: Vec<NativeModule>) -> Registry {
+        Registry {
+            defined: Mutex::new(HashMap::new()),
+            async_ids: Mutex::new(HashSet::new()),
+            natives,
+            project: OnceCell::new(),
+            import_map: OnceCell::new(),
+            extensions: OnceCell::new(),
+            lock: OnceCell::new(),
+            tsconfigs: TsConfigs::default(),
+            source_maps: SourceMaps::default(),
+            lunos: true,
+            node: true,
+            offline: false,
+            no_cache: false,
+        }
+    }
+}
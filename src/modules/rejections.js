@@ -0,0 +1,181 @@
+// Lunos promise rejection tracking.
+//
+// JavaScriptCore calls `track` with every promise that got rejected without
+// a handler, once the microtasks of a turn have run. At the end of each
+// event loop turn `flush` fires an `unhandledrejection` event on the global
+// object for each of them, and returns the reasons no listener prevented,
+// for Lunos to report. A promise that gets a handler after it was reported
+// fires `rejectionhandled` at the end of the next turn.
+(function (global) {
+    "use strict";
+
+    const listeners = new Map();
+    // rejected promises without a handler, by the order they were rejected
+    const pending = new Map();
+    const handled = [];
+
+    if (typeof global.Event !== "function") {
+        class Event {
+            constructor(type, init = {}) {
+                this.type = String(type);
+                this.cancelable = Boolean(init.cancelable);
+                this.defaultPrevented = false;
+                this.timeStamp = Date.now();
+            }
+
+            preventDefault() {
+                if (this.cancelable) {
+                    this.defaultPrevented = true;
+                }
+            }
+        }
+        Object.defineProperty(global, "Event", { value: Event, writable: true, configurable: true });
+    }
+
+    class PromiseRejectionEvent extends global.Event {
+        constructor(type, init) {
+            super(type, init);
+            this.promise = init.promise;
+            this.reason = init.reason;
+        }
+    }
+
+    function addEventListener(type, listener, options) {
+        if (typeof listener !== "function" && !(listener && typeof listener.handleEvent === "function")) {
+            return;
+        }
+        const once = typeof options === "object" && options !== null && Boolean(options.once);
+        const entries = listeners.get(type) || [];
+        if (!entries.some((entry) => entry.listener === listener)) {
+            entries.push({ listener, once });
+        }
+        listeners.set(type, entries);
+    }
+
+    function removeEventListener(type, listener) {
+        const entries = listeners.get(type);
+        if (entries) {
+            listeners.set(type, entries.filter((entry) => entry.listener !== listener));
+        }
+    }
+
+    // A listener that throws doesn't keep the others from running, what it
+    // threw is thrown once they have.
+    function dispatchEvent(event) {
+        let thrown;
+        for (const entry of [...(listeners.get(event.type) || [])]) {
+            if (entry.once) {
+                removeEventListener(event.type, entry.listener);
+            }
+            try {
+                if (typeof entry.listener === "function") {
+                    entry.listener.call(global, event);
+                } else {
+                    entry.listener.handleEvent(event);
+                }
+            } catch (error) {
+                thrown = thrown || { error };
+            }
+        }
+        if (thrown) {
+            throw thrown.error;
+        }
+        return !event.defaultPrevented;
+    }
+
+    for (const [name, value] of Object.entries({ addEventListener, removeEventListener, dispatchEvent })) {
+        if (typeof global[name] !== "function") {
+            Object.defineProperty(global, name, { value, writable: true, configurable: true });
+        }
+    }
+    if (typeof global.PromiseRejectionEvent !== "function") {
+        Object.defineProperty(global, "PromiseRejectionEvent", {
+            value: PromiseRejectionEvent,
+            writable: true,
+            configurable: true,
+        });
+    }
+
+    // JavaScriptCore only reports promises rejected without a handler, not
+    // when one gets a handler later, so that's told from the `constructor`
+    // lookup attaching a handler takes: `then` does it for its species, and
+    // `await` to tell whether it's a promise of its own. A reported promise
+    // gets a prototype of its own until then, between it and
+    // `Promise.prototype`, with a `constructor` accessor finding the same
+    // value. Lookups by `Promise.resolve` and the combinators only compare
+    // it, they're told apart by the function making them and don't count
+    // (the combinators then call `then`, which does). A lookup from script
+    // code counts, as that's what an `await` looks like, so a plain read of
+    // `promise.constructor` counts as a handler too.
+    const COMPARING = new Set(["resolve", "promiseResolve", "all", "allSettled", "any", "race"]);
+
+    function lookedUpByCompare() {
+        // this function, the accessor, then whoever made the lookup, as
+        // `name@[native code]` or `at Function.name (<anonymous>)`
+        const frames = String(new Error().stack)
+            .split("\n")
+            .map((line) => line.trim())
+            .filter((line) => line !== "" && line !== "Error");
+        const caller = frames[2] || "";
+        if (!/\[native code\]|\(<anonymous>\)$/.test(caller)) {
+            return false;
+        }
+        const name = /^at (?:\S+\.)?(\S+) \(/.exec(caller) || /^([^@]*)@/.exec(caller);
+        return name !== null && COMPARING.has(name[1]);
+    }
+
+    function watchHandlers(promise, reason) {
+        const prototype = Object.getPrototypeOf(promise);
+        const watching = Object.create(prototype, {
+            constructor: {
+                get() {
+                    if (!lookedUpByCompare()) {
+                        unwatch();
+                        handled.push([promise, reason]);
+                    }
+                    return Reflect.get(prototype, "constructor", promise);
+                },
+                set(value) {
+                    unwatch();
+                    promise.constructor = value;
+                },
+            },
+        });
+        const unwatch = () => {
+            if (Object.getPrototypeOf(promise) === watching) {
+                Object.setPrototypeOf(promise, prototype);
+            }
+        };
+        // a frozen promise can't be watched
+        Reflect.setPrototypeOf(promise, watching);
+    }
+
+    function track(promise, reason) {
+        pending.set(promise, reason);
+    }
+
+    // `null` once there's nothing left to do for this turn.
+    function flush() {
+        if (pending.size === 0 && handled.length === 0) {
+            return null;
+        }
+        for (const [promise, reason] of handled.splice(0)) {
+            global.dispatchEvent(new PromiseRejectionEvent("rejectionhandled", { promise, reason }));
+        }
+        const unhandled = [];
+        for (const [promise, reason] of [...pending]) {
+            pending.delete(promise);
+            const event = new PromiseRejectionEvent("unhandledrejection", { promise, reason, cancelable: true });
+            const prevented = !global.dispatchEvent(event);
+            watchHandlers(promise, reason);
+            if (!prevented) {
+                unhandled.push(reason);
+            }
+        }
+        return unhandled;
+    }
+
+    Object.defineProperty(global, "__lunos_rejections", {
+        value: Object.freeze({ track, flush }),
+    });
+})(globalThis);
@@ -0,0 +1,64 @@
+use super::convert::JsValue;
+use super::es6::evaluate;
+use super::exception;
+use super::state::State;
+use crate::lunos::options::UnhandledRejections;
+use rusty_jsc::*;
+
+/// The tracker JavaScriptCore reports rejections nobody handled to, and the
+/// `unhandledrejection` and `rejectionhandled` events.
+const REJECTIONS_JS: &str = include_str!("rejections.js");
+
+pub struct Rejections;
+
+impl Rejections {
+    pub fn bind_to_context(context: *mut OpaqueJSContext) {
+        unsafe {
+            let track = match evaluate(context, REJECTIONS_JS)
+                .and_then(|_| evaluate(context, "__lunos_rejections.track"))
+            {
+                Ok(track) => JSValueToObject(context, track, std::ptr::null_mut()),
+                Err(_) => {
+                    eprintln!("Failed to initialize the promise rejection tracker");
+                    return;
+                }
+            };
+            let mut exception: *const OpaqueJSValue = std::ptr::null();
+            JSGlobalContextSetUnhandledRejectionCallback(context, track, &mut exception);
+            if !exception.is_null() {
+                eprintln!("Failed to initialize the promise rejection tracker");
+            }
+        }
+    }
+
+    /// Fires the events for the rejections of the turn that just ended, and
+    /// deals with those no listener handled the way the runtime was built
+    /// to, see `RuntimeBuilder::unhandled_rejections`. Fails with the first
+    /// of them in strict mode, or with what a listener threw.
+    pub(crate) fn check(context: *const OpaqueJSContext) -> Result<(), *const OpaqueJSValue> {
+        let state = State::of(context);
+        // a listener may reject more promises, which are known once its
+        // microtasks have run
+        loop {
+            let unhandled = unsafe { evaluate(context, "__lunos_rejections.flush()") }?;
+            if unsafe { JSValueIsNull(context, unhandled) } {
+                return Ok(());
+            }
+            let unhandled: Vec<JsValue> =
+                unsafe { JsValue(unhandled).get(context) }.unwrap_or_default();
+            for reason in unhandled {
+                match state.unhandled_rejections {
+                    UnhandledRejections::Strict => return Err(reason.0),
+                    UnhandledRejections::Warn => unsafe {
+                        state.console.flush();
+                        eprint!(
+                            "Warning: Unhandled promise rejection: {}",
+                            exception::describe(context, reason.0)
+                        );
+                    },
+                    UnhandledRejections::None => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use super::import_map::{self, ImportMap, Mapped};
+use super::registry::Registry;
+use super::resolver::is_relative;
+use crate::lunos::{cache, constants::VERSION};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Integrity hashes of every URL import, next to the project's `lunos.json`
+/// (or the entry script). A module whose content no longer matches its hash
+/// fails to load.
+const LOCK_FILE: &str = "lunos.lock";
+const LOCK_VERSION: u64 = 1;
+
+pub(crate) struct LockFile {
+    path: PathBuf,
+    remote: BTreeMap<String, String>,
+}
+
+/// A downloaded module.
+pub(crate) struct Fetched {
+    /// The URL it was served from after redirects, its module id.
+    pub url: String,
+    pub bytes: Vec<u8>,
+}
+
+pub(crate) fn is_url(specifier: &str) -> bool {
+    specifier.starts_with("https://") || specifier.starts_with("http://")
+}
+
+/// Splits `url` into its origin, `https://host:port`, and the path after it.
+fn split_origin(url: &str) -> (&str, &str) {
+    let host_start = url.find("://").map_or(0, |i| i + 3);
+    match url[host_start..].find(['/', '?', '#']) {
+        Some(i) => url.split_at(host_start + i),
+        None => (url, ""),
+    }
+}
+
+/// Resolves `.` and `..` segments of an absolute URL path, keeping its query
+/// and fragment as they are.
+fn normalize_path(path: &str) -> String {
+    let (path, suffix) = path.split_at(path.find(['?', '#']).unwrap_or(path.len()));
+    let parts: Vec<&str> = path.split('/').skip(1).collect();
+    let mut segments = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i + 1 == parts.len();
+        match *part {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            part => {
+                segments.push(part);
+                continue;
+            }
+        }
+        // `./` and `../` at the end still name a directory
+        if last {
+            segments.push("");
+        }
+    }
+    format!("/{}{suffix}", segments.join("/"))
+}
+
+fn normalize(url: &str) -> String {
+    let (origin, path) = split_origin(url);
+    format!("{origin}{}", normalize_path(path))
+}
+
+/// Resolves `specifier` against the URL of the module importing it.
+pub(crate) fn join(base: &str, specifier: &str) -> String {
+    if is_url(specifier) {
+        return normalize(specifier);
+    }
+    let (origin, path) = split_origin(base);
+    if specifier.starts_with("//") {
+        let scheme = base.split_once(':').map_or("https", |(scheme, _)| scheme);
+        return join(base, &format!("{scheme}:{specifier}"));
+    }
+    if specifier.starts_with('/') {
+        return format!("{origin}{}", normalize_path(specifier));
+    }
+    let path = &path[..path.find(['?', '#']).unwrap_or(path.len())];
+    let dir = path.rfind('/').map_or("/", |i| &path[..=i]);
+    format!("{origin}{}", normalize_path(&format!("{dir}{specifier}")))
+}
+
+/// The URL `specifier` names when imported from `referrer`: a URL itself,
+/// a relative specifier in a module that was itself imported by URL, or
+/// anything the import map turns into a URL.
+pub(crate) fn resolve(registry: &Registry, referrer: &Path, specifier: &str) -> Option<String> {
+    let referrer = referrer.to_string_lossy();
+    if is_url(&referrer) && is_relative(specifier) {
+        return Some(join(&referrer, specifier));
+    }
+    let mapped = match ImportMap::map(registry, specifier, Path::new(referrer.as_ref())) {
+        Some(Mapped::Specifier(mapped)) => mapped,
+        Some(Mapped::Path(_)) => return None,
+        None => specifier.to_string(),
+    };
+    is_url(&mapped).then(|| normalize(&mapped))
+}
+
+/// Where downloaded modules are kept: each one's content under its hash, and
+/// for each requested URL which content and final URL it got.
+fn cache_dir() -> PathBuf {
+    cache::dir().join("remote")
+}
+
+fn index_path(url: &str) -> PathBuf {
+    cache_dir()
+        .join("urls")
+        .join(format!("{}.json", cache::hash(url.as_bytes())))
+}
+
+fn content_path(hash: &str) -> PathBuf {
+    cache_dir().join(hash)
+}
+
+/// A module from the cache and its hash, if `url` was downloaded before and
+/// its content is still intact.
+fn read_cached(url: &str) -> Option<(Fetched, String)> {
+    let index: Value = serde_json::from_str(&fs::read_to_string(index_path(url)).ok()?).ok()?;
+    let hash = index.get("hash")?.as_str()?;
+    let bytes = fs::read(content_path(hash)).ok()?;
+    if cache::hash(&bytes) != hash {
+        return None;
+    }
+    let fetched = Fetched {
+        url: index.get("final")?.as_str()?.to_string(),
+        bytes,
+    };
+    Some((fetched, hash.to_string()))
+}
+
+fn download(url: &str) -> Result<Fetched, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .user_agent(&format!("lunos/{VERSION}"))
+        .build();
+    let response = agent.get(url).call().map_err(|e| match e {
+        ureq::Error::Status(status, response) => {
+            format!(
+                "the server responded with {status} {}",
+                response.status_text()
+            )
+        }
+        ureq::Error::Transport(transport) => transport.to_string(),
+    })?;
+    let final_url = response.get_url().to_string();
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    Ok(Fetched {
+        url: final_url,
+        bytes,
+    })
+}
+
+/// Stores a download in the cache. Failing to is only worth a warning, the
+/// module can still be used.
+fn store(url: &str, fetched: &Fetched, hash: &str) {
+    let index = json!({ "url": url, "final": fetched.url, "hash": hash });
+    let stored = cache::write(&content_path(hash), &fetched.bytes)
+        .and_then(|_| cache::write(&index_path(url), index.to_string().as_bytes()));
+    if let Err(e) = stored {
+        eprintln!("Warning: could not cache {url}: {e}");
+    }
+}
+
+/// The module at `url`, from the cache or else downloaded into it, checked
+/// against the lock file.
+pub(crate) fn fetch(registry: &Registry, url: &str) -> Result<Fetched, String> {
+    let (fetched, hash) = match read_cached(url) {
+        Some(cached) => cached,
+        None if registry.offline => {
+            return Err("it isn't in the cache and --offline was given".to_string());
+        }
+        None => {
+            let fetched = download(url)?;
+            let hash = cache::hash(&fetched.bytes);
+            store(url, &fetched, &hash);
+            (fetched, hash)
+        }
+    };
+    LockFile::check(registry, url, &hash)?;
+    Ok(fetched)
+}
+
+impl LockFile {
+    /// Uses the lock file of the project `entry` belongs to, reading the
+    /// hashes it already has, for `registry`.
+    pub fn install(registry: &Registry, entry: &Path) -> Result<(), String> {
+        let dir = import_map::project_dir(entry.parent().unwrap_or(Path::new(".")));
+        let path = dir.join(LOCK_FILE);
+
+        let mut remote = BTreeMap::new();
+        if let Ok(contents) = fs::read_to_string(&path) {
+            let json: Value = serde_json::from_str(&contents)
+                .map_err(|e| format!("Error parsing {}: {e}", path.display()))?;
+            let entries = json.get("remote").and_then(Value::as_object);
+            for (url, hash) in entries.into_iter().flatten() {
+                let Some(hash) = hash.as_str() else {
+                    return Err(format!(
+                        "The hash of {url} in {} must be a string",
+                        path.display()
+                    ));
+                };
+                remote.insert(url.clone(), hash.to_string());
+            }
+        }
+        let _ = registry.lock.set(Mutex::new(LockFile { path, remote }));
+        Ok(())
+    }
+
+    /// Checks `hash` against the one locked for `url`, locking it if there
+    /// is none yet.
+    fn check(registry: &Registry, url: &str, hash: &str) -> Result<(), String> {
+        let Some(lock) = registry.lock.get() else {
+            return Ok(());
+        };
+        let mut lock = lock.lock().unwrap();
+        let hash = format!("sha256-{hash}");
+        match lock.remote.get(url) {
+            Some(locked) if *locked == hash => Ok(()),
+            Some(locked) => Err(format!(
+                "its integrity check failed, {} has {locked} but it is {hash}",
+                lock.path.display()
+            )),
+            None => {
+                lock.remote.insert(url.to_string(), hash);
+                lock.save();
+                Ok(())
+            }
+        }
+    }
+
+    fn save(&self) {
+        let remote: Map<String, Value> = self
+            .remote
+            .iter()
+            .map(|(url, hash)| (url.clone(), Value::String(hash.clone())))
+            .collect();
+        let json = json!({ "version": LOCK_VERSION, "remote": remote });
+        let contents = serde_json::to_string_pretty(&json).unwrap_or_default() + "\n";
+        if let Err(e) = fs::write(&self.path, contents) {
+            eprintln!("Warning: could not write {}: {e}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,671 @@
+use super::builtins;
+use super::import_map::{self, ImportMap, Mapped};
+use super::package_json::PackageJson;
+use super::registry::Registry;
+use super::remote;
+use super::source_map::SourceMap;
+use super::tsconfig::TsConfig;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The extensions tried, in order, for a path given without one and for the
+/// `index` file of a directory, unless `lunos.json` has its own
+/// `"extensions"`.
+const DEFAULT_EXTENSIONS: [&str; 7] = ["ts", "tsx", "mts", "js", "mjs", "cjs", "json"];
+
+/// The extension order in effect.
+fn extensions(registry: &Registry) -> &[String] {
+    registry
+        .extensions
+        .get_or_init(|| DEFAULT_EXTENSIONS.map(String::from).to_vec())
+}
+
+/// Uses the `"extensions"` of the `lunos.json` of the project `entry` belongs
+/// to, a list like `[".ts", ".js"]`, for all further resolution in
+/// `registry`.
+pub(crate) fn install_extensions(registry: &Registry, entry: &Path) -> Result<(), String> {
+    let entry_dir = entry.parent().unwrap_or(Path::new("."));
+    let Some(config_path) = import_map::find_project_config(entry_dir) else {
+        return Ok(());
+    };
+    let contents = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Error reading {}: {e}", config_path.display()))?;
+    let config: Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("Error parsing {}: {e}", config_path.display()))?;
+    let Some(list) = config.get("extensions") else {
+        return Ok(());
+    };
+    let invalid = || {
+        format!(
+            "\"extensions\" in {} must be a list of extensions",
+            config_path.display()
+        )
+    };
+    let list = list.as_array().ok_or_else(invalid)?;
+    let mut extensions = Vec::new();
+    for extension in list {
+        let extension = extension.as_str().ok_or_else(invalid)?;
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+        if extension.is_empty() || extension.contains('/') {
+            return Err(invalid());
+        }
+        extensions.push(extension.to_string());
+    }
+    let _ = registry.extensions.set(extensions);
+    Ok(())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ResolveMode {
+    Import,
+    Require,
+}
+
+impl ResolveMode {
+    /// Conditions matched against `exports`/`imports` targets, in addition to
+    /// `default` which always matches.
+    fn conditions(self) -> &'static [&'static str] {
+        match self {
+            ResolveMode::Import => &["lunos", "node", "import"],
+            ResolveMode::Require => &["lunos", "node", "require"],
+        }
+    }
+}
+
+pub(crate) fn is_relative(specifier: &str) -> bool {
+    specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || specifier == "."
+        || specifier == ".."
+        || specifier.starts_with('/')
+}
+
+/// The `node_modules` directories in `start_dir` and above it, nearest
+/// first. There are none for a directory that doesn't exist, such as that of
+/// a module imported by URL.
+fn find_node_modules(start_dir: &Path) -> Vec<PathBuf> {
+    let mut current_dir = start_dir.to_path_buf();
+    if !current_dir.is_dir() {
+        return Vec::new();
+    }
+
+    let mut found = Vec::new();
+    loop {
+        let node_modules = current_dir.join("node_modules");
+        if node_modules.is_dir() {
+            found.push(node_modules);
+        }
+        if !current_dir.pop() {
+            break;
+        }
+    }
+
+    found
+}
+
+/// Splits a bare specifier into its package name (`lib` or `@org/lib`) and
+/// the subpath inside the package (`.` or `./sub/path`).
+fn split_package_specifier(specifier: &str) -> Option<(&str, String)> {
+    let name_end = if specifier.starts_with('@') {
+        let scope_end = specifier.find('/')?;
+        specifier[scope_end + 1..]
+            .find('/')
+            .map_or(specifier.len(), |i| scope_end + 1 + i)
+    } else {
+        specifier.find('/').unwrap_or(specifier.len())
+    };
+
+    let name = &specifier[..name_end];
+    if name.is_empty() || name.ends_with('/') || name.starts_with('.') {
+        return None;
+    }
+    Some((name, format!(".{}", &specifier[name_end..])))
+}
+
+/// The legacy entry point of a package without `exports`: `module` when
+/// importing, then `main`, then `index.*`.
+fn resolve_package_main(
+    registry: &Registry,
+    package_dir: &Path,
+    package: Option<&PackageJson>,
+    mode: ResolveMode,
+) -> Option<PathBuf> {
+    let entries = package.into_iter().flat_map(|package| {
+        let module = package
+            .module
+            .as_ref()
+            .filter(|_| mode == ResolveMode::Import);
+        module.into_iter().chain(package.main.as_ref())
+    });
+
+    for entry in entries {
+        let entry_path = package_dir.join(entry);
+        if let Some(found) =
+            resolve_file(registry, &entry_path).or_else(|| resolve_index(registry, &entry_path))
+        {
+            return Some(found);
+        }
+    }
+
+    resolve_index(registry, package_dir)
+}
+
+/// Node's LOAD_AS_FILE: the exact path, then the path with each extension.
+/// Like TypeScript, a `.js`, `.mjs` or `.cjs` path that doesn't exist also
+/// finds the TypeScript module it's compiled from.
+fn resolve_file(registry: &Registry, path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+
+    let sources: &[&str] = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("js") => &["ts", "tsx"],
+        Some("jsx") => &["tsx"],
+        Some("mjs") => &["mts"],
+        Some("cjs") => &["cts"],
+        _ => &[],
+    };
+    let source = sources.iter().find_map(|ext| {
+        let candidate = path.with_extension(ext);
+        candidate.is_file().then_some(candidate)
+    });
+    source.or_else(|| {
+        extensions(registry).iter().find_map(|ext| {
+            let mut candidate = path.as_os_str().to_owned();
+            candidate.push(format!(".{ext}"));
+            let candidate = PathBuf::from(candidate);
+            candidate.is_file().then_some(candidate)
+        })
+    })
+}
+
+fn resolve_index(registry: &Registry, dir: &Path) -> Option<PathBuf> {
+    if !dir.is_dir() {
+        return None;
+    }
+
+    extensions(registry).iter().find_map(|ext| {
+        let candidate = dir.join(format!("index.{ext}"));
+        candidate.is_file().then_some(candidate)
+    })
+}
+
+/// Node's LOAD_AS_FILE followed by LOAD_AS_DIRECTORY.
+fn resolve_file_or_directory(
+    registry: &Registry,
+    path: &Path,
+    mode: ResolveMode,
+) -> Option<PathBuf> {
+    resolve_file(registry, path).or_else(|| {
+        if path.is_dir() {
+            resolve_package_main(registry, path, PackageJson::read(path).as_ref(), mode)
+        } else {
+            None
+        }
+    })
+}
+
+/// PACKAGE_TARGET_RESOLVE: follows a target of an `exports` or `imports`
+/// entry through arrays and condition objects down to a file.
+fn resolve_target(
+    registry: &Registry,
+    package_dir: &Path,
+    target: &Value,
+    pattern_match: Option<&str>,
+    mode: ResolveMode,
+    is_imports: bool,
+) -> Option<PathBuf> {
+    match target {
+        Value::String(target) => {
+            let target = match pattern_match {
+                Some(matched) => target.replace('*', matched),
+                None => target.clone(),
+            };
+
+            if let Some(relative) = target.strip_prefix("./") {
+                if relative
+                    .split('/')
+                    .any(|segment| segment == ".." || segment == "node_modules")
+                {
+                    return None;
+                }
+                let path = package_dir.join(relative);
+                return path.is_file().then_some(path);
+            }
+
+            // `imports` may map to another package: `"#dep": "some-package"`
+            if is_imports && !is_relative(&target) {
+                return resolve_package(registry, package_dir, &target, mode);
+            }
+            None
+        }
+        Value::Array(targets) => targets.iter().find_map(|target| {
+            resolve_target(
+                registry,
+                package_dir,
+                target,
+                pattern_match,
+                mode,
+                is_imports,
+            )
+        }),
+        Value::Object(conditions) => conditions.iter().find_map(|(condition, target)| {
+            if condition == "default" || mode.conditions().contains(&condition.as_str()) {
+                resolve_target(
+                    registry,
+                    package_dir,
+                    target,
+                    pattern_match,
+                    mode,
+                    is_imports,
+                )
+            } else {
+                None
+            }
+        }),
+        _ => None,
+    }
+}
+
+/// PACKAGE_IMPORTS_EXPORTS_RESOLVE: looks `key` up in a subpath map, either
+/// exactly or through the most specific `*` pattern.
+fn resolve_subpath_map(
+    registry: &Registry,
+    package_dir: &Path,
+    map: &Map<String, Value>,
+    key: &str,
+    mode: ResolveMode,
+    is_imports: bool,
+) -> Option<PathBuf> {
+    if !key.contains('*')
+        && let Some(target) = map.get(key)
+    {
+        return resolve_target(registry, package_dir, target, None, mode, is_imports);
+    }
+
+    let mut best: Option<(&str, &str)> = None;
+    for pattern in map.keys() {
+        let Some((prefix, suffix)) = pattern.split_once('*') else {
+            continue;
+        };
+        if key.len() < prefix.len() + suffix.len()
+            || !key.starts_with(prefix)
+            || !key.ends_with(suffix)
+            || key == prefix
+        {
+            continue;
+        }
+
+        let matched = &key[prefix.len()..key.len() - suffix.len()];
+        let better = match best {
+            None => true,
+            Some((current, _)) => {
+                let current_prefix = current.find('*').unwrap_or(current.len());
+                prefix.len() > current_prefix
+                    || (prefix.len() == current_prefix && pattern.len() > current.len())
+            }
+        };
+        if better {
+            best = Some((pattern, matched));
+        }
+    }
+
+    let (pattern, matched) = best?;
+    resolve_target(
+        registry,
+        package_dir,
+        &map[pattern],
+        Some(matched),
+        mode,
+        is_imports,
+    )
+}
+
+fn resolve_exports(
+    registry: &Registry,
+    package: &PackageJson,
+    subpath: &str,
+    mode: ResolveMode,
+) -> Option<PathBuf> {
+    let map = package.exports_map()?;
+    resolve_subpath_map(registry, &package.dir, &map, subpath, mode, false)
+}
+
+/// `#internal` specifiers, looked up in the `imports` field of the closest
+/// `package.json`.
+fn resolve_package_imports(
+    registry: &Registry,
+    base_dir: &Path,
+    specifier: &str,
+    mode: ResolveMode,
+) -> Option<PathBuf> {
+    let package = PackageJson::find_scope(base_dir)?;
+    let Some(Value::Object(imports)) = package.imports.as_ref() else {
+        return None;
+    };
+    resolve_subpath_map(registry, &package.dir, imports, specifier, mode, true)
+}
+
+/// PACKAGE_RESOLVE: a package may refer to itself by name through its own
+/// `exports`, otherwise `node_modules` directories are searched upwards.
+fn resolve_package(
+    registry: &Registry,
+    base_dir: &Path,
+    specifier: &str,
+    mode: ResolveMode,
+) -> Option<PathBuf> {
+    let (package_name, subpath) = split_package_specifier(specifier)?;
+
+    if let Some(scope) = PackageJson::find_scope(base_dir)
+        && scope.name.as_deref() == Some(package_name)
+        && scope.exports.is_some()
+    {
+        return resolve_exports(registry, &scope, &subpath, mode);
+    }
+
+    for node_modules_dir in find_node_modules(base_dir) {
+        let package_dir = node_modules_dir.join(package_name);
+        if !package_dir.is_dir() {
+            continue;
+        }
+
+        let package = PackageJson::read(&package_dir);
+        if let Some(package) = package.as_ref().filter(|p| p.exports.is_some()) {
+            return resolve_exports(registry, package, &subpath, mode);
+        }
+
+        if subpath == "." {
+            return resolve_package_main(registry, &package_dir, package.as_ref(), mode);
+        }
+        return resolve_file_or_directory(registry, &package_dir.join(&subpath[2..]), mode);
+    }
+
+    None
+}
+
+/// Resolves `specifier` as imported or required from the file at `base_path`,
+/// after applying the import map if there is one.
+/// From a module imported by URL, the import map is the only way to a local
+/// file.
+pub(crate) fn resolve(
+    registry: &Registry,
+    base_path: &Path,
+    specifier: &str,
+    mode: ResolveMode,
+) -> Option<PathBuf> {
+    match ImportMap::map(registry, specifier, base_path) {
+        Some(Mapped::Path(path)) => resolve_file_or_directory(registry, &path, mode),
+        // a module imported by URL has no directory to look for packages or
+        // files in, only the import map can point it at local ones
+        _ if remote::is_url(&base_path.to_string_lossy()) => None,
+        Some(Mapped::Specifier(mapped)) => resolve_unmapped(registry, base_path, &mapped, mode),
+        None => resolve_unmapped(registry, base_path, specifier, mode),
+    }
+}
+
+fn resolve_unmapped(
+    registry: &Registry,
+    base_path: &Path,
+    specifier: &str,
+    mode: ResolveMode,
+) -> Option<PathBuf> {
+    let base_dir = base_path.parent().unwrap_or(Path::new("."));
+
+    if is_relative(specifier) {
+        return resolve_file_or_directory(registry, &base_dir.join(specifier), mode);
+    }
+
+    if specifier.starts_with('#') {
+        return resolve_package_imports(registry, base_dir, specifier, mode);
+    }
+
+    // `paths` aliases and `baseUrl` from the tsconfig come before packages
+    if let Some(config) = TsConfig::for_file(registry, base_path) {
+        let found = config
+            .path_candidates(specifier)
+            .iter()
+            .find_map(|candidate| resolve_file_or_directory(registry, candidate, mode));
+        if found.is_some() {
+            return found;
+        }
+    }
+
+    if let Some(found) = resolve_package(registry, base_dir, specifier, mode) {
+        return Some(found);
+    }
+
+    // bare names that aren't packages fall back to a sibling file
+    resolve_file(registry, &base_dir.join(specifier))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ResolveErrorKind {
+    /// No file matched the specifier.
+    NotFound,
+    /// The module was found but doesn't export the imported name.
+    MissingExport(String),
+    /// The module was found but couldn't be read.
+    Unreadable(String),
+    /// The `type` import attribute isn't one Lunos knows.
+    UnsupportedType(String),
+}
+
+/// A module that failed to resolve or link, reported to scripts as a thrown
+/// `ResolveError` (or `SyntaxError` for a missing export).
+#[derive(Debug, Clone)]
+pub(crate) struct ResolveError {
+    pub kind: ResolveErrorKind,
+    pub specifier: String,
+    pub referrer: PathBuf,
+    /// 1-based line and column of the specifier or imported name.
+    pub position: Option<(u32, u32)>,
+    pub suggestion: Option<String>,
+}
+
+impl ResolveError {
+    pub fn new(kind: ResolveErrorKind, specifier: &str, referrer: &Path) -> ResolveError {
+        ResolveError {
+            kind,
+            specifier: specifier.to_string(),
+            referrer: referrer.to_path_buf(),
+            position: None,
+            suggestion: None,
+        }
+    }
+
+    pub fn not_found(
+        registry: &Registry,
+        specifier: &str,
+        referrer: &Path,
+        mode: ResolveMode,
+    ) -> ResolveError {
+        let mut error = ResolveError::new(ResolveErrorKind::NotFound, specifier, referrer);
+        error.suggestion = suggest_specifier(registry, referrer, specifier, mode);
+        error
+    }
+
+    pub fn missing_export(
+        specifier: &str,
+        referrer: &Path,
+        name: &str,
+        exports: &[String],
+    ) -> ResolveError {
+        let kind = ResolveErrorKind::MissingExport(name.to_string());
+        let mut error = ResolveError::new(kind, specifier, referrer);
+        error.suggestion = closest(name, exports.iter().map(String::as_str)).map(str::to_string);
+        error
+    }
+
+    /// Records where the failing specifier or name appears. `offset` is into
+    /// `source`, the module's code after the transforms `maps` are of, in the
+    /// order they ran; the position is the one in the original file, with a
+    /// UTF-16 column like those of stack traces.
+    pub fn at(mut self, source: &str, offset: usize, maps: &[SourceMap]) -> ResolveError {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let generated = (
+            before.matches('\n').count() as u32,
+            before[line_start..].encode_utf16().count() as u32,
+        );
+        let (line, column) = maps
+            .iter()
+            .rev()
+            .try_fold(generated, |(line, column), map| map.lookup(line, column))
+            .unwrap_or(generated);
+        self.position = Some((line + 1, column + 1));
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            ResolveErrorKind::MissingExport(_) => "SyntaxError",
+            ResolveErrorKind::UnsupportedType(_) => "TypeError",
+            _ => "ResolveError",
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self.kind {
+            ResolveErrorKind::NotFound | ResolveErrorKind::Unreadable(_) => "ERR_MODULE_NOT_FOUND",
+            ResolveErrorKind::MissingExport(_) => "ERR_MODULE_LINK",
+            ResolveErrorKind::UnsupportedType(_) => "ERR_IMPORT_ATTRIBUTE_UNSUPPORTED",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        let mut message = match &self.kind {
+            ResolveErrorKind::NotFound => format!(
+                "Cannot find module '{}' imported from {}",
+                self.specifier,
+                self.location()
+            ),
+            ResolveErrorKind::MissingExport(name) => format!(
+                "The requested module '{}' does not provide an export named '{name}' (imported from {})",
+                self.specifier,
+                self.location()
+            ),
+            ResolveErrorKind::Unreadable(reason) => format!(
+                "Cannot read module '{}' imported from {}: {reason}",
+                self.specifier,
+                self.location()
+            ),
+            ResolveErrorKind::UnsupportedType(import_type) => format!(
+                "Import attribute type '{import_type}' of module '{}' is not supported (imported from {})",
+                self.specifier,
+                self.location()
+            ),
+        };
+        if let Some(suggestion) = &self.suggestion {
+            message.push_str(&format!(". Did you mean '{suggestion}'?"));
+        }
+        message
+    }
+
+    fn location(&self) -> String {
+        match self.position {
+            Some((line, column)) => format!("{}:{line}:{column}", self.referrer.display()),
+            None => self.referrer.display().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name(), self.message())
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The candidate closest to `name`, if any is close enough to be a typo.
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| {
+            let distance = if candidate.eq_ignore_ascii_case(name) {
+                0
+            } else {
+                edit_distance(name, candidate)
+            };
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// A specifier close to one that didn't resolve: a sibling file for relative
+/// specifiers, an installed package for bare ones and another built-in for
+/// `lunos:` ones.
+fn suggest_specifier(
+    registry: &Registry,
+    referrer: &Path,
+    specifier: &str,
+    mode: ResolveMode,
+) -> Option<String> {
+    let base_dir = referrer.parent().unwrap_or(Path::new("."));
+
+    if builtins::is_builtin_specifier(specifier) {
+        return closest(specifier, builtins::names(registry)).map(str::to_string);
+    }
+
+    if is_relative(specifier) {
+        let (dir_part, file_part) = specifier.rsplit_once('/')?;
+        let dir = if dir_part.is_empty() {
+            PathBuf::from("/")
+        } else {
+            base_dir.join(dir_part)
+        };
+        let entries: Vec<String> = std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect();
+        // compare against both `name.ext` and `name`, since extensions are optional
+        let found = entries.iter().find_map(|entry| {
+            let stem = entry
+                .rsplit_once('.')
+                .map_or(entry.as_str(), |(stem, _)| stem);
+            (stem == file_part && entry != file_part).then_some(entry.as_str())
+        });
+        let found = found.or_else(|| {
+            let names = entries.iter().flat_map(|entry| {
+                let stem = entry.rsplit_once('.').map(|(stem, _)| stem);
+                std::iter::once(entry.as_str()).chain(stem)
+            });
+            let close = closest(file_part, names)?;
+            entries
+                .iter()
+                .find(|entry| {
+                    entry.as_str() == close
+                        || (entry.starts_with(close) && entry[close.len()..].starts_with('.'))
+                })
+                .map(String::as_str)
+        })?;
+        let candidate = format!("{dir_part}/{found}");
+        return resolve(registry, referrer, &candidate, mode).map(|_| candidate);
+    }
+
+    let (package_name, _) = split_package_specifier(specifier)?;
+    let installed: Vec<String> = find_node_modules(base_dir)
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    closest(package_name, installed.iter().map(String::as_str)).map(str::to_string)
+}
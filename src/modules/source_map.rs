@@ -0,0 +1,380 @@
+//! Source maps for the code the loader generates.
+//!
+//! Every rewrite of a module (JSX lowering, type erasure, the module wrapper)
+//! records where its output came from. Since the rewrites keep line breaks
+//! where they can, a map only needs an anchor wherever copied source starts:
+//! within copied source the columns advance together, and a line break in
+//! generated code stands for one in the source. Stack traces name each module
+//! by its path, and `original_position` follows the maps of a module back to
+//! the line and column in the original file.
+
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A position in generated code and the position in the source it maps to,
+/// with zero-based lines and UTF-16 columns.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    column: u32,
+    source_line: u32,
+    source_column: u32,
+}
+
+/// A version 3 source map of one source, by generated line.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourceMap {
+    lines: Vec<Vec<Segment>>,
+}
+
+impl SourceMap {
+    /// The source position of the zero-based `line` and `column`. Between
+    /// segments the column moves on as far as it does in the generated code.
+    pub(crate) fn lookup(&self, line: u32, column: u32) -> Option<(u32, u32)> {
+        let segments = self.lines.get(line as usize)?;
+        let segment = segments
+            .iter()
+            .rev()
+            .find(|segment| segment.column <= column)
+            .or_else(|| segments.first())?;
+        let offset = column.saturating_sub(segment.column);
+        Some((segment.source_line, segment.source_column + offset))
+    }
+
+    /// The map as source map JSON.
+    pub(crate) fn to_json(&self) -> String {
+        json!({
+            "version": 3,
+            "sources": [""],
+            "names": [],
+            "mappings": self.mappings(),
+        })
+        .to_string()
+    }
+
+    /// Reads a map written by `to_json`.
+    pub(crate) fn from_json(json: &str) -> Option<SourceMap> {
+        let value: Value = serde_json::from_str(json).ok()?;
+        if value.get("version")?.as_u64()? != 3 {
+            return None;
+        }
+        Self::parse_mappings(value.get("mappings")?.as_str()?)
+    }
+
+    /// The `mappings` field: segments of base64 VLQs, relative to the one
+    /// before, separated by `,` and lines by `;`.
+    fn mappings(&self) -> String {
+        let mut out = String::new();
+        let (mut source_line, mut source_column) = (0i64, 0i64);
+        for (i, segments) in self.lines.iter().enumerate() {
+            if i > 0 {
+                out.push(';');
+            }
+            let mut column = 0i64;
+            for (j, segment) in segments.iter().enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                encode_vlq(&mut out, segment.column as i64 - column);
+                encode_vlq(&mut out, 0);
+                encode_vlq(&mut out, segment.source_line as i64 - source_line);
+                encode_vlq(&mut out, segment.source_column as i64 - source_column);
+                column = segment.column as i64;
+                source_line = segment.source_line as i64;
+                source_column = segment.source_column as i64;
+            }
+        }
+        out
+    }
+
+    fn parse_mappings(mappings: &str) -> Option<SourceMap> {
+        let mut map = SourceMap::default();
+        let (mut source_line, mut source_column) = (0i64, 0i64);
+        for line in mappings.split(';') {
+            let mut segments = Vec::new();
+            let mut column = 0i64;
+            for segment in line.split(',').filter(|segment| !segment.is_empty()) {
+                let mut bytes = segment.bytes();
+                let mut fields = Vec::new();
+                while let Some(value) = decode_vlq(&mut bytes)? {
+                    fields.push(value);
+                }
+                column += fields[0];
+                // a segment without a source maps to nothing
+                if fields.len() < 4 {
+                    continue;
+                }
+                source_line += fields[2];
+                source_column += fields[3];
+                segments.push(Segment {
+                    column: u32::try_from(column).ok()?,
+                    source_line: u32::try_from(source_line).ok()?,
+                    source_column: u32::try_from(source_column).ok()?,
+                });
+            }
+            map.lines.push(segments);
+        }
+        Some(map)
+    }
+}
+
+fn encode_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 {
+        (-value << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 31;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 32;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// The next VLQ in `bytes`, `Some(None)` at their end and `None` if they
+/// aren't valid.
+fn decode_vlq(bytes: &mut impl Iterator<Item = u8>) -> Option<Option<i64>> {
+    let mut value = 0i64;
+    let mut shift = 0;
+    let Some(mut byte) = bytes.next() else {
+        return Some(None);
+    };
+    loop {
+        let digit = BASE64.iter().position(|&b| b == byte)? as i64;
+        value |= (digit & 31) << shift;
+        if digit & 32 == 0 {
+            break;
+        }
+        shift += 5;
+        if shift > 60 {
+            return None;
+        }
+        byte = bytes.next()?;
+    }
+    let negative = value & 1 == 1;
+    value >>= 1;
+    Some(Some(if negative { -value } else { value }))
+}
+
+/// Where the code that follows an anchor came from.
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    /// Byte offset in the generated code.
+    at: usize,
+    /// Byte offset in the source.
+    source: usize,
+    /// Whether the code is the source itself, character for character, or
+    /// was written in its place.
+    copied: bool,
+}
+
+/// Generated code, built up with where each part of it came from.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Generated {
+    code: String,
+    anchors: Vec<Anchor>,
+}
+
+impl Generated {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn code(&self) -> &str {
+        &self.code
+    }
+
+    fn anchor(&mut self, source: usize, copied: bool) {
+        let at = self.code.len();
+        if let Some(last) = self.anchors.last_mut()
+            && last.at == at
+        {
+            *last = Anchor { at, source, copied };
+        } else {
+            self.anchors.push(Anchor { at, source, copied });
+        }
+    }
+
+    /// Starts copying the source from byte `source`. What's pushed after this
+    /// is taken to be the source, one character for another.
+    pub(crate) fn copy_from(&mut self, source: usize) {
+        self.anchor(source, true);
+    }
+
+    /// Copies `src[start..end]`.
+    pub(crate) fn push_source(&mut self, src: &str, start: usize, end: usize) {
+        self.copy_from(start);
+        self.code.push_str(&src[start..end]);
+    }
+
+    /// Adds code written in place of the source at byte `source`.
+    pub(crate) fn push_code(&mut self, code: &str, source: usize) {
+        if code.is_empty() {
+            return;
+        }
+        self.anchor(source, false);
+        self.code.push_str(code);
+    }
+
+    /// Adds to the code, as part of what it's adding already.
+    pub(crate) fn push_str(&mut self, code: &str) {
+        self.code.push_str(code);
+    }
+
+    pub(crate) fn push(&mut self, c: char) {
+        self.code.push(c);
+    }
+
+    pub(crate) fn append(&mut self, other: Generated) {
+        let offset = self.code.len();
+        self.code.push_str(&other.code);
+        for anchor in other.anchors {
+            self.anchors.push(Anchor {
+                at: anchor.at + offset,
+                ..anchor
+            });
+        }
+    }
+
+    /// Inserts code written in place of the source at byte `source` at byte
+    /// `at` of the code.
+    pub(crate) fn insert_code(&mut self, at: usize, code: &str, source: usize) {
+        self.code.insert_str(at, code);
+        let index = self.anchors.partition_point(|anchor| anchor.at < at);
+        for anchor in &mut self.anchors[index..] {
+            anchor.at += code.len();
+        }
+        // the code before the insertion goes on after it
+        if let Some(before) = index.checked_sub(1).map(|i| self.anchors[i])
+            && !(index < self.anchors.len() && self.anchors[index].at == at + code.len())
+        {
+            let skipped = if before.copied { at - before.at } else { 0 };
+            self.anchors.insert(
+                index,
+                Anchor {
+                    at: at + code.len(),
+                    source: before.source + skipped,
+                    ..before
+                },
+            );
+        }
+        self.anchors.insert(
+            index,
+            Anchor {
+                at,
+                source,
+                copied: false,
+            },
+        );
+    }
+
+    /// The code, and its map back to `src`.
+    pub(crate) fn finish(self, src: &str) -> (String, SourceMap) {
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let position = |offset: usize| {
+            let offset = offset.min(src.len());
+            let line = line_starts.partition_point(|&start| start <= offset) - 1;
+            let column = src[line_starts[line]..offset].encode_utf16().count();
+            (line as u32, column as u32)
+        };
+
+        let mut map = SourceMap {
+            lines: vec![Vec::new()],
+        };
+        let (mut line, mut column) = (0usize, 0u32);
+        let mut anchors = self.anchors.iter().peekable();
+        let mut current: Option<(Anchor, u32, u32, usize)> = None;
+        for (i, c) in self.code.char_indices() {
+            let mut reached = None;
+            while let Some(anchor) = anchors.next_if(|anchor| anchor.at <= i) {
+                reached = Some(anchor);
+            }
+            if let Some(anchor) = reached {
+                let (source_line, source_column) = position(anchor.source);
+                current = Some((*anchor, source_line, source_column, anchor.source));
+                map.lines[line].push(Segment {
+                    column,
+                    source_line,
+                    source_column,
+                });
+            }
+            let Some((anchor, source_line, source_column, source)) = current.as_mut() else {
+                if c == '\n' {
+                    line += 1;
+                    column = 0;
+                    map.lines.push(Vec::new());
+                } else {
+                    column += c.len_utf16() as u32;
+                }
+                continue;
+            };
+
+            if anchor.copied {
+                if let Some(s) = src.get(*source..).and_then(|rest| rest.chars().next()) {
+                    *source += s.len_utf8();
+                    if s == '\n' {
+                        *source_line += 1;
+                        *source_column = 0;
+                    } else {
+                        *source_column += s.len_utf16() as u32;
+                    }
+                }
+            } else if c == '\n' {
+                *source_line += 1;
+                *source_column = 0;
+            }
+
+            if c == '\n' {
+                line += 1;
+                column = 0;
+                map.lines.push(vec![Segment {
+                    column: 0,
+                    source_line: *source_line,
+                    source_column: *source_column,
+                }]);
+            } else {
+                column += c.len_utf16() as u32;
+            }
+        }
+        (self.code, map)
+    }
+}
+
+/// The maps of every module a runtime evaluated by its source URL, in the
+/// order they are followed: the module wrapper first, then its transforms in
+/// reverse.
+#[derive(Default)]
+pub(crate) struct SourceMaps(Mutex<HashMap<String, Vec<SourceMap>>>);
+
+impl SourceMaps {
+    /// Keeps the maps of the module evaluated as `url`, outermost first.
+    pub(crate) fn register(&self, url: &str, maps: Vec<SourceMap>) {
+        self.0.lock().unwrap().insert(url.to_string(), maps);
+    }
+
+    /// The one-based line and column in the original file of a position in
+    /// the code evaluated as `url`.
+    pub(crate) fn original_position(
+        &self,
+        url: &str,
+        line: u32,
+        column: u32,
+    ) -> Option<(u32, u32)> {
+        let maps = self.0.lock().unwrap();
+        let mut position = (line.checked_sub(1)?, column.saturating_sub(1));
+        for map in maps.get(url)? {
+            position = map.lookup(position.0, position.1)?;
+        }
+        Some((position.0 + 1, position.1 + 1))
+    }
+}
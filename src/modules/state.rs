@@ -0,0 +1,72 @@
+//! What each runtime keeps for itself: its console buffer, timers, HTTP
+//! servers and module registry.
+//!
+//! Native functions only get the context they're called in, so the state
+//! of a runtime is looked up by its context's global object. Contexts are
+//! used on the thread that created them, and so is their state.
+
+use super::console::Console;
+use super::node::HttpState;
+use super::registry::Registry;
+use super::timers::TimerQueue;
+use crate::lunos::options::UnhandledRejections;
+use rusty_jsc::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+thread_local! {
+    static STATES: RefCell<HashMap<usize, Rc<State>>> = RefCell::new(HashMap::new());
+}
+
+pub(crate) struct State {
+    pub console: Console,
+    pub timers: Mutex<TimerQueue>,
+    pub http: Mutex<HttpState>,
+    pub modules: Registry,
+    pub unhandled_rejections: UnhandledRejections,
+    /// The script and its arguments, see `RuntimeBuilder::argv`.
+    pub argv: Vec<String>,
+}
+
+impl State {
+    pub fn new(modules: Registry) -> State {
+        State {
+            console: Console::new(),
+            timers: Mutex::new(TimerQueue::new()),
+            http: Mutex::new(HttpState::new()),
+            modules,
+            unhandled_rejections: UnhandledRejections::Strict,
+            argv: Vec::new(),
+        }
+    }
+
+    /// Makes `state` the state of `context`, for as long as the runtime
+    /// owning it lives.
+    pub fn attach(context: *const OpaqueJSContext, state: State) -> Rc<State> {
+        let state = Rc::new(state);
+        let key = unsafe { key(context) };
+        STATES.with(|states| states.borrow_mut().insert(key, state.clone()));
+        state
+    }
+
+    /// Forgets the state of `context`, before it's released.
+    pub fn detach(context: *const OpaqueJSContext) {
+        let key = unsafe { key(context) };
+        STATES.with(|states| states.borrow_mut().remove(&key));
+    }
+
+    /// The state of the runtime `context` belongs to.
+    pub fn of(context: *const OpaqueJSContext) -> Rc<State> {
+        let key = unsafe { key(context) };
+        STATES
+            .with(|states| states.borrow().get(&key).cloned())
+            .expect("a context of a live runtime")
+    }
+}
+
+/// The global object is the same in every context a callback runs in.
+unsafe fn key(context: *const OpaqueJSContext) -> usize {
+    unsafe { JSContextGetGlobalObject(context) as usize }
+}
@@ -0,0 +1,184 @@
+use super::convert::{JsFunction, JsObject, JsValue};
+use super::errors::NativeError;
+use super::native::{Args, define_functions, native_function};
+use super::state::State;
+use rusty_jsc::*;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// A scheduled callback. The callback and its arguments stay protected from
+/// garbage collection until the timer is done or cleared.
+struct Timer {
+    deadline: Instant,
+    interval: Option<Duration>,
+    callback: JsFunction,
+    arguments: Vec<JsValue>,
+}
+
+unsafe impl Send for Timer {}
+
+pub(crate) struct TimerQueue {
+    next_id: u32,
+    timers: BTreeMap<u32, Timer>,
+    /// The timer whose callback is running, and whether it got cleared.
+    running: Option<(u32, bool)>,
+}
+
+impl TimerQueue {
+    pub fn new() -> TimerQueue {
+        TimerQueue {
+            next_id: 1,
+            timers: BTreeMap::new(),
+            running: None,
+        }
+    }
+
+    /// Clears every timer, before the context they're in is released.
+    pub fn clear(&mut self, context: *const OpaqueJSContext) {
+        for timer in std::mem::take(&mut self.timers).values() {
+            unsafe { Timers::release(context, timer) };
+        }
+    }
+}
+
+pub struct Timers;
+
+impl Timers {
+    pub fn bind_to_context(context: *mut OpaqueJSContext) {
+        unsafe {
+            define_functions(
+                context,
+                JsObject::global(context),
+                &[
+                    ("setTimeout", Self::set_timeout_callback),
+                    ("setInterval", Self::set_interval_callback),
+                    ("clearTimeout", Self::clear_callback),
+                    ("clearInterval", Self::clear_callback),
+                ],
+            );
+        }
+    }
+
+    /// Runs the timer that's due first, sleeping until then. Returns
+    /// `Ok(false)` once nothing is scheduled, or what the callback threw.
+    pub(crate) fn run_next(context: *const OpaqueJSContext) -> Result<bool, *const OpaqueJSValue> {
+        let state = State::of(context);
+        let (id, timer) = {
+            let mut queue = state.timers.lock().unwrap();
+            let Some(id) = queue
+                .timers
+                .iter()
+                .min_by_key(|(id, timer)| (timer.deadline, **id))
+                .map(|(id, _)| *id)
+            else {
+                return Ok(false);
+            };
+            let timer = queue.timers.remove(&id).unwrap();
+            queue.running = Some((id, false));
+            (id, timer)
+        };
+
+        let now = Instant::now();
+        if timer.deadline > now {
+            std::thread::sleep(timer.deadline - now);
+        }
+
+        let result = unsafe { timer.callback.call(context, None, &timer.arguments) };
+
+        let mut queue = state.timers.lock().unwrap();
+        let cleared = queue.running.take().is_some_and(|(_, cleared)| cleared);
+        match timer.interval {
+            Some(interval) if !cleared && result.is_ok() => {
+                let deadline = Instant::now() + interval;
+                queue.timers.insert(id, Timer { deadline, ..timer });
+            }
+            _ => unsafe { Self::release(context, &timer) },
+        }
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(exception) => Err(exception.0),
+        }
+    }
+
+    unsafe fn release(context: *const OpaqueJSContext, timer: &Timer) {
+        unsafe {
+            JSValueUnprotect(context, timer.callback.0);
+            for argument in &timer.arguments {
+                JSValueUnprotect(context, argument.0);
+            }
+        }
+    }
+
+    fn schedule(args: &Args, repeat: bool) -> Result<u32, NativeError> {
+        let context = args.context();
+        let callback: JsFunction = args.next("callback")?;
+        // like in browsers the delay is converted to a number, and anything
+        // that isn't a positive one is no delay
+        let delay = args.values().nth(1).map_or(0.0, |delay| unsafe {
+            JSValueToNumber(context, delay.0, std::ptr::null_mut())
+        });
+        let delay = if delay.is_finite() && delay > 0.0 {
+            Duration::from_secs_f64(delay / 1000.0)
+        } else {
+            Duration::ZERO
+        };
+
+        let extra = args.values().skip(2).collect::<Vec<_>>();
+        unsafe {
+            JSValueProtect(context, callback.0);
+            for argument in &extra {
+                JSValueProtect(context, argument.0);
+            }
+        }
+
+        let state = State::of(context);
+        let mut queue = state.timers.lock().unwrap();
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.timers.insert(
+            id,
+            Timer {
+                deadline: Instant::now() + delay,
+                interval: repeat.then_some(delay),
+                callback,
+                arguments: extra,
+            },
+        );
+        Ok(id)
+    }
+
+    native_function! {
+        fn set_timeout_callback(args) -> Result<u32, NativeError> {
+            Self::schedule(&args, false)
+        }
+    }
+
+    native_function! {
+        fn set_interval_callback(args) -> Result<u32, NativeError> {
+            Self::schedule(&args, true)
+        }
+    }
+
+    native_function! {
+        fn clear_callback(args) -> Result<(), NativeError> {
+            let Some(id) = args.values().next() else {
+                return Ok(());
+            };
+            let id = unsafe { JSValueToNumber(args.context(), id.0, std::ptr::null_mut()) };
+            let state = State::of(args.context());
+            let mut queue = state.timers.lock().unwrap();
+            if id.is_finite() && id >= 0.0 {
+                let id = id as u32;
+                if let Some(timer) = queue.timers.remove(&id) {
+                    unsafe { Self::release(args.context(), &timer) };
+                } else if let Some((running, cleared)) = queue.running.as_mut()
+                    && *running == id
+                {
+                    *cleared = true;
+                }
+            }
+            Ok(())
+        }
+    }
+}
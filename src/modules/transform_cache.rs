@@ -0,0 +1,69 @@
+use super::registry::Registry;
+use super::source_map::SourceMap;
+use crate::lunos::{cache, constants::VERSION};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// Where the output of `transform` on `source` is kept. The key covers
+/// everything the output depends on: the source, the Lunos version and the
+/// transform with its options. Its source map is kept next to it.
+fn entry_path(source: &str, transform: &str) -> PathBuf {
+    let key = cache::hash(format!("{VERSION}\0{transform}\0{source}").as_bytes());
+    cache::dir().join("transform").join(&key[..2]).join(key)
+}
+
+/// Runs `transform` over `source`, or reuses its output and source map from
+/// an earlier run on the same source, unless the runtime was built without
+/// the cache. `name` identifies the transform and its options.
+pub(crate) fn transform(
+    registry: &Registry,
+    name: &str,
+    source: &str,
+    transform: impl FnOnce(&str) -> (String, SourceMap),
+) -> (String, SourceMap) {
+    if registry.no_cache {
+        return transform(source);
+    }
+    let path = entry_path(source, name);
+    let map_path = path.with_extension("map");
+    if let Ok(output) = fs::read_to_string(&path)
+        && let Some(map) = fs::read_to_string(&map_path)
+            .ok()
+            .and_then(|json| SourceMap::from_json(&json))
+    {
+        return (output, map);
+    }
+    let (output, map) = transform(source);
+    // an unwritable cache only costs the next run the same work again
+    let _ = cache::write(&path, output.as_bytes());
+    let _ = cache::write(&map_path, map.to_json().as_bytes());
+    (output, map)
+}
+
+/// Runs `analyze` over `source`, or reuses what it found in an earlier run
+/// like `transform` does. The result is kept as the JSON `to_json` makes of
+/// it; one `from_json` can't read is analyzed again.
+pub(crate) fn analysis<T>(
+    registry: &Registry,
+    name: &str,
+    source: &str,
+    to_json: impl FnOnce(&T) -> Value,
+    from_json: impl FnOnce(&Value) -> Option<T>,
+    analyze: impl FnOnce(&str) -> T,
+) -> T {
+    if registry.no_cache {
+        return analyze(source);
+    }
+    let path = entry_path(source, name);
+    if let Some(result) = fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .and_then(|json| from_json(&json))
+    {
+        return result;
+    }
+    let result = analyze(source);
+    let _ = cache::write(&path, to_json(&result).to_string().as_bytes());
+    result
+}
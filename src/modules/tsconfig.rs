@@ -0,0 +1,267 @@
+//! The `tsconfig.json` that applies to a module: the closest one at or above
+//! its directory, or else a `jsconfig.json`, merged with the configs it
+//! `extends`. Only what changes how Lunos resolves and transforms modules is
+//! read: the compiler options, `baseUrl` and `paths`.
+
+use super::registry::Registry;
+use super::remote;
+use super::resolver;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const CONFIG_FILES: [&str; 2] = ["tsconfig.json", "jsconfig.json"];
+
+/// The config a runtime found for each directory it looked at so far.
+#[derive(Default)]
+pub(crate) struct TsConfigs(Mutex<HashMap<PathBuf, Option<Arc<TsConfig>>>>);
+
+#[derive(Default)]
+pub(crate) struct TsConfig {
+    pub compiler_options: Map<String, Value>,
+    /// `baseUrl`, made absolute.
+    pub base_url: Option<PathBuf>,
+    /// `paths`, with the directory of the config that set it. Its targets
+    /// are relative to `base_url` when there is one, else to that directory.
+    pub paths: Option<(Map<String, Value>, PathBuf)>,
+}
+
+impl TsConfig {
+    /// The config that applies to the module at `path`. Modules imported by
+    /// URL have none, and the lookup doesn't leave a package in
+    /// `node_modules` for the project around it.
+    pub fn for_file(registry: &Registry, path: &Path) -> Option<Arc<TsConfig>> {
+        if remote::is_url(&path.to_string_lossy()) {
+            return None;
+        }
+        TsConfig::for_dir(&registry.tsconfigs, path.parent()?)
+    }
+
+    fn for_dir(configs: &TsConfigs, dir: &Path) -> Option<Arc<TsConfig>> {
+        if dir.file_name().is_some_and(|name| name == "node_modules") {
+            return None;
+        }
+        if let Some(config) = configs.0.lock().unwrap().get(dir) {
+            return config.clone();
+        }
+        let config = match CONFIG_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+        {
+            Some(path) => TsConfig::read(&path).map(Arc::new),
+            None => dir
+                .parent()
+                .and_then(|parent| TsConfig::for_dir(configs, parent)),
+        };
+        configs
+            .0
+            .lock()
+            .unwrap()
+            .insert(dir.to_path_buf(), config.clone());
+        config
+    }
+
+    /// Reads a config file. One that can't be used is reported and then
+    /// ignored, like having none.
+    fn read(path: &Path) -> Option<TsConfig> {
+        match TsConfig::load(path, &mut Vec::new()) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                eprintln!("Warning: ignoring {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Loads the config at `path` on top of the ones it extends. `chain`
+    /// holds the configs being loaded, to catch a config extending itself.
+    fn load(path: &Path, chain: &mut Vec<PathBuf>) -> Result<TsConfig, String> {
+        if chain.iter().any(|loading| loading == path) {
+            return Err(format!("{} extends itself", path.display()));
+        }
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let Value::Object(mut json) =
+            serde_json::from_str(&strip_jsonc(&text)).map_err(|e| e.to_string())?
+        else {
+            return Err("expected an object".to_string());
+        };
+        let dir = path.parent().unwrap_or(Path::new("."));
+
+        let bases = match json.remove("extends") {
+            Some(Value::String(base)) => vec![base],
+            Some(Value::Array(bases)) => bases
+                .into_iter()
+                .filter_map(|base| base.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut config = TsConfig::default();
+        chain.push(path.to_path_buf());
+        for base in bases {
+            let base_path = extends_path(dir, &base)
+                .ok_or_else(|| format!("cannot find \"{base}\" to extend"))?;
+            let base = TsConfig::load(&base_path, chain)?;
+            config.compiler_options.extend(base.compiler_options);
+            config.base_url = base.base_url.or(config.base_url);
+            config.paths = base.paths.or(config.paths);
+        }
+        chain.pop();
+
+        if let Some(Value::Object(options)) = json.remove("compilerOptions") {
+            if let Some(base_url) = options.get("baseUrl").and_then(Value::as_str) {
+                config.base_url = Some(dir.join(base_url));
+            }
+            if let Some(Value::Object(paths)) = options.get("paths") {
+                config.paths = Some((paths.clone(), dir.to_path_buf()));
+            }
+            config.compiler_options.extend(options);
+        }
+        Ok(config)
+    }
+
+    /// A string compiler option.
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.compiler_options.get(name)?.as_str()
+    }
+
+    /// A boolean compiler option.
+    pub fn flag(&self, name: &str) -> Option<bool> {
+        self.compiler_options.get(name)?.as_bool()
+    }
+
+    /// The paths to try for a bare specifier: the targets of the `paths`
+    /// pattern it matches, then the specifier under `baseUrl`.
+    pub fn path_candidates(&self, specifier: &str) -> Vec<PathBuf> {
+        let mut candidates = Vec::new();
+        if let Some((paths, paths_dir)) = &self.paths
+            && let Some((pattern, matched)) = match_pattern(paths, specifier)
+            && let Some(Value::Array(targets)) = paths.get(pattern)
+        {
+            let base = self.base_url.as_deref().unwrap_or(paths_dir);
+            for target in targets.iter().filter_map(Value::as_str) {
+                candidates.push(base.join(target.replacen('*', matched, 1)));
+            }
+        }
+        if let Some(base_url) = &self.base_url {
+            candidates.push(base_url.join(specifier));
+        }
+        candidates
+    }
+}
+
+/// The `paths` key that `specifier` matches, exactly or as the `*` pattern
+/// with the longest prefix, and the part the `*` stands for.
+fn match_pattern<'a, 'b>(
+    paths: &'a Map<String, Value>,
+    specifier: &'b str,
+) -> Option<(&'a str, &'b str)> {
+    if paths.contains_key(specifier) {
+        return paths
+            .get_key_value(specifier)
+            .map(|(key, _)| (key.as_str(), ""));
+    }
+    let mut best: Option<(&str, &str)> = None;
+    for pattern in paths.keys() {
+        let Some((prefix, suffix)) = pattern.split_once('*') else {
+            continue;
+        };
+        if specifier.len() < prefix.len() + suffix.len()
+            || !specifier.starts_with(prefix)
+            || !specifier.ends_with(suffix)
+        {
+            continue;
+        }
+        if best.is_none_or(|(current, _)| prefix.len() > current.find('*').unwrap_or(0)) {
+            best = Some((
+                pattern,
+                &specifier[prefix.len()..specifier.len() - suffix.len()],
+            ));
+        }
+    }
+    best
+}
+
+/// The config file an `extends` entry names: a path relative to the config
+/// extending it, or a package in `node_modules`, whose `tsconfig.json` is
+/// meant unless the entry names a file in it. `.json` may be left out.
+fn extends_path(dir: &Path, specifier: &str) -> Option<PathBuf> {
+    let file = |path: PathBuf| {
+        if path.is_file() {
+            return Some(path);
+        }
+        let mut with_json = path.into_os_string();
+        with_json.push(".json");
+        let with_json = PathBuf::from(with_json);
+        with_json.is_file().then_some(with_json)
+    };
+    if resolver::is_relative(specifier) || Path::new(specifier).is_absolute() {
+        return file(dir.join(specifier));
+    }
+    dir.ancestors().find_map(|ancestor| {
+        let path = ancestor.join("node_modules").join(specifier);
+        if path.is_dir() {
+            Some(path.join("tsconfig.json")).filter(|config| config.is_file())
+        } else {
+            file(path)
+        }
+    })
+}
+
+/// Turns the JSON with comments and trailing commas that TypeScript accepts
+/// into plain JSON.
+fn strip_jsonc(text: &str) -> String {
+    let text = text.trim_start_matches('\u{feff}');
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    // the output length after the last value, where a `,` was written
+    let mut comma: Option<usize> = None;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'"' => {
+                let start = pos;
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+                out.push_str(&text[start..pos]);
+                comma = None;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                pos = text[pos..].find('\n').map_or(bytes.len(), |i| pos + i);
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos = text[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |i| pos + i + 4);
+            }
+            b',' => {
+                comma = Some(out.len());
+                out.push(',');
+                pos += 1;
+            }
+            c @ (b'}' | b']') => {
+                if let Some(at) = comma.take() {
+                    out.replace_range(at..at + 1, " ");
+                }
+                out.push(c as char);
+                pos += 1;
+            }
+            c if c.is_ascii_whitespace() => {
+                out.push(c as char);
+                pos += 1;
+            }
+            _ => {
+                let len = text[pos..].chars().next().map_or(1, char::len_utf8);
+                out.push_str(&text[pos..pos + len]);
+                pos += len;
+                comma = None;
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,1856 @@
+//! Type erasure for TypeScript modules.
+//!
+//! TypeScript runs as the JavaScript that is left once its types are taken
+//! out. Like the rest of the loader this works on tokens rather than a syntax
+//! tree: it knows the places the grammar allows a type (after the `:` of a
+//! binding, parameter or return type, between the `<` and `>` of type
+//! parameters and arguments, after `as` and `satisfies`, ...) and skips each
+//! type there as a whole. Erased text is overwritten with spaces, keeping line
+//! breaks, so lines and columns in errors still point into the original file.
+//!
+//! A few constructs do have a runtime meaning: enums, namespaces,
+//! constructor parameter properties and decorators. They become the code
+//! `tsc` emits for them, written over the original tokens so every line stays
+//! where it was.
+
+mod decorators;
+
+use super::lexer::{Token, TokenKind, regex_allowed, string_value, tokenize};
+use super::registry::Registry;
+use super::source_map::{Generated, SourceMap};
+use super::tsconfig::TsConfig;
+use decorators::{Decorator, Member, MemberKind, Param};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// The compiler options that change what erasing types produces.
+#[derive(Clone, Debug)]
+pub(crate) struct TsOptions {
+    /// `useDefineForClassFields`: class fields are defined on the instance
+    /// the way JavaScript does it. Without it they're assignments in the
+    /// constructor, and fields without an initializer go away.
+    pub define_class_fields: bool,
+    /// `verbatimModuleSyntax`: only imports marked `type` are removed, not
+    /// the ones the module happens to use only as types.
+    pub verbatim_module_syntax: bool,
+    /// `experimentalDecorators`: decorators are the ones TypeScript has had
+    /// for long, called with the prototype, key and property descriptor,
+    /// rather than the ones JavaScript standardized.
+    pub experimental_decorators: bool,
+    /// `emitDecoratorMetadata`: legacy decorators also see the types of what
+    /// they decorate, through `Reflect.metadata`.
+    pub emit_decorator_metadata: bool,
+}
+
+impl Default for TsOptions {
+    fn default() -> Self {
+        TsOptions {
+            define_class_fields: true,
+            verbatim_module_syntax: false,
+            experimental_decorators: false,
+            emit_decorator_metadata: false,
+        }
+    }
+}
+
+impl TsOptions {
+    /// The options the tsconfig of the module at `path` sets.
+    pub fn for_file(registry: &Registry, path: &Path) -> TsOptions {
+        let Some(config) = TsConfig::for_file(registry, path) else {
+            return TsOptions::default();
+        };
+        // like tsc, fields are defined from target ES2022 on, and the target
+        // defaults to ES5
+        let modern_target = config.option("target").is_some_and(|target| {
+            let target = target.to_ascii_lowercase();
+            target == "esnext"
+                || target
+                    .strip_prefix("es")
+                    .and_then(|year| year.parse::<u32>().ok())
+                    .is_some_and(|year| year >= 2022)
+        });
+        TsOptions {
+            define_class_fields: config
+                .flag("useDefineForClassFields")
+                .unwrap_or(modern_target),
+            verbatim_module_syntax: config.flag("verbatimModuleSyntax").unwrap_or(false),
+            experimental_decorators: config.flag("experimentalDecorators").unwrap_or(false),
+            emit_decorator_metadata: config.flag("emitDecoratorMetadata").unwrap_or(false),
+        }
+    }
+
+    /// Names the transform with these options for the transform cache.
+    pub fn cache_name(&self) -> String {
+        format!("erase_types\0{self:?}")
+    }
+}
+
+/// Modifiers of class members and constructor parameters that only exist in
+/// TypeScript.
+const TS_MODIFIERS: [&str; 5] = ["public", "private", "protected", "readonly", "override"];
+
+/// Everything that may come before the name of a class member.
+const MEMBER_MODIFIERS: [&str; 12] = [
+    "public",
+    "private",
+    "protected",
+    "readonly",
+    "override",
+    "abstract",
+    "declare",
+    "static",
+    "async",
+    "get",
+    "set",
+    "accessor",
+];
+
+/// Keywords after which an expression starts, besides the ones the lexer
+/// already knows about for regular expressions.
+const STATEMENT_KEYWORDS: [&str; 14] = [
+    "export",
+    "default",
+    "import",
+    "extends",
+    "implements",
+    "let",
+    "const",
+    "var",
+    "function",
+    "class",
+    "if",
+    "while",
+    "for",
+    "with",
+];
+
+/// A binding of an import declaration.
+struct Binding {
+    /// The tokens to erase when it isn't used.
+    start: usize,
+    end: usize,
+    /// The token of its local name.
+    local: usize,
+}
+
+/// An import declaration whose bindings are dropped when the module only
+/// uses them as types, the way `tsc` elides them.
+struct Import {
+    start: usize,
+    end: usize,
+    default: Option<Binding>,
+    /// The `,` after the default binding.
+    comma: Option<usize>,
+    namespace: Option<Binding>,
+    /// The braces of the named imports and the ones among them that aren't
+    /// `type` imports.
+    braces: Option<(usize, usize)>,
+    named: Vec<Binding>,
+}
+
+/// A class field that becomes an assignment in the constructor.
+struct Field {
+    /// The tokens of the whole member.
+    start: usize,
+    end: usize,
+    /// What follows `this` to name the property: `.x` or `["x"]`.
+    target: String,
+    /// The tokens of the initializer.
+    init: Option<(usize, usize)>,
+    /// The call a decorated field's value goes through, without its closing
+    /// parenthesis, when the field has decorators.
+    wrap: Option<String>,
+}
+
+/// What the parameters of a function declare besides their types.
+struct Params {
+    /// The tokens naming parameter properties,
+    /// `constructor(private x: number)`.
+    properties: Vec<usize>,
+    /// The decorators and type of each parameter.
+    list: Vec<Param>,
+}
+
+/// What a class body holds that's rewritten once the whole class is known.
+struct ClassBody {
+    /// The `class` token, which names the helpers of its decorators.
+    id: usize,
+    fields: Vec<Field>,
+    /// The decorated members, in order; a member's index is its slot in the
+    /// decorator helpers.
+    members: Vec<Member>,
+    /// The parameters of the constructor, if it has one.
+    constructor_params: Option<Vec<Param>>,
+}
+
+/// A piece of code that ends up somewhere else than where it's written.
+enum Part {
+    Code(String),
+    /// A byte range of the source, with its types erased.
+    Source(usize, usize),
+}
+
+/// Code inserted at byte offset `at` that's partly moved from elsewhere in
+/// the source, which is blanked out at its old place.
+struct Relocation {
+    at: usize,
+    parts: Vec<Part>,
+    removed: Vec<(usize, usize)>,
+}
+
+struct Eraser<'a> {
+    src: &'a str,
+    options: TsOptions,
+    tokens: Vec<Token>,
+    /// For each bracket, the index of the one matching it (`tokens.len()`
+    /// when there is none).
+    pairs: Vec<usize>,
+    erased: Vec<bool>,
+    ranges: Vec<(usize, usize)>,
+    /// Code to insert at byte offsets of the source, in the order added.
+    inserts: Vec<(usize, String)>,
+    imports: Vec<Import>,
+    /// The namespace whose body is being scanned.
+    namespace: Option<String>,
+    /// Functions the namespace exports, assigned to it at the top of its
+    /// body since function declarations are hoisted.
+    hoisted: Vec<String>,
+    /// Set by `export` in a namespace for the variable declaration after it.
+    export_declaration: bool,
+    /// The braces of the constructor body of the class being scanned.
+    constructor_body: Option<(usize, usize)>,
+    relocations: Vec<Relocation>,
+    /// Decorators read before a class, for it to take.
+    pending_decorators: Vec<Decorator>,
+    /// Names that emitted decorator metadata refers to, so the imports of
+    /// them stay.
+    metadata_names: HashSet<String>,
+}
+
+/// Turns TypeScript source into JavaScript by blanking out its types, with
+/// the map of where the JavaScript came from.
+pub(crate) fn erase_types(src: &str, options: &TsOptions) -> (String, SourceMap) {
+    let mut eraser = Eraser::new(src, options.clone());
+    eraser.scan(0, eraser.tokens.len(), &|_, _| false);
+    if !eraser.options.verbatim_module_syntax {
+        eraser.elide_imports();
+    }
+    eraser.output().finish(src)
+}
+
+/// The lexer reads `>>` and `>>>` as one token, which in types close nested
+/// type arguments, `Array<Array<T>>`. Here each `>` gets a token of its own.
+fn split_angles(src: &str, tokens: Vec<Token>) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    for mut token in tokens {
+        while token.kind == TokenKind::Punct && token.text(src).starts_with(">>") {
+            out.push(Token {
+                end: token.start + 1,
+                ..token
+            });
+            token.start += 1;
+            token.nl_before = false;
+        }
+        out.push(token);
+    }
+    out
+}
+
+/// The value of a numeric literal, for the members of an enum.
+fn number_value(literal: &str) -> Option<f64> {
+    let literal = literal.replace('_', "");
+    let radix = match literal.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => return literal.parse().ok(),
+    };
+    u64::from_str_radix(&literal[2..], radix)
+        .ok()
+        .map(|n| n as f64)
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+fn pair_brackets(src: &str, tokens: &[Token]) -> Vec<usize> {
+    let mut pairs = vec![tokens.len(); tokens.len()];
+    let mut open = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Punct {
+            continue;
+        }
+        match token.text(src) {
+            "{" | "(" | "[" => open.push(i),
+            "}" | ")" | "]" => {
+                if let Some(start) = open.pop() {
+                    pairs[start] = i;
+                    pairs[i] = start;
+                }
+            }
+            _ => {}
+        }
+    }
+    pairs
+}
+
+impl<'a> Eraser<'a> {
+    fn new(src: &'a str, options: TsOptions) -> Self {
+        let tokens = split_angles(src, tokenize(src));
+        let pairs = pair_brackets(src, &tokens);
+        Eraser {
+            src,
+            options,
+            erased: vec![false; tokens.len()],
+            tokens,
+            pairs,
+            ranges: Vec::new(),
+            inserts: Vec::new(),
+            imports: Vec::new(),
+            namespace: None,
+            hoisted: Vec::new(),
+            export_declaration: false,
+            constructor_body: None,
+            relocations: Vec::new(),
+            pending_decorators: Vec::new(),
+            metadata_names: HashSet::new(),
+        }
+    }
+
+    fn text(&self, i: usize) -> &'a str {
+        self.tokens.get(i).map_or("", |token| token.text(self.src))
+    }
+
+    fn is(&self, i: usize, text: &str) -> bool {
+        self.text(i) == text
+    }
+
+    fn kind(&self, i: usize) -> Option<TokenKind> {
+        self.tokens.get(i).map(|token| token.kind)
+    }
+
+    fn is_ident(&self, i: usize) -> bool {
+        self.kind(i) == Some(TokenKind::Ident)
+    }
+
+    fn on_new_line(&self, i: usize) -> bool {
+        self.tokens.get(i).is_some_and(|token| token.nl_before)
+    }
+
+    /// The token closing the bracket at `i`.
+    fn close(&self, i: usize) -> usize {
+        self.pairs.get(i).copied().unwrap_or(self.tokens.len())
+    }
+
+    /// The last token before `i` that is still there.
+    fn prev(&self, i: usize) -> Option<usize> {
+        (0..i).rev().find(|&j| !self.erased[j])
+    }
+
+    fn after_dot(&self, i: usize) -> bool {
+        self.prev(i)
+            .is_some_and(|p| self.is(p, ".") || self.is(p, "?."))
+    }
+
+    fn blank(&mut self, start: usize, end: usize) {
+        let end = end.min(self.tokens.len());
+        if start >= end {
+            return;
+        }
+        self.erased[start..end].fill(true);
+        self.ranges
+            .push((self.tokens[start].start, self.tokens[end - 1].end));
+    }
+
+    /// Writes `code` in place of tokens `start..end`.
+    fn replace(&mut self, start: usize, end: usize, code: String) {
+        self.blank(start, end);
+        self.insert_before(start, code);
+    }
+
+    fn insert_before(&mut self, i: usize, code: String) {
+        let at = self
+            .tokens
+            .get(i)
+            .map_or(self.src.len(), |token| token.start);
+        self.inserts.push((at, code));
+    }
+
+    fn insert_after(&mut self, i: usize, code: String) {
+        self.inserts.push((self.tokens[i].end, code));
+    }
+
+    /// Whether the token at `i` can end an expression, so that what follows
+    /// continues it: `x!`, `f<T>()`, `x as T`.
+    fn ends_expression(&self, i: usize) -> bool {
+        let token = &self.tokens[i];
+        let text = token.text(self.src);
+        match token.kind {
+            TokenKind::Ident => {
+                !regex_allowed(self.src, Some(token)) && !STATEMENT_KEYWORDS.contains(&text)
+            }
+            TokenKind::Punct if text == ")" => !self
+                .prev(self.close(i).min(i))
+                .is_some_and(|p| matches!(self.text(p), "if" | "while" | "for" | "with")),
+            TokenKind::Punct => matches!(text, "]" | "}"),
+            TokenKind::Template => text.ends_with('`'),
+            _ => true,
+        }
+    }
+
+    /// Erases the types in tokens `i..end`, statements or an expression, up
+    /// to the first token `stop` accepts. Returns where it stopped.
+    fn scan(&mut self, mut i: usize, end: usize, stop: &dyn Fn(&Self, usize) -> bool) -> usize {
+        let end = end.min(self.tokens.len());
+        // inside `let`, `const` or `var`, where each `,` starts a binding
+        let mut declaring = false;
+        // the namespace exporting those bindings
+        let mut exporting = None;
+        while i < end && !stop(self, i) {
+            let text = self.text(i);
+            if text == ";" {
+                declaring = false;
+                exporting = None;
+                i += 1;
+            } else if declaring && text == "," {
+                i = self.declarator(i + 1, exporting.as_deref());
+            } else if matches!(text, "let" | "const" | "var")
+                && !self.after_dot(i)
+                && !self.is(i + 1, "enum")
+                && (self.is_ident(i + 1) || self.is(i + 1, "{") || self.is(i + 1, "["))
+            {
+                declaring = true;
+                exporting = None;
+                if std::mem::take(&mut self.export_declaration) {
+                    exporting = self.namespace.clone();
+                }
+                i = self.declarator(i + 1, exporting.as_deref());
+            } else {
+                i = self.step(i);
+            }
+        }
+        i
+    }
+
+    /// Erases the types starting at token `i`, returning the next token to
+    /// look at.
+    fn step(&mut self, i: usize) -> usize {
+        let token = self.tokens[i];
+        match (token.kind, token.text(self.src)) {
+            (TokenKind::Punct, "(") => self.paren(i),
+            (TokenKind::Punct, "{" | "[") => {
+                let close = self.close(i);
+                self.scan(i + 1, close, &|_, _| false);
+                close + 1
+            }
+            (TokenKind::Punct, "<") => self.angle(i),
+            (TokenKind::Punct, "@") => {
+                // decorators of the class after them
+                let (decorators, end) = self.decorators(i);
+                self.pending_decorators = decorators;
+                end
+            }
+            (TokenKind::Punct, "!") => {
+                // a non-null assertion, `x!.y`
+                if !token.nl_before && self.prev(i).is_some_and(|p| self.ends_expression(p)) {
+                    self.blank(i, i + 1);
+                }
+                i + 1
+            }
+            (TokenKind::Ident, _) if self.after_dot(i) => i + 1,
+            (TokenKind::Ident, "function") => self.function(i),
+            (TokenKind::Ident, "class") => self.class(i),
+            (TokenKind::Ident, "import") => self.import(i),
+            (TokenKind::Ident, "export") => self.export(i),
+            (TokenKind::Ident, "as" | "satisfies") => self.assertion(i),
+            (TokenKind::Ident, "type" | "interface" | "declare" | "abstract") => {
+                self.declaration(i).unwrap_or(i + 1)
+            }
+            (TokenKind::Ident, "enum") => self.enum_declaration(i, None).unwrap_or(i + 1),
+            (TokenKind::Ident, "namespace" | "module") => {
+                self.namespace_declaration(i, None).unwrap_or(i + 1)
+            }
+            _ => i + 1,
+        }
+    }
+
+    /// A declarator of `let`, `const` or `var` at `i`. In a namespace that
+    /// exports it, its value is also assigned to the namespace:
+    /// `export const x = 1` becomes `const x = ns.x = 1`.
+    fn declarator(&mut self, i: usize, namespace: Option<&str>) -> usize {
+        let end = self.binding(i);
+        if let Some(namespace) = namespace
+            && self.is_ident(i)
+        {
+            let name = self.text(i);
+            if self.is(end, "=") {
+                self.insert_after(end, format!(" {namespace}.{name} ="));
+            } else {
+                self.insert_after(end - 1, format!(" = {namespace}.{name} = void 0"));
+            }
+        }
+        end
+    }
+
+    /// A binding name or pattern at `i`, erasing the `?`, `!` and type
+    /// annotation after it.
+    fn binding(&mut self, mut i: usize) -> usize {
+        if self.is(i, "{") || self.is(i, "[") {
+            let close = self.close(i);
+            self.scan(i + 1, close, &|_, _| false);
+            i = close + 1;
+        } else if self.is_ident(i) {
+            i += 1;
+        } else {
+            return i;
+        }
+        if self.is(i, "?") || self.is(i, "!") {
+            self.blank(i, i + 1);
+            i += 1;
+        }
+        self.annotation(i)
+    }
+
+    /// Erases the `: T` at `i`, of a binding or a return type.
+    fn annotation(&mut self, i: usize) -> usize {
+        if !self.is(i, ":") {
+            return i;
+        }
+        match self.skip_type(i + 1) {
+            Some(end) => {
+                self.blank(i, end);
+                end
+            }
+            None => i,
+        }
+    }
+
+    fn paren(&mut self, open: usize) -> usize {
+        let close = self.close(open);
+        if self.is_params(open, close) {
+            self.params(open, close);
+            return self.annotation(close + 1);
+        }
+        self.scan(open + 1, close, &|_, _| false);
+        close + 1
+    }
+
+    /// Whether the parentheses at `open` hold the parameters of an arrow
+    /// function or a method rather than an expression.
+    fn is_params(&self, open: usize, close: usize) -> bool {
+        let typed = |then: &str| {
+            self.is(close + 1, ":")
+                && self
+                    .skip_type(close + 2)
+                    .is_some_and(|end| self.is(end, then))
+        };
+        if self.is(close + 1, "=>") || typed("=>") {
+            return true;
+        }
+        let Some(name) = self.prev(open) else {
+            return false;
+        };
+        let named = match self.tokens[name].kind {
+            TokenKind::Ident => {
+                !matches!(self.text(name), "if" | "while" | "for" | "switch" | "with")
+            }
+            TokenKind::Str | TokenKind::Number => true,
+            TokenKind::Punct => self.is(name, "]"),
+            _ => false,
+        };
+        named && ((self.is(close + 1, "{") && !self.on_new_line(close + 1)) || typed("{"))
+    }
+
+    /// Erases the types of the parameters between `open` and `close`.
+    fn params(&mut self, open: usize, close: usize) -> Params {
+        let mut params = Params {
+            properties: Vec::new(),
+            list: Vec::new(),
+        };
+        let mut i = open + 1;
+        while i < close {
+            let start = i;
+            let (mut decorators, after) = self.decorators(i);
+            i = after;
+            // only legacy decorators apply to parameters
+            if !self.options.experimental_decorators {
+                for decorator in decorators.drain(..) {
+                    self.blank(decorator.start, decorator.end);
+                }
+            }
+            let modifiers = i;
+            while TS_MODIFIERS.contains(&self.text(i))
+                && (self.is_ident(i + 1) || matches!(self.text(i + 1), "{" | "[" | "..."))
+            {
+                self.blank(i, i + 1);
+                i += 1;
+            }
+            if i > modifiers && self.is_ident(i) {
+                params.properties.push(i);
+            }
+            // `this: T` only types `this` inside the function
+            if self.is(i, "this") && matches!(self.text(i + 1), ":" | "," | ")") {
+                let mut end = i + 1;
+                if self.is(end, ":") {
+                    end = self.skip_type(end + 1).unwrap_or(end + 1);
+                }
+                if self.is(end, ",") {
+                    end += 1;
+                }
+                self.blank(i, end);
+                i = end;
+                continue;
+            }
+            if self.is(i, "...") {
+                i += 1;
+            }
+            let binding = i;
+            i = self.binding(i);
+            if i > binding {
+                params.list.push(Param {
+                    decorators,
+                    annotation: self.binding_type(binding),
+                });
+            }
+            if self.is(i, "=") {
+                i = self.scan(i + 1, close, &|eraser, j| eraser.is(j, ","));
+            }
+            if self.is(i, ",") {
+                i += 1;
+            }
+            if i == start {
+                i = self.step(i);
+            }
+        }
+        params
+    }
+
+    /// A `<` that starts type arguments, `f<T>(x)`, type parameters of an
+    /// arrow function, `<T>(x: T) => x`, or a type assertion, `<T>x`, erasing
+    /// them. Otherwise it's a comparison.
+    fn angle(&mut self, i: usize) -> usize {
+        let Some(end) = self.skip_angle(i) else {
+            return i + 1;
+        };
+        let continues = self.prev(i).is_some_and(|p| self.ends_expression(p));
+        let call = self.is(end, "(")
+            || (self.kind(end) == Some(TokenKind::Template) && self.text(end).starts_with('`'));
+        if continues && !call && !self.constructed_without_arguments(i, end) {
+            return i + 1;
+        }
+        self.blank(i, end);
+        end
+    }
+
+    /// Whether the `<` at `i`, closed before `end`, follows the class of a
+    /// `new` without parentheses, as in `new Foo<Bar>;`.
+    fn constructed_without_arguments(&self, i: usize, end: usize) -> bool {
+        let ends_here = end >= self.tokens.len()
+            || self.on_new_line(end)
+            || matches!(self.text(end), ";" | "," | ")" | "]" | "}");
+        let mut j = i;
+        while let Some(p) = self.prev(j)
+            && self.is_ident(p)
+        {
+            match self.prev(p) {
+                Some(dot) if self.is(dot, ".") => j = dot,
+                Some(keyword) => return ends_here && self.is(keyword, "new"),
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// `x as T`, `x as const` and `x satisfies T`.
+    fn assertion(&mut self, i: usize) -> usize {
+        let continues = self.prev(i).is_some_and(|p| self.ends_expression(p));
+        if !continues || self.on_new_line(i) {
+            return i + 1;
+        }
+        if self.is(i + 1, "const") {
+            self.blank(i, i + 2);
+            return i + 2;
+        }
+        match self.skip_type(i + 1) {
+            Some(end) => {
+                self.blank(i, end);
+                end
+            }
+            None => i + 1,
+        }
+    }
+
+    /// The first token of the statement whose keyword is at `i`, including
+    /// `export` and `export default` before it.
+    fn exported(&self, i: usize) -> usize {
+        let mut start = i;
+        for keyword in ["async", "default", "export"] {
+            if let Some(p) = self.prev(start)
+                && self.is(p, keyword)
+            {
+                start = p;
+            }
+        }
+        start
+    }
+
+    fn function(&mut self, i: usize) -> usize {
+        let start = self.exported(i);
+        let mut j = i + 1;
+        if self.is(j, "*") {
+            j += 1;
+        }
+        if self.is_ident(j) {
+            j += 1;
+        }
+        if self.is(j, "<")
+            && let Some(end) = self.skip_angle(j)
+        {
+            self.blank(j, end);
+            j = end;
+        }
+        if !self.is(j, "(") {
+            return j;
+        }
+        let close = self.close(j);
+        self.params(j, close);
+        j = self.annotation(close + 1);
+        if self.is(j, "{") {
+            return j;
+        }
+        // an overload signature, `function f(a: string): string;`
+        if self.is(j, ";") {
+            j += 1;
+        }
+        self.blank(start, j);
+        j
+    }
+
+    fn class(&mut self, i: usize) -> usize {
+        let decorators = std::mem::take(&mut self.pending_decorators);
+        let mut j = i + 1;
+        if self.is_ident(j) && !matches!(self.text(j), "extends" | "implements") {
+            j += 1;
+        }
+        if self.is(j, "<")
+            && let Some(end) = self.skip_angle(j)
+        {
+            self.blank(j, end);
+            j = end;
+        }
+        let derived = self.is(j, "extends");
+        if derived {
+            j += 1;
+            // the superclass is an expression, maybe with type arguments
+            while j < self.tokens.len() && !self.is(j, "{") && !self.is(j, "implements") {
+                if self.is(j, "<")
+                    && let Some(end) = self.skip_angle(j)
+                    && (self.is(end, "{") || self.is(end, "implements"))
+                {
+                    self.blank(j, end);
+                    j = end;
+                } else {
+                    j = self.step(j);
+                }
+            }
+        }
+        if self.is(j, "implements") {
+            let end = self.skip_type_list(j + 1);
+            self.blank(j, end);
+            j = end;
+        }
+        if !self.is(j, "{") {
+            return j;
+        }
+        let close = self.close(j);
+        let outer_constructor = self.constructor_body.take();
+        let mut body = ClassBody {
+            id: i,
+            fields: Vec::new(),
+            members: Vec::new(),
+            constructor_params: None,
+        };
+        let mut member = j + 1;
+        while member < close {
+            member = self.class_member(member, close, derived, &mut body);
+        }
+        self.decorate_class(i, j, close, decorators, &body);
+        if !body.fields.is_empty() {
+            self.assign_fields(j, derived, body.fields);
+        }
+        self.constructor_body = outer_constructor;
+        close + 1
+    }
+
+    /// Whether a class member's name can start at `i`.
+    fn starts_member_name(&self, i: usize) -> bool {
+        matches!(
+            self.kind(i),
+            Some(TokenKind::Ident | TokenKind::Str | TokenKind::Number)
+        ) || matches!(self.text(i), "[" | "*" | "#")
+    }
+
+    /// Whether a class field initializer ended at the line break before `i`
+    /// and the next member starts there.
+    fn member_starts(&self, i: usize) -> bool {
+        // a decorator can't follow an expression otherwise
+        if self.is(i, "@") {
+            return self.prev(i).is_some_and(|p| self.ends_expression(p));
+        }
+        self.on_new_line(i)
+            && matches!(
+                self.kind(i),
+                Some(TokenKind::Ident | TokenKind::Str | TokenKind::Number)
+            )
+            && self.prev(i).is_some_and(|p| self.ends_expression(p))
+    }
+
+    /// Erases the types of the class member at `first`. Without
+    /// `useDefineForClassFields` instance fields are added to the fields of
+    /// `body`, and decorated members to its members.
+    fn class_member(
+        &mut self,
+        first: usize,
+        close: usize,
+        derived: bool,
+        body: &mut ClassBody,
+    ) -> usize {
+        if self.is(first, ";") {
+            return first + 1;
+        }
+        if self.is(first, "static") && self.is(first + 1, "{") {
+            let block_close = self.close(first + 1);
+            self.scan(first + 2, block_close, &|_, _| false);
+            return block_close + 1;
+        }
+        let (decorators, start) = self.decorators(first);
+        let mut i = start;
+        // abstract and declared members have no code at all
+        let mut ts_only = false;
+        let mut is_static = false;
+        let mut is_async = false;
+        let mut accessor = None;
+        let mut kind = MemberKind::Method;
+        while MEMBER_MODIFIERS.contains(&self.text(i)) && self.starts_member_name(i + 1) {
+            match self.text(i) {
+                "abstract" | "declare" => ts_only = true,
+                "static" => is_static = true,
+                modifier if TS_MODIFIERS.contains(&modifier) => self.blank(i, i + 1),
+                "async" => is_async = true,
+                "get" => kind = MemberKind::Getter,
+                "set" => kind = MemberKind::Setter,
+                "accessor" => accessor = Some(i),
+                _ => {}
+            }
+            i += 1;
+        }
+        if self.is(i, "*") {
+            i += 1;
+        }
+        // an index signature, `[key: string]: T;`
+        if self.is(i, "[") && self.is_ident(i + 1) && self.is(i + 2, ":") {
+            let mut end = self.close(i) + 1;
+            if self.is(end, ":") {
+                end = self.skip_type(end + 1).unwrap_or(end + 1);
+            }
+            if self.is(end, ";") {
+                end += 1;
+            }
+            self.blank(first, end);
+            return end;
+        }
+        let name = i;
+        if self.is(i, "[") {
+            let key_close = self.close(i);
+            self.scan(i + 1, key_close, &|_, _| false);
+            i = key_close + 1;
+        } else {
+            if self.is(i, "#") {
+                i += 1;
+            }
+            i += 1;
+        }
+        if self.is(i, "?") || self.is(i, "!") {
+            self.blank(i, i + 1);
+            i += 1;
+        }
+        if self.is(i, "<")
+            && let Some(end) = self.skip_angle(i)
+        {
+            self.blank(i, end);
+            i = end;
+        }
+        if self.is(i, "(") {
+            let params_close = self.close(i);
+            let params = self.params(i, params_close);
+            let returns = self.type_after(params_close + 1);
+            i = self.annotation(params_close + 1);
+            if self.is(i, "{") && !ts_only {
+                let body_close = self.close(i);
+                if self.is(name, "constructor") && !params.properties.is_empty() {
+                    self.assign_properties(i, body_close, derived, &params.properties);
+                }
+                self.scan(i + 1, body_close, &|_, _| false);
+                if self.is(name, "constructor") {
+                    self.constructor_body = Some((i, body_close));
+                    body.constructor_params = Some(params.list);
+                } else {
+                    let member = Member {
+                        decorators,
+                        kind,
+                        is_static,
+                        is_async,
+                        name,
+                        params: params.list,
+                        annotation: returns,
+                    };
+                    self.method_member(body, member, body_close);
+                }
+                return body_close + 1;
+            }
+            // abstract methods and overload signatures have no body
+            if self.is(i, ";") {
+                i += 1;
+            }
+            self.blank(first, i);
+            return i;
+        }
+        let annotation = self.type_after(i);
+        i = self.annotation(i);
+        let mut init = None;
+        if self.is(i, "=") {
+            let init_start = i + 1;
+            i = self.scan(init_start, close, &|eraser, j| {
+                eraser.is(j, ";") || eraser.member_starts(j)
+            });
+            init = Some((init_start, i)).filter(|&(start, end)| start < end);
+        }
+        let value_end = i;
+        if self.is(i, ";") {
+            i += 1;
+        }
+        if ts_only {
+            self.blank(first, i);
+            return i;
+        }
+        let target = match self.kind(name) {
+            // private names stay fields whatever the options, and so does
+            // the storage of an accessor
+            Some(TokenKind::Ident) if !self.text(name).starts_with('#') => {
+                Some(format!(".{}", self.text(name)))
+            }
+            Some(TokenKind::Str | TokenKind::Number) => Some(format!("[{}]", self.text(name))),
+            _ => None,
+        }
+        .filter(|_| !is_static && accessor.is_none() && !self.options.define_class_fields);
+        let member = Member {
+            decorators,
+            kind: if accessor.is_some() {
+                MemberKind::Accessor
+            } else {
+                MemberKind::Field
+            },
+            is_static,
+            is_async,
+            name,
+            params: Vec::new(),
+            annotation,
+        };
+        let wrap = self.field_member(body, member, accessor, init, value_end, target.is_some());
+        if let Some(target) = target {
+            body.fields.push(Field {
+                start,
+                end: i,
+                target,
+                init,
+                wrap,
+            });
+        }
+        i
+    }
+
+    /// Where code setting up `this` goes in the constructor body between
+    /// `open` and `close`: after the `{`, or right after the `super(...)` call
+    /// in a derived class, where `this` can first be used. Also whether that
+    /// call still needs a `;`.
+    fn constructor_start(&self, open: usize, close: usize, derived: bool) -> (usize, bool) {
+        let mut i = open + 1;
+        while derived && i < close {
+            if self.is(i, "super") && self.is(i + 1, "(") {
+                let call_close = self.close(i + 1);
+                return if self.is(call_close + 1, ";") {
+                    (call_close + 1, false)
+                } else {
+                    (call_close, true)
+                };
+            }
+            i = match self.text(i) {
+                "(" | "[" | "{" => self.close(i) + 1,
+                _ => i + 1,
+            };
+        }
+        (open, false)
+    }
+
+    /// Assigns the parameter properties at the start of the constructor body
+    /// between `open` and `close`.
+    fn assign_properties(
+        &mut self,
+        open: usize,
+        close: usize,
+        derived: bool,
+        properties: &[usize],
+    ) {
+        let mut code: String = properties
+            .iter()
+            .map(|&name| format!(" this.{0} = {0};", self.text(name)))
+            .collect();
+        let (at, semicolon) = self.constructor_start(open, close, derived);
+        if semicolon {
+            code.insert(0, ';');
+        }
+        self.insert_after(at, code);
+    }
+
+    /// Turns the instance fields of the class whose body opens at `open` into
+    /// assignments in its constructor, after the parameter properties, the
+    /// way `tsc` does without `useDefineForClassFields`. A class without a
+    /// constructor gets one. Initializers spanning lines move their line
+    /// breaks along.
+    fn assign_fields(&mut self, open: usize, derived: bool, fields: Vec<Field>) {
+        let mut parts = Vec::new();
+        for field in &fields {
+            if field.init.is_none() && field.wrap.is_none() {
+                continue;
+            }
+            parts.push(Part::Code(format!(" this{} = ", field.target)));
+            if let Some(wrap) = &field.wrap {
+                parts.push(Part::Code(wrap.clone()));
+            }
+            if let Some((start, end)) = field.init {
+                if field.wrap.is_some() {
+                    parts.push(Part::Code(", ".to_string()));
+                }
+                parts.push(Part::Source(
+                    self.tokens[start].start,
+                    self.tokens[end - 1].end,
+                ));
+            }
+            if field.wrap.is_some() {
+                parts.push(Part::Code(")".to_string()));
+            }
+            parts.push(Part::Code(";".to_string()));
+        }
+        let at = match self.constructor_body {
+            _ if parts.is_empty() => open,
+            Some((body_open, body_close)) => {
+                let (at, semicolon) = self.constructor_start(body_open, body_close, derived);
+                if semicolon {
+                    parts.insert(0, Part::Code(";".to_string()));
+                }
+                at
+            }
+            None => {
+                let constructor = if derived {
+                    " constructor() { super(...arguments);"
+                } else {
+                    " constructor() {"
+                };
+                parts.insert(0, Part::Code(constructor.to_string()));
+                parts.push(Part::Code(" }".to_string()));
+                open
+            }
+        };
+        let removed = fields
+            .iter()
+            .map(|field| {
+                (
+                    self.tokens[field.start].start,
+                    self.tokens[field.end - 1].end,
+                )
+            })
+            .collect();
+        self.relocations.push(Relocation {
+            at: self.tokens[at].end,
+            parts,
+            removed,
+        });
+    }
+
+    /// `type`, `interface` and `declare` statements, which go away entirely,
+    /// and `abstract` before a class.
+    fn declaration(&mut self, i: usize) -> Option<usize> {
+        if !self.is_ident(i + 1) || self.on_new_line(i + 1) {
+            return None;
+        }
+        if self.is(i, "abstract") {
+            if !self.is(i + 1, "class") {
+                return None;
+            }
+            self.blank(i, i + 1);
+            return Some(i + 1);
+        }
+        let end = match self.text(i) {
+            "type" => self.type_alias_end(i)?,
+            "interface" => self.interface_end(i)?,
+            _ => self.declared_end(i + 1)?,
+        };
+        self.blank(self.exported(i), end);
+        Some(end)
+    }
+
+    /// The end of `type Name<T> = ...;` starting at `type`.
+    fn type_alias_end(&self, i: usize) -> Option<usize> {
+        let mut j = i + 2;
+        if self.is(j, "<") {
+            j = self.skip_angle(j)?;
+        }
+        if !self.is(j, "=") {
+            return None;
+        }
+        j = self.skip_type(j + 1)?;
+        Some(if self.is(j, ";") { j + 1 } else { j })
+    }
+
+    /// The end of `interface Name<T> extends A, B { ... }`.
+    fn interface_end(&self, i: usize) -> Option<usize> {
+        let mut j = i + 2;
+        if self.is(j, "<") {
+            j = self.skip_angle(j)?;
+        }
+        if self.is(j, "extends") {
+            j = self.skip_type_list(j + 1);
+        }
+        self.is(j, "{").then(|| self.close(j) + 1)
+    }
+
+    /// The end of what follows `declare`.
+    fn declared_end(&self, i: usize) -> Option<usize> {
+        let mut j = i;
+        match self.text(i) {
+            "type" => return self.type_alias_end(i),
+            "interface" => return self.interface_end(i),
+            "async" => return self.declared_end(i + 1),
+            "const" | "let" | "var" => loop {
+                j += 1;
+                j = if self.is(j, "{") || self.is(j, "[") {
+                    self.close(j) + 1
+                } else {
+                    j + 1
+                };
+                if self.is(j, ":") {
+                    j = self.skip_type(j + 1)?;
+                }
+                if !self.is(j, ",") {
+                    break;
+                }
+            },
+            "function" => {
+                j = (i..self.tokens.len()).find(|&k| self.is(k, "("))?;
+                j = self.close(j) + 1;
+                if self.is(j, ":") {
+                    j = self.skip_type(j + 1)?;
+                }
+            }
+            // classes, enums, modules, namespaces and `global`
+            _ => {
+                while j < self.tokens.len() {
+                    match self.text(j) {
+                        "{" => return Some(self.close(j) + 1),
+                        ";" => return Some(j + 1),
+                        "<" => j = self.skip_angle(j)?,
+                        _ => j += 1,
+                    }
+                }
+                return Some(j);
+            }
+        }
+        Some(if self.is(j, ";") { j + 1 } else { j })
+    }
+
+    /// Whether the `type` at `i` inside import or export braces marks a
+    /// type-only specifier, `{ type A }`, rather than naming `type`.
+    fn type_modifier(&self, i: usize) -> bool {
+        self.is(i, "type")
+            && (self.is_ident(i + 1) || self.kind(i + 1) == Some(TokenKind::Str))
+            && (!self.is(i + 1, "as") || self.is(i + 2, "as"))
+    }
+
+    /// The specifiers between the braces at `open` and `close`, erasing the
+    /// `type` ones.
+    fn specifiers(&mut self, open: usize, close: usize) -> Vec<Binding> {
+        let mut values = Vec::new();
+        let mut i = open + 1;
+        while i < close {
+            let start = i;
+            let type_only = self.type_modifier(i);
+            if type_only {
+                i += 1;
+            }
+            let mut local = i;
+            i += 1;
+            if self.is(i, "as") {
+                local = i + 1;
+                i += 2;
+            }
+            if self.is(i, ",") {
+                i += 1;
+            }
+            if type_only {
+                self.blank(start, i);
+            } else {
+                values.push(Binding {
+                    start,
+                    end: i,
+                    local,
+                });
+            }
+        }
+        values
+    }
+
+    /// The end of an import or export statement from the `from` at `i`, or
+    /// from the `;` when there is none.
+    fn clause_end(&self, mut i: usize) -> usize {
+        if self.is(i, "from") {
+            i += 1;
+        }
+        if self.kind(i) == Some(TokenKind::Str) {
+            i += 1;
+        }
+        if (self.is(i, "with") || self.is(i, "assert"))
+            && self.is(i + 1, "{")
+            && !self.on_new_line(i)
+        {
+            i = self.close(i + 1) + 1;
+        }
+        if self.is(i, ";") {
+            i += 1;
+        }
+        i
+    }
+
+    fn import(&mut self, i: usize) -> usize {
+        // `import(...)` and `import.meta` are expressions
+        if self.is(i + 1, "(") || self.is(i + 1, ".") {
+            return i + 1;
+        }
+        let mut j = i + 1;
+        if self.kind(j) == Some(TokenKind::Str) {
+            return self.clause_end(j);
+        }
+        // `import type { A } from "a"`, but not a default import named `type`
+        if self.is(j, "type") && !self.is(j + 1, ",") && !self.is(j + 1, "from") {
+            let end = (j..self.tokens.len())
+                .find(|&k| self.is(k, "from"))
+                .map_or(self.tokens.len(), |k| self.clause_end(k));
+            self.blank(i, end);
+            return end;
+        }
+        let mut import = Import {
+            start: i,
+            end: i,
+            default: None,
+            comma: None,
+            namespace: None,
+            braces: None,
+            named: Vec::new(),
+        };
+        if self.is_ident(j) && !(self.is(j, "from") && self.kind(j + 1) == Some(TokenKind::Str)) {
+            // `import x = require("x")` and `import x = A.B` declare a constant
+            if self.is(j + 1, "=") {
+                self.replace(i, i + 1, "const".to_string());
+                return j;
+            }
+            import.default = Some(Binding {
+                start: j,
+                end: j + 1,
+                local: j,
+            });
+            j += 1;
+            if self.is(j, ",") {
+                import.comma = Some(j);
+                j += 1;
+            }
+        }
+        if self.is(j, "*") {
+            import.namespace = Some(Binding {
+                start: j,
+                end: j + 3,
+                local: j + 2,
+            });
+            j += 3;
+        } else if self.is(j, "{") {
+            let close = self.close(j);
+            let count = self.specifier_count(j, close);
+            import.named = self.specifiers(j, close);
+            import.braces = Some((j, close));
+            j = close + 1;
+            // nothing but types, which leaves an import for side effects with
+            // `verbatimModuleSyntax`
+            if count > 0
+                && import.named.is_empty()
+                && import.default.is_none()
+                && !self.options.verbatim_module_syntax
+            {
+                let end = self.clause_end(j);
+                self.blank(i, end);
+                return end;
+            }
+        }
+        import.end = self.clause_end(j);
+        let end = import.end;
+        self.imports.push(import);
+        end
+    }
+
+    fn specifier_count(&self, open: usize, close: usize) -> usize {
+        if open + 1 >= close {
+            return 0;
+        }
+        1 + (open + 1..close)
+            .filter(|&k| self.is(k, ",") && k + 1 < close)
+            .count()
+    }
+
+    fn export(&mut self, i: usize) -> usize {
+        let j = i + 1;
+        if let Some(namespace) = self.namespace.clone()
+            && let Some(end) = self.namespace_export(i, &namespace)
+        {
+            return end;
+        }
+        // `export = x` in a CommonJS module
+        if self.is(j, "=") {
+            self.replace(i, j + 1, "module.exports =".to_string());
+            return j + 1;
+        }
+        // `export type { A }` and `export type * from "a"`
+        if self.is(j, "type") && (self.is(j + 1, "{") || self.is(j + 1, "*")) {
+            let end = self.export_clause_end(j + 1);
+            self.blank(i, end);
+            return end;
+        }
+        if self.is(j, "{") {
+            let close = self.close(j);
+            let count = self.specifier_count(j, close);
+            let values = self.specifiers(j, close);
+            let end = self.export_clause_end(j);
+            if count > 0 && values.is_empty() {
+                self.blank(i, end);
+            }
+            return end;
+        }
+        if self.is(j, "*") {
+            return self.export_clause_end(j);
+        }
+        // `export as namespace Name;` only describes a script for typings
+        if self.is(j, "as") && self.is(j + 1, "namespace") {
+            let end = if self.is(j + 3, ";") { j + 4 } else { j + 3 };
+            self.blank(i, end);
+            return end;
+        }
+        j
+    }
+
+    /// An `export` at `i` inside a namespace, which assigns the declaration
+    /// after it to the namespace object instead.
+    fn namespace_export(&mut self, i: usize, namespace: &str) -> Option<usize> {
+        let j = i + 1;
+        match self.text(j) {
+            "const" if self.is(j + 1, "enum") => {
+                self.blank(i, j);
+                self.enum_declaration(j + 1, Some(namespace))
+            }
+            "const" | "let" | "var" => {
+                self.blank(i, j);
+                self.export_declaration = true;
+                Some(j)
+            }
+            "function" | "async" => {
+                let mut name = if self.is(j, "async") { j + 2 } else { j + 1 };
+                if self.is(name, "*") {
+                    name += 1;
+                }
+                if !self.is_ident(name) {
+                    return None;
+                }
+                self.blank(i, j);
+                self.hoisted.push(self.text(name).to_string());
+                Some(j)
+            }
+            "class" | "abstract" => {
+                let class = if self.is(j, "abstract") { j + 1 } else { j };
+                if !self.is(class, "class") || !self.is_ident(class + 1) {
+                    return None;
+                }
+                self.blank(i, class);
+                let name = self.text(class + 1);
+                let end = self.class(class);
+                // after the code a decorated class ends with
+                self.relocations.push(Relocation {
+                    at: self.tokens[end - 1].end,
+                    parts: vec![Part::Code(format!(" {namespace}.{name} = {name};"))],
+                    removed: Vec::new(),
+                });
+                Some(end)
+            }
+            "enum" => {
+                self.blank(i, j);
+                self.enum_declaration(j, Some(namespace))
+            }
+            "namespace" | "module" => {
+                self.blank(i, j);
+                self.namespace_declaration(j, Some(namespace))
+            }
+            _ => None,
+        }
+    }
+
+    /// How an enum or namespace called `name` gets its object: the existing
+    /// one when it is declared more than once, else a new one, also set on
+    /// the namespace containing it.
+    fn container_argument(name: &str, container: Option<&str>) -> String {
+        match container {
+            Some(outer) => format!("{name} = {outer}.{name} || ({outer}.{name} = {{}})"),
+            None => format!("{name} || ({name} = {{}})"),
+        }
+    }
+
+    /// `enum Name { ... }` at `i`, also `const enum`, which becomes the
+    /// object `tsc` creates for it: each member maps its name to its value,
+    /// and numeric values back to their names.
+    fn enum_declaration(&mut self, i: usize, container: Option<&str>) -> Option<usize> {
+        if !self.is_ident(i + 1) || self.on_new_line(i + 1) || !self.is(i + 2, "{") {
+            return None;
+        }
+        let name = self.text(i + 1);
+        let start = match self.prev(i) {
+            Some(p) if self.is(p, "const") => p,
+            _ => i,
+        };
+        let close = self.close(i + 2);
+        self.replace(start, i + 3, format!("var {name}; (function ({name}) {{"));
+
+        let mut members: Vec<&str> = Vec::new();
+        // the value of a member without an initializer
+        let mut next = Some(0.0);
+        let mut previous = None;
+        let mut j = i + 3;
+        while j < close {
+            let member = j;
+            let key = match self.kind(j) {
+                Some(TokenKind::Str) => string_value(self.text(j)),
+                _ => self.text(j).to_string(),
+            };
+            let key = serde_json::to_string(&key).unwrap_or_default();
+            j += 1;
+            let end_code = if self.is(j, "=") {
+                let value = j + 1;
+                j = self.scan(value, close, &|eraser, k| eraser.is(k, ","));
+                // other members are referred to by their bare names
+                for k in value..j {
+                    if self.is_ident(k)
+                        && !self.erased[k]
+                        && !self.after_dot(k)
+                        && members.contains(&self.text(k))
+                    {
+                        let member = self.text(k);
+                        self.replace(k, k + 1, format!("{name}.{member}"));
+                    }
+                }
+                let text = self.text(value);
+                let string = j == value + 1
+                    && (self.kind(value) == Some(TokenKind::Str)
+                        || (self.kind(value) == Some(TokenKind::Template) && text.ends_with('`')));
+                next = match self.kind(value) {
+                    Some(TokenKind::Number) if j == value + 1 => {
+                        number_value(text).map(|n| n + 1.0)
+                    }
+                    _ => None,
+                };
+                if string {
+                    self.replace(member, value, format!("{name}[{key}] = "));
+                    ";".to_string()
+                } else {
+                    self.replace(member, value, format!("{name}[{name}[{key}] = "));
+                    format!("] = {key};")
+                }
+            } else {
+                let value = match next {
+                    Some(n) => format_number(n),
+                    None => format!("{name}[{}] + 1", previous.unwrap_or_default()),
+                };
+                next = next.map(|n| n + 1.0);
+                self.replace(member, j, format!("{name}[{name}[{key}] = {value}"));
+                format!("] = {key};")
+            };
+            if self.is(j, ",") {
+                self.replace(j, j + 1, end_code);
+                j += 1;
+            } else {
+                self.insert_after(j - 1, end_code);
+            }
+            members.push(self.text(member));
+            previous = Some(key);
+        }
+        let argument = Self::container_argument(name, container);
+        self.replace(close, close + 1, format!("}})({argument});"));
+        Some(close + 1)
+    }
+
+    /// `namespace A.B { ... }` at `i`, or the older `module A.B { ... }`,
+    /// which becomes a function filling in the namespace object with what
+    /// its body exports.
+    fn namespace_declaration(&mut self, i: usize, container: Option<&str>) -> Option<usize> {
+        let mut names = Vec::new();
+        let mut j = i + 1;
+        while self.is_ident(j) && !self.on_new_line(j) {
+            names.push(self.text(j));
+            if !self.is(j + 1, ".") {
+                break;
+            }
+            j += 2;
+        }
+        if names.is_empty() || !self.is(j + 1, "{") {
+            return None;
+        }
+        let open = j + 1;
+        let close = self.close(open);
+        let header: String = names
+            .iter()
+            .map(|name| format!("var {name}; (function ({name}) {{ "))
+            .collect();
+        self.replace(i, open + 1, header);
+
+        let outer = std::mem::replace(
+            &mut self.namespace,
+            names.last().map(|name| name.to_string()),
+        );
+        let outer_hoisted = std::mem::take(&mut self.hoisted);
+        self.scan(open + 1, close, &|_, _| false);
+        let hoisted = std::mem::replace(&mut self.hoisted, outer_hoisted);
+        self.namespace = outer;
+
+        let inner = names.last().copied().unwrap_or_default();
+        let exports: String = hoisted
+            .iter()
+            .map(|function| format!("{inner}.{function} = {function}; "))
+            .collect();
+        self.insert_after(open, exports);
+        let footer: String = (0..names.len())
+            .rev()
+            .map(|k| {
+                let outer = if k == 0 {
+                    container
+                } else {
+                    Some(names[k - 1])
+                };
+                format!("}})({}); ", Self::container_argument(names[k], outer))
+            })
+            .collect();
+        self.replace(close, close + 1, footer.trim_end().to_string());
+        Some(close + 1)
+    }
+
+    /// The end of an export statement from its `{` or `*` at `i`.
+    fn export_clause_end(&self, i: usize) -> usize {
+        let mut j = if self.is(i, "{") {
+            self.close(i) + 1
+        } else {
+            i + 1
+        };
+        if self.is(j, "as") {
+            j += 2;
+        }
+        if self.is(j, "from") {
+            return self.clause_end(j);
+        }
+        if self.is(j, ";") { j + 1 } else { j }
+    }
+
+    /// Drops the imports that are only used as types, which the erasure
+    /// has just removed: their modules may not export anything at runtime
+    /// under those names.
+    fn elide_imports(&mut self) {
+        let imports = std::mem::take(&mut self.imports);
+        let mut inside = vec![false; self.tokens.len()];
+        for import in &imports {
+            inside[import.start..import.end.min(self.tokens.len())].fill(true);
+        }
+        let used: HashSet<&str> = (0..self.tokens.len())
+            .filter(|&i| !self.erased[i] && !inside[i] && self.is_ident(i))
+            .map(|i| self.text(i))
+            .chain(self.metadata_names.iter().map(String::as_str))
+            .collect();
+        let is_used = |binding: &Option<Binding>| {
+            binding
+                .as_ref()
+                .is_some_and(|binding| used.contains(self.text(binding.local)))
+        };
+
+        let mut blanks = Vec::new();
+        for import in imports {
+            let keep_default = is_used(&import.default);
+            let keep_namespace = is_used(&import.namespace);
+            let (kept, unused): (Vec<_>, Vec<_>) = import
+                .named
+                .iter()
+                .partition(|binding| used.contains(self.text(binding.local)));
+            let has_bindings =
+                import.default.is_some() || import.namespace.is_some() || !import.named.is_empty();
+            if !has_bindings {
+                continue;
+            }
+            if !keep_default && !keep_namespace && kept.is_empty() {
+                blanks.push((import.start, import.end));
+                continue;
+            }
+            let comma_end = import.comma.map_or(0, |comma| comma + 1);
+            if let Some(default) = &import.default
+                && !keep_default
+            {
+                blanks.push((default.start, comma_end.max(default.end)));
+            }
+            if let Some(namespace) = &import.namespace
+                && !keep_namespace
+            {
+                blanks.push((import.comma.unwrap_or(namespace.start), namespace.end));
+            }
+            if let Some((open, close)) = import.braces
+                && kept.is_empty()
+                && !import.named.is_empty()
+            {
+                blanks.push((import.comma.unwrap_or(open), close + 1));
+                continue;
+            }
+            for binding in unused {
+                blanks.push((binding.start, binding.end));
+            }
+        }
+        for (start, end) in blanks {
+            self.blank(start, end);
+        }
+    }
+
+    /// The end of `A, B<T>, C`, as after `implements`.
+    fn skip_type_list(&self, mut i: usize) -> usize {
+        while let Some(end) = self.skip_type(i) {
+            i = end;
+            if !self.is(i, ",") {
+                break;
+            }
+            i += 1;
+        }
+        i
+    }
+
+    /// The end of the type parameters or arguments at the `<` at `i`.
+    fn skip_angle(&self, i: usize) -> Option<usize> {
+        let mut j = i + 1;
+        loop {
+            while matches!(self.text(j), "in" | "out" | "const") && self.is_ident(j + 1) {
+                j += 1;
+            }
+            j = self.skip_type(j)?;
+            if self.is(j, "extends") {
+                j = self.skip_type(j + 1)?;
+            }
+            if self.is(j, "=") {
+                j = self.skip_type(j + 1)?;
+            }
+            match self.text(j) {
+                ">" => return Some(j + 1),
+                "," if self.is(j + 1, ">") => return Some(j + 2),
+                "," => j += 1,
+                _ => return None,
+            }
+        }
+    }
+
+    /// The end of the type starting at `i`, if a type starts there.
+    fn skip_type(&self, i: usize) -> Option<usize> {
+        let mut j = i;
+        if self.is(j, "|") || self.is(j, "&") {
+            j += 1;
+        }
+        loop {
+            j = self.skip_type_operand(j)?;
+            if !self.is(j, "|") && !self.is(j, "&") {
+                break;
+            }
+            j += 1;
+        }
+        // a conditional type, `T extends U ? X : Y`
+        if self.is(j, "extends")
+            && let Some(check) = self.skip_type(j + 1)
+            && self.is(check, "?")
+            && let Some(then) = self.skip_type(check + 1)
+            && self.is(then, ":")
+        {
+            return self.skip_type(then + 1);
+        }
+        Some(j)
+    }
+
+    fn skip_type_operand(&self, mut i: usize) -> Option<usize> {
+        while matches!(
+            self.text(i),
+            "keyof" | "typeof" | "readonly" | "unique" | "infer" | "asserts" | "abstract"
+        ) && (self.is_ident(i + 1) || matches!(self.text(i + 1), "(" | "[" | "{" | "<"))
+        {
+            i += 1;
+        }
+        // constructor types, `new (x: T) => U`
+        if self.is(i, "new") && matches!(self.text(i + 1), "(" | "<") {
+            i += 1;
+        }
+        if self.is(i, "-") && self.kind(i + 1) == Some(TokenKind::Number) {
+            i += 1;
+        }
+        let mut j = match (self.kind(i)?, self.text(i)) {
+            (TokenKind::Punct, "(") => {
+                let close = self.close(i);
+                // a function type, `(x: T) => U`
+                if self.is(close + 1, "=>") {
+                    return self.skip_type(close + 2);
+                }
+                close + 1
+            }
+            (TokenKind::Punct, "<") => {
+                let open = self.skip_angle(i)?;
+                let close = self.close(open);
+                if !self.is(open, "(") || !self.is(close + 1, "=>") {
+                    return None;
+                }
+                return self.skip_type(close + 2);
+            }
+            (TokenKind::Punct, "{" | "[") => self.close(i) + 1,
+            (TokenKind::Str | TokenKind::Number, _) => i + 1,
+            (TokenKind::Template, _) => self.skip_template_type(i)?,
+            (TokenKind::Ident, _) => {
+                // a type predicate, `x is T`
+                if self.is(i + 1, "is") && !self.on_new_line(i + 1) {
+                    return self.skip_type(i + 2);
+                }
+                let mut j = i + 1;
+                if self.is(i, "import") && self.is(j, "(") {
+                    j = self.close(j) + 1;
+                }
+                while self.is(j, ".") && self.is_ident(j + 1) {
+                    j += 2;
+                }
+                if self.is(j, "<")
+                    && !self.on_new_line(j)
+                    && let Some(end) = self.skip_angle(j)
+                {
+                    j = end;
+                }
+                j
+            }
+            _ => return None,
+        };
+        // array types and indexed access, `T[]` and `T["key"]`
+        while self.is(j, "[") && !self.on_new_line(j) {
+            j = self.close(j) + 1;
+        }
+        Some(j)
+    }
+
+    /// The end of a template literal type, `` `id-${number}` ``.
+    fn skip_template_type(&self, mut i: usize) -> Option<usize> {
+        loop {
+            if self.kind(i) != Some(TokenKind::Template) {
+                return None;
+            }
+            if !self.text(i).ends_with("${") {
+                return Some(i + 1);
+            }
+            i = self.skip_type(i + 1)?;
+        }
+    }
+
+    /// The source with every erased range blanked, line breaks kept, and the
+    /// inserted code added.
+    fn output(&self) -> Generated {
+        let mut blanked = vec![false; self.src.len()];
+        for &(start, end) in &self.ranges {
+            blanked[start..end].fill(true);
+        }
+        let mut inserts: Vec<(usize, Generated)> = self
+            .inserts
+            .iter()
+            .map(|(at, code)| {
+                let mut generated = Generated::new();
+                generated.push_code(code, *at);
+                (*at, generated)
+            })
+            .collect();
+        // a class nested in moved code is done first, since it ends first
+        for relocation in &self.relocations {
+            let mut code = Generated::new();
+            for part in &relocation.parts {
+                match part {
+                    Part::Code(text) => code.push_code(text, relocation.at),
+                    Part::Source(start, end) => {
+                        code.append(self.render(*start, *end, &blanked, &inserts))
+                    }
+                }
+            }
+            for &(start, end) in &relocation.removed {
+                blanked[start..end].fill(true);
+            }
+            // what was inserted into the moved code went along with it
+            inserts.retain(|(at, _)| {
+                !relocation
+                    .removed
+                    .iter()
+                    .any(|&(start, end)| start < *at && *at < end)
+            });
+            inserts.push((relocation.at, code));
+        }
+        self.render(0, self.src.len(), &blanked, &inserts)
+    }
+
+    /// The source between byte offsets `start` and `end` as it's output.
+    fn render(
+        &self,
+        start: usize,
+        end: usize,
+        blanked: &[bool],
+        inserts: &[(usize, Generated)],
+    ) -> Generated {
+        let mut inserts: Vec<&(usize, Generated)> = inserts
+            .iter()
+            .filter(|(at, _)| *at >= start && (*at < end || end == self.src.len()))
+            .collect();
+        inserts.sort_by_key(|(at, _)| *at);
+        let mut inserts = inserts.into_iter().peekable();
+
+        let mut out = Generated::new();
+        out.copy_from(start);
+        for (i, c) in self.src[start..end].char_indices() {
+            let i = start + i;
+            if inserts.peek().is_some_and(|(at, _)| *at <= i) {
+                while let Some((_, code)) = inserts.next_if(|(at, _)| *at <= i) {
+                    out.append(code.clone());
+                }
+                out.copy_from(i);
+            }
+            if blanked[i] && c != '\n' && c != '\r' {
+                out.push(' ');
+            } else {
+                out.push(c);
+            }
+        }
+        for (_, code) in inserts {
+            out.append(code.clone());
+        }
+        out
+    }
+}
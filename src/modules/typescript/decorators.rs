@@ -0,0 +1,717 @@
+//! Decorators, which JavaScript engines don't run yet.
+//!
+//! A decorated class becomes a call to the helpers in `decorators.js`. With
+//! the standard decorators the class is wrapped in
+//! `__lunos_decorators.standard([class decorators], (helpers) => class ...)`
+//! and a static block at the start of its body applies the decorators of its
+//! members. With `experimentalDecorators` the class is passed to
+//! `__lunos_decorators.legacy` along with a function calling `decorate` on
+//! each member and then the class, like the `__decorate` calls `tsc` emits.
+//! `accessor` fields become a private field with a getter and setter.
+
+use super::{ClassBody, Eraser, Part, Relocation};
+use crate::modules::lexer::TokenKind;
+
+/// A decorator, the tokens from its `@` to its end.
+#[derive(Clone, Copy)]
+pub(super) struct Decorator {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A parameter of a method or constructor, as far as decorators go.
+pub(super) struct Param {
+    pub decorators: Vec<Decorator>,
+    /// The tokens of its type.
+    pub annotation: Option<(usize, usize)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum MemberKind {
+    Method,
+    Getter,
+    Setter,
+    Field,
+    Accessor,
+}
+
+impl MemberKind {
+    /// The `kind` the context of a standard decorator has.
+    fn name(self) -> &'static str {
+        match self {
+            MemberKind::Method => "method",
+            MemberKind::Getter => "getter",
+            MemberKind::Setter => "setter",
+            MemberKind::Field => "field",
+            MemberKind::Accessor => "accessor",
+        }
+    }
+}
+
+/// A decorated class member.
+pub(super) struct Member {
+    pub decorators: Vec<Decorator>,
+    pub kind: MemberKind,
+    pub is_static: bool,
+    pub is_async: bool,
+    /// The token of its name, or the `[` of a computed one.
+    pub name: usize,
+    pub params: Vec<Param>,
+    /// The tokens of the type of a field or the return type of a method.
+    pub annotation: Option<(usize, usize)>,
+}
+
+/// The name of the parameter the helpers of the class at `class` are passed
+/// in.
+fn helpers(class: usize) -> String {
+    format!("__lunos_d{class}")
+}
+
+/// The private field an `accessor` named `name` stores its value in.
+fn accessor_storage(name: &str) -> String {
+    format!("#{}_accessor_storage", name.trim_start_matches('#'))
+}
+
+/// Joins lists of parts with commas.
+fn join(items: Vec<Vec<Part>>) -> Vec<Part> {
+    let mut parts = Vec::new();
+    for (n, item) in items.into_iter().enumerate() {
+        if n > 0 {
+            parts.push(Part::Code(", ".to_string()));
+        }
+        parts.extend(item);
+    }
+    parts
+}
+
+impl Eraser<'_> {
+    /// The decorators starting at `i`, erasing the types in them, and the
+    /// token after them.
+    pub(super) fn decorators(&mut self, mut i: usize) -> (Vec<Decorator>, usize) {
+        let mut decorators = Vec::new();
+        while self.is(i, "@") {
+            let end = self.decorator(i);
+            if end == i + 1 {
+                break;
+            }
+            decorators.push(Decorator { start: i, end });
+            i = end;
+        }
+        (decorators, i)
+    }
+
+    /// The end of the decorator at `at`: `@(expression)`, or a name, maybe
+    /// qualified, maybe called.
+    fn decorator(&mut self, at: usize) -> usize {
+        let mut i = at + 1;
+        if self.is(i, "(") {
+            let close = self.close(i);
+            self.scan(i + 1, close, &|_, _| false);
+            return close + 1;
+        }
+        if !self.is_ident(i) {
+            return at + 1;
+        }
+        i += 1;
+        while self.is(i, ".") && self.is_ident(i + 1) {
+            i += 2;
+        }
+        if self.is(i, "<")
+            && let Some(end) = self.skip_angle(i)
+            && self.is(end, "(")
+        {
+            self.blank(i, end);
+            i = end;
+        }
+        if self.is(i, "(") {
+            let close = self.close(i);
+            self.scan(i + 1, close, &|_, _| false);
+            i = close + 1;
+        }
+        i
+    }
+
+    /// The tokens of the type after the `:` at `i`, if there is one.
+    pub(super) fn type_after(&self, i: usize) -> Option<(usize, usize)> {
+        if !self.is(i, ":") {
+            return None;
+        }
+        self.skip_type(i + 1).map(|end| (i + 1, end))
+    }
+
+    /// The tokens of the type of the binding at `i`.
+    pub(super) fn binding_type(&self, i: usize) -> Option<(usize, usize)> {
+        let mut j = if self.is(i, "{") || self.is(i, "[") {
+            self.close(i) + 1
+        } else {
+            i + 1
+        };
+        if self.is(j, "?") || self.is(j, "!") {
+            j += 1;
+        }
+        self.type_after(j)
+    }
+
+    fn is_private(&self, name: usize) -> bool {
+        self.text(name).starts_with('#')
+    }
+
+    /// Erases the decorators of a member they can't apply to: legacy
+    /// decorators of private members.
+    fn drop_decorators(&mut self, member: &Member) {
+        for decorator in &member.decorators {
+            self.blank(decorator.start, decorator.end);
+        }
+    }
+
+    /// Adds a method, getter or setter to the decorated members of `body`
+    /// when it's decorated. A private one is renamed and replaced with an
+    /// accessor that calls what the decorators made of it, after the `}` at
+    /// `body_close`, since standard decorators can't replace it otherwise.
+    pub(super) fn method_member(
+        &mut self,
+        body: &mut ClassBody,
+        member: Member,
+        body_close: usize,
+    ) {
+        let params_decorated = self.options.experimental_decorators
+            && member
+                .params
+                .iter()
+                .any(|param| !param.decorators.is_empty());
+        if member.decorators.is_empty() && !params_decorated {
+            return;
+        }
+        if self.is_private(member.name) {
+            if self.options.experimental_decorators {
+                self.drop_decorators(&member);
+                return;
+            }
+            let slot = body.members.len();
+            let helpers = helpers(body.id);
+            let name = self.text(member.name);
+            let (suffix, replacement) = match member.kind {
+                MemberKind::Getter => (
+                    "getter",
+                    format!("get {name}() {{ return {helpers}.get({slot}).call(this); }}"),
+                ),
+                MemberKind::Setter => (
+                    "setter",
+                    format!("set {name}(value) {{ {helpers}.set({slot}).call(this, value); }}"),
+                ),
+                _ => (
+                    "method",
+                    format!("get {name}() {{ return {helpers}.value({slot}); }}"),
+                ),
+            };
+            self.replace(member.name, member.name + 1, format!("{name}_{suffix}"));
+            let modifier = if member.is_static { "static " } else { "" };
+            self.insert_after(body_close, format!(" {modifier}{replacement}"));
+        }
+        body.members.push(member);
+    }
+
+    /// Adds a field or `accessor` to the decorated members of `body` when
+    /// it's decorated, lowering an `accessor` at all events. With standard
+    /// decorators its value, the tokens `init` ending before `value_end`,
+    /// goes through the initializers the decorators add; for a field that's
+    /// `moved` to the constructor that call is returned for it to use.
+    pub(super) fn field_member(
+        &mut self,
+        body: &mut ClassBody,
+        member: Member,
+        accessor: Option<usize>,
+        init: Option<(usize, usize)>,
+        value_end: usize,
+        moved: bool,
+    ) -> Option<String> {
+        let legacy = self.options.experimental_decorators;
+        let private = self.is_private(member.name);
+        // legacy decorators don't apply to private members
+        let applies = !legacy || !private;
+        if !applies {
+            self.drop_decorators(&member);
+        }
+        let decorated = applies && !member.decorators.is_empty();
+        let slot = body.members.len();
+        let helpers = helpers(body.id);
+        let mut wrap = None;
+        if decorated && !legacy {
+            let call = format!("{helpers}.init(this, {slot}");
+            if moved {
+                wrap = Some(call);
+            } else if let Some((start, end)) = init {
+                self.insert_before(start, format!("{call}, "));
+                self.insert_after(end - 1, ")".to_string());
+            } else {
+                self.insert_after(value_end - 1, format!(" = {call})"));
+            }
+        }
+        if let Some(accessor) = accessor
+            && self.is_ident(member.name)
+        {
+            let name = self.text(member.name);
+            let storage = accessor_storage(name);
+            let (get, set) = if decorated && !legacy && private {
+                (
+                    format!("return {helpers}.get({slot}).call(this);"),
+                    format!("{helpers}.set({slot}).call(this, value);"),
+                )
+            } else {
+                (
+                    format!("return this.{storage};"),
+                    format!("this.{storage} = value;"),
+                )
+            };
+            let modifier = if member.is_static { "static " } else { "" };
+            self.blank(accessor, accessor + 1);
+            self.replace(member.name, member.name + 1, storage);
+            self.insert_after(
+                value_end - 1,
+                format!(
+                    "; {modifier}get {name}() {{ {get} }} {modifier}set {name}(value) {{ {set} }}"
+                ),
+            );
+        }
+        if decorated {
+            body.members.push(member);
+        }
+        wrap
+    }
+
+    /// The code of the decorator expressions of `decorators`.
+    fn decorator_parts(&self, decorators: &[Decorator]) -> Vec<Vec<Part>> {
+        decorators
+            .iter()
+            .map(|decorator| {
+                vec![Part::Source(
+                    self.tokens[decorator.start + 1].start,
+                    self.tokens[decorator.end - 1].end,
+                )]
+            })
+            .collect()
+    }
+
+    fn decorator_ranges(&self, decorators: &[Decorator]) -> Vec<(usize, usize)> {
+        decorators
+            .iter()
+            .map(|decorator| {
+                (
+                    self.tokens[decorator.start].start,
+                    self.tokens[decorator.end - 1].end,
+                )
+            })
+            .collect()
+    }
+
+    /// The key of the member named at `name`: a string for a name, or the
+    /// expression of a computed key.
+    fn member_key(&self, name: usize) -> Part {
+        match self.kind(name) {
+            Some(TokenKind::Ident) => Part::Code(format!("\"{}\"", self.text(name))),
+            _ if self.is(name, "[") => {
+                let close = self.close(name);
+                Part::Source(self.tokens[name + 1].start, self.tokens[close - 1].end)
+            }
+            _ => Part::Code(self.text(name).to_string()),
+        }
+    }
+
+    /// Rewrites the class at `class`, whose body is between `open` and
+    /// `close`, to apply its decorators once it's defined. A class
+    /// declaration becomes a `let` holding what the decorators return.
+    pub(super) fn decorate_class(
+        &mut self,
+        class: usize,
+        open: usize,
+        close: usize,
+        decorators: Vec<Decorator>,
+        body: &ClassBody,
+    ) {
+        let legacy = self.options.experimental_decorators;
+        let constructor_decorated = legacy
+            && body
+                .constructor_params
+                .as_ref()
+                .is_some_and(|params| params.iter().any(|param| !param.decorators.is_empty()));
+        if decorators.is_empty() && body.members.is_empty() && !constructor_decorated {
+            return;
+        }
+
+        // `@dec export class`, `export @dec class` and `export default` too
+        let first = decorators
+            .first()
+            .map_or(class, |decorator| decorator.start);
+        let start = self.exported(first);
+        let keyword = |text: &str| {
+            (start..class).find(|&k| {
+                self.is(k, text)
+                    && !decorators
+                        .iter()
+                        .any(|decorator| (decorator.start..decorator.end).contains(&k))
+            })
+        };
+        let export = keyword("export");
+        let default = keyword("default");
+        let declaration = match self.prev(start) {
+            None => true,
+            Some(p) => {
+                matches!(self.text(p), ";" | "{" | "}")
+                    || (self.on_new_line(start) && self.ends_expression(p))
+            }
+        };
+        let binding = Some(class + 1)
+            .filter(|&name| {
+                declaration
+                    && self.is_ident(name)
+                    && !matches!(self.text(name), "extends" | "implements")
+            })
+            .map(|name| self.text(name));
+
+        let mut prefix = binding.map_or(String::new(), |name| format!("let {name} = "));
+        let mut suffix = if binding.is_some() { ";" } else { "" }.to_string();
+        if let (Some(export), Some(default), Some(name)) = (export, default, binding) {
+            self.blank(export, export + 1);
+            self.blank(default, default + 1);
+            suffix.push_str(&format!(" export default {name};"));
+        }
+
+        if legacy {
+            prefix.push_str("__lunos_decorators.legacy(");
+            self.relocations.push(Relocation {
+                at: self.tokens[class].start,
+                parts: vec![Part::Code(prefix)],
+                removed: Vec::new(),
+            });
+            self.legacy_decorators(close, decorators, body, suffix);
+            return;
+        }
+
+        let helpers = helpers(body.id);
+        let mut parts = vec![Part::Code(format!("{prefix}__lunos_decorators.standard(["))];
+        parts.extend(join(self.decorator_parts(&decorators)));
+        parts.push(Part::Code(format!("], ({helpers}) => ")));
+        self.relocations.push(Relocation {
+            at: self.tokens[class].start,
+            parts,
+            removed: self.decorator_ranges(&decorators),
+        });
+        self.standard_decorators(open, body);
+        self.relocations.push(Relocation {
+            at: self.tokens[close].end,
+            parts: vec![Part::Code(format!("){suffix}"))],
+            removed: Vec::new(),
+        });
+    }
+
+    /// The static block applying standard decorators to the members of the
+    /// class whose body opens at `open`: first the methods and accessors,
+    /// static ones first, then the fields. Instance members also get their
+    /// initializers run by the first instance field.
+    fn standard_decorators(&mut self, open: usize, body: &ClassBody) {
+        let helpers = helpers(body.id);
+        let mut parts = vec![Part::Code(format!(" static {{ {helpers}.begin(this, ["))];
+        parts.extend(join(
+            body.members
+                .iter()
+                .map(|member| {
+                    let mut list = vec![Part::Code("[".to_string())];
+                    list.extend(join(self.decorator_parts(&member.decorators)));
+                    list.push(Part::Code("]".to_string()));
+                    list
+                })
+                .collect(),
+        ));
+        parts.push(Part::Code("]);".to_string()));
+
+        let is_field = |member: &Member| member.kind == MemberKind::Field;
+        let mut order: Vec<usize> = (0..body.members.len()).collect();
+        order.sort_by_key(|&slot| {
+            let member = &body.members[slot];
+            (is_field(member), !member.is_static)
+        });
+        for slot in order {
+            let member = &body.members[slot];
+            let private = self.is_private(member.name);
+            parts.push(Part::Code(format!(
+                " {helpers}.member(this, {slot}, {{ kind: \"{}\", name: ",
+                member.kind.name()
+            )));
+            parts.push(self.member_key(member.name));
+            parts.push(Part::Code(format!(
+                ", static: {}, private: {private}",
+                member.is_static
+            )));
+            if private {
+                parts.push(Part::Code(self.private_access(member)));
+            }
+            parts.push(Part::Code(" });".to_string()));
+        }
+        parts.push(Part::Code(format!(" {helpers}.end(this); }}")));
+        if body.members.iter().any(|member| !member.is_static) {
+            parts.push(Part::Code(format!(
+                " #__lunos_init = {helpers}.construct(this);"
+            )));
+        }
+        let removed = body
+            .members
+            .iter()
+            .flat_map(|member| self.decorator_ranges(&member.decorators))
+            .collect();
+        self.relocations.push(Relocation {
+            at: self.tokens[open].end,
+            parts,
+            removed,
+        });
+    }
+
+    /// The `access` of the context of a private member, and the function or
+    /// accessors a private member's decorators start from, which reach it
+    /// under the name `method_member` or `field_member` gave it.
+    fn private_access(&self, member: &Member) -> String {
+        let name = self.text(member.name);
+        let get = format!("get: (o) => o.{name}");
+        let set = format!("set: (o, v) => {{ o.{name} = v; }}");
+        let access = match member.kind {
+            MemberKind::Method | MemberKind::Getter => get,
+            MemberKind::Setter => set,
+            MemberKind::Field | MemberKind::Accessor => format!("{get}, {set}"),
+        };
+        let original = match member.kind {
+            MemberKind::Method => {
+                format!(", value: function (...args) {{ return this.{name}_method(...args); }}")
+            }
+            MemberKind::Getter => format!(", get: function () {{ return this.{name}_getter; }}"),
+            MemberKind::Setter => {
+                format!(", set: function (value) {{ this.{name}_setter = value; }}")
+            }
+            MemberKind::Accessor => {
+                let storage = accessor_storage(name);
+                format!(
+                    ", get: function () {{ return this.{storage}; }}, set: function (value) {{ this.{storage} = value; }}"
+                )
+            }
+            MemberKind::Field => String::new(),
+        };
+        format!(", access: {{ has: (o) => {name} in o, {access} }}{original}")
+    }
+
+    /// Ends the class at `close` with the function applying legacy
+    /// decorators to it, instance members first, then static ones, then the
+    /// class itself.
+    fn legacy_decorators(
+        &mut self,
+        close: usize,
+        decorators: Vec<Decorator>,
+        body: &ClassBody,
+        suffix: String,
+    ) {
+        let mut parts = vec![Part::Code(", (__lunos_class) => {".to_string())];
+        let mut removed = self.decorator_ranges(&decorators);
+        let statics = [false, true];
+        for is_static in statics {
+            for member in body.members.iter().filter(|m| m.is_static == is_static) {
+                let mut items = self.decorator_parts(&member.decorators);
+                items.extend(self.param_decorators(&member.params));
+                items.extend(self.member_metadata(member));
+                removed.extend(self.decorator_ranges(&member.decorators));
+                for param in &member.params {
+                    removed.extend(self.decorator_ranges(&param.decorators));
+                }
+                parts.push(Part::Code(" __lunos_decorators.decorate([".to_string()));
+                parts.extend(join(items));
+                let target = if is_static {
+                    "__lunos_class"
+                } else {
+                    "__lunos_class.prototype"
+                };
+                parts.push(Part::Code(format!("], {target}, ")));
+                parts.push(self.member_key(member.name));
+                let descriptor = if member.kind == MemberKind::Field {
+                    "void 0"
+                } else {
+                    "null"
+                };
+                parts.push(Part::Code(format!(", {descriptor});")));
+            }
+        }
+
+        let params = body.constructor_params.as_deref().unwrap_or_default();
+        let mut items = self.decorator_parts(&decorators);
+        items.extend(self.param_decorators(params));
+        for param in params {
+            removed.extend(self.decorator_ranges(&param.decorators));
+        }
+        if items.is_empty() {
+            parts.push(Part::Code(" return __lunos_class; })".to_string()));
+        } else {
+            if self.options.emit_decorator_metadata && body.constructor_params.is_some() {
+                let types = self.param_types(params);
+                items.push(metadata("design:paramtypes", &types));
+            }
+            parts.push(Part::Code(
+                " return __lunos_decorators.decorate([".to_string(),
+            ));
+            parts.extend(join(items));
+            parts.push(Part::Code("], __lunos_class); })".to_string()));
+        }
+        parts.push(Part::Code(suffix));
+        self.relocations.push(Relocation {
+            at: self.tokens[close].end,
+            parts,
+            removed,
+        });
+    }
+
+    /// The decorators of parameters, `__lunos_decorators.param(index, dec)`.
+    fn param_decorators(&self, params: &[Param]) -> Vec<Vec<Part>> {
+        let mut items = Vec::new();
+        for (index, param) in params.iter().enumerate() {
+            for decorator in self.decorator_parts(&param.decorators) {
+                let mut item = vec![Part::Code(format!("__lunos_decorators.param({index}, "))];
+                item.extend(decorator);
+                item.push(Part::Code(")".to_string()));
+                items.push(item);
+            }
+        }
+        items
+    }
+
+    /// The `design:` metadata of a member with `emitDecoratorMetadata`.
+    fn member_metadata(&mut self, member: &Member) -> Vec<Vec<Part>> {
+        if !self.options.emit_decorator_metadata {
+            return Vec::new();
+        }
+        match member.kind {
+            MemberKind::Method => {
+                let params = self.param_types(&member.params);
+                let returns = match member.annotation {
+                    Some(_) => self.serialize_type(member.annotation),
+                    None if member.is_async => "Promise".to_string(),
+                    None => "void 0".to_string(),
+                };
+                vec![
+                    metadata("design:type", "Function"),
+                    metadata("design:paramtypes", &params),
+                    metadata("design:returntype", &returns),
+                ]
+            }
+            MemberKind::Setter => {
+                let annotation = member.params.first().and_then(|param| param.annotation);
+                let value = self.serialize_type(annotation);
+                let params = self.param_types(&member.params);
+                vec![
+                    metadata("design:type", &value),
+                    metadata("design:paramtypes", &params),
+                ]
+            }
+            _ => {
+                let value = self.serialize_type(member.annotation);
+                vec![metadata("design:type", &value)]
+            }
+        }
+    }
+
+    fn param_types(&mut self, params: &[Param]) -> String {
+        let types: Vec<String> = params
+            .iter()
+            .map(|param| self.serialize_type(param.annotation))
+            .collect();
+        format!("[{}]", types.join(", "))
+    }
+
+    /// The runtime value standing for the type in tokens `annotation`, the
+    /// way `tsc` serializes types for decorator metadata: the constructor of
+    /// its values where there is one, `Object` otherwise, `void 0` for no
+    /// value at all.
+    fn serialize_type(&mut self, annotation: Option<(usize, usize)>) -> String {
+        let Some((start, end)) = annotation else {
+            return "Object".to_string();
+        };
+        let mut i = start;
+        if self.is(i, "|") {
+            i += 1;
+        }
+        // a union of one type, besides null and undefined, is that type
+        let mut types: Vec<String> = Vec::new();
+        loop {
+            let Some(operand_end) = self.skip_type_operand(i).filter(|&j| j <= end) else {
+                return "Object".to_string();
+            };
+            let serialized = self.serialize_operand(i, operand_end);
+            if serialized != "void 0" && !types.contains(&serialized) {
+                types.push(serialized);
+            }
+            if operand_end == end {
+                break;
+            }
+            if !self.is(operand_end, "|") {
+                return "Object".to_string();
+            }
+            i = operand_end + 1;
+        }
+        match types.len() {
+            0 => "void 0".to_string(),
+            1 => types.remove(0),
+            _ => "Object".to_string(),
+        }
+    }
+
+    fn serialize_operand(&mut self, i: usize, end: usize) -> String {
+        let serialized = match self.kind(i) {
+            Some(TokenKind::Str | TokenKind::Template) => "String",
+            Some(TokenKind::Number) => "Number",
+            // arrays, tuples and indexed access, `T[]`, `[A, B]`, `T["key"]`
+            _ if self.is(end - 1, "]") => {
+                let open = self.close(end - 1);
+                if open == i || open + 1 == end - 1 {
+                    "Array"
+                } else {
+                    "Object"
+                }
+            }
+            _ if self.is(i + 1, "is") => "Boolean",
+            _ => match self.text(i) {
+                "(" => {
+                    let close = self.close(i);
+                    // a function type, else a type in parentheses
+                    if close + 1 < end {
+                        "Function"
+                    } else {
+                        return self.serialize_type(Some((i + 1, close)));
+                    }
+                }
+                "readonly" => return self.serialize_operand(i + 1, end),
+                "new" | "<" => "Function",
+                "-" => "Number",
+                "string" => "String",
+                "number" => "Number",
+                "boolean" | "true" | "false" => "Boolean",
+                "bigint" => "BigInt",
+                "symbol" | "unique" => "Symbol",
+                "void" | "undefined" | "null" | "never" => "void 0",
+                "{" | "any" | "unknown" | "object" | "this" | "keyof" | "typeof" | "infer"
+                | "import" => "Object",
+                name => {
+                    // a reference to a class or other value, `A.B<T>`
+                    self.metadata_names.insert(name.to_string());
+                    let mut path = name.to_string();
+                    let mut j = i + 1;
+                    while self.is(j, ".") && self.is_ident(j + 1) && j + 1 < end {
+                        path.push('.');
+                        path.push_str(self.text(j + 1));
+                        j += 2;
+                    }
+                    return format!("__lunos_decorators.type(() => {path})");
+                }
+            },
+        };
+        serialized.to_string()
+    }
+}
+
+fn metadata(key: &str, value: &str) -> Vec<Part> {
+    vec![Part::Code(format!(
+        "__lunos_decorators.metadata(\"{key}\", {value})"
+    ))]
+}
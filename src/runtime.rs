@@ -0,0 +1,428 @@
+use crate::lunos::options::UnhandledRejections;
+use crate::modules::builtins::NativeModule;
+use crate::modules::console::Console;
+use crate::modules::convert::{JsObject, JsString, JsValue};
+use crate::modules::es6;
+use crate::modules::exception;
+use crate::modules::import_map::{self, ImportMap};
+use crate::modules::lunos::Lunos;
+use crate::modules::native::{NativeCallback, define_functions};
+use crate::modules::node::Node;
+use crate::modules::registry::Registry;
+use crate::modules::rejections::Rejections;
+use crate::modules::remote::LockFile;
+use crate::modules::resolver;
+use crate::modules::state::State;
+use crate::modules::timers::Timers;
+use rusty_jsc::*;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Chooses what a `Runtime` gets installed. Everything is by default.
+pub struct RuntimeBuilder {
+    console: bool,
+    lunos: bool,
+    timers: bool,
+    node: bool,
+    import_map: Option<PathBuf>,
+    offline: bool,
+    no_cache: bool,
+    unhandled_rejections: UnhandledRejections,
+    argv: Vec<String>,
+    functions: Vec<(String, NativeCallback)>,
+    modules: Vec<(String, Vec<(String, NativeCallback)>)>,
+}
+
+/// A JavaScript context with the Lunos globals and module system, and what
+/// it keeps between calls: its console buffer, timers, servers and modules.
+/// Runtimes don't share any of it, several can run side by side on a
+/// thread. Dropping one releases its context.
+pub struct Runtime {
+    context: *mut OpaqueJSContext,
+    state: Rc<State>,
+    import_map: Option<PathBuf>,
+}
+
+/// Why running a script or module failed.
+#[derive(Debug)]
+pub enum RuntimeError {
+    /// The entry module couldn't be read.
+    Read { path: PathBuf, error: io::Error },
+    /// The import map, a `tsconfig.json` or the lock file is invalid, or an
+    /// entry module is outside the project of the first.
+    Config(String),
+    /// Something was thrown and not caught.
+    Uncaught(Exception),
+    /// The event loop ran out of work while the entry module was still
+    /// waiting on a top-level `await`.
+    UnsettledTopLevelAwait,
+}
+
+/// A value that was thrown and not caught. It's protected from garbage
+/// collection until the exception is dropped.
+#[derive(Debug)]
+pub struct Exception {
+    context: *mut OpaqueJSContext,
+    value: JsValue,
+    report: String,
+}
+
+impl Default for RuntimeBuilder {
+    fn default() -> Self {
+        RuntimeBuilder {
+            console: true,
+            lunos: true,
+            timers: true,
+            node: true,
+            import_map: None,
+            offline: false,
+            no_cache: false,
+            unhandled_rejections: UnhandledRejections::Strict,
+            argv: Vec::new(),
+            functions: Vec::new(),
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl RuntimeBuilder {
+    pub fn new() -> Self {
+        RuntimeBuilder::default()
+    }
+
+    /// The `console` global.
+    pub fn console(mut self, enabled: bool) -> Self {
+        self.console = enabled;
+        self
+    }
+
+    /// The `Lunos` global, and the `lunos:` modules made of it.
+    pub fn lunos(mut self, enabled: bool) -> Self {
+        self.lunos = enabled;
+        self
+    }
+
+    /// `setTimeout`, `setInterval` and clearing them.
+    pub fn timers(mut self, enabled: bool) -> Self {
+        self.timers = enabled;
+        self
+    }
+
+    /// The `node:` modules, `fs` and `http` among them, and what they run on.
+    pub fn node(mut self, enabled: bool) -> Self {
+        self.node = enabled;
+        self
+    }
+
+    /// The import map entry modules resolve through, instead of the one in
+    /// the `lunos.json` of their project.
+    pub fn import_map(mut self, path: impl Into<PathBuf>) -> Self {
+        self.import_map = Some(path.into());
+        self
+    }
+
+    /// Only use URL imports that are already cached, never the network.
+    pub fn offline(mut self, enabled: bool) -> Self {
+        self.offline = enabled;
+        self
+    }
+
+    /// Transform every module again instead of using the transform cache.
+    pub fn no_cache(mut self, enabled: bool) -> Self {
+        self.no_cache = enabled;
+        self
+    }
+
+    /// What to do about promise rejections nobody handles. They're uncaught
+    /// exceptions by default.
+    pub fn unhandled_rejections(mut self, mode: UnhandledRejections) -> Self {
+        self.unhandled_rejections = mode;
+        self
+    }
+
+    /// The script's path and its arguments, which `process.argv` and
+    /// `Lunos.argv()` give. There are none by default.
+    pub fn argv(mut self, argv: Vec<String>) -> Self {
+        self.argv = argv;
+        self
+    }
+
+    /// A global function, see `native_function!`.
+    pub fn function(mut self, name: impl Into<String>, callback: NativeCallback) -> Self {
+        self.functions.push((name.into(), callback));
+        self
+    }
+
+    /// A module scripts of this runtime can import as `name`, exporting
+    /// `functions` by their names and all of them as `default`. The names of
+    /// the built-in modules can't be taken.
+    pub fn module(mut self, name: impl Into<String>, functions: &[(&str, NativeCallback)]) -> Self {
+        let functions = functions
+            .iter()
+            .map(|&(name, callback)| (name.to_string(), callback))
+            .collect();
+        self.modules.push((name.into(), functions));
+        self
+    }
+
+    pub fn build(self) -> Runtime {
+        unsafe {
+            let context = JSGlobalContextCreate(std::ptr::null_mut());
+            let natives = self
+                .modules
+                .iter()
+                .map(|(name, functions)| NativeModule {
+                    name: name.clone(),
+                    exports: functions.iter().map(|(name, _)| name.clone()).collect(),
+                })
+                .collect();
+            let registry = Registry {
+                lunos: self.lunos,
+                node: self.node,
+                offline: self.offline,
+                no_cache: self.no_cache,
+                ..Registry::new(natives)
+            };
+            let state = State {
+                unhandled_rejections: self.unhandled_rejections,
+                argv: self.argv,
+                ..State::new(registry)
+            };
+            let state = State::attach(context, state);
+
+            if self.console {
+                Console::bind_to_context(context);
+            }
+            // the module loader keeps the `Lunos` global it starts with
+            if self.lunos {
+                Lunos::bind_to_context(context);
+            }
+            if self.timers {
+                Timers::bind_to_context(context);
+            }
+            if self.node {
+                Node::bind_to_context(context);
+            }
+            es6::bind_to_context(context);
+            Rejections::bind_to_context(context);
+
+            let global = JsObject::global(context);
+            for (name, callback) in &self.functions {
+                define_functions(context, global, &[(name, *callback)]);
+            }
+            if !self.modules.is_empty() {
+                let registry = JsObject::new(context);
+                for (name, functions) in &self.modules {
+                    let module = JsObject::new(context);
+                    let functions: Vec<(&str, NativeCallback)> = functions
+                        .iter()
+                        .map(|(name, callback)| (name.as_str(), *callback))
+                        .collect();
+                    define_functions(context, module, &functions);
+                    registry.set(context, name, module);
+                }
+                let name = JsString::new("__lunos_native_modules");
+                JSObjectSetProperty(
+                    context,
+                    global.0,
+                    name.as_ptr(),
+                    registry.0,
+                    kJSPropertyAttributeDontEnum | kJSPropertyAttributeReadOnly,
+                    std::ptr::null_mut(),
+                );
+            }
+
+            Runtime {
+                context,
+                state,
+                import_map: self.import_map,
+            }
+        }
+    }
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Runtime::new()
+    }
+}
+
+impl Runtime {
+    /// A runtime with everything installed.
+    pub fn new() -> Runtime {
+        RuntimeBuilder::new().build()
+    }
+
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::new()
+    }
+
+    /// The JavaScriptCore context, for the conversions in `convert`.
+    pub fn context(&self) -> *mut OpaqueJSContext {
+        self.context
+    }
+
+    pub fn global(&self) -> JsObject {
+        unsafe { JsObject::global(self.context) }
+    }
+
+    /// Evaluates `code` as a classic script, not a module.
+    pub fn evaluate(&self, code: &str) -> Result<JsValue, RuntimeError> {
+        unsafe { es6::evaluate(self.context, code) }
+            .map(JsValue)
+            .map_err(|exception| self.uncaught(exception))
+    }
+
+    /// Runs the file at `path` as the entry module, resolving its imports
+    /// with the import map, extension order and lock file that apply to it.
+    /// Timers and asynchronous work it starts run in `run_event_loop`.
+    ///
+    /// The modules of a runtime share one configuration, so every entry has
+    /// to belong to the project of the first, or it's a `Config` error.
+    pub fn evaluate_module(&self, path: &Path) -> Result<(), RuntimeError> {
+        let registry = &self.state.modules;
+        let project = import_map::project_dir(path.parent().unwrap_or(Path::new(".")));
+        match registry.project.get() {
+            Some(first) if *first != project => {
+                return Err(RuntimeError::Config(format!(
+                    "{} is not in {}, the project this runtime already runs modules of",
+                    path.display(),
+                    first.display()
+                )));
+            }
+            Some(_) => {}
+            None => {
+                ImportMap::install(registry, path, self.import_map.as_deref())
+                    .and_then(|_| resolver::install_extensions(registry, path))
+                    .and_then(|_| LockFile::install(registry, path))
+                    .map_err(RuntimeError::Config)?;
+                let _ = registry.project.set(project);
+            }
+        }
+        let code = fs::read_to_string(path).map_err(|error| RuntimeError::Read {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        let scripts = es6::process_es6_modules(registry, &path.to_string_lossy(), &code);
+        for script in &scripts {
+            if let Err(exception) = unsafe { es6::evaluate_script(self.context, script) } {
+                return Err(self.uncaught(exception));
+            }
+        }
+        self.flush_console();
+        Ok(())
+    }
+
+    /// Runs timers until none are left, or the entry module rejects from a
+    /// top-level `await`. Each turn ends with the promise rejections nobody
+    /// handled.
+    pub fn run_event_loop(&self) -> Result<(), RuntimeError> {
+        let context = self.context;
+        loop {
+            es6::entry_settled(context).map_err(|exception| self.uncaught(exception))?;
+            Rejections::check(context).map_err(|reason| self.uncaught(reason))?;
+            match Timers::run_next(context) {
+                Ok(true) => self.flush_console(),
+                Ok(false) => break,
+                Err(exception) => return Err(self.uncaught(exception)),
+            }
+        }
+
+        match es6::entry_settled(context) {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                self.flush_console();
+                Err(RuntimeError::UnsettledTopLevelAwait)
+            }
+            Err(exception) => Err(self.uncaught(exception)),
+        }
+    }
+
+    /// Writes out what the console buffered.
+    pub fn flush_console(&self) {
+        self.state.console.flush();
+    }
+
+    /// Flushes the console, so its output comes before the exception's
+    /// report.
+    fn uncaught(&self, exception: *const OpaqueJSValue) -> RuntimeError {
+        self.flush_console();
+        let report = if exception.is_null() {
+            "Error evaluating script!\n".to_string()
+        } else {
+            unsafe { exception::describe(self.context, exception) }
+        };
+        unsafe {
+            JSGlobalContextRetain(self.context);
+            if !exception.is_null() {
+                JSValueProtect(self.context, exception);
+            }
+        }
+        RuntimeError::Uncaught(Exception {
+            context: self.context,
+            value: JsValue(exception),
+            report,
+        })
+    }
+}
+
+impl Drop for Runtime {
+    /// Timers still scheduled are cleared before the context is released,
+    /// and servers still listening close with the runtime's state.
+    fn drop(&mut self) {
+        self.state.timers.lock().unwrap().clear(self.context);
+        State::detach(self.context);
+        unsafe { JSGlobalContextRelease(self.context) };
+    }
+}
+
+impl Drop for Exception {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.value.0.is_null() {
+                JSValueUnprotect(self.context, self.value.0);
+            }
+            JSGlobalContextRelease(self.context);
+        }
+    }
+}
+
+impl Exception {
+    /// What was thrown, in the context `context` returns. Both stay valid
+    /// while the exception is alive, even once its runtime is dropped.
+    pub fn value(&self) -> JsValue {
+        self.value
+    }
+
+    pub fn context(&self) -> *mut OpaqueJSContext {
+        self.context
+    }
+
+    /// The report of the exception: its name and message, stack trace and
+    /// the code around where it was thrown. It's colored when standard
+    /// error is a terminal.
+    pub fn report(&self) -> &str {
+        &self.report
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::Read { path, error } => {
+                write!(f, "Error reading file {}: {error}", path.display())
+            }
+            RuntimeError::Config(message) => f.write_str(message),
+            RuntimeError::Uncaught(exception) => f.write_str(exception.report.trim_end()),
+            RuntimeError::UnsettledTopLevelAwait => {
+                f.write_str("Detected unsettled top-level await")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
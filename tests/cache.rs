@@ -0,0 +1,27 @@
+use lunos::lunos::cache;
+use std::fs;
+use std::thread;
+
+#[test]
+fn concurrent_writes_of_an_entry_and_its_map_stay_apart() {
+    let dir = std::env::temp_dir().join(format!("lunos-cache-write-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let output = dir.join("entry");
+    let map = dir.join("entry.map");
+
+    for round in 0..200 {
+        // big enough for the two writes to overlap
+        let output_contents = format!("output {round}").repeat(1 << 14);
+        let map_contents = format!("map {round}").repeat(1 << 14);
+        thread::scope(|scope| {
+            scope.spawn(|| cache::write(&output, output_contents.as_bytes()).unwrap());
+            scope.spawn(|| cache::write(&map, map_contents.as_bytes()).unwrap());
+        });
+        assert!(fs::read_to_string(&output).unwrap() == output_contents);
+        assert!(fs::read_to_string(&map).unwrap() == map_contents);
+    }
+    // no temporary file is left behind
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,21 @@
+// Helpers shared by the integration tests. Each test binary compiles its own
+// copy and only uses some of them.
+#![allow(dead_code)]
+
+use lunos::{FromJs, Runtime};
+use std::fs;
+use std::path::PathBuf;
+
+/// The value of evaluating `code` as a script.
+pub fn eval<T: FromJs>(runtime: &Runtime, code: &str) -> T {
+    let value = runtime.evaluate(code).unwrap();
+    unsafe { value.get(runtime.context()) }.unwrap()
+}
+
+/// A fresh directory for the files of one test.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lunos-test-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::canonicalize(dir).unwrap()
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::temp_dir;
+use lunos::{Runtime, RuntimeError};
+use std::fs;
+
+/// The report without its colors, in case standard error is a terminal.
+fn plain(report: &str) -> String {
+    let mut out = String::new();
+    let mut rest = report;
+    while let Some(start) = rest.find('\x1b') {
+        out.push_str(&rest[..start]);
+        rest = rest[start..].split_once('m').map_or("", |(_, rest)| rest);
+    }
+    out.push_str(rest);
+    out
+}
+
+#[test]
+fn uncaught_exception_report() {
+    let dir = temp_dir("report");
+    let entry = dir.join("main.js");
+    fs::write(
+        &entry,
+        "const before = 1;\nfunction fail() {\n    throw new TypeError(\"boom\");\n}\nfail();\n",
+    )
+    .unwrap();
+
+    let runtime = Runtime::new();
+    let error = runtime.evaluate_module(&entry).unwrap_err();
+    let RuntimeError::Uncaught(exception) = &error else {
+        panic!("expected the module to throw, got {error}");
+    };
+    let report = plain(exception.report());
+    let lines: Vec<&str> = report.lines().collect();
+
+    assert_eq!(lines[0], "TypeError: boom");
+    let url = entry.display().to_string();
+    assert!(
+        lines[1].starts_with(&format!("    at fail ({url}:3:")),
+        "{report}"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with(&format!("    at {url}:5:"))),
+        "{report}"
+    );
+    // the code around the throw, with a caret under where it happened
+    let marked = lines
+        .iter()
+        .position(|line| line.starts_with("> 3 | "))
+        .expect(&report);
+    assert_eq!(lines[marked], "> 3 |     throw new TypeError(\"boom\");");
+    assert_eq!(lines[marked - 1], "  2 | function fail() {");
+    assert!(lines[marked + 1].trim_end().ends_with('^'), "{report}");
+    assert_eq!(error.to_string(), exception.report().trim_end());
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{eval, temp_dir};
+use lunos::Runtime;
+use std::fs;
+
+#[test]
+fn runtimes_side_by_side_share_nothing() {
+    let first = Runtime::builder()
+        .argv(vec!["first.js".into(), "a".into()])
+        .build();
+    let second = Runtime::builder()
+        .argv(vec!["second.js".into(), "b".into()])
+        .build();
+    first.evaluate("globalThis.owner = 'first'").unwrap();
+    assert_eq!(eval::<String>(&second, "typeof owner"), "undefined");
+    assert_eq!(eval::<Vec<String>>(&first, "Lunos.argv()"), ["a"]);
+    assert_eq!(eval::<Vec<String>>(&second, "Lunos.argv()"), ["b"]);
+}
+
+#[test]
+fn dropping_a_runtime_leaves_the_others_running() {
+    let dir = temp_dir("drop");
+    let entry = dir.join("main.js");
+    fs::write(
+        &entry,
+        "setTimeout(() => { globalThis.fired = true; }, 1);\n",
+    )
+    .unwrap();
+
+    let dropped = Runtime::new();
+    let kept = Runtime::new();
+    dropped.evaluate_module(&entry).unwrap();
+    kept.evaluate_module(&entry).unwrap();
+    drop(dropped);
+
+    kept.run_event_loop().unwrap();
+    assert!(eval::<bool>(&kept, "fired"));
+}
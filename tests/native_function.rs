@@ -0,0 +1,57 @@
+use lunos::{NativeError, Runtime, native_function};
+
+native_function! {
+    fn repeat(args, text: String, times: f64, separator: Option<String>) -> Result<String, NativeError> {
+        let parts = vec![text; times as usize];
+        Ok(parts.join(separator.as_deref().unwrap_or_default()))
+    }
+}
+
+native_function! {
+    fn count(args) -> Result<f64, NativeError> {
+        Ok(args.values().count() as f64)
+    }
+}
+
+/// What calling `call` gives: its result, or the class, code and message of
+/// the error it throws.
+fn outcome(call: &str) -> String {
+    let runtime = Runtime::builder()
+        .function("repeat", repeat)
+        .function("count", count)
+        .build();
+    let code = format!(
+        "try {{ String({call}) }} catch (e) {{ `${{e.constructor.name}} ${{e.code}}: ${{e.message}}` }}"
+    );
+    let value = runtime.evaluate(&code).unwrap();
+    unsafe { value.get(runtime.context()) }.unwrap()
+}
+
+#[test]
+fn arguments_are_converted() {
+    assert_eq!(outcome("repeat('ab', 2)"), "abab");
+    assert_eq!(outcome("repeat('ab', 3, '-')"), "ab-ab-ab");
+    assert_eq!(outcome("repeat('ab', 2, undefined)"), "abab");
+    assert_eq!(outcome("repeat('ab', 2, null)"), "abab");
+    assert_eq!(outcome("count(1, 'two', {})"), "3");
+}
+
+#[test]
+fn a_missing_argument_is_a_type_error() {
+    assert_eq!(
+        outcome("repeat('ab')"),
+        "TypeError ERR_MISSING_ARGS: The \"times\" argument must be specified"
+    );
+}
+
+#[test]
+fn an_argument_of_the_wrong_type_is_a_type_error() {
+    assert_eq!(
+        outcome("repeat(1, 2)"),
+        "TypeError ERR_INVALID_ARG_TYPE: The \"text\" argument must be of type string. Received type number (1)"
+    );
+    assert_eq!(
+        outcome("repeat('ab', '2')"),
+        "TypeError ERR_INVALID_ARG_TYPE: The \"times\" argument must be of type number. Received type string ('2')"
+    );
+}
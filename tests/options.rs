@@ -0,0 +1,30 @@
+use lunos::lunos::options::Options;
+
+fn parse(flags: &[&str]) -> Result<Options, String> {
+    let args: Vec<String> = ["lunos"]
+        .iter()
+        .chain(flags)
+        .chain(&["main.js"])
+        .map(|arg| arg.to_string())
+        .collect();
+    Options::parse(&args)
+}
+
+#[test]
+fn switches_take_an_optional_boolean() {
+    let options = parse(&["--offline", "--no-cache"]).unwrap();
+    assert!(options.offline && options.no_cache);
+    assert_eq!(options.script_index, 3);
+
+    let options = parse(&["--offline=true", "--no-cache=false"]).unwrap();
+    assert!(options.offline && !options.no_cache);
+
+    let options = parse(&["--offline=false"]).unwrap();
+    assert!(!options.offline);
+
+    let error = parse(&["--no-cache=0"]).unwrap_err();
+    assert_eq!(
+        error,
+        "Invalid value for --no-cache: 0 (expected true or false)"
+    );
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{eval, temp_dir};
+use lunos::{JsObject, NativeError, Runtime, RuntimeError, native_function};
+use std::fs;
+
+native_function! {
+    fn add(args, a: f64, b: f64) -> Result<f64, NativeError> {
+        Ok(a + b)
+    }
+}
+
+#[test]
+fn evaluate_returns_the_completion_value() {
+    let runtime = Runtime::new();
+    assert_eq!(eval::<f64>(&runtime, "1 + 2"), 3.0);
+    assert_eq!(eval::<String>(&runtime, "'lu' + 'nos'"), "lunos");
+    assert_eq!(eval::<Option<String>>(&runtime, "undefined"), None);
+}
+
+#[test]
+fn an_exception_outlives_its_runtime() {
+    let runtime = Runtime::new();
+    let Err(RuntimeError::Uncaught(exception)) = runtime.evaluate("throw new RangeError('late')")
+    else {
+        panic!("expected the script to throw");
+    };
+    drop(runtime);
+
+    let context = exception.context();
+    let error: JsObject = unsafe { exception.value().get(context) }.unwrap();
+    let message: String = unsafe { error.get(context, "message") }.unwrap();
+    assert_eq!(message, "late");
+}
+
+#[test]
+fn functions_and_modules_from_rust() {
+    let dir = temp_dir("native");
+    let entry = dir.join("main.js");
+    fs::write(
+        &entry,
+        "import { add } from \"math\";\nglobalThis.sum = add(4, 5);\n",
+    )
+    .unwrap();
+
+    let runtime = Runtime::builder()
+        .function("add", add)
+        .module("math", &[("add", add)])
+        .build();
+    assert_eq!(eval::<f64>(&runtime, "add(2, 3)"), 5.0);
+    runtime.evaluate_module(&entry).unwrap();
+    assert_eq!(eval::<f64>(&runtime, "sum"), 9.0);
+}
+
+#[test]
+fn disabled_builtin_groups_dont_resolve() {
+    let dir = temp_dir("groups");
+    for (name, specifier) in [("node.js", "node:path"), ("lunos.js", "lunos:fs")] {
+        fs::write(dir.join(name), format!("import \"{specifier}\";\n")).unwrap();
+    }
+
+    let runtime = Runtime::builder().node(false).lunos(false).build();
+    for (name, specifier) in [("node.js", "node:path"), ("lunos.js", "lunos:fs")] {
+        let error = runtime.evaluate_module(&dir.join(name)).unwrap_err();
+        let RuntimeError::Uncaught(exception) = &error else {
+            panic!("expected {specifier} not to resolve, got {error}");
+        };
+        let message = format!("Cannot find module '{specifier}'");
+        assert!(exception.report().contains(&message), "{error}");
+    }
+}
+
+#[test]
+fn entries_share_the_project_of_the_first() {
+    let dir = temp_dir("project");
+    let other = temp_dir("other-project");
+    fs::write(
+        dir.join("lunos.json"),
+        "{ \"imports\": { \"answer\": \"./answer.js\" } }",
+    )
+    .unwrap();
+    fs::write(dir.join("answer.js"), "export default 42;\n").unwrap();
+    fs::write(dir.join("first.js"), "globalThis.first = 1;\n").unwrap();
+    fs::write(
+        dir.join("second.js"),
+        "import answer from \"answer\";\nglobalThis.second = answer;\n",
+    )
+    .unwrap();
+    fs::write(other.join("main.js"), "globalThis.other = 1;\n").unwrap();
+
+    let runtime = Runtime::new();
+    runtime.evaluate_module(&dir.join("first.js")).unwrap();
+    runtime.evaluate_module(&dir.join("second.js")).unwrap();
+    assert_eq!(eval::<f64>(&runtime, "second"), 42.0);
+
+    let error = runtime.evaluate_module(&other.join("main.js")).unwrap_err();
+    assert!(matches!(error, RuntimeError::Config(_)), "{error}");
+    assert_eq!(eval::<Option<f64>>(&runtime, "globalThis.other"), None);
+}
@@ -0,0 +1,64 @@
+use lunos::{Runtime, RuntimeBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Every file under `dir`.
+fn files(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            found.extend(files(&path));
+        } else {
+            found.push(path);
+        }
+    }
+    found
+}
+
+/// Runs `entry` and returns the `result` it sets.
+fn run(builder: RuntimeBuilder, entry: &Path) -> f64 {
+    let runtime: Runtime = builder.build();
+    runtime.evaluate_module(entry).unwrap();
+    let value = runtime.evaluate("result").unwrap();
+    unsafe { value.get(runtime.context()) }.unwrap()
+}
+
+// One test, since the cache directory is set for the whole process.
+#[test]
+fn transforms_are_cached_unless_disabled() {
+    let dir = std::env::temp_dir().join(format!("lunos-cache-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let dir = fs::canonicalize(dir).unwrap();
+    let cache = dir.join("cache");
+    // SAFETY: no other thread runs in this test binary
+    unsafe { std::env::set_var("LUNOS_CACHE_DIR", &cache) };
+
+    let entry = dir.join("main.ts");
+    fs::write(
+        &entry,
+        "const answer: number = 1;\nglobalThis.result = answer;\n",
+    )
+    .unwrap();
+    assert_eq!(run(Runtime::builder(), &entry), 1.0);
+
+    // a run that reads the cache gets what's in it, not a new transform
+    let mut tampered = 0;
+    for file in files(&cache.join("transform")) {
+        let contents = fs::read_to_string(&file).unwrap();
+        if contents.contains("= answer;") && file.extension().is_none() {
+            fs::write(&file, contents.replace("= answer;", "= 2 + 0;")).unwrap();
+            tampered += 1;
+        }
+    }
+    assert_eq!(tampered, 1);
+    assert_eq!(run(Runtime::builder(), &entry), 2.0);
+
+    // and one without the cache neither reads nor writes it
+    fs::remove_dir_all(&cache).unwrap();
+    assert_eq!(run(Runtime::builder().no_cache(true), &entry), 1.0);
+    assert!(!cache.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,55 @@
+// Type definitions for the built-in `lunos:` modules. Point your editor at
+// this file (e.g. `"types": ["./types/lunos.d.ts"]` in a tsconfig) to get
+// completion for `import { serve } from "lunos:http"` and friends.
+
+declare module "lunos:http" {
+    export interface ServeOptions {
+        /** Defaults to 9595. */
+        port?: number;
+        responseText?: string;
+        contentType?: string;
+        type?: string;
+        /** Serves the files in this directory. */
+        dir?: string;
+        /** Serves this file for every request. */
+        file?: string;
+        logMiddleware?: boolean;
+    }
+
+    export function serve(options: ServeOptions): void;
+
+    const http: { serve: typeof serve };
+    export default http;
+}
+
+declare module "lunos:fs" {
+    /** Reads a file relative to the working directory. */
+    export function loadFile(path: string): string;
+
+    const fs: { loadFile: typeof loadFile };
+    export default fs;
+}
+
+declare module "lunos:process" {
+    /** The arguments given after the script. */
+    export function argv(): string[];
+    export function exit(code?: number): never;
+    /** Prints `prompt` and reads a line from stdin. */
+    export function input(prompt?: string): string | null;
+
+    const process: { argv: typeof argv; exit: typeof exit; input: typeof input };
+    export default process;
+}
+
+declare module "lunos:shell" {
+    export interface ShellResult {
+        result: string;
+        error: string;
+    }
+
+    /** Runs `command` with `shell -c`, collecting its stdout and stderr. */
+    export function shell(shell: string, command: string): ShellResult;
+
+    const shellModule: { shell: typeof shell };
+    export default shellModule;
+}